@@ -0,0 +1,509 @@
+use std::{
+    ops::{Deref, DerefMut},
+    pin::Pin,
+};
+
+use anyhow::anyhow;
+use anyhttp::{HttpClient, Response};
+use anyml_core::{
+    decode::ndjson_lines,
+    maybe_send::{BoxStream, MaybeSend},
+    models::ToolCall,
+    providers::chat::{ChatError, ChatOptions, FinishReason, Usage},
+};
+use anyml_macros::json_string;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use http::Request;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::{
+    AnthropicProvider, BatchError,
+    chat::{finish_reason, request_body},
+};
+
+impl<C: HttpClient> AnthropicProvider<C> {
+    /// Submits a batch of message requests to be processed asynchronously.
+    pub async fn create_batch(
+        &self,
+        requests: &[BatchRequest],
+    ) -> Result<MessageBatch, BatchError> {
+        let body: String = json_string! { "requests": @serde requests };
+
+        let request = Request::post(format!("{}/v1/messages/batches", self.url));
+        let response = self.send(request, body).await?;
+        parse_json(response).await
+    }
+
+    pub async fn get_batch(&self, batch_id: &str) -> Result<MessageBatch, BatchError> {
+        let request = Request::get(format!("{}/v1/messages/batches/{batch_id}", self.url));
+        let response = self.send(request, String::new()).await?;
+        parse_json(response).await
+    }
+
+    /// Streams the results of an ended batch, one per request.
+    /// Results are not guaranteed to be in the same order as the requests.
+    pub async fn batch_results(&self, batch_id: &str) -> Result<BatchResults, BatchError> {
+        let request = Request::get(format!(
+            "{}/v1/messages/batches/{batch_id}/results",
+            self.url
+        ));
+        let response = self.send(request, String::new()).await?;
+
+        Ok(BatchResults::new(
+            ndjson_lines(response.bytes_stream()).map(|line| {
+                let line = line.map_err(BatchError::ResponseFetchFailed)?;
+                serde_json::from_str(&line)
+                    .map_err(|this| BatchError::ParseError(anyhow::Error::new(this)))
+            }),
+        ))
+    }
+
+    async fn send(
+        &self,
+        request: http::request::Builder,
+        body: String,
+    ) -> Result<Response, BatchError> {
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(BatchError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(request)
+            .header("anthropic-version", "2023-06-01")
+            .header("x-api-key", api_key.expose_secret())
+            .body(body.into_bytes())
+            .map_err(|this| BatchError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(BatchError::ResponseFetchFailed)?;
+
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(BatchError::RequestError(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        Ok(response)
+    }
+}
+
+async fn parse_json<T: for<'de> Deserialize<'de>>(response: Response) -> Result<T, BatchError> {
+    let body = response
+        .bytes()
+        .await
+        .map_err(BatchError::ResponseFetchFailed)?;
+
+    serde_json::from_slice(&body).map_err(|this| BatchError::ParseError(anyhow::Error::new(this)))
+}
+
+/// A single request within a batch.
+#[derive(Serialize, Debug, Clone)]
+pub struct BatchRequest {
+    /// Identifies the request's result, which may arrive in any order.
+    pub custom_id: String,
+    params: Box<RawValue>,
+}
+
+impl BatchRequest {
+    /// Builds a request from the same options used for chat requests.
+    /// Streaming is always disabled, as batches can't stream.
+    pub fn new(custom_id: impl Into<String>, options: &ChatOptions<'_>) -> Result<Self, ChatError> {
+        let body = request_body(&options.clone().stream(false))?;
+        let params = RawValue::from_string(body)
+            .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        Ok(Self {
+            custom_id: custom_id.into(),
+            params,
+        })
+    }
+}
+
+/// The status of a message batch.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct MessageBatch {
+    pub id: String,
+    pub processing_status: ProcessingStatus,
+    pub request_counts: RequestCounts,
+    /// When the batch was created, as an RFC 3339 timestamp.
+    pub created_at: String,
+    /// When the batch ended, as an RFC 3339 timestamp.
+    #[serde(default)]
+    pub ended_at: Option<String>,
+    /// When the batch expires if it hasn't ended, as an RFC 3339 timestamp.
+    pub expires_at: String,
+    #[serde(default)]
+    pub results_url: Option<String>,
+}
+
+impl MessageBatch {
+    /// Whether processing has ended and results can be fetched.
+    pub fn is_ended(&self) -> bool {
+        self.processing_status == ProcessingStatus::Ended
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingStatus {
+    InProgress,
+    Canceling,
+    Ended,
+}
+
+/// How many of a batch's requests are in each state.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct RequestCounts {
+    pub processing: usize,
+    pub succeeded: usize,
+    pub errored: usize,
+    pub canceled: usize,
+    pub expired: usize,
+}
+
+/// The result of a single request within a batch.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchResult {
+    pub custom_id: String,
+    pub result: BatchOutcome,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "WireOutcome")]
+pub enum BatchOutcome {
+    Succeeded(BatchMessage),
+    Errored { kind: String, message: String },
+    Canceled,
+    Expired,
+}
+
+/// A message produced by a successful batch request.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchMessage {
+    pub id: String,
+    pub model: String,
+    pub content: Vec<ContentBlock>,
+    #[serde(default)]
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: WireUsage,
+}
+
+impl BatchMessage {
+    /// The text of the message, with all text blocks joined.
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn thinking(&self) -> Option<String> {
+        let thinking = self
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Thinking { thinking } => Some(thinking.as_str()),
+                _ => None,
+            })
+            .collect::<String>();
+
+        (!thinking.is_empty()).then_some(thinking)
+    }
+
+    pub fn tool_calls(&self) -> Vec<ToolCall> {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolUse { id, name, input } => {
+                    Some(ToolCall::new(id, name, input.to_string()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.stop_reason.clone().map(finish_reason)
+    }
+
+    pub fn usage(&self) -> Usage {
+        Usage {
+            input_tokens: self.usage.input_tokens,
+            output_tokens: self.usage.output_tokens,
+            cached_input_tokens: None,
+            timing: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        thinking: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// A stream of [`BatchResult`]s.
+pub struct BatchResults(BoxStream<'static, Result<BatchResult, BatchError>>);
+
+impl BatchResults {
+    pub fn new(
+        stream: impl Stream<Item = Result<BatchResult, BatchError>> + MaybeSend + 'static,
+    ) -> Self {
+        Self(Box::pin(stream))
+    }
+
+    pub async fn next(&mut self) -> Option<Result<BatchResult, BatchError>> {
+        self.0.next().await
+    }
+
+    /// Collects all remaining results.
+    /// If any error occurs then it will be returned instead.
+    pub async fn collect_all(&mut self) -> Result<Vec<BatchResult>, BatchError> {
+        let mut results = Vec::new();
+
+        while let Some(result) = self.next().await {
+            results.push(result?);
+        }
+
+        Ok(results)
+    }
+}
+
+impl Stream for BatchResults {
+    type Item = Result<BatchResult, BatchError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
+
+impl Deref for BatchResults {
+    type Target = BoxStream<'static, Result<BatchResult, BatchError>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for BatchResults {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WireOutcome {
+    Succeeded { message: BatchMessage },
+    Errored { error: WireErrorEnvelope },
+    Canceled,
+    Expired,
+}
+
+#[derive(Deserialize)]
+struct WireErrorEnvelope {
+    error: WireError,
+}
+
+#[derive(Deserialize)]
+struct WireError {
+    r#type: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+struct WireUsage {
+    #[serde(default)]
+    input_tokens: Option<usize>,
+    #[serde(default)]
+    output_tokens: Option<usize>,
+}
+
+impl From<WireOutcome> for BatchOutcome {
+    fn from(outcome: WireOutcome) -> Self {
+        match outcome {
+            WireOutcome::Succeeded { message } => Self::Succeeded(message),
+            WireOutcome::Errored { error } => Self::Errored {
+                kind: error.error.r#type,
+                message: error.error.message,
+            },
+            WireOutcome::Canceled => Self::Canceled,
+            WireOutcome::Expired => Self::Expired,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::models::Message;
+    use http::StatusCode;
+
+    const BATCH: &str = r#"{
+        "id": "msgbatch_01",
+        "type": "message_batch",
+        "processing_status": "in_progress",
+        "request_counts": {"processing": 2, "succeeded": 0, "errored": 0, "canceled": 0, "expired": 0},
+        "ended_at": null,
+        "created_at": "2024-09-24T18:37:24.100435Z",
+        "expires_at": "2024-09-25T18:37:24.100435Z",
+        "results_url": null
+    }"#;
+
+    #[tokio::test]
+    async fn test_create_batch() {
+        let client =
+            MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(BATCH));
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = [Message::user("Hi")];
+        let requests = [
+            BatchRequest::new(
+                "first",
+                &ChatOptions::new("claude-3-haiku").messages(&messages),
+            )
+            .unwrap(),
+            BatchRequest::new(
+                "second",
+                &ChatOptions::new("claude-3-haiku")
+                    .messages(&messages)
+                    .system("Be brief."),
+            )
+            .unwrap(),
+        ];
+
+        let batch = provider.create_batch(&requests).await.unwrap();
+
+        assert_eq!(batch.id, "msgbatch_01");
+        assert_eq!(batch.processing_status, ProcessingStatus::InProgress);
+        assert_eq!(batch.request_counts.processing, 2);
+        assert!(!batch.is_ended());
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://api.anthropic.com/v1/messages/batches"
+        );
+        assert_eq!(request.headers().get("x-api-key").unwrap(), "test-api-key");
+
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["requests"][0]["custom_id"], "first");
+        assert_eq!(body["requests"][0]["params"]["model"], "claude-3-haiku");
+        assert_eq!(
+            body["requests"][0]["params"]["messages"][0]["content"],
+            "Hi"
+        );
+        assert!(body["requests"][0]["params"].get("stream").is_none());
+        assert_eq!(body["requests"][1]["params"]["system"], "Be brief.");
+    }
+
+    #[tokio::test]
+    async fn test_get_batch() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(BATCH.replace("in_progress", "ended")),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let batch = provider.get_batch("msgbatch_01").await.unwrap();
+
+        assert!(batch.is_ended());
+        assert_eq!(
+            client.last_request().unwrap().uri(),
+            "https://api.anthropic.com/v1/messages/batches/msgbatch_01"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_results() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "{\"custom_id\":\"first\",\"result\":{\"type\":\"succeeded\",\"message\":{\"id\":\"msg_01\",\"type\":\"message\",\"role\":\"assistant\",\"model\":\"claude-3-haiku\",\"content\":[{\"type\":\"text\",\"text\":\"Hello!\"},{\"type\":\"tool_use\",\"id\":\"toolu_01\",\"name\":\"get_weather\",\"input\":{\"city\":\"Paris\"}}],\"stop_reason\":\"tool_use\",\"usage\":{\"input_tokens\":10,\"output_tokens\":5}}}}\n\
+             {\"custom_id\":\"second\",\"result\":{\"type\":\"errored\",\"error\":{\"type\":\"error\",\"error\":{\"type\":\"invalid_request_error\",\"message\":\"Bad request\"}}}}\n\
+             {\"custom_id\":\"third\",\"result\":{\"type\":\"expired\"}}\n",
+        ));
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let results = provider
+            .batch_results("msgbatch_01")
+            .await
+            .unwrap()
+            .collect_all()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client.last_request().unwrap().uri(),
+            "https://api.anthropic.com/v1/messages/batches/msgbatch_01/results"
+        );
+        assert_eq!(results.len(), 3);
+
+        let BatchOutcome::Succeeded(message) = &results[0].result else {
+            panic!("expected the first request to succeed");
+        };
+        assert_eq!(message.text(), "Hello!");
+        assert_eq!(message.finish_reason(), Some(FinishReason::ToolCalls));
+        assert_eq!(message.usage().output_tokens, Some(5));
+        let tool_calls = message.tool_calls();
+        assert_eq!(tool_calls[0].name, "get_weather");
+        assert_eq!(
+            tool_calls[0].arguments_json().unwrap(),
+            serde_json::json!({ "city": "Paris" })
+        );
+
+        assert_eq!(
+            results[1].result,
+            BatchOutcome::Errored {
+                kind: "invalid_request_error".into(),
+                message: "Bad request".into(),
+            }
+        );
+        assert_eq!(results[2].custom_id, "third");
+        assert_eq!(results[2].result, BatchOutcome::Expired);
+    }
+
+    #[tokio::test]
+    async fn test_get_batch_http_error() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::NOT_FOUND).body("not found"));
+
+        let provider = AnthropicProvider::new(client, "test-api-key");
+        let result = provider.get_batch("missing").await;
+
+        assert!(matches!(result, Err(BatchError::RequestError(_))));
+    }
+}
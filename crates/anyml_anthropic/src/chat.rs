@@ -1,62 +1,71 @@
+use std::{borrow::Cow, collections::HashMap};
+
 use anyhow::anyhow;
 use anyhttp::HttpClient;
+use anyml_core::decode::SseDecoder;
+use anyml_core::models::{ContentPart, Message, MessageRole, Tool};
 use anyml_core::providers::chat::{
-    ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError, Thinking,
+    ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError, FinishReason,
+    Messages, ServerTool, ServerToolResult, ServerToolUse, Thinking, ToolCallDelta, ToolChoice,
+    Usage,
 };
 use anyml_macros::json_string;
 use bytes::Bytes;
 use futures::StreamExt;
 use http::Request;
-use itertools::Itertools;
-use secrecy::ExposeSecret;
+use secrecy::{ExposeSecret, SecretString};
 use serde::Deserialize;
 use thiserror::Error;
 
-use crate::AnthropicProvider;
+use crate::{
+    AnthropicChatExt, AnthropicProvider,
+    list_models::{DEFAULT_MAX_OUTPUT_TOKENS, MAX_OUTPUT_TOKENS, THINKING_MODELS},
+};
 
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl<C: HttpClient> ChatProvider for AnthropicProvider<C> {
     async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
-        let messages_json = options.messages.to_json();
-
-        let body: String = match &options.thinking {
-            Some(Thinking::Effort(effort)) => json_string! {
-                "model": options.model,
-                "messages": @raw messages_json,
-                "stream": options.stream,
-                "max_tokens": options.max_tokens,
-                "thinking": {
-                    "type": "adaptive",
-                    "effort": effort
-                }
-            },
-            Some(thinking) => {
-                let budget = match thinking {
-                    Thinking::BudgetTokens(b) => *b,
-                    _ => 10000,
-                };
-                json_string! {
-                    "model": options.model,
-                    "messages": @raw messages_json,
-                    "stream": options.stream,
-                    "max_tokens": options.max_tokens,
-                    "thinking": {
-                        "type": "enabled",
-                        "budget_tokens": budget
-                    }
-                }
-            }
-            None => json_string! {
-                "model": options.model,
-                "messages": @raw messages_json,
-                "stream": options.stream,
-                "max_tokens": options.max_tokens
-            },
-        };
+        let options = options.or_default_model(self.default_model.as_deref());
+        options.with_timeouts(self.send_chat(&options)).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.default_model.as_deref()
+    }
+}
 
-        let request = Request::post(format!("{}/v1/messages", self.url))
+impl<C: HttpClient> AnthropicProvider<C> {
+    /// An empty key is only rejected for the hosted API, as other servers may not need one.
+    fn check_api_key(&self, api_key: &SecretString, url: &str) -> Result<(), ChatError> {
+        if api_key.expose_secret().is_empty() && url == crate::DEFAULT_URL {
+            return Err(ChatError::InvalidOptions {
+                field: "api_key",
+                reason: "it is empty".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn send_chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse<'_>, ChatError> {
+        let overrides = &options.overrides;
+        let url = overrides.url_or(&self.url);
+        let api_key = overrides
+            .api_key_or(&*self.api_key)
+            .await
+            .map_err(ChatError::RequestBuildFailed)?;
+        self.check_api_key(&api_key, url)?;
+        let body = request_body(options)?;
+
+        let request = self
+            .headers
+            .apply(Request::post(format!("{url}{}", self.chat_path)));
+        let request = overrides
+            .headers
+            .apply(request)
             .header("anthropic-version", "2023-06-01")
-            .header("x-api-key", self.api_key.expose_secret())
+            .header("x-api-key", api_key.expose_secret())
             .body(body.into_bytes())
             .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
 
@@ -66,82 +75,563 @@ impl<C: HttpClient> ChatProvider for AnthropicProvider<C> {
             .await
             .map_err(|this| ChatError::ResponseFetchFailed(this))?;
 
-        if !response.status().is_success() {
+        let status = response.status();
+        if !status.is_success() {
             let err_body = response
                 .bytes()
                 .await
                 .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
 
-            return Err(ChatError::RequestError(anyhow!(
-                String::from_utf8_lossy(&err_body).into_owned()
-            )));
+            return Err(ChatError::from_error_response(status, &err_body));
         }
 
         let stream = response.bytes_stream();
 
+        let include_raw = options.include_raw;
         Ok(ChatResponse::new(
             stream
-                .scan(String::new(), |buffer, chunk| {
-                    let chunks = parse_sse_batch(&chunk, buffer);
+                .scan(StreamState::default(), move |state, chunk| {
+                    let chunks = parse_sse_batch(&chunk, state, include_raw);
                     futures::future::ready(Some(chunks))
                 })
-                .flat_map(futures::stream::iter),
+                .flat_map(futures::stream::iter)
+                // Ends at `message_stop`, without waiting for the connection to close.
+                .take_while(|chunk| futures::future::ready(chunk.is_some()))
+                .filter_map(futures::future::ready),
         ))
     }
 }
 
+/// Builds the body of a `/v1/messages` request.
+pub(crate) fn request_body(options: &ChatOptions<'_>) -> Result<String, ChatError> {
+    options.validate()?;
+    if options.response_format.is_some() {
+        return Err(ChatError::Unsupported {
+            feature: "response_format",
+        });
+    }
+    if options.logprobs {
+        return Err(ChatError::Unsupported {
+            feature: "logprobs",
+        });
+    }
+    if options.audio_output.is_some() {
+        return Err(ChatError::Unsupported {
+            feature: "audio_output",
+        });
+    }
+
+    let default_ext = AnthropicChatExt::default();
+    let ext = options.extensions.get().unwrap_or(&default_ext);
+    ext.validate()?;
+
+    let (messages_json, system) =
+        messages_json(&options.messages, options.system, options.prefill)?;
+    let tools_json = tools_json(options.tools, &options.server_tools);
+
+    // Unknown models are passed through and left to the API to reject.
+    let modes = THINKING_MODELS.get(options.model);
+    if let Some(thinking) = &options.thinking
+        && let Some(modes) = modes
+        && !modes.supports(thinking)
+    {
+        return Err(ChatError::InvalidOptions {
+            field: "thinking",
+            reason: format!("{} doesn't support {thinking:?}", options.model),
+        });
+    }
+
+    let thinking_budget = match &options.thinking {
+        Some(Thinking::BudgetTokens(budget)) => Some(*budget),
+        // Models without a budget only think adaptively.
+        Some(Thinking::Enabled) if modes.is_some_and(|modes| modes.budget.is_none()) => None,
+        Some(Thinking::Enabled) => Some(10000),
+        Some(Thinking::Effort(_)) | None => None,
+    };
+
+    // The API requires `max_tokens`, so default to as many as the model can generate.
+    let max_tokens = options.max_tokens.unwrap_or_else(|| {
+        match MAX_OUTPUT_TOKENS.get(options.model) {
+            Some(max_tokens) => *max_tokens,
+            // `max_tokens` includes the thinking budget, so leave room for the reply.
+            None => DEFAULT_MAX_OUTPUT_TOKENS + thinking_budget.unwrap_or(0),
+        }
+    });
+    // Caught here, as the API only rejects it with a bare 400.
+    if let Some(budget) = thinking_budget
+        && budget >= max_tokens
+    {
+        return Err(match options.max_tokens {
+            Some(_) => ChatError::InvalidOptions {
+                field: "max_tokens",
+                reason: format!("it must be greater than the thinking budget of {budget} tokens"),
+            },
+            None => ChatError::InvalidOptions {
+                field: "thinking",
+                reason: format!(
+                    "its budget of {budget} tokens must be less than the {max_tokens} tokens {} can generate",
+                    options.model
+                ),
+            },
+        });
+    }
+
+    let body: String = json_string! {
+        "model": options.model,
+        "messages": @raw messages_json,
+        if options.stream {
+            "stream": true
+        }
+        "max_tokens": max_tokens,
+        if let Some(system) = &system {
+            "system": system
+        }
+        if let Some(Thinking::Effort(effort)) = &options.thinking {
+            "thinking": {
+                "type": "adaptive",
+                "effort": effort
+            }
+        } else if let Some(budget) = thinking_budget {
+            "thinking": {
+                "type": "enabled",
+                "budget_tokens": budget
+            }
+        } else if options.thinking.is_some() {
+            "thinking": {
+                "type": "adaptive"
+            }
+        }
+        if let Some(tools) = &tools_json {
+            "tools": @raw tools,
+            "tool_choice"?: tool_choice_json(options.tool_choice.as_ref(), options.parallel_tool_calls)
+        }
+        "temperature"?: ext.temperature,
+        "top_p"?: ext.top_p,
+        "top_k"?: ext.top_k,
+        if !ext.stop_sequences.is_empty() {
+            "stop_sequences": ext.stop_sequences
+        }
+        if let Some(user_id) = &ext.user_id {
+            "metadata": {
+                "user_id": user_id
+            }
+        }
+        @merge &options.extra
+    };
+
+    Ok(body)
+}
+
+/// Serializes messages into Anthropic's format, where tool calls and their results
+/// are content blocks rather than dedicated fields.
+///
+/// Anthropic takes the system prompt as a top-level field, so `system` and any
+/// system messages are joined and returned separately.
+fn messages_json(
+    messages: &Messages<'_>,
+    system: Option<&str>,
+    prefill: Option<&str>,
+) -> Result<(String, Option<String>), ChatError> {
+    // Anthropic rejects a final assistant message that ends with whitespace.
+    let prefill = prefill.map(str::trim_end);
+
+    let Messages::Raw(messages) = messages else {
+        return Ok((
+            messages.to_json_with(None, prefill),
+            system.map(str::to_owned),
+        ));
+    };
+
+    let (system_messages, messages): (Vec<_>, Vec<_>) = messages
+        .iter()
+        .partition(|message| matches!(message.role, MessageRole::System));
+
+    let system = system
+        .into_iter()
+        .chain(
+            system_messages
+                .iter()
+                .map(|message| message.content.as_str()),
+        )
+        .collect::<Vec<_>>();
+    let system = (!system.is_empty()).then(|| system.join("\n"));
+
+    let prefill =
+        prefill.map(|prefill| Ok(serde_json::json!({ "role": "assistant", "content": prefill })));
+    let messages = messages
+        .into_iter()
+        .map(message_json)
+        .chain(prefill)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((
+        serde_json::Value::from(merge_consecutive_roles(messages)).to_string(),
+        system,
+    ))
+}
+
+/// Merges consecutive messages with the same role into one, as Anthropic requires
+/// roles to alternate and the results of parallel tool calls in a single message.
+///
+/// History from other providers often breaks this, e.g. with a user message
+/// straight after tool results, which are sent as user messages.
+fn merge_consecutive_roles(messages: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+    let mut merged: Vec<serde_json::Value> = Vec::with_capacity(messages.len());
+    for message in messages {
+        match merged.last_mut() {
+            Some(last) if last["role"] == message["role"] => {
+                let mut blocks = content_blocks(last["content"].take());
+                blocks.extend(content_blocks(message["content"].clone()));
+                last["content"] = blocks.into();
+            }
+            _ => merged.push(message),
+        }
+    }
+    merged
+}
+
+/// The content of a message as content blocks, leaving out empty text.
+fn content_blocks(content: serde_json::Value) -> Vec<serde_json::Value> {
+    match content {
+        serde_json::Value::Array(blocks) => blocks,
+        serde_json::Value::String(text) if text.is_empty() => Vec::new(),
+        serde_json::Value::String(text) => {
+            vec![serde_json::json!({ "type": "text", "text": text })]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Serializes a message into Anthropic's format, with images as content blocks
+/// after the text. Anthropic takes no audio, so it's rejected.
+fn message_json(message: &Message) -> Result<serde_json::Value, ChatError> {
+    Ok(match message.role {
+        MessageRole::Tool => serde_json::json!({
+            "role": "user",
+            "content": [{
+                "type": "tool_result",
+                "tool_use_id": message.tool_call_id,
+                "content": message.content,
+            }],
+        }),
+        MessageRole::Assistant
+            if !message.tool_calls.is_empty() || !message.thinking.is_empty() =>
+        {
+            let mut content =
+                Vec::with_capacity(message.thinking.len() + message.tool_calls.len() + 1);
+            // Thinking comes first, and is sent back verbatim for its signature to verify.
+            for thinking in &message.thinking {
+                content.push(serde_json::json!({
+                    "type": "thinking",
+                    "thinking": thinking.thinking,
+                    "signature": thinking.signature,
+                }));
+            }
+            if !message.content.is_empty() {
+                content.push(serde_json::json!({ "type": "text", "text": message.content }));
+            }
+            for tool_call in &message.tool_calls {
+                content.push(serde_json::json!({
+                    "type": "tool_use",
+                    "id": tool_call.id,
+                    "name": tool_call.name,
+                    "input": tool_call
+                        .arguments_json()
+                        .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?,
+                }));
+            }
+            serde_json::json!({ "role": "assistant", "content": content })
+        }
+        _ if !message.parts.is_empty() => {
+            let text = (!message.content.is_empty())
+                .then(|| serde_json::json!({ "type": "text", "text": message.content }));
+            let parts = message
+                .parts
+                .iter()
+                .map(|part| match part {
+                    ContentPart::Image(image) => Ok(serde_json::json!({
+                        "type": "image",
+                        "source": {
+                            "type": "base64",
+                            "media_type": image.media_type,
+                            "data": image.data,
+                        },
+                    })),
+                    ContentPart::Audio(_) => Err(ChatError::Unsupported {
+                        feature: "audio_input",
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let content = text.into_iter().chain(parts).collect::<Vec<_>>();
+            serde_json::json!({ "role": message.role, "content": content })
+        }
+        _ => serde_json::json!({ "role": message.role, "content": message.content }),
+    })
+}
+
+/// Serializes tools into Anthropic's `tools` array, or `None` if there are none.
+fn tools_json(tools: &[Tool], server_tools: &[ServerTool]) -> Option<String> {
+    if tools.is_empty() && server_tools.is_empty() {
+        return None;
+    }
+
+    let server_tools = server_tools.iter().map(|server_tool| match server_tool {
+        ServerTool::WebSearch { max_uses } => {
+            let mut json = serde_json::json!({
+                "type": "web_search_20250305",
+                "name": "web_search",
+            });
+            if let Some(max_uses) = max_uses {
+                json["max_uses"] = (*max_uses).into();
+            }
+            json
+        }
+    });
+    let tools = tools
+        .iter()
+        .map(|tool| {
+            let mut json = serde_json::json!({
+                "name": tool.name,
+                "input_schema": tool.parameters,
+            });
+            if let Some(description) = &tool.description {
+                json["description"] = description.as_str().into();
+            }
+            json
+        })
+        .chain(server_tools)
+        .collect::<Vec<_>>();
+
+    Some(serde_json::Value::from(tools).to_string())
+}
+
+/// Serializes the tool choice into Anthropic's `tool_choice` object, which also carries
+/// whether parallel tool use is disabled, or `None` if neither is set.
+fn tool_choice_json(
+    tool_choice: Option<&ToolChoice>,
+    parallel_tool_calls: Option<bool>,
+) -> Option<serde_json::Value> {
+    if tool_choice.is_none() && parallel_tool_calls.is_none() {
+        return None;
+    }
+
+    let mut json = match tool_choice.unwrap_or(&ToolChoice::Auto) {
+        ToolChoice::Auto => serde_json::json!({ "type": "auto" }),
+        // Anthropic rejects `disable_parallel_tool_use` alongside `none`.
+        ToolChoice::None => return Some(serde_json::json!({ "type": "none" })),
+        ToolChoice::Required => serde_json::json!({ "type": "any" }),
+        ToolChoice::Tool(name) => serde_json::json!({ "type": "tool", "name": name }),
+    };
+    if parallel_tool_calls == Some(false) {
+        json["disable_parallel_tool_use"] = true.into();
+    }
+
+    Some(json)
+}
+
+/// What a stream has received but not yet parsed into chunks.
+#[derive(Default)]
+struct StreamState {
+    events: SseDecoder,
+    /// The content blocks which have started but not yet stopped, by index.
+    blocks: HashMap<usize, ContentBlock>,
+}
+
+/// A content block which is still streaming in, which its deltas are parsed for.
+enum ContentBlock {
+    Text,
+    /// Its signature streams in after the thinking, and is sent once the block stops.
+    Thinking {
+        signature: String,
+    },
+    ToolUse,
+    /// Its input streams in as JSON deltas, and is sent once the block stops.
+    ServerToolUse {
+        id: String,
+        name: String,
+        input: String,
+    },
+    /// Such as `redacted_thinking`, which arrive whole.
+    Other,
+}
+
+/// Parses every SSE event completed by the new chunk, keeping any trailing
+/// partial event in the state for the next call. `None` marks the end of the message.
 fn parse_sse_batch(
     chunk: &Result<bytes::Bytes, anyhow::Error>,
-    buffer: &mut String,
-) -> Vec<Result<ChatChunk, ChatStreamError>> {
+    state: &mut StreamState,
+    include_raw: bool,
+) -> Vec<Option<Result<ChatChunk, ChatStreamError>>> {
     let chunk = match chunk {
         Ok(chunk) => chunk,
-        Err(err) => return vec![Err(ChatStreamError::ParseError(anyhow!("{err}")))],
+        Err(err) => return vec![Some(Err(ChatStreamError::ParseError(anyhow!("{err}"))))],
     };
+    state.events.push(chunk);
 
-    let chunk = buffer.drain(..).collect::<String>() + &String::from_utf8_lossy(chunk);
     let mut results = Vec::new();
 
-    let mut saved_next_event: Option<&str> = None;
-    for (event, next_event) in chunk.split("\n\n").tuple_windows() {
-        saved_next_event = Some(next_event);
-        process_event(event, &mut results);
-    }
-
-    if let Some(event) = saved_next_event {
-        if event.ends_with("\n\n") {
-            process_event(event, &mut results);
-        } else {
-            buffer.push_str(event);
-        }
+    while let Some(event) = state.events.next_event() {
+        process_event(event, include_raw, &mut state.blocks, &mut results);
     }
 
     results
 }
 
-fn process_event(event: &str, results: &mut Vec<Result<ChatChunk, ChatStreamError>>) {
+fn process_event(
+    event: &str,
+    include_raw: bool,
+    blocks: &mut HashMap<usize, ContentBlock>,
+    results: &mut Vec<Option<Result<ChatChunk, ChatStreamError>>>,
+) {
+    if include_raw && !event.trim().is_empty() {
+        results.push(Some(Ok(ChatChunk::Raw {
+            provider_event: event.trim().to_owned(),
+        })));
+    }
+
     let parsed = match parse_event(event) {
         Ok(parsed) => parsed,
         Err(_) => return,
     };
 
-    match parsed.delta.r#type.as_str() {
-        "thinking_delta" => {
-            if let Some(text) = parsed.delta.thinking {
-                if !text.is_empty() {
-                    results.push(Ok(ChatChunk::Thinking(text)));
+    match parsed {
+        AnthropicEvent::ContentBlockStart(start) => {
+            let block = start.content_block;
+            let content_block = match block.r#type.as_str() {
+                "text" => {
+                    if let Some(text) = block.text.filter(|this| !this.is_empty()) {
+                        results.push(Some(Ok(ChatChunk::Content(text.into()))));
+                    }
+                    ContentBlock::Text
+                }
+                "thinking" => {
+                    if let Some(thinking) = block.thinking.filter(|this| !this.is_empty()) {
+                        results.push(Some(Ok(ChatChunk::Thinking(thinking.into()))));
+                    }
+                    ContentBlock::Thinking {
+                        signature: block.signature.unwrap_or_default(),
+                    }
+                }
+                "tool_use" => {
+                    results.push(Some(Ok(ChatChunk::ToolCallDelta(ToolCallDelta {
+                        index: start.index,
+                        id: block.id,
+                        name: block.name,
+                        arguments: String::new(),
+                    }))));
+                    ContentBlock::ToolUse
+                }
+                "server_tool_use" => ContentBlock::ServerToolUse {
+                    id: block.id.unwrap_or_default(),
+                    name: block.name.unwrap_or_default(),
+                    input: String::new(),
+                },
+                // Such as `web_search_tool_result`, which arrive whole.
+                r#type if r#type.ends_with("_tool_result") => {
+                    results.push(Some(Ok(ChatChunk::ServerToolResult(ServerToolResult {
+                        tool_use_id: block.tool_use_id,
+                        content: block.content.unwrap_or_default(),
+                    }))));
+                    ContentBlock::Other
+                }
+                _ => ContentBlock::Other,
+            };
+            blocks.insert(start.index, content_block);
+        }
+        AnthropicEvent::ContentBlockStop(stop) => match blocks.remove(&stop.index) {
+            Some(ContentBlock::Thinking { signature }) if !signature.is_empty() => {
+                results.push(Some(Ok(ChatChunk::ThinkingSignature(signature))));
+            }
+            Some(ContentBlock::ServerToolUse { id, name, input }) => {
+                // Tools without parameters stream no input at all.
+                let input = if input.is_empty() {
+                    Ok(serde_json::Value::Object(Default::default()))
+                } else {
+                    serde_json::from_str(&input)
+                };
+                results.push(Some(
+                    input
+                        .map(|input| ChatChunk::ServerToolUse(ServerToolUse { id, name, input }))
+                        .map_err(|err| ChatStreamError::ParseError(anyhow::Error::new(err))),
+                ));
+            }
+            _ => {}
+        },
+        // Deltas are parsed by their type, as a block's start may have been missed,
+        // with the blocks they belong to deciding where JSON and signatures go.
+        AnthropicEvent::ContentBlockDelta(parsed) => {
+            let block = blocks.get_mut(&parsed.index);
+            match (parsed.delta.r#type.as_ref(), block) {
+                ("text_delta", _) if !parsed.delta.text.is_empty() => {
+                    results.push(Some(Ok(ChatChunk::Content(parsed.delta.text.into()))));
+                }
+                ("thinking_delta", _) => {
+                    if let Some(text) = parsed.delta.thinking.filter(|this| !this.is_empty()) {
+                        results.push(Some(Ok(ChatChunk::Thinking(text.into()))));
+                    }
+                }
+                ("signature_delta", Some(ContentBlock::Thinking { signature })) => {
+                    signature.push_str(parsed.delta.signature.as_deref().unwrap_or_default());
                 }
+                ("input_json_delta", Some(ContentBlock::ServerToolUse { input, .. })) => {
+                    input.push_str(parsed.delta.partial_json.as_deref().unwrap_or_default());
+                }
+                ("input_json_delta", _) => {
+                    if let Some(partial_json) = parsed.delta.partial_json {
+                        results.push(Some(Ok(ChatChunk::ToolCallDelta(ToolCallDelta {
+                            index: parsed.index,
+                            arguments: partial_json,
+                            ..Default::default()
+                        }))));
+                    }
+                }
+                // Such as `citations_delta`, which don't add to the reply's text.
+                _ => {}
+            }
+        }
+        AnthropicEvent::MessageStart(start) => {
+            if let Some(usage) = start.message.usage {
+                results.push(Some(Ok(ChatChunk::Usage(Usage {
+                    input_tokens: usage.input_tokens,
+                    ..Default::default()
+                }))));
             }
         }
-        _ => {
-            if !parsed.delta.text.is_empty() {
-                results.push(Ok(ChatChunk::Content(parsed.delta.text)));
+        AnthropicEvent::MessageDelta(delta) => {
+            if let Some(stop_reason) = delta.delta.stop_reason {
+                results.push(Some(Ok(ChatChunk::Finish(finish_reason(stop_reason)))));
+            }
+            if let Some(usage) = delta.usage {
+                results.push(Some(Ok(ChatChunk::Usage(Usage {
+                    input_tokens: usage.input_tokens,
+                    output_tokens: usage.output_tokens,
+                    cached_input_tokens: None,
+                    timing: None,
+                }))));
             }
         }
+        AnthropicEvent::Error(event) => {
+            results.push(Some(Err(ChatStreamError::ProviderError {
+                kind: event.error.r#type,
+                message: event.error.message,
+            })));
+        }
+        AnthropicEvent::MessageStop => results.push(None),
+        AnthropicEvent::Ping | AnthropicEvent::Unknown => {}
+    }
+}
+
+pub(crate) fn finish_reason(stop_reason: String) -> FinishReason {
+    match stop_reason.as_str() {
+        "end_turn" | "stop_sequence" => FinishReason::Stop,
+        "max_tokens" => FinishReason::Length,
+        "tool_use" => FinishReason::ToolCalls,
+        "refusal" => FinishReason::ContentFilter,
+        _ => FinishReason::Other(stop_reason),
     }
 }
 
-fn parse_event(event: &str) -> Result<AnthropicChunkResponse, ParseEventError> {
+fn parse_event(event: &str) -> Result<AnthropicEvent<'_>, ParseEventError> {
     let event_body = match event.split_once("event:") {
         Some((_event_prefix, event_body)) => event_body,
         None => {
@@ -159,17 +649,33 @@ fn parse_event(event: &str) -> Result<AnthropicChunkResponse, ParseEventError> {
     };
 
     match event_name {
-        "content_block_delta" => parse_content_block_delta_event(event_data),
+        "message_start" => parse_event_data(event_data).map(AnthropicEvent::MessageStart),
+        "message_delta" => parse_event_data(event_data).map(AnthropicEvent::MessageDelta),
+        "message_stop" => Ok(AnthropicEvent::MessageStop),
+        "ping" => Ok(AnthropicEvent::Ping),
+        "content_block_start" => {
+            parse_event_data(event_data).map(AnthropicEvent::ContentBlockStart)
+        }
+        "content_block_delta" => {
+            parse_event_data(event_data).map(AnthropicEvent::ContentBlockDelta)
+        }
+        "content_block_stop" => parse_event_data(event_data).map(AnthropicEvent::ContentBlockStop),
+        "error" | "overloaded_error" => Ok(AnthropicEvent::Error(
+            // Still surface the error if its body is malformed, so the stream doesn't just end.
+            parse_event_data(event_data).unwrap_or_else(|_| AnthropicErrorEvent {
+                error: AnthropicError {
+                    r#type: event_name.to_owned(),
+                    message: event_data.trim().to_owned(),
+                },
+            }),
+        )),
 
-        _ => Err(ParseEventError::InvalidBody {
-            reason: anyhow!("Event has invalid name."),
-        }),
+        // Events added to the API later are skipped rather than breaking the stream.
+        _ => Ok(AnthropicEvent::Unknown),
     }
 }
 
-fn parse_content_block_delta_event(
-    event_body: &str,
-) -> Result<AnthropicChunkResponse, ParseEventError> {
+fn parse_event_data<'a, T: Deserialize<'a>>(event_body: &'a str) -> Result<T, ParseEventError> {
     let event_data = event_body
         .split("\n")
         .find_map(|field| {
@@ -183,26 +689,122 @@ fn parse_content_block_delta_event(
         })
         .ok_or_else(|| ParseEventError::MissingField { field: "data" })?;
 
-    serde_json::from_str::<AnthropicChunkResponse>(event_data).map_err(|this| {
-        ParseEventError::InvalidBody {
-            reason: anyhow::Error::new(this),
-        }
+    serde_json::from_str::<T>(event_data).map_err(|this| ParseEventError::InvalidBody {
+        reason: anyhow::Error::new(this),
     })
 }
 
+enum AnthropicEvent<'a> {
+    MessageStart(AnthropicMessageStart),
+    MessageDelta(AnthropicMessageDelta),
+    ContentBlockStart(AnthropicContentBlockStart),
+    ContentBlockDelta(AnthropicChunkResponse<'a>),
+    ContentBlockStop(AnthropicContentBlockStop),
+    Error(AnthropicErrorEvent),
+    MessageStop,
+    /// Sent periodically to keep the connection alive.
+    Ping,
+    /// An event this crate doesn't know about.
+    Unknown,
+}
+
 #[derive(Deserialize, Debug)]
-struct AnthropicChunkResponse {
-    delta: AnthropicChunkResponseDelta,
+struct AnthropicMessageStart {
+    message: AnthropicMessage,
 }
 
 #[derive(Deserialize, Debug)]
-struct AnthropicChunkResponseDelta {
+struct AnthropicMessage {
     #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicMessageDelta {
+    delta: AnthropicMessageDeltaBody,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicMessageDeltaBody {
+    #[serde(default)]
+    stop_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: Option<usize>,
+    #[serde(default)]
+    output_tokens: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicErrorEvent {
+    error: AnthropicError,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicError {
     r#type: String,
     #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicContentBlockStart {
+    index: usize,
+    content_block: AnthropicContentBlock,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicContentBlock {
+    #[serde(default)]
+    r#type: String,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    /// The server tool use a `*_tool_result` block answers.
+    #[serde(default)]
+    tool_use_id: Option<String>,
+    #[serde(default)]
+    content: Option<serde_json::Value>,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    thinking: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicContentBlockStop {
+    index: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicChunkResponse<'a> {
+    #[serde(default)]
+    index: usize,
+    #[serde(borrow)]
+    delta: AnthropicChunkResponseDelta<'a>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnthropicChunkResponseDelta<'a> {
+    /// Borrowed from the event, as it's only matched on.
+    #[serde(borrow, default)]
+    r#type: Cow<'a, str>,
+    #[serde(default)]
     text: String,
     #[serde(default)]
     thinking: Option<String>,
+    #[serde(default)]
+    partial_json: Option<String>,
+    #[serde(default)]
+    signature: Option<String>,
 }
 
 #[derive(Error, Debug)]
@@ -221,6 +823,7 @@ enum ParseEventError {
 mod tests {
     use super::*;
     use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::models::{Audio, Image, SignedThinking, ToolCall};
     use http::StatusCode;
 
     #[tokio::test]
@@ -276,6 +879,28 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_chat_default_headers() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "my-secret-key")
+            .header("Ocp-Apim-Subscription-Key", "gateway-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("claude-3-haiku").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.headers().get("Ocp-Apim-Subscription-Key").unwrap(),
+            "gateway-key"
+        );
+        assert_eq!(request.headers().get("x-api-key").unwrap(), "my-secret-key");
+    }
+
     #[tokio::test]
     async fn test_chat_ignores_non_content_events() {
         let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
@@ -294,6 +919,164 @@ mod tests {
         assert!(matches!(chunk, ChatChunk::Content(ref s) if s == "Hello"));
     }
 
+    #[tokio::test]
+    async fn test_chat_ends_at_message_stop() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "event: ping\ndata: {\"type\": \"ping\"}\n\n\
+             event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n\
+             event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":0}\n\n\
+             event: citations_delta_v2\ndata: {\"type\":\"citations_delta_v2\"}\n\n\
+             event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n\
+             event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\" again\"}}\n\n",
+        ));
+
+        let provider = AnthropicProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("claude-3-haiku").messages(messages);
+
+        let response = provider.chat(&options).await.unwrap();
+        let chunks = response.collect::<Vec<_>>().await;
+
+        assert!(matches!(
+            chunks.as_slice(),
+            [Ok(ChatChunk::Content(content))] if content == "Hello"
+        ));
+    }
+
+    #[test]
+    fn test_parse_sse_batch_across_chunks() {
+        let body = "event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Grüße\"}}\n\n";
+        // Splits inside the "ü", before the event is complete.
+        let split = body.find('ü').unwrap() + 1;
+
+        let mut state = StreamState::default();
+        let first = parse_sse_batch(
+            &Ok(Bytes::copy_from_slice(&body.as_bytes()[..split])),
+            &mut state,
+            false,
+        );
+        let second = parse_sse_batch(
+            &Ok(Bytes::copy_from_slice(&body.as_bytes()[split..])),
+            &mut state,
+            false,
+        );
+
+        assert!(first.is_empty());
+        assert!(matches!(
+            second.as_slice(),
+            [Some(Ok(ChatChunk::Content(content)))] if content == "Grüße"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_include_raw() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n\
+             event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        ));
+
+        let provider = AnthropicProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("claude-3-haiku")
+            .messages(messages)
+            .include_raw(true);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = response.next().await {
+            chunks.push(chunk.unwrap());
+        }
+
+        assert!(matches!(
+            chunks.as_slice(),
+            [
+                ChatChunk::Raw { provider_event: first },
+                ChatChunk::Content(content),
+                ChatChunk::Raw { provider_event: second },
+            ] if first.starts_with("event: content_block_delta")
+                && content == "Hello"
+                && second == "event: message_stop\ndata: {\"type\":\"message_stop\"}"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_finish_reason_and_usage() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_01\",\"usage\":{\"input_tokens\":25,\"output_tokens\":1}}}\n\n\
+             event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n\
+             event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"max_tokens\",\"stop_sequence\":null},\"usage\":{\"output_tokens\":15}}\n\n\
+             event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        ));
+
+        let provider = AnthropicProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("claude-3-haiku").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Hello");
+        assert_eq!(result.finish_reason, Some(FinishReason::Length));
+        let usage = result.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(25));
+        assert_eq!(usage.output_tokens, Some(15));
+    }
+
+    #[test]
+    fn test_finish_reason_mapping() {
+        assert_eq!(finish_reason("end_turn".into()), FinishReason::Stop);
+        assert_eq!(finish_reason("stop_sequence".into()), FinishReason::Stop);
+        assert_eq!(finish_reason("tool_use".into()), FinishReason::ToolCalls);
+        assert_eq!(
+            finish_reason("pause_turn".into()),
+            FinishReason::Other("pause_turn".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_error_event() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n\
+             event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n",
+        ));
+
+        let provider = AnthropicProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("claude-3-haiku").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await;
+
+        let Err(err) = result else {
+            panic!("expected the stream to fail");
+        };
+        assert!(err.is_retryable());
+        assert!(matches!(
+            err,
+            ChatStreamError::ProviderError { ref kind, ref message }
+                if kind == "overloaded_error" && message == "Overloaded"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_malformed_error_event() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body("event: overloaded_error\ndata: Overloaded\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("claude-3-haiku").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let chunk = response.next().await.unwrap();
+
+        assert!(matches!(
+            chunk,
+            Err(ChatStreamError::ProviderError { ref kind, .. }) if kind == "overloaded_error"
+        ));
+    }
+
     #[tokio::test]
     async fn test_chat_with_thinking() {
         let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
@@ -314,4 +1097,563 @@ mod tests {
         assert_eq!(result.thinking.as_deref(), Some("Let me reason..."));
         assert_eq!(result.content, "The answer is 42.");
     }
+
+    #[tokio::test]
+    async fn test_chat_with_tool_calls() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n\
+             event: content_block_delta\ndata: {\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Checking.\"}}\n\n\
+             event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_01\",\"name\":\"get_weather\",\"input\":{}}}\n\n\
+             event: content_block_delta\ndata: {\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"city\\\": \"}}\n\n\
+             event: content_block_delta\ndata: {\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"Paris\\\"}\"}}\n\n",
+        ));
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &["What's the weather in Paris?".into()];
+        let tools = &[Tool::new(
+            "get_weather",
+            serde_json::json!({ "type": "object", "properties": { "city": { "type": "string" } } }),
+        )
+        .description("Gets the current weather")];
+        let options = ChatOptions::new("claude-3-haiku")
+            .messages(messages)
+            .tools(tools);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Checking.");
+        assert_eq!(result.tool_calls.len(), 1);
+        assert_eq!(result.tool_calls[0].id, "toolu_01");
+        assert_eq!(result.tool_calls[0].name, "get_weather");
+        assert_eq!(
+            result.tool_calls[0].arguments_json().unwrap(),
+            serde_json::json!({ "city": "Paris" })
+        );
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["tools"][0]["name"], "get_weather");
+        assert_eq!(body["tools"][0]["description"], "Gets the current weather");
+        assert_eq!(body["tools"][0]["input_schema"]["type"], "object");
+        assert!(body.get("tool_choice").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_tool_choice() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK));
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &["What's the weather in Paris?".into()];
+        let tools = &[Tool::new(
+            "get_weather",
+            serde_json::json!({ "type": "object" }),
+        )];
+        let options = ChatOptions::new("claude-3-haiku")
+            .messages(messages)
+            .tools(tools)
+            .tool_choice(ToolChoice::Required)
+            .parallel_tool_calls(false);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["tool_choice"],
+            serde_json::json!({ "type": "any", "disable_parallel_tool_use": true })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_content_block_lifecycle() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "event: content_block_start\ndata: {\"index\":0,\"content_block\":{\"type\":\"thinking\",\"thinking\":\"\",\"signature\":\"\"}}\n\n\
+             event: content_block_delta\ndata: {\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"I should check.\"}}\n\n\
+             event: content_block_delta\ndata: {\"index\":0,\"delta\":{\"type\":\"signature_delta\",\"signature\":\"EqQBCgIYAh\"}}\n\n\
+             event: content_block_delta\ndata: {\"index\":0,\"delta\":{\"type\":\"signature_delta\",\"signature\":\"IaQC1EA==\"}}\n\n\
+             event: content_block_stop\ndata: {\"index\":0}\n\n\
+             event: content_block_start\ndata: {\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_01\",\"name\":\"get_weather\",\"input\":{}}}\n\n\
+             event: content_block_delta\ndata: {\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"city\\\": \\\"Paris\\\"}\"}}\n\n\
+             event: content_block_stop\ndata: {\"index\":1}\n\n\
+             event: content_block_start\ndata: {\"index\":2,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n\
+             event: content_block_delta\ndata: {\"index\":2,\"delta\":{\"type\":\"citations_delta\",\"citation\":{\"type\":\"char_location\"}}}\n\n\
+             event: content_block_delta\ndata: {\"index\":2,\"delta\":{\"type\":\"text_delta\",\"text\":\"Checking.\"}}\n\n\
+             event: content_block_stop\ndata: {\"index\":2}\n\n\
+             event: message_stop\ndata: {}\n\n",
+        ));
+
+        let provider = AnthropicProvider::new(client, "test-api-key");
+        let messages = &["What's the weather in Paris?".into()];
+        let options = ChatOptions::new("claude-sonnet-4-5").messages(messages);
+
+        let chunks = provider
+            .chat(&options)
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            chunks,
+            [
+                ChatChunk::Thinking("I should check.".into()),
+                ChatChunk::ThinkingSignature("EqQBCgIYAhIaQC1EA==".to_owned()),
+                ChatChunk::ToolCallDelta(ToolCallDelta {
+                    index: 1,
+                    id: Some("toolu_01".to_owned()),
+                    name: Some("get_weather".to_owned()),
+                    arguments: String::new(),
+                }),
+                ChatChunk::ToolCallDelta(ToolCallDelta {
+                    index: 1,
+                    arguments: "{\"city\": \"Paris\"}".to_owned(),
+                    ..Default::default()
+                }),
+                ChatChunk::Content("Checking.".into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_tool_results() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Sunny.\"}}\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &[
+            Message::user("What's the weather in Paris?"),
+            Message::assistant("Checking.").tool_calls(vec![ToolCall::new(
+                "toolu_01",
+                "get_weather",
+                "{\"city\":\"Paris\"}",
+            )]),
+            Message::tool("toolu_01", "sunny"),
+        ];
+        let options = ChatOptions::new("claude-3-haiku").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        let messages = &body["messages"];
+
+        assert_eq!(messages[1]["content"][0]["type"], "text");
+        assert_eq!(messages[1]["content"][1]["type"], "tool_use");
+        assert_eq!(messages[1]["content"][1]["input"]["city"], "Paris");
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"][0]["type"], "tool_result");
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "toolu_01");
+        assert_eq!(messages[2]["content"][0]["content"], "sunny");
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_images_as_content_blocks() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"A cat.\"}}\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &[Message::user("What's this?").part(Image::new(b"png", "image/png"))];
+        let options = ChatOptions::new("claude-3-haiku").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"],
+            serde_json::json!([
+                { "type": "text", "text": "What's this?" },
+                {
+                    "type": "image",
+                    "source": { "type": "base64", "media_type": "image/png", "data": "cG5n" },
+                },
+            ])
+        );
+
+        let messages = &[Message::user("").part(Audio::new(b"wav", "wav"))];
+        let options = ChatOptions::new("claude-3-haiku").messages(messages);
+        assert!(matches!(
+            provider.chat(&options).await,
+            Err(ChatError::Unsupported {
+                feature: "audio_input"
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_merges_consecutive_roles() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Sunny.\"}}\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &[
+            Message::user("What's the weather in Paris and Rome?"),
+            Message::assistant("").tool_calls(vec![
+                ToolCall::new("toolu_01", "get_weather", "{\"city\":\"Paris\"}"),
+                ToolCall::new("toolu_02", "get_weather", "{\"city\":\"Rome\"}"),
+            ]),
+            Message::tool("toolu_01", "sunny"),
+            Message::tool("toolu_02", "rainy"),
+            Message::user("And in Oslo?"),
+        ];
+        let options = ChatOptions::new("claude-3-haiku").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        let messages = body["messages"].as_array().unwrap();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(
+            messages[2]["content"],
+            serde_json::json!([
+                { "type": "tool_result", "tool_use_id": "toolu_01", "content": "sunny" },
+                { "type": "tool_result", "tool_use_id": "toolu_02", "content": "rainy" },
+                { "type": "text", "text": "And in Oslo?" },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_signed_thinking() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Sunny.\"}}\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &[
+            Message::user("What's the weather in Paris?"),
+            Message::assistant("")
+                .thinking(vec![SignedThinking::new(
+                    "I should check.",
+                    "EqQBCgIYAhIaQC1EA==",
+                )])
+                .tool_calls(vec![ToolCall::new(
+                    "toolu_01",
+                    "get_weather",
+                    "{\"city\":\"Paris\"}",
+                )]),
+            Message::tool("toolu_01", "sunny"),
+        ];
+        let options = ChatOptions::new("claude-sonnet-4-5")
+            .messages(messages)
+            .thinking(Thinking::BudgetTokens(2048));
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        let content = &body["messages"][1]["content"];
+
+        assert_eq!(
+            content[0],
+            serde_json::json!({
+                "type": "thinking",
+                "thinking": "I should check.",
+                "signature": "EqQBCgIYAhIaQC1EA==",
+            })
+        );
+        assert_eq!(content[1]["type"], "tool_use");
+    }
+
+    #[tokio::test]
+    async fn test_chat_system_prompt() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &[Message::system("Answer in French."), Message::user("Hi")];
+        let options = ChatOptions::new("claude-3-haiku")
+            .system("Be concise.")
+            .messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+        assert_eq!(body["system"], "Be concise.\nAnswer in French.");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(body["messages"][0]["role"], "user");
+    }
+
+    #[tokio::test]
+    async fn test_chat_extra_fields() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("claude-3-haiku")
+            .messages(messages)
+            .extra("metadata", serde_json::json!({ "user_id": "user-1234" }));
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["metadata"]["user_id"], "user-1234");
+    }
+
+    #[tokio::test]
+    async fn test_chat_extension() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("claude-3-haiku")
+            .messages(messages)
+            .extension(
+                AnthropicChatExt::new()
+                    .top_k(40)
+                    .stop_sequences(["END"])
+                    .user_id("user-1234"),
+            );
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["top_k"], 40);
+        assert_eq!(body["stop_sequences"], serde_json::json!(["END"]));
+        assert_eq!(body["metadata"]["user_id"], "user-1234");
+        assert!(body.get("temperature").is_none());
+    }
+
+    #[test]
+    fn test_request_body_extension_invalid() {
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("claude-3-haiku")
+            .messages(messages)
+            .extension(AnthropicChatExt::new().temperature(1.5));
+
+        assert!(matches!(
+            request_body(&options),
+            Err(ChatError::InvalidOptions {
+                field: "temperature",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_request_body_default_max_tokens() {
+        let messages = &["Hi".into()];
+        let cases = [
+            (ChatOptions::new("claude-opus-4-20250514"), 32000),
+            (
+                ChatOptions::new("claude-opus-4-20250514").max_tokens(100),
+                100,
+            ),
+            (ChatOptions::new("claude-unknown"), 4096),
+            (
+                ChatOptions::new("claude-unknown").thinking(Thinking::budget_tokens(2048)),
+                6144,
+            ),
+        ];
+
+        for (options, expected) in cases {
+            let body = request_body(&options.messages(messages)).unwrap();
+            let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(body["max_tokens"], expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_thinking_budget_exceeds_max_tokens() {
+        let cases = [
+            (
+                ChatOptions::new("claude-sonnet-4-20250514")
+                    .max_tokens(2048)
+                    .thinking(Thinking::budget_tokens(2048)),
+                "max_tokens",
+            ),
+            (
+                ChatOptions::new("claude-opus-4-20250514").thinking(Thinking::budget_tokens(40000)),
+                "thinking",
+            ),
+        ];
+
+        for (options, expected) in cases {
+            // No response is queued, so a request would fail differently.
+            let provider = AnthropicProvider::new(MockHttpClient::new(), "test-api-key");
+            let messages = &["Hi".into()];
+
+            assert!(matches!(
+                provider.chat(&options.messages(messages)).await,
+                Err(ChatError::InvalidOptions { field: option, .. }) if option == expected
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_web_search() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "event: content_block_start\ndata: {\"index\":0,\"content_block\":{\"type\":\"server_tool_use\",\"id\":\"srvtoolu_1\",\"name\":\"web_search\",\"input\":{}}}\n\n\
+             event: content_block_delta\ndata: {\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"query\\\": \"}}\n\n\
+             event: content_block_delta\ndata: {\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"rust\\\"}\"}}\n\n\
+             event: content_block_stop\ndata: {\"index\":0}\n\n\
+             event: content_block_start\ndata: {\"index\":1,\"content_block\":{\"type\":\"web_search_tool_result\",\"tool_use_id\":\"srvtoolu_1\",\"content\":[{\"type\":\"web_search_result\",\"url\":\"https://www.rust-lang.org\"}]}}\n\n\
+             event: content_block_stop\ndata: {\"index\":1}\n\n\
+             event: content_block_delta\ndata: {\"index\":2,\"delta\":{\"type\":\"text_delta\",\"text\":\"Rust is a language.\"}}\n\n",
+        ));
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &["What is Rust?".into()];
+        let options = ChatOptions::new("claude-sonnet-4-5")
+            .messages(messages)
+            .server_tool(ServerTool::WebSearch { max_uses: Some(3) });
+
+        let chat = provider
+            .chat(&options)
+            .await
+            .unwrap()
+            .aggregate()
+            .await
+            .unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["tools"],
+            serde_json::json!([
+                { "type": "web_search_20250305", "name": "web_search", "max_uses": 3 }
+            ])
+        );
+
+        assert_eq!(
+            chat.server_tool_uses,
+            [ServerToolUse {
+                id: "srvtoolu_1".to_owned(),
+                name: "web_search".to_owned(),
+                input: serde_json::json!({ "query": "rust" }),
+            }]
+        );
+        assert_eq!(
+            chat.server_tool_results,
+            [ServerToolResult {
+                tool_use_id: Some("srvtoolu_1".to_owned()),
+                content: serde_json::json!([
+                    { "type": "web_search_result", "url": "https://www.rust-lang.org" }
+                ]),
+            }]
+        );
+        assert!(chat.tool_calls.is_empty());
+        assert_eq!(chat.content, "Rust is a language.");
+    }
+
+    #[tokio::test]
+    async fn test_chat_prefill() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"\\\"a\\\": 1}\"}}\n\n"),
+        );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let messages = &["Reply with a JSON object.".into()];
+        let options = ChatOptions::new("claude-3-haiku")
+            .messages(messages)
+            .prefill("{ ");
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+        assert_eq!(result.content, "\"a\": 1}");
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+        assert_eq!(body["messages"][1]["role"], "assistant");
+        assert_eq!(body["messages"][1]["content"], "{");
+    }
+
+    #[tokio::test]
+    async fn test_chat_thinking_body() {
+        let cases = [
+            (
+                "claude-sonnet-4-6",
+                Thinking::effort("high"),
+                serde_json::json!({ "type": "adaptive", "effort": "high" }),
+            ),
+            (
+                "claude-sonnet-4-20250514",
+                Thinking::budget_tokens(2048),
+                serde_json::json!({ "type": "enabled", "budget_tokens": 2048 }),
+            ),
+            (
+                "claude-sonnet-4-20250514",
+                Thinking::enabled(),
+                serde_json::json!({ "type": "enabled", "budget_tokens": 10000 }),
+            ),
+            (
+                "claude-opus-4-6",
+                Thinking::enabled(),
+                serde_json::json!({ "type": "adaptive" }),
+            ),
+        ];
+
+        for (model, thinking, expected) in cases {
+            let client = MockHttpClient::new().with_response(
+                MockResponse::new(StatusCode::OK)
+                    .body("event: content_block_delta\ndata: {\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n"),
+            );
+
+            let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+            let messages = &["Hi".into()];
+            let options = ChatOptions::new(model)
+                .messages(messages)
+                .thinking(thinking);
+
+            provider.chat(&options).await.unwrap();
+
+            let request = client.last_request().unwrap();
+            let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+            assert_eq!(body["thinking"], expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_thinking_unsupported() {
+        let cases = [
+            ("claude-sonnet-4-5-20250929", Thinking::effort("high")),
+            ("claude-opus-4-6", Thinking::budget_tokens(2048)),
+            ("claude-sonnet-4-6", Thinking::budget_tokens(512)),
+        ];
+
+        for (model, thinking) in cases {
+            let client = MockHttpClient::new();
+            let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+            let messages = &["Hi".into()];
+            let options = ChatOptions::new(model)
+                .messages(messages)
+                .thinking(thinking);
+
+            let result = provider.chat(&options).await;
+
+            assert!(matches!(
+                result,
+                Err(ChatError::InvalidOptions { field: "thinking", reason }) if reason.starts_with(model)
+            ));
+            assert!(client.last_request().is_none());
+        }
+    }
 }
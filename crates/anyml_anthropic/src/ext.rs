@@ -0,0 +1,82 @@
+use std::ops::RangeInclusive;
+
+use anyml_core::providers::chat::ChatError;
+use serde::Serialize;
+
+/// Anthropic-specific chat options, attached with
+/// [`ChatOptions::extension`](anyml_core::providers::chat::ChatOptions::extension).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AnthropicChatExt {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub stop_sequences: Vec<String>,
+    pub user_id: Option<String>,
+}
+
+impl AnthropicChatExt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the sampling temperature, between `0.0` and `1.0`.
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Sets the nucleus sampling probability mass, between `0.0` and `1.0`.
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Only samples from the `top_k` most likely tokens.
+    pub fn top_k(mut self, top_k: u32) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    /// Sets the sequences which stop generation when produced.
+    pub fn stop_sequences<I, S>(mut self, stop_sequences: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stop_sequences = stop_sequences.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Identifies the end user, sent as `metadata.user_id`.
+    pub fn user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ChatError> {
+        check_range("temperature", self.temperature, 0.0..=1.0)?;
+        check_range("top_p", self.top_p, 0.0..=1.0)?;
+        if self.top_k == Some(0) {
+            return Err(ChatError::InvalidOptions {
+                field: "top_k",
+                reason: "it must be at least 1".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn check_range(
+    field: &'static str,
+    value: Option<f32>,
+    range: RangeInclusive<f32>,
+) -> Result<(), ChatError> {
+    match value {
+        Some(value) if !range.contains(&value) => Err(ChatError::InvalidOptions {
+            field,
+            reason: format!("{value} is outside of {range:?}"),
+        }),
+        _ => Ok(()),
+    }
+}
@@ -1,16 +1,31 @@
 use anyhttp::HttpClient;
+use anyml_core::providers::{ApiKeyProvider, DefaultHeaders};
 use secrecy::SecretString;
 use std::borrow::Cow;
 
+mod batches;
 mod chat;
+mod error;
+mod ext;
 mod list_models;
 
+pub use batches::{
+    BatchMessage, BatchOutcome, BatchRequest, BatchResult, BatchResults, ContentBlock,
+    MessageBatch, ProcessingStatus, RequestCounts,
+};
+pub use error::BatchError;
+pub use ext::AnthropicChatExt;
+
 const DEFAULT_URL: &str = "https://api.anthropic.com";
+const DEFAULT_CHAT_PATH: &str = "/v1/messages";
 
 pub struct AnthropicProvider<C: HttpClient> {
     client: C,
     url: Cow<'static, str>,
-    api_key: SecretString,
+    chat_path: Cow<'static, str>,
+    default_model: Option<Cow<'static, str>>,
+    headers: DefaultHeaders,
+    api_key: Box<dyn ApiKeyProvider>,
 }
 
 impl<C: HttpClient> AnthropicProvider<C> {
@@ -18,7 +33,10 @@ impl<C: HttpClient> AnthropicProvider<C> {
         Self {
             client,
             url: Cow::Borrowed(DEFAULT_URL),
-            api_key: api_key.into(),
+            chat_path: Cow::Borrowed(DEFAULT_CHAT_PATH),
+            default_model: None,
+            headers: DefaultHeaders::new(),
+            api_key: Box::new(api_key.into()),
         }
     }
 
@@ -27,8 +45,39 @@ impl<C: HttpClient> AnthropicProvider<C> {
         self
     }
 
+    /// Sets the path chat requests are sent to, after `url`. Defaults to `/v1/messages`.
+    ///
+    /// It may end with a query string, for gateways which need one (e.g. `?api-version=`).
+    pub fn chat_path(mut self, path: impl Into<Cow<'static, str>>) -> Self {
+        self.chat_path = path.into();
+        self
+    }
+
+    /// Sets the model chats are sent to when their options leave it to the provider,
+    /// as with [`ChatOptions::default_model`](anyml_core::providers::chat::ChatOptions::default_model).
+    pub fn with_default_model(mut self, model: impl Into<Cow<'static, str>>) -> Self {
+        self.default_model = Some(model.into());
+        self
+    }
+
+    /// Adds a header sent with every request, e.g. for a proxy or gateway.
+    pub fn header(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
     pub fn api_key(mut self, api_key: impl Into<SecretString>) -> Self {
-        self.api_key = api_key.into();
+        self.api_key = Box::new(api_key.into());
+        self
+    }
+
+    /// Fetches the API key before each request, for keys which rotate or expire.
+    pub fn api_key_provider(mut self, provider: impl ApiKeyProvider + 'static) -> Self {
+        self.api_key = Box::new(provider);
         self
     }
 }
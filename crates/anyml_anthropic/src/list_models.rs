@@ -1,11 +1,14 @@
 use anyhow::anyhow;
 use anyhttp::HttpClient;
 use anyml_core::{
-    models::{Model, ThinkingBudget, ThinkingModes},
-    providers::list_models::{ListModelsError, ListModelsProvider},
+    models::{Model, ModelCapabilities, ThinkingBudget, ThinkingModes},
+    providers::{
+        HealthCheckProvider, HealthStatus,
+        list_models::{ListModelsError, ListModelsProvider},
+    },
 };
 use bytes::Bytes;
-use http::Request;
+use http::{Request, StatusCode};
 use phf::phf_map;
 use secrecy::ExposeSecret;
 use serde::Deserialize;
@@ -14,7 +17,7 @@ use crate::AnthropicProvider;
 
 type StaticThinkingModes = ThinkingModes<&'static [&'static str]>;
 
-static THINKING_MODELS: phf::Map<&'static str, StaticThinkingModes> = phf_map! {
+pub(crate) static THINKING_MODELS: phf::Map<&'static str, StaticThinkingModes> = phf_map! {
     "claude-3-7-sonnet-20250219" => StaticThinkingModes { modes: &[], budget: Some(ThinkingBudget { min: 1024, max: 128000 }) },
     "claude-sonnet-4-20250514" => StaticThinkingModes { modes: &[], budget: Some(ThinkingBudget { min: 1024, max: 128000 }) },
     "claude-sonnet-4-5-20250929" => StaticThinkingModes { modes: &[], budget: Some(ThinkingBudget { min: 1024, max: 128000 }) },
@@ -26,12 +29,41 @@ static THINKING_MODELS: phf::Map<&'static str, StaticThinkingModes> = phf_map! {
     "claude-haiku-4-5-20251001" => StaticThinkingModes { modes: &[], budget: Some(ThinkingBudget { min: 1024, max: 128000 }) },
 };
 
-#[async_trait::async_trait]
+/// The most tokens each model can generate, sent as `max_tokens` when the request
+/// doesn't set one, as the API requires it.
+pub(crate) static MAX_OUTPUT_TOKENS: phf::Map<&'static str, usize> = phf_map! {
+    "claude-3-haiku-20240307" => 4096,
+    "claude-3-5-haiku-20241022" => 8192,
+    "claude-3-7-sonnet-20250219" => 64000,
+    "claude-sonnet-4-20250514" => 64000,
+    "claude-sonnet-4-5-20250929" => 64000,
+    "claude-sonnet-4-6" => 64000,
+    "claude-opus-4-20250514" => 32000,
+    "claude-opus-4-1-20250805" => 32000,
+    "claude-opus-4-5-20251101" => 64000,
+    "claude-opus-4-6" => 128000,
+    "claude-haiku-4-5-20251001" => 64000,
+};
+
+/// The `max_tokens` sent for models missing from [`MAX_OUTPUT_TOKENS`], which every
+/// model supports.
+pub(crate) const DEFAULT_MAX_OUTPUT_TOKENS: usize = 4096;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl<C: HttpClient> ListModelsProvider for AnthropicProvider<C> {
     async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
-        let request = Request::get(format!("{}/v1/models", self.url))
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(ListModelsError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/v1/models", self.url)))
             .header("anthropic-version", "2023-06-01")
-            .header("x-api-key", self.api_key.expose_secret())
+            .header("x-api-key", api_key.expose_secret())
             .body(Vec::new())
             .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
 
@@ -63,25 +95,87 @@ impl<C: HttpClient> ListModelsProvider for AnthropicProvider<C> {
         let models = anthropic_response
             .data
             .into_iter()
-            .map(|m| {
-                let thinking =
-                    THINKING_MODELS
-                        .get(m.id.as_str())
-                        .map(|s| ThinkingModes {
-                            modes: s.modes.iter().map(|s| (*s).into()).collect(),
-                            budget: s.budget,
-                        });
-                Model {
-                    id: m.id,
-                    parameters: None,
-                    quantization: None,
-                    thinking,
-                }
-            })
+            .map(AnthropicModel::into_model)
             .collect();
 
         Ok(models)
     }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(ListModelsError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/v1/models/{id}", self.url)))
+            .header("anthropic-version", "2023-06-01")
+            .header("x-api-key", api_key.expose_secret())
+            .body(Vec::new())
+            .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ListModelsError::NotFound { id: id.to_owned() });
+        }
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(ListModelsError::ResponseFetchFailed(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        let model: AnthropicModel = serde_json::from_slice(&body)
+            .map_err(|e| ListModelsError::ParseError(anyhow::Error::new(e)))?;
+
+        Ok(model.into_model())
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> HealthCheckProvider for AnthropicProvider<C> {
+    /// Lists a single model, which is free and checks the API key.
+    async fn health(&self) -> HealthStatus {
+        let api_key = match self.api_key.current_key().await {
+            Ok(api_key) => api_key,
+            Err(err) => {
+                return HealthStatus::Unhealthy {
+                    reason: format!("{err:#}"),
+                };
+            }
+        };
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/v1/models?limit=1", self.url)))
+            .header("anthropic-version", "2023-06-01")
+            .header("x-api-key", api_key.expose_secret())
+            .body(Vec::new());
+
+        match request {
+            Ok(request) => HealthStatus::probe(&self.client, request).await,
+            Err(err) => HealthStatus::Unhealthy {
+                reason: err.to_string(),
+            },
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -94,6 +188,32 @@ struct AnthropicModel {
     id: String,
 }
 
+impl AnthropicModel {
+    fn into_model(self) -> Model {
+        let thinking = THINKING_MODELS
+            .get(self.id.as_str())
+            .map(|s| ThinkingModes {
+                modes: s.modes.iter().map(|s| (*s).into()).collect(),
+                budget: s.budget,
+            });
+        // Every model served by the API accepts images and tools.
+        let capabilities = ModelCapabilities {
+            vision: true,
+            tool_use: true,
+            thinking: thinking.is_some(),
+            ..Default::default()
+        };
+        Model {
+            id: self.id,
+            parameters: None,
+            quantization: None,
+            thinking,
+            capabilities,
+            ..Default::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,6 +235,51 @@ mod tests {
         assert_eq!(models[1].id, "claude-3-sonnet");
     }
 
+    #[tokio::test]
+    async fn test_get_model() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{"type":"model","id":"claude-sonnet-4-6","display_name":"Claude Sonnet 4.6"}"#,
+        ));
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+        let model = provider.get_model("claude-sonnet-4-6").await.unwrap();
+
+        assert_eq!(model.id, "claude-sonnet-4-6");
+        assert!(model.capabilities.thinking);
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://api.anthropic.com/v1/models/claude-sonnet-4-6"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_model_not_found() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::NOT_FOUND).body("not_found_error"));
+
+        let provider = AnthropicProvider::new(client, "test-api-key");
+        let result = provider.get_model("claude-unknown").await;
+
+        assert!(matches!(result, Err(ListModelsError::NotFound { id }) if id == "claude-unknown"));
+    }
+
+    #[tokio::test]
+    async fn test_list_models_capabilities() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body(r#"{"data":[{"id":"claude-sonnet-4-6"},{"id":"claude-3-haiku-20240307"}]}"#),
+        );
+
+        let provider = AnthropicProvider::new(client, "test-api-key");
+        let models = provider.list_models().await.unwrap();
+
+        assert!(models[0].capabilities.vision);
+        assert!(models[0].capabilities.tool_use);
+        assert!(models[0].capabilities.thinking);
+        assert!(!models[1].capabilities.thinking);
+    }
+
     #[tokio::test]
     async fn test_list_models_empty() {
         let client = MockHttpClient::new()
@@ -164,4 +329,26 @@ mod tests {
             "2023-06-01"
         );
     }
+
+    #[tokio::test]
+    async fn test_health() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"data":[]}"#))
+            .with_response(
+                MockResponse::new(StatusCode::UNAUTHORIZED)
+                    .body(r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#),
+            );
+
+        let provider = AnthropicProvider::new(client.clone(), "test-api-key");
+
+        assert_eq!(provider.health().await, HealthStatus::Healthy);
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "https://api.anthropic.com/v1/models?limit=1");
+        assert_eq!(request.headers().get("x-api-key").unwrap(), "test-api-key");
+
+        assert!(matches!(
+            provider.health().await,
+            HealthStatus::Unhealthy { reason } if reason.contains("authentication_error")
+        ));
+    }
 }
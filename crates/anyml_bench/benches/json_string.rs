@@ -0,0 +1,109 @@
+//! Compares building a typical chat request body with `json_string!` against
+//! `serde_json`, both through its `json!` macro and through a derived `Serialize`.
+
+use std::hint::black_box;
+
+use anyml_macros::json_string;
+use criterion::{Criterion, criterion_group, criterion_main};
+use serde::Serialize;
+use serde_json::json;
+
+#[derive(Serialize)]
+struct Message<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Serialize)]
+struct Body<'a> {
+    model: &'a str,
+    messages: &'a [Message<'a>],
+    stream: bool,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<&'a str>,
+}
+
+const MODEL: &str = "gpt-4o";
+const MESSAGES: &[Message] = &[
+    Message {
+        role: "system",
+        content: "You are a helpful assistant. Answer concisely.",
+    },
+    Message {
+        role: "user",
+        content: "What's the difference between a \"process\" and a thread?",
+    },
+    Message {
+        role: "assistant",
+        content: "A process has its own address space, while threads share one.\nThreads are cheaper to create.",
+    },
+    Message {
+        role: "user",
+        content: "And which should I use for a web server?",
+    },
+];
+
+fn json_string(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("chat body");
+    let temperature = Some(0.7);
+    let effort: Option<&str> = None;
+
+    group.bench_function("json_string!", |bench| {
+        bench.iter(|| {
+            let (temperature, effort) = black_box((temperature, effort));
+            let body: String = json_string! {
+                "model": MODEL,
+                "messages": @serde black_box(MESSAGES),
+                "stream": true,
+                "max_tokens": 1024,
+                "temperature"?: temperature,
+                if let Some(effort) = effort {
+                    "reasoning_effort": effort
+                }
+            };
+            body
+        })
+    });
+
+    group.bench_function("serde_json::json!", |bench| {
+        bench.iter(|| {
+            let (temperature, effort) = black_box((temperature, effort));
+            let mut body = json!({
+                "model": MODEL,
+                "messages": black_box(MESSAGES),
+                "stream": true,
+                "max_tokens": 1024,
+            });
+            if let Some(temperature) = temperature {
+                body["temperature"] = json!(temperature);
+            }
+            if let Some(effort) = effort {
+                body["reasoning_effort"] = json!(effort);
+            }
+            serde_json::to_string(&body).unwrap()
+        })
+    });
+
+    group.bench_function("#[derive(Serialize)]", |bench| {
+        bench.iter(|| {
+            let (temperature, effort) = black_box((temperature, effort));
+            serde_json::to_string(&Body {
+                model: MODEL,
+                messages: black_box(MESSAGES),
+                stream: true,
+                max_tokens: 1024,
+                temperature,
+                reasoning_effort: effort,
+            })
+            .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, json_string);
+criterion_main!(benches);
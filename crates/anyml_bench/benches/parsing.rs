@@ -0,0 +1,78 @@
+//! Measures how quickly each provider parses a streamed chat reply, from the bytes of
+//! the response to its chunks, through a mock HTTP client.
+
+use anyhttp::mock::{MockHttpClient, MockResponse};
+use anyml_anthropic::AnthropicProvider;
+use anyml_bench::{anthropic_sse, ollama_ndjson, openai_sse};
+use anyml_core::providers::chat::{ChatOptions, ChatProvider};
+use anyml_groq::GroqProvider;
+use anyml_ollama::OllamaProvider;
+use anyml_openai::OpenAiProvider;
+use anyml_openrouter::OpenRouterProvider;
+use criterion::{
+    BatchSize, BenchmarkGroup, Criterion, Throughput, criterion_group, criterion_main,
+    measurement::WallTime,
+};
+use futures::{StreamExt, executor::block_on};
+use http::StatusCode;
+
+/// The number of content deltas in each reply.
+const DELTAS: usize = 1_000;
+
+fn parsing(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("parse stream");
+
+    bench_provider(&mut group, "anthropic", anthropic_sse(DELTAS), |client| {
+        AnthropicProvider::new(client, "key")
+    });
+    bench_provider(&mut group, "openai", openai_sse(DELTAS), |client| {
+        OpenAiProvider::new(client, "key")
+    });
+    bench_provider(&mut group, "groq", openai_sse(DELTAS), |client| {
+        GroqProvider::new(client, "key")
+    });
+    bench_provider(&mut group, "openrouter", openai_sse(DELTAS), |client| {
+        OpenRouterProvider::new(client, "key")
+    });
+    bench_provider(
+        &mut group,
+        "ollama",
+        ollama_ndjson(DELTAS),
+        OllamaProvider::new,
+    );
+
+    group.finish();
+}
+
+fn bench_provider<P: ChatProvider>(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    name: &str,
+    body: String,
+    provider: impl Fn(MockHttpClient) -> P,
+) {
+    let messages = &["Hi".into()];
+    let options = ChatOptions::new("model").messages(messages);
+
+    group.throughput(Throughput::Bytes(body.len() as u64));
+    group.bench_function(name, |bench| {
+        bench.iter_batched(
+            || {
+                provider(
+                    MockHttpClient::new()
+                        .with_response(MockResponse::new(StatusCode::OK).body(body.clone())),
+                )
+            },
+            |provider| {
+                block_on(async {
+                    let response = provider.chat(&options).await.unwrap();
+                    let chunks = response.map(Result::unwrap).count().await;
+                    assert!(chunks >= DELTAS);
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, parsing);
+criterion_main!(benches);
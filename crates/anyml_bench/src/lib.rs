@@ -0,0 +1,69 @@
+//! Fixtures for the benchmarks, which live in `benches/`.
+//!
+//! Run them with `cargo bench -p anyml_bench`, optionally followed by the name of
+//! a benchmark (e.g. `cargo bench -p anyml_bench -- anthropic`).
+
+/// The text of the `index`th delta of a streamed reply.
+///
+/// Deltas are a few tokens long, and some need escaping in JSON, as real replies do.
+pub fn delta_text(index: usize) -> &'static str {
+    const DELTAS: [&str; 8] = [
+        "Hello",
+        ", how",
+        " can I",
+        " help you",
+        " today?\\n\\n",
+        "Here's a \\\"quote\\\"",
+        " and some ünïcödé",
+        " text.",
+    ];
+    DELTAS[index % DELTAS.len()]
+}
+
+/// A streamed OpenAI-compatible chat completion of `deltas` content deltas,
+/// as sent by OpenAI, Groq and OpenRouter.
+pub fn openai_sse(deltas: usize) -> String {
+    let mut body = String::new();
+    for index in 0..deltas {
+        body.push_str(&format!(
+            "data: {{\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1700000000,\"model\":\"gpt-4o\",\"choices\":[{{\"index\":0,\"delta\":{{\"content\":\"{}\"}},\"finish_reason\":null}}]}}\n\n",
+            delta_text(index)
+        ));
+    }
+    body.push_str("data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1700000000,\"model\":\"gpt-4o\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n");
+    body.push_str("data: [DONE]\n\n");
+    body
+}
+
+/// A streamed Anthropic message of `deltas` text deltas.
+pub fn anthropic_sse(deltas: usize) -> String {
+    let mut body = String::from(
+        "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"type\":\"message\",\"role\":\"assistant\",\"content\":[],\"model\":\"claude-sonnet-4-5\",\"usage\":{\"input_tokens\":25,\"output_tokens\":1}}}\n\n\
+         event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+    );
+    for index in 0..deltas {
+        body.push_str(&format!(
+            "event: content_block_delta\ndata: {{\"type\":\"content_block_delta\",\"index\":0,\"delta\":{{\"type\":\"text_delta\",\"text\":\"{}\"}}}}\n\n",
+            delta_text(index)
+        ));
+    }
+    body.push_str(
+        "event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":0}\n\n\
+         event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":15}}\n\n\
+         event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+    );
+    body
+}
+
+/// A streamed Ollama chat of `deltas` content deltas, as newline-delimited JSON.
+pub fn ollama_ndjson(deltas: usize) -> String {
+    let mut body = String::new();
+    for index in 0..deltas {
+        body.push_str(&format!(
+            "{{\"model\":\"llama3\",\"created_at\":\"2025-01-01T00:00:00Z\",\"message\":{{\"role\":\"assistant\",\"content\":\"{}\"}},\"done\":false}}\n",
+            delta_text(index)
+        ));
+    }
+    body.push_str("{\"model\":\"llama3\",\"created_at\":\"2025-01-01T00:00:00Z\",\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"done_reason\":\"stop\",\"prompt_eval_count\":25,\"eval_count\":15}\n");
+    body
+}
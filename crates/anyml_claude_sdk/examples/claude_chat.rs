@@ -56,6 +56,7 @@ async fn stream_response(mut response: ChatResponse<'_>) {
                 out.write_all(text.as_bytes()).await.unwrap();
                 out.flush().await.unwrap();
             }
+            Ok(_) => {}
             Err(e) => {
                 eprintln!("stream error: {e}");
             }
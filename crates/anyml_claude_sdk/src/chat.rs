@@ -2,22 +2,89 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use anyhow::anyhow;
+use anyml_core::models::ContentPart;
 use anyml_core::providers::chat::{
     ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError, Messages,
     Thinking,
 };
 use claude_sdk::{
-    AgentError, AgentHandle, AgentMessage, Message, QueryOptions, Role, StreamDelta, StreamEvent,
-    ThinkingConfig, create_session,
+    AgentError, AgentHandle, AgentMessage, Image, Message, QueryOptions, Role, StreamDelta,
+    StreamEvent, ThinkingConfig, create_session,
 };
 use futures::{Stream, StreamExt};
 
-use crate::ClaudeSdkProvider;
+use crate::{ClaudeSdkProvider, list_models::thinking_modes};
 
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl ChatProvider for ClaudeSdkProvider {
     async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
-        let (messages, system_prompt) = convert_messages(&options.messages)?;
+        let options = options.or_default_model(self.default_model.as_deref());
+        options.with_timeouts(self.send_chat(&options)).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.default_model.as_deref()
+    }
+}
+
+impl ClaudeSdkProvider {
+    async fn send_chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse<'_>, ChatError> {
+        options.validate()?;
+        if options.prefill.is_some() {
+            return Err(ChatError::Unsupported { feature: "prefill" });
+        }
+        if options.response_format.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "response_format",
+            });
+        }
+        if options.logprobs {
+            return Err(ChatError::Unsupported {
+                feature: "logprobs",
+            });
+        }
+        if options.audio_output.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "audio_output",
+            });
+        }
+        if !options.server_tools.is_empty() {
+            return Err(ChatError::Unsupported {
+                feature: "server_tools",
+            });
+        }
+        if options.include_raw {
+            return Err(ChatError::Unsupported {
+                feature: "include_raw",
+            });
+        }
+        if !options.extra.is_empty() {
+            return Err(ChatError::Unsupported { feature: "extra" });
+        }
+        if !options.overrides.is_empty() {
+            return Err(ChatError::Unsupported {
+                feature: "overrides",
+            });
+        }
+        let modes = thinking_modes(options.model);
+        if let Some(thinking) = &options.thinking
+            && let Some(modes) = &modes
+            && !modes.supports(thinking)
+        {
+            return Err(ChatError::InvalidOptions {
+                field: "thinking",
+                reason: format!("{} doesn't support {thinking:?}", options.model),
+            });
+        }
+
+        let (messages, mut system_prompt) = convert_messages(&options.messages)?;
+        if let Some(system) = options.system {
+            system_prompt = Some(match system_prompt {
+                Some(rest) => format!("{system}\n{rest}"),
+                None => system.to_owned(),
+            });
+        }
 
         // When there is conversation history (more than just the last user
         // message), write a temp .jsonl session file so the CLI can resume
@@ -47,6 +114,10 @@ impl ChatProvider for ClaudeSdkProvider {
             thinking: options.thinking.as_ref().map(|t| match t {
                 Thinking::BudgetTokens(n) => ThinkingConfig::BudgetTokens(*n),
                 Thinking::Effort(level) => ThinkingConfig::Effort(level.clone()),
+                // Models without a budget only think adaptively, "high" is their default effort.
+                Thinking::Enabled if modes.is_some_and(|modes| modes.budget.is_none()) => {
+                    ThinkingConfig::Effort("high".to_owned())
+                }
                 Thinking::Enabled => ThinkingConfig::BudgetTokens(10000),
             }),
             ..Default::default()
@@ -62,8 +133,10 @@ impl ChatProvider for ClaudeSdkProvider {
                 Ok(AgentMessage::StreamEvent {
                     event: StreamEvent::ContentBlockDelta { delta },
                 }) => match delta {
-                    StreamDelta::Text { text } => Some(Ok(ChatChunk::Content(text))),
-                    StreamDelta::Thinking { thinking } => Some(Ok(ChatChunk::Thinking(thinking))),
+                    StreamDelta::Text { text } => Some(Ok(ChatChunk::Content(text.into()))),
+                    StreamDelta::Thinking { thinking } => {
+                        Some(Ok(ChatChunk::Thinking(thinking.into())))
+                    }
                     StreamDelta::Other => None,
                 },
                 Ok(AgentMessage::Result {
@@ -121,33 +194,36 @@ fn convert_messages(messages: &Messages<'_>) -> Result<(Vec<Message>, Option<Str
     let mut system_prompt = None;
 
     for msg in core_messages {
-        match msg.role {
+        let role = match msg.role {
             anyml_core::MessageRole::System => {
                 let sp = system_prompt.get_or_insert_with(String::new);
                 if !sp.is_empty() {
                     sp.push('\n');
                 }
                 sp.push_str(&msg.content);
+                continue;
             }
-            anyml_core::MessageRole::User => {
-                sdk_messages.push(Message {
-                    role: Role::User,
-                    content: msg.content,
-                });
-            }
-            anyml_core::MessageRole::Assistant => {
-                sdk_messages.push(Message {
-                    role: Role::Assistant,
-                    content: msg.content,
-                });
-            }
-            _ => {
-                sdk_messages.push(Message {
-                    role: Role::User,
-                    content: msg.content,
-                });
-            }
-        }
+            anyml_core::MessageRole::Assistant => Role::Assistant,
+            _ => Role::User,
+        };
+        let images = msg
+            .parts
+            .into_iter()
+            .map(|part| match part {
+                ContentPart::Image(image) => Ok(Image {
+                    media_type: image.media_type,
+                    data: image.data,
+                }),
+                ContentPart::Audio(_) => Err(ChatError::Unsupported {
+                    feature: "audio_input",
+                }),
+            })
+            .collect::<Result<_, _>>()?;
+        sdk_messages.push(Message {
+            role,
+            content: msg.content,
+            images,
+        });
     }
 
     Ok((sdk_messages, system_prompt))
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{borrow::Cow, path::PathBuf};
 
 mod chat;
 mod list_models;
@@ -7,12 +7,14 @@ pub use claude_sdk::install_cli;
 
 pub struct ClaudeSdkProvider {
     sdk: claude_sdk::ClaudeSDK,
+    default_model: Option<Cow<'static, str>>,
 }
 
 impl ClaudeSdkProvider {
     pub fn new(cli_path: impl Into<PathBuf>) -> Self {
         Self {
             sdk: claude_sdk::ClaudeSDK::new(cli_path),
+            default_model: None,
         }
     }
 
@@ -20,4 +22,11 @@ impl ClaudeSdkProvider {
         self.sdk = self.sdk.api_key(key);
         self
     }
+
+    /// Sets the model chats are sent to when their options leave it to the provider,
+    /// as with [`ChatOptions::default_model`](anyml_core::providers::chat::ChatOptions::default_model).
+    pub fn with_default_model(mut self, model: impl Into<Cow<'static, str>>) -> Self {
+        self.default_model = Some(model.into());
+        self
+    }
 }
@@ -94,7 +94,18 @@ const MODELS: &[StaticModel] = &[
     },
 ];
 
-#[async_trait::async_trait]
+/// The thinking modes of `id`, or `None` if it isn't a known model.
+pub(crate) fn thinking_modes(id: &str) -> Option<ThinkingModes<&'static [&'static str]>> {
+    let thinking = MODELS.iter().find(|m| m.id == id)?.thinking.as_ref()?;
+
+    Some(ThinkingModes {
+        modes: thinking.modes,
+        budget: thinking.budget,
+    })
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl ListModelsProvider for ClaudeSdkProvider {
     async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
         Ok(MODELS
@@ -107,6 +118,7 @@ impl ListModelsProvider for ClaudeSdkProvider {
                     modes: t.modes.iter().map(|s| (*s).to_owned()).collect(),
                     budget: t.budget,
                 }),
+                ..Default::default()
             })
             .collect())
     }
@@ -0,0 +1,85 @@
+//! Measures the allocations and time taken to split a long stream into server-sent events,
+//! against copying each event out of the buffer as the providers used to.
+//!
+//! Run with `cargo bench -p anyml_core --bench decode`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use anyml_core::decode::{SseDecoder, Utf8Decoder};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const EVENTS: usize = 10_000;
+/// Roughly the size of the chunks providers send, which rarely line up with events.
+const CHUNK_SIZE: usize = 61;
+
+fn main() {
+    let sse = (0..EVENTS)
+        .map(|i| format!("data: {{\"choices\":[{{\"delta\":{{\"content\":\"token {i}\"}}}}]}}\n\n"))
+        .collect::<String>();
+
+    bench("sse, copying each event", &sse, |chunks| {
+        let mut decoder = Utf8Decoder::new();
+        let mut buffer = String::new();
+        let mut events = 0;
+        for chunk in chunks {
+            buffer.push_str(&decoder.decode(chunk));
+            while let Some(end) = buffer.find("\n\n") {
+                let event: String = buffer.drain(..end + 2).collect();
+                events += black_box(event).len().min(1);
+            }
+        }
+        events
+    });
+    bench("sse, SseDecoder", &sse, |chunks| {
+        let mut decoder = SseDecoder::new();
+        let mut events = 0;
+        for chunk in chunks {
+            decoder.push(chunk);
+            while let Some(event) = decoder.next_event() {
+                events += black_box(event).len().min(1);
+            }
+        }
+        events
+    });
+}
+
+fn bench<'a>(name: &str, body: &'a str, decode: impl Fn(std::slice::Chunks<'a, u8>) -> usize) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    let events = decode(body.as_bytes().chunks(CHUNK_SIZE));
+    let elapsed = started.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    assert_eq!(events, EVENTS);
+    println!(
+        "{name:<24} {allocations:>7} allocations ({:.2} per event) in {elapsed:?}",
+        allocations as f64 / EVENTS as f64
+    );
+}
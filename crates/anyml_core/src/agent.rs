@@ -0,0 +1,327 @@
+use std::{collections::HashMap, future::Future};
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::{
+    maybe_send::{MaybeSend, MaybeSync},
+    models::{Message, Tool, ToolCall},
+    providers::chat::{
+        AggregatedChat, ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse,
+        ChatStreamError, FinishReason, Messages,
+    },
+};
+
+/// Runs one of an [`Agent`]'s tools.
+///
+/// Implemented for async closures taking the call's arguments, so most tools
+/// don't need a type of their own.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait ToolHandler: MaybeSend + MaybeSync {
+    /// Calls the tool with the arguments the model produced.
+    ///
+    /// A string result is sent back to the model verbatim, and any other value as JSON.
+    async fn call(&self, arguments: Value) -> Result<Value, anyhow::Error>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<F, Fut> ToolHandler for F
+where
+    F: Fn(Value) -> Fut + MaybeSend + MaybeSync,
+    Fut: Future<Output = Result<Value, anyhow::Error>> + MaybeSend,
+{
+    async fn call(&self, arguments: Value) -> Result<Value, anyhow::Error> {
+        self(arguments).await
+    }
+}
+
+/// The tools an [`Agent`] offers the model, each with the handler which runs it.
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: Vec<Tool>,
+    handlers: HashMap<String, Box<dyn ToolHandler>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `tool`, replacing any tool already registered under its name.
+    pub fn register(mut self, tool: Tool, handler: impl ToolHandler + 'static) -> Self {
+        self.insert(tool, handler);
+        self
+    }
+
+    /// Registers `tool`, replacing any tool already registered under its name.
+    pub fn insert(&mut self, tool: Tool, handler: impl ToolHandler + 'static) {
+        self.tools.retain(|registered| registered.name != tool.name);
+        self.handlers.insert(tool.name.clone(), Box::new(handler));
+        self.tools.push(tool);
+    }
+
+    /// The declarations of every registered tool, in the order they were registered.
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// Runs a tool call, returning the message which reports its result to the model.
+    ///
+    /// Failures, including calls to unknown tools, are reported to the model
+    /// as the result, so it can correct itself.
+    pub async fn call(&self, tool_call: &ToolCall) -> Message {
+        let result = match self.handlers.get(&tool_call.name) {
+            Some(handler) => match tool_call.arguments_json() {
+                Ok(arguments) => handler.call(arguments).await,
+                Err(err) => Err(anyhow!("the arguments aren't valid JSON: {err}")),
+            },
+            None => Err(anyhow!("there is no tool named \"{}\"", tool_call.name)),
+        };
+
+        let content = match result {
+            Ok(Value::String(content)) => content,
+            Ok(value) => value.to_string(),
+            Err(err) => format!("Error: {err:#}"),
+        };
+        Message::tool(&tool_call.id, content)
+    }
+}
+
+/// Answers chats by calling tools for the model until it replies without calling any.
+///
+/// Each of the model's tool calls is run through the [`ToolRegistry`] and its
+/// result sent back in a follow-up request, up to a limit of requests.
+pub struct Agent<P> {
+    provider: P,
+    tools: ToolRegistry,
+    max_iterations: usize,
+}
+
+impl<P: ChatProvider> Agent<P> {
+    pub fn new(provider: P, tools: ToolRegistry) -> Self {
+        Self {
+            provider,
+            tools,
+            max_iterations: 10,
+        }
+    }
+
+    /// Sets how many requests a single run may make, including the first (10 by default).
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations.max(1);
+        self
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn tools(&self) -> &ToolRegistry {
+        &self.tools
+    }
+
+    /// Sends the chat with the registered tools, replacing any in `options`,
+    /// and streams the model's replies until one doesn't call any tools.
+    ///
+    /// Text and thinking from every request are streamed, but the tool calls
+    /// themselves are handled by the agent and left out. If a follow-up
+    /// request fails the stream ends with [`ChatStreamError::RequestFailed`],
+    /// and if the model is still calling tools after the last request it ends
+    /// with [`ChatStreamError::ToolLoopLimit`].
+    pub async fn run<'a>(
+        &'a self,
+        options: &ChatOptions<'a>,
+    ) -> Result<ChatResponse<'a>, ChatError> {
+        let Messages::Raw(messages) = &options.messages else {
+            return Err(ChatError::InvalidOptions {
+                field: "messages",
+                reason: "an agent needs them unserialized to append tool results".to_owned(),
+            });
+        };
+
+        let options = options.clone().tools(self.tools.tools());
+        let response = self.provider.chat(&options).await?;
+
+        let run = AgentRun {
+            agent: self,
+            messages: messages.to_vec(),
+            options,
+            response,
+            turn: AggregatedChat::default(),
+            iterations: 1,
+        };
+        Ok(ChatResponse::new(futures::stream::unfold(
+            Some(run),
+            |run| async move { run?.next().await },
+        )))
+    }
+}
+
+/// The state of an [`Agent::run`] between items of its stream.
+struct AgentRun<'a, P> {
+    agent: &'a Agent<P>,
+    messages: Vec<Message>,
+    options: ChatOptions<'a>,
+    response: ChatResponse<'a>,
+    /// The reply to the latest request so far.
+    turn: AggregatedChat,
+    iterations: usize,
+}
+
+type RunItem<'a, P> = (Result<ChatChunk, ChatStreamError>, Option<AgentRun<'a, P>>);
+
+impl<'a, P: ChatProvider> AgentRun<'a, P> {
+    /// Yields the next item to stream, along with the state to continue from
+    /// unless the run is over.
+    async fn next(mut self) -> Option<RunItem<'a, P>> {
+        loop {
+            match self.response.next().await {
+                Some(Ok(chunk)) => {
+                    self.turn.push(&chunk);
+                    if !matches!(
+                        chunk,
+                        ChatChunk::ToolCallDelta(_) | ChatChunk::Finish(FinishReason::ToolCalls)
+                    ) {
+                        return Some((Ok(chunk), Some(self)));
+                    }
+                }
+                Some(Err(err)) => return Some((Err(err), None)),
+                None => {
+                    let turn = std::mem::take(&mut self.turn);
+                    if turn.tool_calls.is_empty() {
+                        return None;
+                    }
+                    if self.iterations >= self.agent.max_iterations {
+                        return Some((
+                            Err(ChatStreamError::ToolLoopLimit {
+                                limit: self.agent.max_iterations,
+                            }),
+                            None,
+                        ));
+                    }
+                    if let Err(err) = self.follow_up(turn).await {
+                        return Some((Err(ChatStreamError::RequestFailed(err.into())), None));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs the turn's tool calls and sends their results back to the model.
+    async fn follow_up(&mut self, turn: AggregatedChat) -> Result<(), ChatError> {
+        let results = futures::future::join_all(
+            turn.tool_calls
+                .iter()
+                .map(|tool_call| self.agent.tools.call(tool_call)),
+        )
+        .await;
+        self.messages.push(turn.into_message());
+        self.messages.extend(results);
+
+        let options = self.options.clone().messages(&self.messages);
+        self.response = self.agent.provider.chat(&options).await?;
+        self.iterations += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{
+        chat::ToolCallDelta,
+        mock::{MockChatProvider, MockReply},
+    };
+    use futures::executor::block_on;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    fn weather_call(id: &str) -> ChatChunk {
+        ChatChunk::ToolCallDelta(ToolCallDelta {
+            index: 0,
+            id: Some(id.to_owned()),
+            name: Some("get_weather".to_owned()),
+            arguments: r#"{"city":"Paris"}"#.to_owned(),
+        })
+    }
+
+    fn calling_reply(id: &str) -> MockReply {
+        MockReply::new()
+            .chunk(ChatChunk::Content("Checking.".into()))
+            .chunk(weather_call(id))
+            .chunk(ChatChunk::Finish(FinishReason::ToolCalls))
+    }
+
+    fn weather_tools(calls: Arc<Mutex<Vec<Value>>>) -> ToolRegistry {
+        ToolRegistry::new().register(
+            Tool::new("get_weather", json!({ "type": "object" })),
+            move |arguments: Value| {
+                calls.lock().unwrap().push(arguments);
+                async { Ok::<_, anyhow::Error>(Value::from("Sunny")) }
+            },
+        )
+    }
+
+    #[test]
+    fn runs_tools_until_the_model_answers() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let provider = MockChatProvider::new()
+            .reply(calling_reply("call_1"))
+            .reply(MockReply::text("It's sunny."));
+        let agent = Agent::new(provider, weather_tools(calls.clone()));
+        let messages = &[Message::user("What's the weather in Paris?")];
+        let options = ChatOptions::new("model").messages(messages);
+
+        let result = block_on(async {
+            let mut response = agent.run(&options).await.unwrap();
+            response.aggregate().await.unwrap()
+        });
+
+        assert_eq!(result.content, "Checking.It's sunny.");
+        assert!(result.tool_calls.is_empty());
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(agent.provider().calls(), 2);
+        assert_eq!(*calls.lock().unwrap(), [json!({ "city": "Paris" })]);
+    }
+
+    #[test]
+    fn stops_after_max_iterations() {
+        let provider = MockChatProvider::new()
+            .reply(calling_reply("call_1"))
+            .reply(calling_reply("call_2"));
+        let agent = Agent::new(provider, weather_tools(Arc::default())).max_iterations(2);
+        let messages = &[Message::user("What's the weather in Paris?")];
+        let options = ChatOptions::new("model").messages(messages);
+
+        let result = block_on(async {
+            let mut response = agent.run(&options).await.unwrap();
+            response.aggregate().await
+        });
+
+        assert!(matches!(
+            result,
+            Err(ChatStreamError::ToolLoopLimit { limit: 2 })
+        ));
+        assert_eq!(agent.provider().calls(), 2);
+    }
+
+    #[test]
+    fn reports_unknown_tools_to_the_model() {
+        let tools = ToolRegistry::new();
+        let tool_call = ToolCall::new("call_1", "get_time", "{}");
+
+        let message = block_on(tools.call(&tool_call));
+
+        assert_eq!(message.tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(
+            message.content,
+            "Error: there is no tool named \"get_time\""
+        );
+    }
+}
@@ -0,0 +1,641 @@
+use thiserror::Error;
+
+use crate::{
+    models::{Message, MessageRole},
+    providers::chat::{
+        AggregatedChat, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError,
+        Thinking,
+    },
+    tokenize::count_message_tokens,
+};
+
+/// The heading of the system message which replaces turns summarized by
+/// [`HistoryPolicy::Summarize`].
+const SUMMARY_HEADING: &str = "Summary of the earlier conversation:";
+
+const SUMMARY_PROMPT: &str = "Summarize the following conversation in a few sentences. \
+Keep any names, facts, decisions and open questions needed to continue it.";
+
+/// How a [`Conversation`] keeps its history within the model's context window.
+///
+/// The policy is applied before each request, and the turns it drops are removed
+/// from the history. System messages are always kept, and the history is only
+/// ever cut before a user turn, so tool calls stay next to their results.
+#[derive(Clone, Debug, Default)]
+pub enum HistoryPolicy {
+    /// Sends the whole history.
+    #[default]
+    KeepAll,
+    /// Keeps the last `n` messages, besides system messages.
+    KeepLast(usize),
+    /// Drops the oldest turns until the history, plus the reply's `max_tokens`,
+    /// fits in `context_length` tokens (e.g. from [`Model::context_length`](crate::models::Model)).
+    ///
+    /// Tokens are counted with [`tokenize`](crate::tokenize), which only estimates
+    /// them for most models, so leave some headroom.
+    TokenWindow { context_length: usize },
+    /// Once there are more than `max_messages` messages, besides system messages,
+    /// asks the model to summarize all but the last `keep_last` and replaces them
+    /// with the summary.
+    Summarize {
+        max_messages: usize,
+        keep_last: usize,
+    },
+}
+
+/// A multi-turn chat which owns its message history.
+///
+/// Each call to [`Conversation::send`] appends the user's turn, queries the
+/// provider with the full history and appends the aggregated assistant reply.
+#[derive(Clone, Debug)]
+pub struct Conversation {
+    model: String,
+    messages: Vec<Message>,
+    max_tokens: Option<usize>,
+    thinking: Option<Thinking>,
+    session_id: Option<String>,
+    history_policy: HistoryPolicy,
+    recover_overflow: bool,
+    dropped: Vec<Message>,
+}
+
+impl Conversation {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            messages: Vec::new(),
+            max_tokens: None,
+            thinking: None,
+            session_id: None,
+            history_policy: HistoryPolicy::KeepAll,
+            recover_overflow: false,
+            dropped: Vec::new(),
+        }
+    }
+
+    /// Starts a conversation from an existing history.
+    pub fn with_messages(model: impl Into<String>, messages: Vec<Message>) -> Self {
+        Self {
+            messages,
+            ..Self::new(model)
+        }
+    }
+
+    /// Prepends a system message to the history.
+    pub fn system(mut self, content: impl Into<String>) -> Self {
+        self.messages.insert(0, Message::system(content));
+        self
+    }
+
+    /// Sets the model used for subsequent turns.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens.max(1));
+        self
+    }
+
+    /// Enables thinking/reasoning for subsequent turns.
+    pub fn thinking(mut self, thinking: Thinking) -> Self {
+        self.thinking = Some(thinking);
+        self
+    }
+
+    /// Sets the session ID for providers with server-side session support.
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Sets how the history is kept within the model's context window.
+    pub fn history_policy(mut self, history_policy: HistoryPolicy) -> Self {
+        self.history_policy = history_policy;
+        self
+    }
+
+    /// Retries requests which don't fit in the model's context window
+    /// ([`ChatError::ContextLengthExceeded`]) after dropping the oldest turn,
+    /// or summarizing older turns under [`HistoryPolicy::Summarize`], until
+    /// only the latest turn is left.
+    pub fn recover_context_overflow(mut self, recover: bool) -> Self {
+        self.recover_overflow = recover;
+        self
+    }
+
+    /// The full message history, including system, user and assistant turns.
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    pub fn into_messages(self) -> Vec<Message> {
+        self.messages
+    }
+
+    /// The messages removed from the history during the last request, by the
+    /// [`HistoryPolicy`] or to recover from a context overflow, oldest first.
+    pub fn dropped(&self) -> &[Message] {
+        &self.dropped
+    }
+
+    /// The most recent assistant reply, if any.
+    pub fn last_reply(&self) -> Option<&Message> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|message| matches!(message.role, MessageRole::Assistant))
+    }
+
+    /// Appends a user turn without querying the provider.
+    pub fn push_user(&mut self, content: impl Into<String>) {
+        self.messages.push(Message::user(content));
+    }
+
+    /// Appends an aggregated assistant reply to the history.
+    ///
+    /// Useful when streaming a response manually via [`Conversation::options`].
+    pub fn push_reply(&mut self, reply: &AggregatedChat) {
+        self.messages.push(reply.clone().into_message());
+    }
+
+    pub fn push(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    /// Removes all messages except system messages.
+    pub fn clear(&mut self) {
+        self.messages
+            .retain(|message| matches!(message.role, MessageRole::System));
+    }
+
+    /// Builds chat options for the current history.
+    pub fn options(&self) -> ChatOptions<'_> {
+        let mut options = ChatOptions::new(&self.model).messages(&self.messages);
+        if let Some(max_tokens) = self.max_tokens {
+            options = options.max_tokens(max_tokens);
+        }
+        if let Some(thinking) = &self.thinking {
+            options = options.thinking(thinking.clone());
+        }
+        if let Some(session_id) = &self.session_id {
+            options = options.session_id(session_id);
+        }
+        options
+    }
+
+    /// Appends a user turn, queries `provider` with the full history and
+    /// appends the assistant's reply.
+    ///
+    /// If the request or the stream fails the user's turn is removed again,
+    /// though any turns dropped by the [`HistoryPolicy`] stay dropped.
+    pub async fn send<P>(
+        &mut self,
+        provider: &P,
+        content: impl Into<String>,
+    ) -> Result<AggregatedChat, ConversationError>
+    where
+        P: ChatProvider + ?Sized,
+    {
+        self.push_user(content);
+
+        let reply = self.complete(provider).await;
+        if reply.is_err() {
+            self.messages.pop();
+        }
+        reply
+    }
+
+    /// Queries `provider` with the current history and appends the reply,
+    /// without adding a new user turn.
+    pub async fn complete<P>(&mut self, provider: &P) -> Result<AggregatedChat, ConversationError>
+    where
+        P: ChatProvider + ?Sized,
+    {
+        self.dropped.clear();
+        self.apply_history_policy(provider).await?;
+
+        let reply = loop {
+            let result = {
+                let options = self.options();
+                provider.chat(&options).await
+            };
+            match result {
+                Ok(mut response) => break response.aggregate().await?,
+                Err(ChatError::ContextLengthExceeded(err)) if self.recover_overflow => {
+                    if !self.shed_oldest_turns(provider).await? {
+                        return Err(ChatError::ContextLengthExceeded(err).into());
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+
+        self.push_reply(&reply);
+        Ok(reply)
+    }
+
+    /// Continues the reply to the latest turn after its stream broke off, from
+    /// `partial`, what was received of it, and appends the whole reply.
+    ///
+    /// Meant for replies streamed manually via [`Conversation::options`], see
+    /// [`ChatResponse::resume`] for how the reply is continued.
+    pub async fn resume<P>(
+        &mut self,
+        provider: &P,
+        partial: &AggregatedChat,
+    ) -> Result<AggregatedChat, ConversationError>
+    where
+        P: ChatProvider + ?Sized,
+    {
+        let mut reply = partial.clone();
+        {
+            let options = self.options();
+            let mut response = ChatResponse::resume(provider, &options, partial).await?;
+            while let Some(chunk) = response.next().await {
+                reply.push(&chunk?);
+            }
+        }
+
+        self.push_reply(&reply);
+        Ok(reply)
+    }
+}
+
+impl Conversation {
+    /// Trims the history according to the [`HistoryPolicy`], querying
+    /// `provider` for a summary if the policy asks for one.
+    async fn apply_history_policy<P>(&mut self, provider: &P) -> Result<(), ConversationError>
+    where
+        P: ChatProvider + ?Sized,
+    {
+        match self.history_policy {
+            HistoryPolicy::KeepAll => {}
+            HistoryPolicy::KeepLast(n) => {
+                let start = self.messages.len().saturating_sub(n);
+                self.drop_turns_before(start);
+            }
+            HistoryPolicy::TokenWindow { context_length } => {
+                let system_tokens: usize = self
+                    .messages
+                    .iter()
+                    .filter(|message| is_system(message))
+                    .map(|message| count_message_tokens(&self.model, message))
+                    .sum();
+                let mut budget = context_length
+                    .saturating_sub(self.max_tokens.unwrap_or(0))
+                    .saturating_sub(system_tokens);
+
+                let mut start = self.messages.len();
+                for (index, message) in self.messages.iter().enumerate().rev() {
+                    if is_system(message) {
+                        continue;
+                    }
+                    let Some(remaining) =
+                        budget.checked_sub(count_message_tokens(&self.model, message))
+                    else {
+                        break;
+                    };
+                    budget = remaining;
+                    start = index;
+                }
+                self.drop_turns_before(start);
+            }
+            HistoryPolicy::Summarize {
+                max_messages,
+                keep_last,
+            } => {
+                let turns = self.messages.iter().filter(|m| !is_system(m)).count();
+                if turns > max_messages {
+                    let start = self.messages.len().saturating_sub(keep_last);
+                    self.summarize_turns_before(provider, start).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Shortens the history after a context overflow, returning whether anything was removed.
+    async fn shed_oldest_turns<P>(&mut self, provider: &P) -> Result<bool, ConversationError>
+    where
+        P: ChatProvider + ?Sized,
+    {
+        if let HistoryPolicy::Summarize { keep_last, .. } = self.history_policy {
+            let start = self.messages.len().saturating_sub(keep_last);
+            if self.summarize_turns_before(provider, start).await? {
+                return Ok(true);
+            }
+        }
+
+        let Some(oldest) = self.messages.iter().position(|m| !is_system(m)) else {
+            return Ok(false);
+        };
+        Ok(self.drop_turns_before(oldest + 1))
+    }
+
+    /// Where the history can be cut to keep the messages from `start` onwards,
+    /// which is the first user turn at or after `start`, or failing that the last one.
+    fn cut_index(&self, start: usize) -> Option<usize> {
+        let is_user = |message: &Message| matches!(message.role, MessageRole::User);
+        self.messages
+            .iter()
+            .enumerate()
+            .skip(start)
+            .find(|(_, message)| is_user(message))
+            .or_else(|| {
+                self.messages
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find(|(_, message)| is_user(message))
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Removes the messages before the cut for `start`, besides system messages,
+    /// returning whether any were removed.
+    fn drop_turns_before(&mut self, start: usize) -> bool {
+        let Some(cut) = self.cut_index(start) else {
+            return false;
+        };
+        self.remove_before(cut, |message| !is_system(message))
+    }
+
+    /// Moves the messages before `cut` which match `removed` to the dropped
+    /// messages, returning whether any were moved.
+    fn remove_before(&mut self, cut: usize, removed: impl Fn(&Message) -> bool) -> bool {
+        let len = self.dropped.len();
+        for (index, message) in std::mem::take(&mut self.messages).into_iter().enumerate() {
+            if index < cut && removed(&message) {
+                self.dropped.push(message);
+            } else {
+                self.messages.push(message);
+            }
+        }
+        self.dropped.len() > len
+    }
+
+    /// Replaces the messages before the cut for `start`, including any earlier
+    /// summary, with a summary written by the model, returning whether there
+    /// was anything to summarize.
+    async fn summarize_turns_before<P>(
+        &mut self,
+        provider: &P,
+        start: usize,
+    ) -> Result<bool, ConversationError>
+    where
+        P: ChatProvider + ?Sized,
+    {
+        let Some(cut) = self.cut_index(start) else {
+            return Ok(false);
+        };
+        if self.messages[..cut].iter().all(is_system) {
+            return Ok(false);
+        }
+        let summarized = |message: &Message| !is_system(message) || is_summary(message);
+
+        let transcript = self.messages[..cut]
+            .iter()
+            .filter(|message| summarized(message))
+            .map(|message| format!("{}: {}", message.role.as_str(), message.content))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let summary = {
+            let messages = [Message::system(SUMMARY_PROMPT), Message::user(transcript)];
+            let options = ChatOptions::new(&self.model).messages(&messages);
+            let mut response = provider.chat(&options).await?;
+            response.aggregate().await?.content
+        };
+
+        self.remove_before(cut, summarized);
+        let position = self.messages.iter().take_while(|m| is_system(m)).count();
+        self.messages.insert(
+            position,
+            Message::system(format!("{SUMMARY_HEADING}\n{summary}")),
+        );
+        Ok(true)
+    }
+}
+
+fn is_system(message: &Message) -> bool {
+    matches!(message.role, MessageRole::System)
+}
+
+fn is_summary(message: &Message) -> bool {
+    is_system(message) && message.content.starts_with(SUMMARY_HEADING)
+}
+
+#[derive(Debug, Error)]
+pub enum ConversationError {
+    #[error(transparent)]
+    Chat(#[from] ChatError),
+
+    #[error(transparent)]
+    Stream(#[from] ChatStreamError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::chat::{ChatChunk, Messages};
+    use crate::providers::mock::{MockChatProvider, MockReply};
+    use futures::executor::block_on;
+
+    /// The number of messages sent in each chat to `provider`.
+    fn message_counts(provider: &MockChatProvider) -> Vec<usize> {
+        provider
+            .requests()
+            .iter()
+            .map(|request| match request.as_options().messages {
+                Messages::Raw(messages) => messages.len(),
+                Messages::Serialized(_) => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn send_appends_user_and_assistant_turns() {
+        let provider = MockChatProvider::new()
+            .reply(MockReply::text("Hello!"))
+            .reply(MockReply::text("Hello!"));
+        let mut conversation = Conversation::new("model").system("Be nice.");
+
+        let reply = block_on(conversation.send(&provider, "Hi")).unwrap();
+        assert_eq!(reply.content, "Hello!");
+
+        block_on(conversation.send(&provider, "Again")).unwrap();
+
+        let roles: Vec<_> = conversation
+            .messages()
+            .iter()
+            .map(|message| message.role.as_str())
+            .collect();
+        assert_eq!(roles, ["system", "user", "assistant", "user", "assistant"]);
+        assert_eq!(message_counts(&provider), [2, 4]);
+        assert_eq!(conversation.last_reply().unwrap().content, "Hello!");
+    }
+
+    #[test]
+    fn failed_send_leaves_history_unchanged() {
+        let provider = MockChatProvider::new().reply(MockReply::fail(ChatError::RequestError(
+            anyhow::anyhow!("boom"),
+        )));
+        let mut conversation = Conversation::new("model");
+
+        let result = block_on(conversation.send(&provider, "Hi"));
+
+        assert!(matches!(result, Err(ConversationError::Chat(_))));
+        assert!(conversation.messages().is_empty());
+    }
+
+    fn roles(conversation: &Conversation) -> Vec<&str> {
+        conversation
+            .messages()
+            .iter()
+            .map(|message| message.role.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn keep_last_drops_older_turns() {
+        let provider = MockChatProvider::new()
+            .reply(MockReply::text("Hello!"))
+            .reply(MockReply::text("Hello!"));
+        let mut conversation = Conversation::new("model")
+            .system("Be nice.")
+            .history_policy(HistoryPolicy::KeepLast(2));
+
+        block_on(conversation.send(&provider, "Hi")).unwrap();
+        block_on(conversation.send(&provider, "Again")).unwrap();
+
+        assert_eq!(message_counts(&provider), [2, 2]);
+        assert_eq!(roles(&conversation), ["system", "user", "assistant"]);
+        assert_eq!(conversation.messages()[1].content, "Again");
+    }
+
+    #[test]
+    fn token_window_drops_turns_which_dont_fit() {
+        let provider = MockChatProvider::new().reply(MockReply::text("Hello!"));
+        let mut conversation = Conversation::with_messages(
+            "model",
+            vec![
+                Message::user("a".repeat(400)),
+                Message::assistant("b".repeat(400)),
+            ],
+        )
+        .history_policy(HistoryPolicy::TokenWindow {
+            context_length: 150,
+        });
+
+        block_on(conversation.send(&provider, "Hi")).unwrap();
+
+        assert_eq!(message_counts(&provider), [1]);
+        assert_eq!(conversation.messages()[0].content, "Hi");
+    }
+
+    #[test]
+    fn summarize_replaces_older_turns() {
+        let provider = MockChatProvider::new()
+            .reply(MockReply::text("They said hi."))
+            .reply(MockReply::text("Hello!"));
+        let mut conversation = Conversation::with_messages(
+            "model",
+            vec![
+                Message::system("Be nice."),
+                Message::user("Hi"),
+                Message::assistant("Hello!"),
+            ],
+        )
+        .history_policy(HistoryPolicy::Summarize {
+            max_messages: 2,
+            keep_last: 1,
+        });
+
+        block_on(conversation.send(&provider, "Again")).unwrap();
+
+        assert_eq!(message_counts(&provider), [2, 3]);
+        assert_eq!(
+            roles(&conversation),
+            ["system", "system", "user", "assistant"]
+        );
+        assert_eq!(
+            conversation.messages()[1].content,
+            "Summary of the earlier conversation:\nThey said hi."
+        );
+    }
+
+    #[test]
+    fn recovers_from_context_overflow_by_dropping_oldest_turns() {
+        let provider = MockChatProvider::new()
+            .reply(MockReply::fail(ChatError::from_error_body(
+                b"prompt is too long: 210000 tokens > 200000 maximum",
+            )))
+            .reply(MockReply::text("Hello!"));
+        let mut conversation = Conversation::with_messages(
+            "model",
+            vec![
+                Message::system("Be nice."),
+                Message::user("Hi"),
+                Message::assistant("Hello!"),
+            ],
+        )
+        .recover_context_overflow(true);
+
+        block_on(conversation.send(&provider, "Again")).unwrap();
+
+        assert_eq!(provider.calls(), 2);
+        assert_eq!(roles(&conversation), ["system", "user", "assistant"]);
+        let dropped: Vec<_> = conversation
+            .dropped()
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect();
+        assert_eq!(dropped, ["Hi", "Hello!"]);
+    }
+
+    #[test]
+    fn context_overflow_fails_without_recovery() {
+        let provider = MockChatProvider::new().reply(MockReply::fail(ChatError::from_error_body(
+            b"context_length_exceeded",
+        )));
+        let mut conversation = Conversation::new("model");
+
+        let result = block_on(conversation.send(&provider, "Hi"));
+
+        assert!(matches!(
+            result,
+            Err(ConversationError::Chat(ChatError::ContextLengthExceeded(_)))
+        ));
+        assert!(conversation.messages().is_empty());
+    }
+
+    #[test]
+    fn resume_appends_the_whole_reply() {
+        let provider = MockChatProvider::new().reply(MockReply::text("lo!"));
+        let mut conversation = Conversation::new("model");
+        conversation.push_user("Hi");
+        let mut partial = AggregatedChat::default();
+        partial.push(&ChatChunk::Content("Hel".into()));
+
+        let reply = block_on(conversation.resume(&provider, &partial)).unwrap();
+
+        assert_eq!(reply.content, "Hello!");
+        assert_eq!(roles(&conversation), ["user", "assistant"]);
+        assert_eq!(conversation.last_reply().unwrap().content, "Hello!");
+    }
+
+    #[test]
+    fn clear_keeps_system_messages() {
+        let mut conversation = Conversation::new("model").system("Be nice.");
+        conversation.push_user("Hi");
+        conversation.clear();
+
+        assert_eq!(conversation.messages().len(), 1);
+        assert!(matches!(
+            conversation.messages()[0].role,
+            MessageRole::System
+        ));
+    }
+}
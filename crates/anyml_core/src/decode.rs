@@ -0,0 +1,249 @@
+//! Incremental decoders for the byte streams of provider responses.
+
+use futures::{Stream, StreamExt, future::ready, stream};
+
+/// Decodes UTF-8 text arriving in chunks, such as a streamed response body.
+///
+/// A multi-byte character split across chunks is held back until the rest of it
+/// arrives, rather than being decoded as U+FFFD like [`String::from_utf8_lossy`]
+/// would on each chunk. Bytes which are actually invalid are still replaced.
+#[derive(Debug, Default, Clone)]
+pub struct Utf8Decoder {
+    /// The start of a character cut off at the end of the last chunk.
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes `chunk`, holding back an incomplete character at its end for the next chunk.
+    pub fn decode(&mut self, chunk: &[u8]) -> String {
+        let mut decoded = String::with_capacity(self.pending.len() + chunk.len());
+        self.decode_into(chunk, &mut decoded);
+        decoded
+    }
+
+    /// Like [`Utf8Decoder::decode`], but appends to `decoded`, so a buffer can be reused.
+    pub fn decode_into(&mut self, chunk: &[u8], decoded: &mut String) {
+        // Only copies the chunk if it completes a character held back from the last one.
+        let joined;
+        let mut rest = if self.pending.is_empty() {
+            chunk
+        } else {
+            let mut bytes = std::mem::take(&mut self.pending);
+            bytes.extend_from_slice(chunk);
+            joined = bytes;
+            joined.as_slice()
+        };
+
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    decoded.push_str(valid);
+                    break;
+                }
+                Err(err) => {
+                    let (valid, invalid) = rest.split_at(err.valid_up_to());
+                    // Borrows `valid` rather than copying it, as it is valid.
+                    decoded.push_str(&String::from_utf8_lossy(valid));
+
+                    match err.error_len() {
+                        Some(len) => {
+                            decoded.push(char::REPLACEMENT_CHARACTER);
+                            rest = &invalid[len..];
+                        }
+                        None => {
+                            self.pending = invalid.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decodes whatever was held back once the stream has ended,
+    /// as U+FFFD since the character was never completed.
+    pub fn finish(&mut self) -> String {
+        String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned()
+    }
+}
+
+/// Splits newline-delimited JSON arriving in chunks into its lines,
+/// regardless of how the lines are split across or merged into chunks.
+#[derive(Debug, Default, Clone)]
+pub struct NdjsonDecoder {
+    utf8: Utf8Decoder,
+    /// The start of a line not yet terminated by a newline.
+    buffer: String,
+}
+
+impl NdjsonDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes `chunk`, returning every line it completes, trimmed and without blank lines.
+    pub fn decode(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.utf8.decode_into(chunk, &mut self.buffer);
+
+        let mut lines = Vec::new();
+        let mut consumed = 0;
+        while let Some(end) = self.buffer[consumed..].find('\n') {
+            let line = self.buffer[consumed..consumed + end].trim();
+            if !line.is_empty() {
+                lines.push(line.to_owned());
+            }
+            consumed += end + 1;
+        }
+        // Shifts the unterminated line to the front once, rather than after every line.
+        self.buffer.drain(..consumed);
+
+        lines
+    }
+
+    /// Returns the last line once the stream has ended, if it wasn't terminated
+    /// by a newline (as in non-streamed responses).
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer) + &self.utf8.finish();
+        let rest = rest.trim();
+        (!rest.is_empty()).then(|| rest.to_owned())
+    }
+}
+
+/// Splits server-sent events arriving in chunks into whole events,
+/// regardless of how the events are split across or merged into chunks.
+///
+/// Events are borrowed from a single buffer, which is reused for the whole stream
+/// rather than copying each event out of it.
+#[derive(Debug, Default, Clone)]
+pub struct SseDecoder {
+    utf8: Utf8Decoder,
+    buffer: String,
+    /// How much of the buffer has already been returned as events.
+    consumed: usize,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `chunk` to the buffer, dropping the events returned so far.
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buffer.drain(..self.consumed);
+        self.consumed = 0;
+        self.utf8.decode_into(chunk, &mut self.buffer);
+    }
+
+    /// Returns the next complete event, including the blank line which ends it.
+    pub fn next_event(&mut self) -> Option<&str> {
+        let start = self.consumed;
+        let end = start + self.buffer[start..].find("\n\n")? + 2;
+        self.consumed = end;
+        Some(&self.buffer[start..end])
+    }
+
+    /// Whether nothing is left in the buffer, so the stream didn't end partway through an event.
+    pub fn is_empty(&self) -> bool {
+        self.consumed == self.buffer.len()
+    }
+}
+
+/// Splits a newline-delimited JSON byte stream into its lines with an [`NdjsonDecoder`],
+/// including a final line without a trailing newline.
+pub fn ndjson_lines<S, B, E>(bytes: S) -> impl Stream<Item = Result<String, E>>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+{
+    bytes
+        .map(Some)
+        .chain(stream::once(ready(None)))
+        .scan(NdjsonDecoder::new(), |decoder, chunk| {
+            let lines = match chunk {
+                Some(Ok(chunk)) => decoder.decode(chunk.as_ref()).into_iter().map(Ok).collect(),
+                Some(Err(err)) => vec![Err(err)],
+                None => decoder.finish().map(Ok).into_iter().collect(),
+            };
+            ready(Some(lines))
+        })
+        .flat_map(stream::iter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn joins_characters_split_across_chunks() {
+        let text = "héllo 👋";
+        let bytes = text.as_bytes();
+        let mut decoder = Utf8Decoder::new();
+
+        // Splits inside the "é" and inside the emoji.
+        let decoded = [&bytes[..2], &bytes[2..9], &bytes[9..]]
+            .iter()
+            .map(|chunk| decoder.decode(chunk))
+            .collect::<Vec<_>>();
+
+        assert_eq!(decoded, ["h", "éllo ", "👋"]);
+        assert_eq!(decoder.finish(), "");
+    }
+
+    #[test]
+    fn replaces_invalid_bytes() {
+        let mut decoder = Utf8Decoder::new();
+
+        assert_eq!(decoder.decode(b"a\xffb\xe2\x82"), "a\u{fffd}b");
+        assert_eq!(decoder.finish(), "\u{fffd}");
+    }
+
+    #[test]
+    fn sse_events_across_chunks() {
+        let mut decoder = SseDecoder::new();
+
+        decoder.push(b"data: 1\n\ndata: \xc3");
+        assert_eq!(decoder.next_event(), Some("data: 1\n\n"));
+        assert_eq!(decoder.next_event(), None);
+
+        decoder.push(b"\xa7a\n\nevent: ping\ndata: {}\n");
+        assert_eq!(decoder.next_event(), Some("data: ça\n\n"));
+        assert_eq!(decoder.next_event(), None);
+
+        decoder.push(b"\n");
+        assert_eq!(decoder.next_event(), Some("event: ping\ndata: {}\n\n"));
+        assert_eq!(decoder.next_event(), None);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn ndjson_lines_across_chunks() {
+        let chunks: [Result<&[u8], ()>; 3] = [
+            Ok(b"{\"a\":1}\n{\"b\""),
+            Ok(b":2}\n\n{\"c\":3}\n{\"d\""),
+            Ok(b":4}"),
+        ];
+
+        let lines: Vec<String> = block_on(
+            ndjson_lines(stream::iter(chunks))
+                .map(Result::unwrap)
+                .collect(),
+        );
+
+        assert_eq!(lines, ["{\"a\":1}", "{\"b\":2}", "{\"c\":3}", "{\"d\":4}"]);
+    }
+
+    #[test]
+    fn ndjson_line_split_inside_character() {
+        let line = "{\"response\":\"Ça va\"}\n".as_bytes();
+        let mut decoder = NdjsonDecoder::new();
+
+        assert!(decoder.decode(&line[..15]).is_empty());
+        assert_eq!(decoder.decode(&line[15..]), ["{\"response\":\"Ça va\"}"]);
+        assert_eq!(decoder.finish(), None);
+    }
+}
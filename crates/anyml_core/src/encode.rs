@@ -0,0 +1,189 @@
+//! Encoders turning chat streams back into bytes, the inverse of [`crate::decode`].
+
+use futures::{Stream, StreamExt, future::ready, stream};
+use serde_json::{Value, json};
+
+use crate::providers::chat::{ChatChunk, ChatStreamError, FinishReason};
+
+/// Encodes the items of a chat's stream as server-sent events, e.g. for a proxy
+/// re-exposing a [`ChatResponse`](crate::providers::ChatResponse) to browsers.
+///
+/// The events follow anyml's own schema, the same whichever provider replied:
+///
+/// - `content` and `thinking`: `{"text": "..."}`, and `thinking` with
+///   `{"signature": "..."}` once a block of thinking is signed.
+/// - `tool_call`: `{"index": 0, "id": "...", "name": "...", "arguments": "..."}`,
+///   a fragment of a tool call, where `id` and `name` may be `null`.
+/// - `usage`: `{"input_tokens": 10, "output_tokens": 20, "cached_input_tokens": null}`.
+/// - `error`: `{"message": "..."}`, after which the stream may go on.
+/// - `done`: `{"finish_reason": "stop"}`, always last, with `null` if the provider
+///   gave no reason.
+///
+/// Other chunks, such as log probabilities and audio, have no event.
+#[derive(Debug, Default, Clone)]
+pub struct SseEncoder {
+    finish_reason: Option<FinishReason>,
+}
+
+impl SseEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encodes an item of the stream, returning `None` for chunks without an event.
+    ///
+    /// The finish reason is held back for the `done` event.
+    pub fn encode(&mut self, item: &Result<ChatChunk, ChatStreamError>) -> Option<String> {
+        let chunk = match item {
+            Ok(chunk) => chunk,
+            Err(err) => return Some(event("error", json!({ "message": err.to_string() }))),
+        };
+
+        match chunk {
+            ChatChunk::Content(text) => Some(event("content", json!({ "text": &**text }))),
+            ChatChunk::Thinking(text) => Some(event("thinking", json!({ "text": &**text }))),
+            ChatChunk::ThinkingSignature(signature) => {
+                Some(event("thinking", json!({ "signature": signature })))
+            }
+            ChatChunk::ToolCallDelta(delta) => Some(event(
+                "tool_call",
+                json!({
+                    "index": delta.index,
+                    "id": delta.id,
+                    "name": delta.name,
+                    "arguments": delta.arguments,
+                }),
+            )),
+            ChatChunk::Usage(usage) => Some(event(
+                "usage",
+                json!({
+                    "input_tokens": usage.input_tokens,
+                    "output_tokens": usage.output_tokens,
+                    "cached_input_tokens": usage.cached_input_tokens,
+                }),
+            )),
+            ChatChunk::Finish(reason) => {
+                self.finish_reason = Some(reason.clone());
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Encodes the `done` event, once the stream has ended.
+    pub fn finish(&mut self) -> String {
+        let finish_reason = self.finish_reason.take();
+        let finish_reason = finish_reason.as_ref().map(FinishReason::as_str);
+        event("done", json!({ "finish_reason": finish_reason }))
+    }
+}
+
+fn event(name: &str, data: Value) -> String {
+    format!("event: {name}\ndata: {data}\n\n")
+}
+
+/// Encodes a chat's stream as server-sent events with an [`SseEncoder`],
+/// ending with the `done` event.
+pub fn sse_events<S>(chunks: S) -> impl Stream<Item = String>
+where
+    S: Stream<Item = Result<ChatChunk, ChatStreamError>>,
+{
+    chunks
+        .map(Some)
+        .chain(stream::once(ready(None)))
+        .scan(SseEncoder::new(), |encoder, item| {
+            let event = match item {
+                Some(item) => encoder.encode(&item),
+                None => Some(encoder.finish()),
+            };
+            ready(Some(event))
+        })
+        .filter_map(ready)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::SseDecoder;
+    use crate::providers::chat::{ToolCallDelta, Usage};
+    use futures::executor::block_on;
+
+    #[test]
+    fn encodes_the_stream_as_events() {
+        let chunks = [
+            Ok(ChatChunk::Thinking("Hmm".into())),
+            Ok(ChatChunk::Content("Hello\n".into())),
+            Ok(ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 0,
+                id: Some("call_1".to_owned()),
+                name: Some("get_time".to_owned()),
+                arguments: "{}".to_owned(),
+            })),
+            Ok(ChatChunk::Finish(FinishReason::ToolCalls)),
+            Ok(ChatChunk::Usage(Usage {
+                input_tokens: Some(10),
+                output_tokens: Some(20),
+                ..Default::default()
+            })),
+            Err(ChatStreamError::IncompleteChunk),
+        ];
+
+        let events: Vec<String> = block_on(sse_events(stream::iter(chunks)).collect());
+
+        let events: Vec<(&str, Value)> = events
+            .iter()
+            .map(|event| {
+                let (name, data) = event
+                    .strip_prefix("event: ")
+                    .and_then(|event| event.strip_suffix("\n\n"))
+                    .and_then(|event| event.split_once("\ndata: "))
+                    .unwrap();
+                (name, serde_json::from_str(data).unwrap())
+            })
+            .collect();
+        assert_eq!(
+            events,
+            [
+                ("thinking", json!({ "text": "Hmm" })),
+                ("content", json!({ "text": "Hello\n" })),
+                (
+                    "tool_call",
+                    json!({ "index": 0, "id": "call_1", "name": "get_time", "arguments": "{}" })
+                ),
+                (
+                    "usage",
+                    json!({ "input_tokens": 10, "output_tokens": 20, "cached_input_tokens": null })
+                ),
+                (
+                    "error",
+                    json!({ "message": "This chunk contains incomplete data." })
+                ),
+                ("done", json!({ "finish_reason": "tool_calls" })),
+            ]
+        );
+    }
+
+    #[test]
+    fn events_decode_one_by_one() {
+        let mut encoder = SseEncoder::new();
+        let mut decoder = SseDecoder::new();
+
+        decoder.push(
+            encoder
+                .encode(&Ok(ChatChunk::Content("a\n\nb".into())))
+                .unwrap()
+                .as_bytes(),
+        );
+        decoder.push(encoder.finish().as_bytes());
+
+        assert_eq!(
+            decoder.next_event(),
+            Some("event: content\ndata: {\"text\":\"a\\n\\nb\"}\n\n")
+        );
+        assert_eq!(
+            decoder.next_event(),
+            Some("event: done\ndata: {\"finish_reason\":null}\n\n")
+        );
+        assert!(decoder.is_empty());
+    }
+}
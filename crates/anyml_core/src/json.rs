@@ -1,3 +1,6 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+
 /// Trait for values that can be written as JSON into a string buffer.
 /// Used by the `json_string!` macro for runtime variable interpolation.
 pub trait JsonValue {
@@ -80,6 +83,111 @@ impl JsonValue for f64 {
     }
 }
 
+impl JsonValue for serde_json::Value {
+    fn write_json(&self, buf: &mut String) {
+        buf.push_str(&self.to_string());
+    }
+}
+
+/// Writes `null` for `None`.
+/// Use `"key"?: expr` in `json_string!` to omit the key instead.
+impl<T: JsonValue> JsonValue for Option<T> {
+    fn write_json(&self, buf: &mut String) {
+        match self {
+            Some(value) => value.write_json(buf),
+            None => buf.push_str("null"),
+        }
+    }
+}
+
+impl<T: JsonValue> JsonValue for [T] {
+    fn write_json(&self, buf: &mut String) {
+        buf.push('[');
+        for (idx, value) in self.iter().enumerate() {
+            if idx != 0 {
+                buf.push(',');
+            }
+            value.write_json(buf);
+        }
+        buf.push(']');
+    }
+}
+
+impl<T: JsonValue, const N: usize> JsonValue for [T; N] {
+    fn write_json(&self, buf: &mut String) {
+        self.as_slice().write_json(buf);
+    }
+}
+
+impl<T: JsonValue> JsonValue for Vec<T> {
+    fn write_json(&self, buf: &mut String) {
+        self.as_slice().write_json(buf);
+    }
+}
+
+impl<K: AsRef<str>, T: JsonValue, S> JsonValue for HashMap<K, T, S> {
+    fn write_json(&self, buf: &mut String) {
+        write_json_object(self.iter(), buf);
+    }
+}
+
+impl<K: AsRef<str>, T: JsonValue> JsonValue for BTreeMap<K, T> {
+    fn write_json(&self, buf: &mut String) {
+        write_json_object(self.iter(), buf);
+    }
+}
+
+fn write_json_object<'a, K, T>(entries: impl Iterator<Item = (&'a K, &'a T)>, buf: &mut String)
+where
+    K: AsRef<str> + 'a,
+    T: JsonValue + 'a,
+{
+    buf.push('{');
+    for (idx, (key, value)) in entries.enumerate() {
+        if idx != 0 {
+            buf.push(',');
+        }
+        buf.push('"');
+        json_escape_into(key.as_ref(), buf);
+        buf.push_str("\":");
+        value.write_json(buf);
+    }
+    buf.push('}');
+}
+
+/// Adapter that writes any [`serde::Serialize`] value as JSON.
+/// Used by the `json_string!` macro for `@serde expr` values.
+///
+/// Serialization only fails for values JSON can't represent (e.g. maps with
+/// non-string keys), which panics in debug builds, and writes `null` otherwise.
+pub struct Serde<T>(pub T);
+
+impl<T: serde::Serialize> JsonValue for Serde<T> {
+    fn write_json(&self, buf: &mut String) {
+        let start = buf.len();
+        if let Err(err) = serde_json::to_writer(StringWriter(buf), &self.0) {
+            debug_assert!(false, "failed to serialize a value as JSON: {err}");
+            buf.truncate(start);
+            buf.push_str("null");
+        }
+    }
+}
+
+/// Lets serde_json write into a `String`, which it does in whole UTF-8 strings.
+struct StringWriter<'a>(&'a mut String);
+
+impl io::Write for StringWriter<'_> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let json = std::str::from_utf8(bytes).map_err(io::Error::other)?;
+        self.0.push_str(json);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 fn json_escape_into(s: &str, buf: &mut String) {
     for ch in s.chars() {
         match ch {
@@ -1,9 +1,45 @@
+pub use bytestring::ByteString;
+
+pub mod agent;
+pub mod conversation;
+pub mod decode;
+pub mod encode;
 pub mod json;
+pub mod maybe_send;
 pub mod models;
+pub mod multipart;
 pub mod providers;
+pub mod tokenize;
 
-pub use models::{Message, MessageRole, Model, ThinkingBudget, ThinkingModes};
+pub use agent::{Agent, ToolHandler, ToolRegistry};
+pub use conversation::{Conversation, ConversationError, HistoryPolicy};
+pub use decode::{NdjsonDecoder, SseDecoder, Utf8Decoder, ndjson_lines};
+pub use encode::{SseEncoder, sse_events};
+pub use maybe_send::{BoxStream, MaybeSend, MaybeSync};
+pub use models::{
+    Audio, AudioOutput, ContentPart, Image, Message, MessageBuilder, MessageRole, Model,
+    ModelCapabilities, ModelPricing, ParamType, SignedThinking, ThinkingBudget, ThinkingModes,
+    Tool, ToolBuilder, ToolCall,
+};
+pub use multipart::Multipart;
+pub use providers::{
+    AbortHandle, AggregatedChat, ApiKeyProvider, Budget, BudgetEvent, BudgetKey, BudgetedProvider,
+    CacheStore, CachedProvider, ChatBlock, ChatChunk, ChatError, ChatInterceptor,
+    ChatOptions, ChatOptionsOwned, ChatProvider, ChatResponse, ChatStreamError, ChunkBoundary,
+    CircuitBreakerPolicy, CircuitBreakerProvider, CircuitState, CombinedModels, CompletionOptions,
+    CompletionProvider, DebugEntry, DebugLogger, DefaultHeaders, DefaultModelProvider, Extensions,
+    FanOut, FanOutError, FinishReason, HealthCheckProvider, HealthStatus, Intercepted,
+    ListModelsError, ListModelsProvider, MemoryCache, MessageRules, Metered, MetricLabels, Metrics,
+    ModelCatalog, ModelChanges, NoopMetrics, OverBudget, OverflowPolicy, ParseJsonError,
+    ProviderRegistry, RateLimit, RateLimitHeaders, RateLimitPolicy, RateLimitedProvider,
+    RateLimiter, RequestOverrides, ResponseFormat,
+    ServerTool, ServerToolResult, ServerToolUse, SplitStream, Thinking, ThrottledProvider,
+    TokenLogProb, ToolCallDelta, ToolChoice, TopLogProb, Transcription, TranscriptionError,
+    TranscriptionOptions, TranscriptionProvider, TranscriptionSegment, Usage, UsageTiming,
+    cache_key, chat_json, coalesce_messages,
+};
+#[cfg(feature = "test-util")]
 pub use providers::{
-    AggregatedChat, ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse,
-    ChatStreamError, ListModelsError, ListModelsProvider, Thinking,
+    Cassette, Interaction, MockChatProvider, MockReply, RecordedRequest, RecordedResponse,
+    RecordingClient, ReplayClient,
 };
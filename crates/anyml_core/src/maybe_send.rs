@@ -0,0 +1,52 @@
+//! Thread-safety bounds which only apply outside of the browser.
+//!
+//! On `wasm32`, requests made through the browser's `fetch` produce futures and
+//! streams which aren't [`Send`], and there are no threads to send them to anyway.
+//! So providers, responses and the traits they implement only require [`Send`]
+//! and [`Sync`] on other targets, which lets the same provider code run in a web build.
+//!
+//! Implementations of the provider traits should use `async_trait(?Send)` on `wasm32`:
+//!
+//! ```ignore
+//! #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+//! #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+//! impl ChatProvider for MyProvider {
+//!     // ...
+//! }
+//! ```
+
+/// [`Send`], except on `wasm32`, where every type implements it.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// [`Send`], except on `wasm32`, where every type implements it.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// [`Sync`], except on `wasm32`, where every type implements it.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSync: Sync {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+
+/// [`Sync`], except on `wasm32`, where every type implements it.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSync {}
+
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSync for T {}
+
+/// A boxed stream, which is [`Send`] except on `wasm32`.
+#[cfg(not(target_arch = "wasm32"))]
+pub use futures::stream::BoxStream;
+
+/// A boxed stream, which is [`Send`] except on `wasm32`.
+#[cfg(target_arch = "wasm32")]
+pub use futures::stream::LocalBoxStream as BoxStream;
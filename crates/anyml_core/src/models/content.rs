@@ -0,0 +1,83 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+
+/// A non-text part of a message, sent alongside its text content.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Audio(Audio),
+    Image(Image),
+}
+
+impl From<Audio> for ContentPart {
+    fn from(audio: Audio) -> Self {
+        Self::Audio(audio)
+    }
+}
+
+impl From<Image> for ContentPart {
+    fn from(image: Image) -> Self {
+        Self::Image(image)
+    }
+}
+
+/// Base64-encoded audio.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Audio {
+    pub data: String,
+    /// The encoding of the audio (e.g. `"wav"`, `"mp3"`).
+    pub format: String,
+}
+
+impl Audio {
+    pub fn new(bytes: impl AsRef<[u8]>, format: impl Into<String>) -> Self {
+        Self {
+            data: STANDARD.encode(bytes),
+            format: format.into(),
+        }
+    }
+
+    /// Decodes the audio's bytes.
+    pub fn bytes(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        STANDARD.decode(&self.data)
+    }
+}
+
+/// A base64-encoded image, for models with vision.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Image {
+    pub data: String,
+    /// The MIME type of the image (e.g. `"image/png"`, `"image/jpeg"`).
+    pub media_type: String,
+}
+
+impl Image {
+    pub fn new(bytes: impl AsRef<[u8]>, media_type: impl Into<String>) -> Self {
+        Self {
+            data: STANDARD.encode(bytes),
+            media_type: media_type.into(),
+        }
+    }
+
+    /// Decodes the image's bytes.
+    pub fn bytes(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        STANDARD.decode(&self.data)
+    }
+}
+
+/// The voice and encoding of audio replies.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AudioOutput {
+    pub voice: String,
+    /// The encoding of the reply (e.g. `"pcm16"`, which most providers require when streaming).
+    pub format: String,
+}
+
+impl AudioOutput {
+    pub fn new(voice: impl Into<String>, format: impl Into<String>) -> Self {
+        Self {
+            voice: voice.into(),
+            format: format.into(),
+        }
+    }
+}
@@ -1,9 +1,29 @@
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::models::{ContentPart, ToolCall};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Message<C = String> {
     pub content: C,
     pub role: MessageRole,
+    /// The ID of the tool call a [`MessageRole::Tool`] message is answering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// The tool calls an assistant message made.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// Non-text parts, such as audio, sent alongside `content`.
+    ///
+    /// Like `thinking`, it isn't serialized, as each API takes parts in its own
+    /// shape, so providers map them explicitly.
+    #[serde(skip)]
+    pub parts: Vec<ContentPart>,
+    /// The signed thinking an assistant message began with, which Anthropic
+    /// requires back unchanged when the message made tool calls.
+    ///
+    /// It isn't serialized, as OpenAI-compatible APIs reject unknown fields.
+    #[serde(skip)]
+    pub thinking: Vec<SignedThinking>,
 }
 
 impl Message {
@@ -11,6 +31,10 @@ impl Message {
         Self {
             content: content.into(),
             role,
+            tool_call_id: None,
+            tool_calls: Vec::new(),
+            parts: Vec::new(),
+            thinking: Vec::new(),
         }
     }
 
@@ -25,6 +49,112 @@ impl Message {
     pub fn system(content: impl Into<String>) -> Self {
         Self::new(content, MessageRole::System)
     }
+
+    /// The result of the tool call with ID `tool_call_id`.
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new(content, MessageRole::Tool)
+        }
+    }
+
+    /// The structured result of the tool call with ID `tool_call_id`, serialized as JSON.
+    pub fn tool_json<T: Serialize>(
+        tool_call_id: impl Into<String>,
+        result: &T,
+    ) -> Result<Self, serde_json::Error> {
+        Ok(Self::tool(tool_call_id, serde_json::to_string(result)?))
+    }
+
+    /// Attaches the tool calls an assistant message made.
+    pub fn tool_calls(mut self, tool_calls: Vec<ToolCall>) -> Self {
+        self.tool_calls = tool_calls;
+        self
+    }
+
+    /// Attaches a non-text part, such as audio.
+    pub fn part(mut self, part: impl Into<ContentPart>) -> Self {
+        self.parts.push(part.into());
+        self
+    }
+
+    /// Attaches the signed thinking an assistant message began with.
+    pub fn thinking(mut self, thinking: Vec<SignedThinking>) -> Self {
+        self.thinking = thinking;
+        self
+    }
+}
+
+/// Builds a conversation's history one message at a time, e.g.
+/// `MessageBuilder::new().system("Be brief.").user("Hi").build()`.
+///
+/// See also [`messages!`](crate::messages), for histories without tool results.
+#[derive(Debug, Clone, Default)]
+pub struct MessageBuilder {
+    messages: Vec<Message>,
+}
+
+impl MessageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn system(self, content: impl Into<String>) -> Self {
+        self.message(Message::system(content))
+    }
+
+    pub fn user(self, content: impl Into<String>) -> Self {
+        self.message(Message::user(content))
+    }
+
+    pub fn assistant(self, content: impl Into<String>) -> Self {
+        self.message(Message::assistant(content))
+    }
+
+    /// Adds the result of the tool call with ID `tool_call_id`.
+    pub fn tool(self, tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        self.message(Message::tool(tool_call_id, content))
+    }
+
+    /// Adds a message built separately, e.g. with tool calls or parts attached.
+    pub fn message(mut self, message: Message) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    pub fn build(self) -> Vec<Message> {
+        self.messages
+    }
+}
+
+/// Builds a `Vec<Message>` from each message's role and content, e.g.
+/// `messages![system "Be brief.", user "Hi", assistant "Hello!"]`.
+///
+/// The roles are `system`, `user` and `assistant`, and the content is anything
+/// convertible into a `String`.
+#[macro_export]
+macro_rules! messages {
+    ($($role:ident $content:expr),* $(,)?) => {
+        <::std::vec::Vec<$crate::models::Message>>::from([
+            $($crate::models::Message::$role($content)),*
+        ])
+    };
+}
+
+/// A block of thinking and the signature the provider verifies it with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SignedThinking {
+    pub thinking: String,
+    pub signature: String,
+}
+
+impl SignedThinking {
+    pub fn new(thinking: impl Into<String>, signature: impl Into<String>) -> Self {
+        Self {
+            thinking: thinking.into(),
+            signature: signature.into(),
+        }
+    }
 }
 
 impl<T> From<T> for Message
@@ -95,3 +225,44 @@ impl<'de> Deserialize<'de> for MessageRole {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roles(messages: &[Message]) -> Vec<&str> {
+        messages
+            .iter()
+            .map(|message| message.role.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn messages_macro_builds_each_role() {
+        let question = String::from("And of Italy?");
+        let messages = crate::messages![
+            system "Be brief.",
+            user "What's the capital of France?",
+            assistant "Paris.",
+            user question,
+        ];
+
+        assert_eq!(roles(&messages), ["system", "user", "assistant", "user"]);
+        assert_eq!(messages[3].content, "And of Italy?");
+        assert!(crate::messages![].is_empty());
+    }
+
+    #[test]
+    fn builder_matches_the_macro() {
+        let messages = MessageBuilder::new()
+            .user("What time is it?")
+            .message(
+                Message::assistant("").tool_calls(vec![ToolCall::new("call_1", "get_time", "{}")]),
+            )
+            .tool("call_1", "12:00")
+            .build();
+
+        assert_eq!(roles(&messages), ["user", "assistant", "tool"]);
+        assert_eq!(messages[2].tool_call_id.as_deref(), Some("call_1"));
+    }
+}
@@ -1,5 +1,11 @@
+mod content;
+pub use content::*;
+
 mod message;
 pub use message::*;
 
 mod model;
 pub use model::*;
+
+mod tool;
+pub use tool::*;
@@ -1,5 +1,7 @@
 use phf::phf_map;
 
+use crate::providers::chat::Thinking;
+
 static REPLACEMENT_WORDS: phf::Map<&'static str, &'static str> = phf_map! {
     "ai" => "AI",
     "api" => "API",
@@ -15,12 +17,41 @@ static REPLACEMENT_WORDS: phf::Map<&'static str, &'static str> = phf_map! {
     "xxl" => "XXL",
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Model {
     pub id: String,
     pub parameters: Option<ModelParams>,
     pub quantization: Option<ModelQuant>,
     pub thinking: Option<ThinkingModes>,
+    /// The maximum number of tokens (prompt + completion) the model accepts.
+    pub context_length: Option<usize>,
+    pub pricing: Option<ModelPricing>,
+    pub capabilities: ModelCapabilities,
+    /// The name of the provider which listed the model, set by
+    /// [`CombinedModels`](crate::providers::CombinedModels).
+    pub provider: Option<String>,
+}
+
+/// Features a model supports beyond plain text chat.
+///
+/// Providers that can't report a capability leave it as `false`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    /// Accepts images as input.
+    pub vision: bool,
+    pub tool_use: bool,
+    /// Supports [`ResponseFormat`](crate::ResponseFormat).
+    pub json_mode: bool,
+    pub thinking: bool,
+    /// Produces embeddings rather than chat completions.
+    pub embeddings: bool,
+}
+
+/// The price of using a model, in USD per token.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -35,7 +66,36 @@ pub struct ThinkingBudget {
     pub max: usize,
 }
 
+impl<M> ThinkingModes<M> {
+    /// Whether `thinking` can be honoured: efforts must be one of the
+    /// `modes` and budgets must fall within `budget`.
+    pub fn supports<S: AsRef<str>>(&self, thinking: &Thinking) -> bool
+    where
+        M: AsRef<[S]>,
+    {
+        match thinking {
+            Thinking::Enabled => true,
+            Thinking::BudgetTokens(tokens) => self
+                .budget
+                .is_some_and(|budget| (budget.min..=budget.max).contains(tokens)),
+            Thinking::Effort(effort) => self
+                .modes
+                .as_ref()
+                .iter()
+                .any(|mode| mode.as_ref() == effort),
+        }
+    }
+}
+
 impl Model {
+    /// Whether the model can think as requested by `thinking`.
+    /// Models without known thinking modes support none.
+    pub fn supports_thinking(&self, thinking: &Thinking) -> bool {
+        self.thinking
+            .as_ref()
+            .is_some_and(|modes| modes.supports(thinking))
+    }
+
     /// Returns a prettified model name.
     ///
     /// Strips the tag suffix after `:`, replaces `_` and `-` with spaces
@@ -139,3 +199,49 @@ impl ToString for Model {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(modes: &[&str], budget: Option<ThinkingBudget>) -> Model {
+        Model {
+            id: "test".to_owned(),
+            thinking: Some(ThinkingModes {
+                modes: modes.iter().map(|mode| (*mode).to_owned()).collect(),
+                budget,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn supports_thinking() {
+        let budget = Some(ThinkingBudget {
+            min: 1024,
+            max: 128000,
+        });
+        let budget_only = model(&[], budget);
+        let effort_only = model(&["low", "high"], None);
+
+        assert!(budget_only.supports_thinking(&Thinking::enabled()));
+        assert!(budget_only.supports_thinking(&Thinking::budget_tokens(2048)));
+        assert!(!budget_only.supports_thinking(&Thinking::budget_tokens(512)));
+        assert!(!budget_only.supports_thinking(&Thinking::effort("high")));
+
+        assert!(effort_only.supports_thinking(&Thinking::enabled()));
+        assert!(effort_only.supports_thinking(&Thinking::effort("high")));
+        assert!(!effort_only.supports_thinking(&Thinking::effort("max")));
+        assert!(!effort_only.supports_thinking(&Thinking::budget_tokens(2048)));
+    }
+
+    #[test]
+    fn supports_no_thinking_without_modes() {
+        let model = Model {
+            id: "test".to_owned(),
+            ..Default::default()
+        };
+
+        assert!(!model.supports_thinking(&Thinking::enabled()));
+    }
+}
@@ -0,0 +1,323 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::DeserializeOwned};
+use serde_json::{Map, Value, json};
+
+/// A function the model may call.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tool {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// A JSON schema describing the function's arguments.
+    pub parameters: Value,
+}
+
+impl Tool {
+    pub fn new(name: impl Into<String>, parameters: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            parameters,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Starts declaring a tool whose parameters are built up one by one.
+    pub fn builder(name: impl Into<String>) -> ToolBuilder {
+        ToolBuilder::new(name)
+    }
+
+    /// Creates a tool whose parameters are described by `T`'s JSON schema.
+    #[cfg(feature = "schemars")]
+    pub fn from_schema<T: schemars::JsonSchema>(name: impl Into<String>) -> Self {
+        let mut parameters = schemars::schema_for!(T).to_value();
+        if let Some(schema) = parameters.as_object_mut() {
+            // Only describes the schema itself, and some providers reject it.
+            schema.remove("$schema");
+            schema.remove("title");
+        }
+        Self::new(name, parameters)
+    }
+}
+
+/// The type of a [`ToolBuilder`] parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    /// A string which must be one of the given values.
+    Enum(Vec<String>),
+    Array(Box<ParamType>),
+    /// A JSON schema, for types which can't be described otherwise.
+    Schema(Value),
+}
+
+impl ParamType {
+    pub fn array(items: ParamType) -> Self {
+        Self::Array(Box::new(items))
+    }
+
+    pub fn enumeration<S: Into<String>>(values: impl IntoIterator<Item = S>) -> Self {
+        Self::Enum(values.into_iter().map(Into::into).collect())
+    }
+
+    fn schema(&self) -> Value {
+        match self {
+            Self::String => json!({ "type": "string" }),
+            Self::Integer => json!({ "type": "integer" }),
+            Self::Number => json!({ "type": "number" }),
+            Self::Boolean => json!({ "type": "boolean" }),
+            Self::Enum(values) => json!({ "type": "string", "enum": values }),
+            Self::Array(items) => json!({ "type": "array", "items": items.schema() }),
+            Self::Schema(schema) => schema.clone(),
+        }
+    }
+}
+
+/// Declares a [`Tool`] without writing its parameters' JSON schema by hand.
+///
+/// ```
+/// use anyml_core::models::{ParamType, Tool};
+///
+/// let tool = Tool::builder("get_weather")
+///     .description("Gets the current weather")
+///     .param("city", ParamType::String, "The city to get the weather for")
+///     .optional_param("unit", ParamType::enumeration(["celsius", "fahrenheit"]), "The unit to use")
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ToolBuilder {
+    name: String,
+    description: Option<String>,
+    properties: Map<String, Value>,
+    required: Vec<String>,
+}
+
+impl ToolBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            properties: Map::new(),
+            required: Vec::new(),
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds a parameter the model must provide.
+    pub fn param(
+        mut self,
+        name: impl Into<String>,
+        param_type: ParamType,
+        description: impl Into<String>,
+    ) -> Self {
+        let name = name.into();
+        self.required.push(name.clone());
+        self.push_property(name, param_type, description.into())
+    }
+
+    /// Adds a parameter the model may leave out.
+    pub fn optional_param(
+        self,
+        name: impl Into<String>,
+        param_type: ParamType,
+        description: impl Into<String>,
+    ) -> Self {
+        self.push_property(name.into(), param_type, description.into())
+    }
+
+    fn push_property(mut self, name: String, param_type: ParamType, description: String) -> Self {
+        let mut schema = param_type.schema();
+        if let Some(schema) = schema.as_object_mut() {
+            schema.insert("description".into(), description.into());
+        }
+        self.properties.insert(name, schema);
+        self
+    }
+
+    pub fn build(self) -> Tool {
+        Tool {
+            name: self.name,
+            description: self.description,
+            parameters: json!({
+                "type": "object",
+                "properties": self.properties,
+                "required": self.required,
+            }),
+        }
+    }
+}
+
+/// A complete tool call made by the model.
+///
+/// Serializes in the `{"id", "type": "function", "function": {"name", "arguments"}}`
+/// shape used by OpenAI-compatible APIs, so it can be sent back as part of the history.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// The arguments as a JSON string, exactly as the model produced them.
+    pub arguments: String,
+}
+
+impl ToolCall {
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        arguments: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            arguments: arguments.into(),
+        }
+    }
+
+    /// Parses the arguments as JSON.
+    /// Empty arguments are treated as an empty object.
+    pub fn arguments_json(&self) -> Result<Value, serde_json::Error> {
+        self.parse_arguments()
+    }
+
+    /// Deserializes the arguments into `T`.
+    /// Empty arguments are treated as an empty object.
+    pub fn parse_arguments<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        if self.arguments.trim().is_empty() {
+            serde_json::from_str("{}")
+        } else {
+            serde_json::from_str(&self.arguments)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireToolCall<S> {
+    id: S,
+    #[serde(default)]
+    r#type: S,
+    function: WireFunction<S>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireFunction<S> {
+    name: S,
+    #[serde(default)]
+    arguments: S,
+}
+
+impl Serialize for ToolCall {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        WireToolCall {
+            id: self.id.as_str(),
+            r#type: "function",
+            function: WireFunction {
+                name: self.name.as_str(),
+                arguments: self.arguments.as_str(),
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolCall {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let wire = WireToolCall::<String>::deserialize(deserializer)?;
+        Ok(ToolCall {
+            id: wire.id,
+            name: wire.function.name,
+            arguments: wire.function.arguments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_produces_object_schema() {
+        let tool = Tool::builder("get_weather")
+            .description("Gets the current weather")
+            .param("city", ParamType::String, "The city")
+            .optional_param(
+                "unit",
+                ParamType::enumeration(["celsius", "fahrenheit"]),
+                "The unit",
+            )
+            .optional_param("days", ParamType::array(ParamType::Integer), "Days ahead")
+            .build();
+
+        assert_eq!(tool.name, "get_weather");
+        assert_eq!(
+            tool.description.as_deref(),
+            Some("Gets the current weather")
+        );
+        assert_eq!(
+            tool.parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "city": { "type": "string", "description": "The city" },
+                    "unit": {
+                        "type": "string",
+                        "enum": ["celsius", "fahrenheit"],
+                        "description": "The unit"
+                    },
+                    "days": {
+                        "type": "array",
+                        "items": { "type": "integer" },
+                        "description": "Days ahead"
+                    }
+                },
+                "required": ["city"]
+            })
+        );
+    }
+
+    #[test]
+    fn builder_without_params_still_describes_an_object() {
+        let tool = Tool::builder("get_time").build();
+
+        assert_eq!(
+            tool.parameters,
+            json!({ "type": "object", "properties": {}, "required": [] })
+        );
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn from_schema_derives_parameters() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct GetWeather {
+            /// The city
+            city: String,
+            unit: Option<String>,
+        }
+
+        let tool = Tool::from_schema::<GetWeather>("get_weather");
+
+        assert_eq!(tool.parameters["type"], "object");
+        assert_eq!(
+            tool.parameters["properties"]["city"]["description"],
+            "The city"
+        );
+        assert_eq!(tool.parameters["required"], json!(["city"]));
+        assert!(tool.parameters.get("$schema").is_none());
+    }
+}
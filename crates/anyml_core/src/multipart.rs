@@ -0,0 +1,134 @@
+//! `multipart/form-data` request bodies, for endpoints which take file uploads.
+
+/// Builds a `multipart/form-data` body from text fields and files.
+///
+/// The boundary is only chosen by [`Multipart::finish`], once every part is known,
+/// so it never occurs inside a part.
+#[derive(Debug, Default, Clone)]
+pub struct Multipart {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+struct Part {
+    /// The part's headers, without the boundary before them.
+    headers: String,
+    body: Vec<u8>,
+}
+
+impl Multipart {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a text field.
+    pub fn text(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.parts.push(Part {
+            headers: format!(
+                "Content-Disposition: form-data; name=\"{}\"\r\n",
+                escape(name)
+            ),
+            body: value.into().into_bytes(),
+        });
+        self
+    }
+
+    /// Adds a file, uploaded as `filename` with the MIME type `content_type`.
+    pub fn file(
+        mut self,
+        name: &str,
+        filename: &str,
+        content_type: &str,
+        bytes: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.parts.push(Part {
+            headers: format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {content_type}\r\n",
+                escape(name),
+                escape(filename),
+            ),
+            body: bytes.into(),
+        });
+        self
+    }
+
+    /// Returns the `Content-Type` header of the body and the body itself.
+    pub fn finish(self) -> (String, Vec<u8>) {
+        let boundary = (0u64..)
+            .map(|attempt| format!("anyml-boundary-{attempt:016x}"))
+            .find(|boundary| {
+                !self.parts.iter().any(|part| {
+                    contains(&part.body, boundary.as_bytes())
+                        || part.headers.contains(boundary.as_str())
+                })
+            })
+            .expect("a boundary which no part contains");
+
+        let mut body = Vec::new();
+        for part in self.parts {
+            body.extend_from_slice(format!("--{boundary}\r\n{}\r\n", part.headers).as_bytes());
+            body.extend_from_slice(&part.body);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+        (format!("multipart/form-data; boundary={boundary}"), body)
+    }
+}
+
+/// Escapes a quoted header parameter.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(['\r', '\n'], " ")
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_fields_and_files() {
+        let (content_type, body) = Multipart::new()
+            .text("model", "whisper-1")
+            .file("file", "audio.wav", "audio/wav", b"RIFF".to_vec())
+            .finish();
+
+        assert_eq!(
+            content_type,
+            "multipart/form-data; boundary=anyml-boundary-0000000000000000"
+        );
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--anyml-boundary-0000000000000000\r\n\
+             Content-Disposition: form-data; name=\"model\"\r\n\r\n\
+             whisper-1\r\n\
+             --anyml-boundary-0000000000000000\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"audio.wav\"\r\n\
+             Content-Type: audio/wav\r\n\r\n\
+             RIFF\r\n\
+             --anyml-boundary-0000000000000000--\r\n"
+        );
+    }
+
+    #[test]
+    fn boundary_avoids_part_contents() {
+        let (content_type, body) = Multipart::new()
+            .text("prompt", "--anyml-boundary-0000000000000000")
+            .finish();
+
+        assert!(content_type.ends_with("anyml-boundary-0000000000000001"));
+        assert!(
+            String::from_utf8(body)
+                .unwrap()
+                .starts_with("--anyml-boundary-0000000000000001\r\n")
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::maybe_send::{MaybeSend, MaybeSync};
+
+/// Supplies the API key sent with each request, for keys which rotate,
+/// expire (such as short-lived STS tokens) or are chosen per tenant.
+///
+/// A fixed [`SecretString`] is the simplest provider.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait ApiKeyProvider: MaybeSend + MaybeSync {
+    /// Returns the key to send with the next request.
+    async fn current_key(&self) -> anyhow::Result<SecretString>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl ApiKeyProvider for SecretString {
+    async fn current_key(&self) -> anyhow::Result<SecretString> {
+        Ok(SecretString::from(self.expose_secret().to_owned()))
+    }
+}
@@ -0,0 +1,529 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{Stream, channel::mpsc};
+use serde::Serialize;
+use web_time::Instant;
+
+use crate::models::{Model, ModelPricing};
+use crate::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse, Messages};
+use crate::providers::health::{HealthCheckProvider, HealthStatus};
+use crate::providers::list_models::{ListModelsError, ListModelsProvider};
+use crate::tokenize::{count_message_tokens, count_tokens, estimate_tokens};
+
+/// How much may be spent, in USD, and over what period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Budget {
+    pub limit: f64,
+    /// How long until the spending starts over, counted from the first chat.
+    /// `None` means never.
+    pub window: Option<Duration>,
+}
+
+impl Budget {
+    pub fn total(limit: f64) -> Self {
+        Self {
+            limit,
+            window: None,
+        }
+    }
+
+    pub fn per(limit: f64, window: Duration) -> Self {
+        Self {
+            limit,
+            window: Some(window),
+        }
+    }
+}
+
+/// Attributes a chat's spending to a key, e.g. a tenant or a user's API key,
+/// when sent through a [`BudgetedProvider`].
+///
+/// Chats without a key share the empty key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct BudgetKey(pub String);
+
+impl BudgetKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+}
+
+/// What a [`BudgetedProvider`] does with chats which would go over budget.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OverBudget {
+    /// Fails the chat with [`ChatError::BudgetExceeded`].
+    #[default]
+    Reject,
+    /// Sends the chat to a cheaper model instead, if it fits in the budget,
+    /// and rejects it otherwise.
+    Downgrade(Cow<'static, str>),
+}
+
+/// Reported by [`BudgetedProvider::subscribe`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetEvent {
+    /// The spending of `key` crossed `fraction` of its limit, one of those set
+    /// with [`BudgetedProvider::alert_at`].
+    Crossed {
+        key: String,
+        fraction: f64,
+        spent: f64,
+        limit: f64,
+    },
+    /// A chat for `requested` was sent to the cheaper `model`.
+    Downgraded {
+        key: String,
+        requested: String,
+        model: String,
+    },
+    /// A chat was rejected with [`ChatError::BudgetExceeded`].
+    Rejected { key: String, model: String },
+}
+
+/// Tracks what the chats sent through a provider cost, by their [`BudgetKey`],
+/// and stops chats which would go over budget.
+///
+/// A chat's cost is its reported [`Usage`](crate::providers::chat::Usage) at
+/// the model's price. Before a chat is sent its prompt is counted, and the chat
+/// is over budget if the prompt's cost would take the spending past the limit.
+/// As replies are only paid for once they're done, chats in flight together can
+/// still overspend by their replies.
+///
+/// Chats to models without a price aren't counted. Cloning the provider shares
+/// its spending.
+#[derive(Debug, Clone)]
+pub struct BudgetedProvider<P> {
+    provider: P,
+    budget: Budget,
+    budgets: HashMap<String, Budget>,
+    prices: HashMap<String, ModelPricing>,
+    over_budget: OverBudget,
+    thresholds: Vec<f64>,
+    ledger: Arc<Ledger>,
+}
+
+impl<P> BudgetedProvider<P> {
+    /// Gives every key `budget`.
+    pub fn new(provider: P, budget: Budget) -> Self {
+        Self {
+            provider,
+            budget,
+            budgets: HashMap::new(),
+            prices: HashMap::new(),
+            over_budget: OverBudget::default(),
+            thresholds: Vec::new(),
+            ledger: Arc::default(),
+        }
+    }
+
+    /// Gives `key` its own budget instead of the default one.
+    pub fn key_budget(mut self, key: impl Into<String>, budget: Budget) -> Self {
+        self.budgets.insert(key.into(), budget);
+        self
+    }
+
+    pub fn price(mut self, model: impl Into<String>, pricing: ModelPricing) -> Self {
+        self.prices.insert(model.into(), pricing);
+        self
+    }
+
+    /// Takes the prices of the listed models which have one, e.g. from OpenRouter.
+    pub fn prices_from<'m>(mut self, models: impl IntoIterator<Item = &'m Model>) -> Self {
+        for model in models {
+            if let Some(pricing) = model.pricing {
+                self.prices.insert(model.id.clone(), pricing);
+            }
+        }
+        self
+    }
+
+    pub fn over_budget(mut self, over_budget: OverBudget) -> Self {
+        self.over_budget = over_budget;
+        self
+    }
+
+    /// Reports a [`BudgetEvent::Crossed`] when a key's spending crosses each of
+    /// these fractions of its limit, e.g. `[0.5, 0.9]`.
+    pub fn alert_at(mut self, fractions: impl IntoIterator<Item = f64>) -> Self {
+        self.thresholds = fractions.into_iter().collect();
+        self.thresholds.sort_by(f64::total_cmp);
+        self
+    }
+
+    /// Streams thresholds being crossed, and chats being downgraded or rejected.
+    pub fn subscribe(&self) -> impl Stream<Item = BudgetEvent> + use<P> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.ledger.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// How much `key` has spent in its current window.
+    pub fn spent(&self, key: &str) -> f64 {
+        self.ledger.spent(key, self.budget_of(key))
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn into_inner(self) -> P {
+        self.provider
+    }
+
+    fn budget_of(&self, key: &str) -> Budget {
+        self.budgets.get(key).copied().unwrap_or(self.budget)
+    }
+
+    /// What the prompt of `options` costs if sent to `model`, if it has a price.
+    fn prompt_cost(&self, model: &str, options: &ChatOptions<'_>) -> Option<f64> {
+        let pricing = self.prices.get(model)?;
+        let system = options
+            .system
+            .map_or(0, |system| count_tokens(model, system));
+        let messages: usize = match &options.messages {
+            Messages::Raw(messages) => messages
+                .iter()
+                .map(|message| count_message_tokens(model, message))
+                .sum(),
+            Messages::Serialized(messages) => estimate_tokens(messages.get()),
+        };
+        Some((system + messages) as f64 * pricing.input)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider> ChatProvider for BudgetedProvider<P> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        // Priced by the model the chat goes to, including the provider's default.
+        let options: &ChatOptions = &options.or_default_model(self.provider.default_model());
+        let key = options
+            .extensions
+            .get::<BudgetKey>()
+            .map_or("", |key| key.0.as_str());
+        let budget = self.budget_of(key);
+        let spent = self.ledger.spent(key, budget);
+        let fits = |model: &str| {
+            self.prompt_cost(model, options)
+                .is_none_or(|cost| spent + cost <= budget.limit)
+        };
+
+        let downgraded;
+        let options = if fits(options.model) {
+            options
+        } else {
+            match &self.over_budget {
+                OverBudget::Downgrade(model) if fits(model) => {
+                    self.ledger.notify(BudgetEvent::Downgraded {
+                        key: key.to_owned(),
+                        requested: options.model.to_owned(),
+                        model: model.to_string(),
+                    });
+                    downgraded = options.clone().model(model);
+                    &downgraded
+                }
+                _ => {
+                    self.ledger.notify(BudgetEvent::Rejected {
+                        key: key.to_owned(),
+                        model: options.model.to_owned(),
+                    });
+                    return Err(ChatError::BudgetExceeded {
+                        key: key.to_owned(),
+                        spent,
+                        limit: budget.limit,
+                    });
+                }
+            }
+        };
+
+        let response = self.provider.chat(options).await?;
+
+        let Some(pricing) = self.prices.get(options.model).copied() else {
+            return Ok(response);
+        };
+        let (ledger, key, thresholds) =
+            (self.ledger.clone(), key.to_owned(), self.thresholds.clone());
+        Ok(response.on_usage(move |usage| {
+            let cost = usage.input_tokens.unwrap_or(0) as f64 * pricing.input
+                + usage.output_tokens.unwrap_or(0) as f64 * pricing.output;
+            ledger.charge(&key, budget, cost, &thresholds);
+        }))
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.provider.default_model()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ListModelsProvider> ListModelsProvider for BudgetedProvider<P> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        self.provider.list_models().await
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.provider.get_model(id).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: HealthCheckProvider> HealthCheckProvider for BudgetedProvider<P> {
+    async fn health(&self) -> HealthStatus {
+        self.provider.health().await
+    }
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    spending: Mutex<HashMap<String, Spending>>,
+    subscribers: Mutex<Vec<mpsc::UnboundedSender<BudgetEvent>>>,
+}
+
+#[derive(Debug)]
+struct Spending {
+    spent: f64,
+    since: Instant,
+    /// How many of the thresholds were already crossed.
+    crossed: usize,
+}
+
+impl Ledger {
+    fn spent(&self, key: &str, budget: Budget) -> f64 {
+        let mut spending = self.spending.lock().unwrap();
+        spending
+            .get_mut(key)
+            .map_or(0.0, |spending| spending.current(budget).spent)
+    }
+
+    fn charge(&self, key: &str, budget: Budget, cost: f64, thresholds: &[f64]) {
+        let mut crossed = Vec::new();
+        {
+            let mut spending = self.spending.lock().unwrap();
+            let spending = spending.entry(key.to_owned()).or_insert(Spending {
+                spent: 0.0,
+                since: Instant::now(),
+                crossed: 0,
+            });
+            let spending = spending.current(budget);
+            spending.spent += cost;
+
+            while let Some(&fraction) = thresholds.get(spending.crossed)
+                && spending.spent >= fraction * budget.limit
+            {
+                crossed.push(BudgetEvent::Crossed {
+                    key: key.to_owned(),
+                    fraction,
+                    spent: spending.spent,
+                    limit: budget.limit,
+                });
+                spending.crossed += 1;
+            }
+        }
+
+        for event in crossed {
+            self.notify(event);
+        }
+    }
+
+    fn notify(&self, event: BudgetEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+    }
+}
+
+impl Spending {
+    /// Starts the spending over if its window has passed.
+    fn current(&mut self, budget: Budget) -> &mut Self {
+        if budget
+            .window
+            .is_some_and(|window| self.since.elapsed() >= window)
+        {
+            *self = Self {
+                spent: 0.0,
+                since: Instant::now(),
+                crossed: 0,
+            };
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use crate::providers::chat::{ChatChunk, Usage};
+    use crate::providers::default_model::DefaultModelProvider;
+    use crate::providers::mock::{MockChatProvider, MockReply};
+    use futures::{StreamExt, executor::block_on};
+
+    /// A reply using 1000 tokens each way.
+    fn reply() -> MockReply {
+        MockReply::text("Hi").chunk(ChatChunk::Usage(Usage {
+            input_tokens: Some(1000),
+            output_tokens: Some(1000),
+            ..Default::default()
+        }))
+    }
+
+    const PER_MILLION: f64 = 1.0 / 1_000_000.0;
+
+    fn provider() -> BudgetedProvider<MockChatProvider> {
+        BudgetedProvider::new(MockChatProvider::new(), Budget::total(0.03))
+            .price(
+                "gpt-4o",
+                ModelPricing {
+                    input: 2.5 * PER_MILLION,
+                    output: 10.0 * PER_MILLION,
+                },
+            )
+            .price(
+                "gpt-4o-mini",
+                ModelPricing {
+                    input: 0.15 * PER_MILLION,
+                    output: 0.6 * PER_MILLION,
+                },
+            )
+    }
+
+    /// Chats with `model`, returning the model the chat was sent to.
+    fn reply_model(
+        provider: &BudgetedProvider<MockChatProvider>,
+        key: &str,
+        model: &str,
+        content: &str,
+    ) -> Result<String, ChatError> {
+        provider.inner().push_reply(reply());
+        let messages = [Message::user(content)];
+        let options = ChatOptions::new(model)
+            .messages(&messages)
+            .extension(BudgetKey::new(key));
+        block_on(async {
+            let mut response = provider.chat(&options).await?;
+            response.aggregate().await.unwrap();
+            let requests = provider.inner().requests();
+            Ok(requests.last().unwrap().model().to_owned())
+        })
+    }
+
+    #[test]
+    fn rejects_chats_once_the_budget_is_spent() {
+        let provider = provider();
+        let events = provider.subscribe();
+
+        // Each chat costs $0.0125.
+        for _ in 0..3 {
+            assert!(reply_model(&provider, "acme", "gpt-4o", "Hello").is_ok());
+        }
+        assert!((provider.spent("acme") - 0.0375).abs() < 1e-9);
+        assert!(matches!(
+            reply_model(&provider, "acme", "gpt-4o", "Hello"),
+            Err(ChatError::BudgetExceeded { key, .. }) if key == "acme"
+        ));
+
+        // Other keys have budgets of their own, and unpriced models are free.
+        assert!(reply_model(&provider, "globex", "gpt-4o", "Hello").is_ok());
+        assert!(reply_model(&provider, "acme", "llama3", "Hello").is_ok());
+
+        drop(provider);
+        let events: Vec<_> = block_on(events.collect());
+        assert_eq!(
+            events,
+            [BudgetEvent::Rejected {
+                key: "acme".to_owned(),
+                model: "gpt-4o".to_owned()
+            }]
+        );
+    }
+
+    #[test]
+    fn charges_the_merged_usage_once() {
+        let provider = provider();
+        // Reported like Anthropic: the input first, then the running totals.
+        let reply = || {
+            MockReply::text("Hi")
+                .chunk(ChatChunk::Usage(Usage {
+                    input_tokens: Some(1000),
+                    ..Default::default()
+                }))
+                .chunk(ChatChunk::Usage(Usage {
+                    input_tokens: Some(1000),
+                    output_tokens: Some(1000),
+                    ..Default::default()
+                }))
+        };
+        let messages = [Message::user("Hello")];
+        let options = ChatOptions::new("gpt-4o").messages(&messages);
+
+        provider.inner().push_reply(reply());
+        let mut response = block_on(provider.chat(&options)).unwrap();
+        block_on(response.aggregate()).unwrap();
+        assert!((provider.spent("") - 0.0125).abs() < 1e-9);
+
+        // A response dropped midway is charged for the usage reported so far.
+        provider.inner().push_reply(reply());
+        let mut response = block_on(provider.chat(&options)).unwrap();
+        while !matches!(block_on(response.next()), Some(Ok(ChatChunk::Usage(_)))) {}
+        drop(response);
+        assert!((provider.spent("") - 0.015).abs() < 1e-9);
+    }
+
+    #[test]
+    fn prices_the_default_model() {
+        let provider = DefaultModelProvider::new(MockChatProvider::new().reply(reply()), "gpt-4o");
+        let provider = BudgetedProvider::new(provider, Budget::total(0.03)).price(
+            "gpt-4o",
+            ModelPricing {
+                input: 2.5 * PER_MILLION,
+                output: 10.0 * PER_MILLION,
+            },
+        );
+        let messages = [Message::user("Hello")];
+
+        let mut response =
+            block_on(provider.chat(&ChatOptions::default_model().messages(&messages))).unwrap();
+        block_on(response.aggregate()).unwrap();
+
+        assert!((provider.spent("") - 0.0125).abs() < 1e-9);
+    }
+
+    #[test]
+    fn downgrades_prompts_which_dont_fit() {
+        let provider = provider()
+            .over_budget(OverBudget::Downgrade("gpt-4o-mini".into()))
+            .alert_at([0.9, 0.5]);
+        let events = provider.subscribe();
+
+        for _ in 0..2 {
+            assert_eq!(
+                reply_model(&provider, "acme", "gpt-4o", "Hello").unwrap(),
+                "gpt-4o"
+            );
+        }
+        // About 3000 tokens, which cost more than the $0.005 left with gpt-4o.
+        let long = "word ".repeat(2500);
+        assert_eq!(
+            reply_model(&provider, "acme", "gpt-4o", &long).unwrap(),
+            "gpt-4o-mini"
+        );
+
+        drop(provider);
+        let events: Vec<_> = block_on(events.collect());
+        assert!(matches!(
+            &events[..],
+            [
+                BudgetEvent::Crossed { fraction: 0.5, .. },
+                BudgetEvent::Downgraded { requested, model, .. },
+            ] if requested == "gpt-4o" && model == "gpt-4o-mini"
+        ));
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use futures::StreamExt;
+
+use crate::maybe_send::{MaybeSend, MaybeSync};
+use crate::models::Model;
+use crate::providers::chat::{AggregatedChat, ChatError, ChatOptions, ChatProvider, ChatResponse};
+use crate::providers::health::{HealthCheckProvider, HealthStatus};
+use crate::providers::list_models::{ListModelsError, ListModelsProvider};
+use crate::providers::overrides::RequestOverrides;
+
+/// Where a [`CachedProvider`] keeps its responses, by the [`cache_key`] of their request.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait CacheStore: MaybeSend + MaybeSync {
+    async fn get(&self, key: u64) -> Option<AggregatedChat>;
+
+    async fn put(&self, key: u64, chat: AggregatedChat);
+}
+
+/// A [`CacheStore`] keeping every response in memory.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    chats: Mutex<HashMap<u64, AggregatedChat>>,
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chats.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.chats.lock().unwrap().clear();
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl CacheStore for MemoryCache {
+    async fn get(&self, key: u64) -> Option<AggregatedChat> {
+        self.chats.lock().unwrap().get(&key).cloned()
+    }
+
+    async fn put(&self, key: u64, chat: AggregatedChat) {
+        self.chats.lock().unwrap().insert(key, chat);
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<S: CacheStore + ?Sized> CacheStore for Arc<S> {
+    async fn get(&self, key: u64) -> Option<AggregatedChat> {
+        (**self).get(key).await
+    }
+
+    async fn put(&self, key: u64, chat: AggregatedChat) {
+        (**self).put(key, chat).await
+    }
+}
+
+/// Identifies the reply a request asks for: its model, messages and every option
+/// affecting the reply, but not options such as timeouts or streaming.
+///
+/// It's the [`ChatOptions::fingerprint`] of the request with those options reset,
+/// so it's stable across runs and can be used by persistent stores. An override
+/// URL is kept, as another server may reply differently, but the override API
+/// key and headers aren't.
+pub fn cache_key(options: &ChatOptions<'_>) -> u64 {
+    let mut options = options.clone();
+    options.stream = true;
+    options.session_id = None;
+    options.timeout = None;
+    options.first_chunk_timeout = None;
+    options.overrides = RequestOverrides {
+        url: options.overrides.url.take(),
+        ..RequestOverrides::new()
+    };
+    options.fingerprint()
+}
+
+/// Caches the responses of a provider, replaying them for identical requests.
+///
+/// Requests are identified by their [`cache_key`]. A response is only cached once
+/// it has streamed to the end without errors, and is replayed as a single chunk per
+/// part of the [`AggregatedChat`] (see [`AggregatedChat::into_chunks`]).
+#[derive(Debug, Clone)]
+pub struct CachedProvider<P, S> {
+    provider: P,
+    store: Arc<S>,
+}
+
+impl<P, S: CacheStore> CachedProvider<P, S> {
+    pub fn new(provider: P, store: S) -> Self {
+        Self {
+            provider,
+            store: Arc::new(store),
+        }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn into_inner(self) -> P {
+        self.provider
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider, S: CacheStore + 'static> ChatProvider for CachedProvider<P, S> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        // Chats left to the default model share entries with chats naming it.
+        let options: &ChatOptions = &options.or_default_model(self.provider.default_model());
+        let key = cache_key(options);
+        if let Some(chat) = self.store.get(key).await {
+            return Ok(ChatResponse::new(futures::stream::iter(
+                chat.into_chunks().into_iter().map(Ok),
+            )));
+        }
+
+        let response = self.provider.chat(options).await?;
+
+        // Aggregates a copy of the response as it streams, until an error spoils it.
+        let aggregated = Arc::new(Mutex::new(Some(AggregatedChat::default())));
+        let record = {
+            let aggregated = aggregated.clone();
+            move |item: &Result<_, _>| {
+                let mut aggregated = aggregated.lock().unwrap();
+                match item {
+                    Ok(chunk) => {
+                        if let Some(chat) = aggregated.as_mut() {
+                            chat.push(chunk);
+                        }
+                    }
+                    Err(_) => *aggregated = None,
+                }
+            }
+        };
+        let store = self.store.clone();
+        let finished = futures::stream::once(async move {
+            let chat = aggregated.lock().unwrap().take();
+            if let Some(chat) = chat {
+                store.put(key, chat).await;
+            }
+        })
+        .filter_map(|()| futures::future::ready(None));
+
+        Ok(response.map_stream(|stream| stream.inspect(record).chain(finished)))
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.provider.default_model()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ListModelsProvider, S: CacheStore + 'static> ListModelsProvider for CachedProvider<P, S> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        self.provider.list_models().await
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.provider.get_model(id).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: HealthCheckProvider, S: CacheStore + 'static> HealthCheckProvider for CachedProvider<P, S> {
+    async fn health(&self) -> HealthStatus {
+        self.provider.health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Image, Message};
+    use crate::providers::chat::{ChatChunk, ChatStreamError, FinishReason, ToolCallDelta, Usage};
+    use crate::providers::mock::{MockChatProvider, MockReply};
+    use futures::executor::block_on;
+    use std::time::Duration;
+
+    /// A reply with every kind of chunk the cache stores, up to its finish.
+    fn reply(content: &str) -> MockReply {
+        MockReply::new()
+            .chunk(ChatChunk::Thinking("Counting...".into()))
+            .chunk(ChatChunk::Content(content.to_owned().into()))
+            .chunk(ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 0,
+                id: Some("call_1".to_owned()),
+                name: Some("count".to_owned()),
+                arguments: "{}".to_owned(),
+            }))
+            .chunk(ChatChunk::Usage(Usage {
+                output_tokens: Some(3),
+                ..Default::default()
+            }))
+    }
+
+    /// A provider replying with its call count.
+    fn counting() -> MockChatProvider {
+        MockChatProvider::new()
+            .reply(reply("Call 1").chunk(ChatChunk::Finish(FinishReason::Stop)))
+            .reply(reply("Call 2").chunk(ChatChunk::Finish(FinishReason::Stop)))
+    }
+
+    fn chat(provider: &impl ChatProvider, options: &ChatOptions<'_>) -> AggregatedChat {
+        let mut response = block_on(provider.chat(options)).unwrap();
+        block_on(response.aggregate_lossy())
+    }
+
+    #[test]
+    fn replays_identical_requests() {
+        let provider = CachedProvider::new(counting(), MemoryCache::new());
+        let messages = [Message::user("Count")];
+        let options = ChatOptions::new("llama3").messages(&messages);
+
+        let first = chat(&provider, &options);
+        let second = chat(
+            &provider,
+            &options.clone().timeout(std::time::Duration::from_secs(5)),
+        );
+
+        assert_eq!(provider.inner().calls(), 1);
+        assert_eq!(second.content, "Call 1");
+        assert_eq!(second.thinking, first.thinking);
+        assert_eq!(second.tool_calls, first.tool_calls);
+        assert_eq!(second.usage, first.usage);
+        assert_eq!(second.finish_reason, Some(FinishReason::Stop));
+    }
+
+    #[test]
+    fn different_requests_miss() {
+        let provider = CachedProvider::new(counting(), MemoryCache::new());
+        let messages = [Message::user("Count")];
+        let options = ChatOptions::new("llama3").messages(&messages);
+
+        chat(&provider, &options);
+        let other = chat(&provider, &options.clone().max_tokens(100));
+
+        assert_eq!(other.content, "Call 2");
+        assert_eq!(provider.store().len(), 2);
+    }
+
+    #[test]
+    fn key_ignores_options_not_affecting_the_reply() {
+        let messages = [Message::user("Hi")];
+        let options = ChatOptions::new("llama3").messages(&messages);
+
+        assert_eq!(
+            cache_key(&options),
+            cache_key(
+                &options
+                    .clone()
+                    .stream(false)
+                    .timeout(Duration::from_secs(5))
+            )
+        );
+        let image = [Message::user("Hi").part(Image::new(b"PNG", "image/png"))];
+        assert_ne!(
+            cache_key(&options),
+            cache_key(&options.clone().messages(&image))
+        );
+    }
+
+    #[test]
+    fn key_keeps_the_override_url() {
+        let messages = [Message::user("Hi")];
+        let options = ChatOptions::new("llama3").messages(&messages);
+        let overridden =
+            |overrides: RequestOverrides| cache_key(&options.clone().overrides(overrides));
+
+        assert_eq!(
+            cache_key(&options),
+            overridden(
+                RequestOverrides::new()
+                    .api_key("secret-key")
+                    .header("X-Trace", "1")
+            )
+        );
+        assert_ne!(
+            cache_key(&options),
+            overridden(RequestOverrides::new().url("http://localhost:8080"))
+        );
+    }
+
+    #[test]
+    fn failed_responses_are_not_cached() {
+        let provider =
+            MockChatProvider::new().reply(reply("Call 1").error(ChatStreamError::IncompleteChunk));
+        let provider = CachedProvider::new(provider, MemoryCache::new());
+        let options = ChatOptions::new("llama3");
+
+        chat(&provider, &options);
+
+        assert!(provider.store().is_empty());
+    }
+}
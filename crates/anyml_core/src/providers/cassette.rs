@@ -0,0 +1,299 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::anyhow;
+use anyhttp::{
+    HttpClient, Response,
+    mock::{MockHttpClient, MockResponse},
+};
+use futures::StreamExt;
+use http::{Request, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::decode::Utf8Decoder;
+
+/// Recorded HTTP exchanges, saved as JSON so tests can replay them offline
+/// with a [`ReplayClient`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), anyhow::Error> {
+        let json = serde_json::to_string_pretty(self)?;
+        Ok(std::fs::write(path, json)?)
+    }
+}
+
+/// A recorded request and the response it got, or the error it failed with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: Result<RecordedResponse, String>,
+}
+
+/// A request as replayed requests are matched against it.
+///
+/// Headers aren't recorded, so API keys stay out of cassettes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub uri: String,
+    pub body: String,
+}
+
+impl<B: AsRef<[u8]>> From<&Request<B>> for RecordedRequest {
+    fn from(request: &Request<B>) -> Self {
+        Self {
+            method: request.method().to_string(),
+            uri: request.uri().to_string(),
+            body: String::from_utf8_lossy(request.body().as_ref()).into_owned(),
+        }
+    }
+}
+
+/// A response's status and the raw frames its body arrived in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub frames: Vec<String>,
+}
+
+impl RecordedResponse {
+    /// Builds the response to hand back to the provider, with the frames joined
+    /// into one body.
+    async fn respond(&self) -> Result<Response, anyhow::Error> {
+        let status = StatusCode::from_u16(self.status)?;
+        MockHttpClient::new()
+            .with_response(MockResponse::new(status).body(self.frames.concat()))
+            .execute(Request::default())
+            .await
+    }
+}
+
+/// Records the requests sent through an HTTP client, and the raw responses
+/// they got, into a [`Cassette`].
+///
+/// Providers are given the recorder as their client, so replaying the cassette
+/// with a [`ReplayClient`] goes through the same provider code. A response is
+/// handed on once its body has been read to the end, and only its status and
+/// body are kept, so clients which read headers, such as
+/// [`RateLimitHeaders`](crate::providers::RateLimitHeaders), should be wrapped by it.
+/// A body which fails to be read is recorded as an error.
+#[derive(Debug, Clone)]
+pub struct RecordingClient<C> {
+    client: C,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl<C> RecordingClient<C> {
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            cassette: Arc::default(),
+        }
+    }
+
+    /// The interactions recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), anyhow::Error> {
+        self.cassette.lock().unwrap().save(path)
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+}
+
+impl<C: HttpClient> RecordingClient<C> {
+    async fn fetch(&self, request: Request<Vec<u8>>) -> Result<RecordedResponse, anyhow::Error> {
+        let response = self.client.execute(request).await?;
+        let status = response.status().as_u16();
+
+        let mut decoder = Utf8Decoder::new();
+        let mut frames = Vec::new();
+        let mut body = response.bytes_stream();
+        while let Some(frame) = body.next().await {
+            frames.push(decoder.decode(&frame?));
+        }
+
+        Ok(RecordedResponse { status, frames })
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> HttpClient for RecordingClient<C> {
+    async fn execute(&self, request: Request<Vec<u8>>) -> Result<Response, anyhow::Error> {
+        let recorded = RecordedRequest::from(&request);
+        let response = self.fetch(request).await;
+        let result = match &response {
+            Ok(response) => Ok(response.clone()),
+            Err(error) => Err(error.to_string()),
+        };
+        self.cassette
+            .lock()
+            .unwrap()
+            .interactions
+            .push(Interaction {
+                request: recorded,
+                response: result,
+            });
+
+        response?.respond().await
+    }
+}
+
+/// Serves the responses of a [`Cassette`] back without a network, so a real
+/// provider can be tested offline.
+///
+/// Requests are matched by their method, URI and body. Identical requests are
+/// served their recorded responses in order, repeating the last one once all
+/// were served. Requests which weren't recorded fail.
+#[derive(Debug)]
+pub struct ReplayClient {
+    interactions: Vec<Interaction>,
+    served: Mutex<Vec<bool>>,
+}
+
+impl ReplayClient {
+    pub fn new(cassette: Cassette) -> Self {
+        Self {
+            served: Mutex::new(vec![false; cassette.interactions.len()]),
+            interactions: cassette.interactions,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+
+    fn find(&self, request: &RecordedRequest) -> Option<&Interaction> {
+        let mut served = self.served.lock().unwrap();
+        let mut matching = self
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.request == *request);
+
+        let (index, interaction) = matching
+            .clone()
+            .find(|(index, _)| !served[*index])
+            .or(matching.next_back())?;
+        served[index] = true;
+        Some(interaction)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl HttpClient for ReplayClient {
+    async fn execute(&self, request: Request<Vec<u8>>) -> Result<Response, anyhow::Error> {
+        let recorded = RecordedRequest::from(&request);
+        let interaction = self.find(&recorded).ok_or_else(|| {
+            anyhow!(
+                "no interaction was recorded for {} {}",
+                recorded.method,
+                recorded.uri
+            )
+        })?;
+
+        match &interaction.response {
+            Ok(response) => response.respond().await,
+            Err(message) => Err(anyhow!(message.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::chat::{ChatChunk, ChatError, ChatResponse};
+    use crate::providers::openai_compat::send_chat;
+    use futures::executor::block_on;
+
+    const STREAM: &str = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi ✨\"}}]}\n\n\
+        data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n\
+        data: [DONE]\n\n";
+
+    fn request(content: &str) -> Request<Vec<u8>> {
+        Request::post("https://api.openai.com/v1/chat/completions")
+            .header("Authorization", "Bearer sk-test")
+            .body(
+                format!(
+                    r#"{{"model":"gpt-4o","messages":[{{"role":"user","content":"{content}"}}]}}"#
+                )
+                .into_bytes(),
+            )
+            .unwrap()
+    }
+
+    fn collect(response: Result<ChatResponse, ChatError>) -> Vec<ChatChunk> {
+        let response = response.unwrap();
+        block_on(response.map(Result::unwrap).collect())
+    }
+
+    #[test]
+    fn replays_recorded_responses_through_the_provider() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(STREAM))
+            .with_response(
+                MockResponse::new(StatusCode::TOO_MANY_REQUESTS)
+                    .body(r#"{"error":{"message":"Slow down","type":"rate_limit_error"}}"#),
+            );
+        let recorder = RecordingClient::new(client);
+
+        let recorded = collect(block_on(send_chat(&recorder, request("Hello"), false)));
+        let Err(recorded_error) = block_on(send_chat(&recorder, request("Again"), false)) else {
+            panic!("expected an error");
+        };
+
+        let cassette = recorder.cassette();
+        assert!(!cassette.interactions[0].request.body.is_empty());
+        assert_eq!(
+            cassette.interactions[0].response.as_ref().unwrap().status,
+            200
+        );
+        assert!(
+            !serde_json::to_string(&cassette)
+                .unwrap()
+                .contains("sk-test")
+        );
+
+        let path = std::env::temp_dir().join(format!("anyml-cassette-{}.json", std::process::id()));
+        recorder.save(&path).unwrap();
+        let replay = ReplayClient::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let replayed = collect(block_on(send_chat(&replay, request("Hello"), false)));
+        assert_eq!(replayed, recorded);
+        let Err(replayed_error) = block_on(send_chat(&replay, request("Again"), false)) else {
+            panic!("expected an error");
+        };
+        assert_eq!(replayed_error.to_string(), recorded_error.to_string());
+    }
+
+    #[test]
+    fn unrecorded_request_fails() {
+        let replay = ReplayClient::new(Cassette::default());
+
+        let result = block_on(send_chat(&replay, request("Hello"), false));
+
+        assert!(matches!(result, Err(ChatError::ResponseFetchFailed(_))));
+    }
+}
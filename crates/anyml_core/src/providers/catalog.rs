@@ -0,0 +1,423 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{Stream, channel::mpsc};
+use web_time::Instant;
+
+use crate::models::Model;
+use crate::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse};
+use crate::providers::health::{HealthCheckProvider, HealthStatus};
+use crate::providers::list_models::{ListModelsError, ListModelsProvider};
+
+/// Caches the models a provider lists, refetching them once they're older than a TTL.
+///
+/// Useful where the models change while the app runs, e.g. with Ollama, where
+/// models are pulled and deleted locally: [`ModelCatalog::subscribe`] reports
+/// the models added and removed each time the list is fetched.
+#[derive(Debug)]
+pub struct ModelCatalog<P> {
+    provider: P,
+    ttl: Duration,
+    cached: Mutex<Option<Cached>>,
+    subscribers: Mutex<Vec<mpsc::UnboundedSender<ModelChanges>>>,
+}
+
+#[derive(Debug)]
+struct Cached {
+    models: Vec<Model>,
+    /// `None` once invalidated. The models are kept to report changes against.
+    fetched_at: Option<Instant>,
+}
+
+/// The models added and removed since a [`ModelCatalog`] last fetched its list.
+#[derive(Debug, Clone, Default)]
+pub struct ModelChanges {
+    pub added: Vec<Model>,
+    pub removed: Vec<Model>,
+}
+
+impl ModelChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl<P> ModelCatalog<P> {
+    pub fn new(provider: P, ttl: Duration) -> Self {
+        Self {
+            provider,
+            ttl,
+            cached: Mutex::new(None),
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Forgets the cached models, so they're fetched again when next needed.
+    pub fn invalidate(&self) {
+        if let Some(cached) = &mut *self.cached.lock().unwrap() {
+            cached.fetched_at = None;
+        }
+    }
+
+    /// Streams the changes each time the models are fetched and differ from the
+    /// last fetch. The first fetch reports every model as added.
+    pub fn subscribe(&self) -> impl Stream<Item = ModelChanges> + use<P> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn into_inner(self) -> P {
+        self.provider
+    }
+}
+
+impl<P: ListModelsProvider> ModelCatalog<P> {
+    /// Returns the cached models, fetching them first if they're missing or stale.
+    pub async fn models(&self) -> Result<Vec<Model>, ListModelsError> {
+        if let Some(cached) = &*self.cached.lock().unwrap()
+            && cached
+                .fetched_at
+                .is_some_and(|fetched_at| fetched_at.elapsed() < self.ttl)
+        {
+            return Ok(cached.models.clone());
+        }
+
+        self.refresh().await
+    }
+
+    /// Fetches the models now, whether or not the cached ones are stale.
+    pub async fn refresh(&self) -> Result<Vec<Model>, ListModelsError> {
+        let models = self.provider.list_models().await?;
+
+        let previous = self.cached.lock().unwrap().replace(Cached {
+            models: models.clone(),
+            fetched_at: Some(Instant::now()),
+        });
+        let previous = previous
+            .as_ref()
+            .map_or(&[][..], |cached| &cached.models[..]);
+        let changes = diff(previous, &models);
+        if !changes.is_empty() {
+            self.subscribers
+                .lock()
+                .unwrap()
+                .retain(|subscriber| subscriber.unbounded_send(changes.clone()).is_ok());
+        }
+
+        Ok(models)
+    }
+}
+
+/// The models in `current` but not `previous`, and the other way around, by ID.
+fn diff(previous: &[Model], current: &[Model]) -> ModelChanges {
+    let missing_from = |models: &[Model], model: &Model| models.iter().all(|m| m.id != model.id);
+    ModelChanges {
+        added: current
+            .iter()
+            .filter(|model| missing_from(previous, model))
+            .cloned()
+            .collect(),
+        removed: previous
+            .iter()
+            .filter(|model| missing_from(current, model))
+            .cloned()
+            .collect(),
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ListModelsProvider> ListModelsProvider for ModelCatalog<P> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        self.models().await
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.models()
+            .await?
+            .into_iter()
+            .find(|model| model.id == id)
+            .ok_or_else(|| ListModelsError::NotFound { id: id.to_owned() })
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: HealthCheckProvider> HealthCheckProvider for ModelCatalog<P> {
+    async fn health(&self) -> HealthStatus {
+        self.provider.health().await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider> ChatProvider for ModelCatalog<P> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        self.provider.chat(options).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.provider.default_model()
+    }
+}
+
+/// Lists the models of several providers as one, e.g. for a single model picker.
+///
+/// Each model's ID is prefixed with the name of its provider (e.g.
+/// `"ollama/llama3.1"`), which a [`ProviderRegistry`](crate::providers::ProviderRegistry)
+/// with the same names routes back to the provider, and [`Model::provider`] is set.
+#[derive(Clone, Default)]
+pub struct CombinedModels {
+    providers: Vec<(String, Arc<dyn ListModelsProvider>)>,
+}
+
+impl CombinedModels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `provider` under `name`, replacing any provider already added under it.
+    pub fn register(
+        mut self,
+        name: impl Into<String>,
+        provider: impl ListModelsProvider + 'static,
+    ) -> Self {
+        let name = name.into();
+        self.providers.retain(|(registered, _)| *registered != name);
+        self.providers.push((name, Arc::new(provider)));
+        self
+    }
+
+    /// Lists every provider's models concurrently, returning each provider's
+    /// result under its name, in the order they were added.
+    pub async fn list_by_provider(&self) -> Vec<(&str, Result<Vec<Model>, ListModelsError>)> {
+        futures::future::join_all(self.providers.iter().map(|(name, provider)| async move {
+            let models = provider.list_models().await.map(|models| {
+                models
+                    .into_iter()
+                    .map(|model| namespaced(name, model))
+                    .collect()
+            });
+            (name.as_str(), models)
+        }))
+        .await
+    }
+}
+
+/// Prefixes the model's ID with `provider`, and records it as the model's provider.
+fn namespaced(provider: &str, model: Model) -> Model {
+    Model {
+        id: format!("{provider}/{}", model.id),
+        provider: Some(provider.to_owned()),
+        ..model
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl ListModelsProvider for CombinedModels {
+    /// Lists the models of every provider which could list them. Fails only
+    /// if none could, with the first provider's error.
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        let mut models = Vec::new();
+        let mut first_error = None;
+        for (_, result) in self.list_by_provider().await {
+            match result {
+                Ok(listed) => models.extend(listed),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        match first_error {
+            Some(err) if models.is_empty() => Err(err),
+            _ => Ok(models),
+        }
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        let not_found = || ListModelsError::NotFound { id: id.to_owned() };
+        let (name, model) = id.split_once('/').ok_or_else(not_found)?;
+        let (name, provider) = self
+            .providers
+            .iter()
+            .find(|(registered, _)| registered == name)
+            .ok_or_else(not_found)?;
+
+        Ok(namespaced(name, provider.get_model(model).await?))
+    }
+}
+
+impl fmt::Debug for CombinedModels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.providers.iter().map(|(name, _)| name).collect();
+        f.debug_struct("CombinedModels")
+            .field("providers", &names)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{StreamExt, executor::block_on};
+    use std::collections::VecDeque;
+
+    /// Lists each of its lists of models in turn.
+    struct ChangingProvider {
+        lists: Mutex<VecDeque<Vec<&'static str>>>,
+        calls: Mutex<usize>,
+    }
+
+    impl ChangingProvider {
+        fn new(lists: impl IntoIterator<Item = Vec<&'static str>>) -> Self {
+            Self {
+                lists: Mutex::new(lists.into_iter().collect()),
+                calls: Mutex::new(0),
+            }
+        }
+
+        fn calls(&self) -> usize {
+            *self.calls.lock().unwrap()
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+    #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+    impl ListModelsProvider for ChangingProvider {
+        async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+            *self.calls.lock().unwrap() += 1;
+            let ids = self.lists.lock().unwrap().pop_front().unwrap_or_default();
+            Ok(ids
+                .into_iter()
+                .map(|id| Model {
+                    id: id.to_owned(),
+                    ..Default::default()
+                })
+                .collect())
+        }
+    }
+
+    /// Fails to list its models.
+    struct FailingProvider;
+
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+    #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+    impl ListModelsProvider for FailingProvider {
+        async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+            Err(ListModelsError::ResponseFetchFailed(anyhow::anyhow!(
+                "connection refused"
+            )))
+        }
+    }
+
+    fn ids(models: &[Model]) -> Vec<&str> {
+        models.iter().map(|model| model.id.as_str()).collect()
+    }
+
+    #[test]
+    fn caches_models_until_invalidated() {
+        let catalog = ModelCatalog::new(
+            ChangingProvider::new([vec!["llama3.2"], vec!["llama3.2", "qwen3"]]),
+            Duration::from_secs(60),
+        );
+
+        assert_eq!(ids(&block_on(catalog.models()).unwrap()), ["llama3.2"]);
+        assert_eq!(ids(&block_on(catalog.models()).unwrap()), ["llama3.2"]);
+        assert_eq!(catalog.inner().calls(), 1);
+
+        catalog.invalidate();
+        assert_eq!(
+            ids(&block_on(catalog.models()).unwrap()),
+            ["llama3.2", "qwen3"]
+        );
+        assert_eq!(catalog.inner().calls(), 2);
+    }
+
+    #[test]
+    fn refetches_stale_models() {
+        let catalog = ModelCatalog::new(
+            ChangingProvider::new([vec!["llama3.2"], vec!["qwen3"]]),
+            Duration::ZERO,
+        );
+
+        block_on(catalog.models()).unwrap();
+        let model = block_on(catalog.get_model("qwen3")).unwrap();
+
+        assert_eq!(model.id, "qwen3");
+        assert_eq!(catalog.inner().calls(), 2);
+    }
+
+    #[test]
+    fn reports_added_and_removed_models() {
+        let catalog = ModelCatalog::new(
+            ChangingProvider::new([vec!["llama3.2"], vec!["llama3.2"], vec!["qwen3"]]),
+            Duration::ZERO,
+        );
+        let mut changes = catalog.subscribe();
+
+        for _ in 0..3 {
+            block_on(catalog.refresh()).unwrap();
+        }
+        drop(catalog);
+        let changes: Vec<_> = block_on(async {
+            let mut all = Vec::new();
+            while let Some(change) = changes.next().await {
+                all.push((ids(&change.added).join(","), ids(&change.removed).join(",")));
+            }
+            all
+        });
+
+        assert_eq!(
+            changes,
+            [
+                ("llama3.2".to_owned(), String::new()),
+                ("qwen3".to_owned(), "llama3.2".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn combines_models_under_their_provider() {
+        let models = CombinedModels::new()
+            .register("ollama", ChangingProvider::new([vec!["llama3.1"]]))
+            .register("offline", FailingProvider)
+            .register(
+                "anthropic",
+                ChangingProvider::new([vec!["claude-sonnet-4-6"], vec!["claude-sonnet-4-6"]]),
+            );
+
+        let listed = block_on(models.list_models()).unwrap();
+        assert_eq!(
+            ids(&listed),
+            ["ollama/llama3.1", "anthropic/claude-sonnet-4-6"]
+        );
+        assert_eq!(listed[0].provider.as_deref(), Some("ollama"));
+
+        let model = block_on(models.get_model("anthropic/claude-sonnet-4-6")).unwrap();
+        assert_eq!(model.id, "anthropic/claude-sonnet-4-6");
+        assert!(matches!(
+            block_on(models.get_model("openai/gpt-4o")),
+            Err(ListModelsError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn fails_when_no_provider_lists_models() {
+        let models = CombinedModels::new().register("offline", FailingProvider);
+
+        assert!(matches!(
+            block_on(models.list_models()),
+            Err(ListModelsError::ResponseFetchFailed(_))
+        ));
+    }
+}
@@ -1,27 +1,81 @@
+use bytestring::ByteString;
 use enum_kinds::EnumKind;
+pub use futures::stream::AbortHandle;
 use futures::{Stream, StreamExt};
+use http::StatusCode;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::value::RawValue;
 use std::{
+    any::Any,
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
     ops::{Deref, DerefMut},
     pin::Pin,
+    time::Duration,
 };
 use thiserror::Error;
 
-use crate::models::Message;
+use crate::maybe_send::{BoxStream, MaybeSend, MaybeSync};
+use crate::models::{AudioOutput, Message, SignedThinking, Tool, ToolCall};
+use crate::providers::extensions::Extensions;
+use crate::providers::overrides::RequestOverrides;
 
-#[async_trait::async_trait]
-pub trait ChatProvider: Send + Sync {
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait ChatProvider: MaybeSend + MaybeSync {
     async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError>;
+
+    /// The model chats are sent to when their options don't name one
+    /// (see [`ChatOptions::default_model`]), if the provider has one.
+    fn default_model(&self) -> Option<&str> {
+        None
+    }
 }
 
-#[derive(Clone, Debug)]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider + ?Sized> ChatProvider for Box<P> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        (**self).chat(options).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        (**self).default_model()
+    }
+}
+
+/// The options of a chat request.
+///
+/// Serializes to JSON for logging requests, without secrets: the override API
+/// key is left out, as are the values of override headers.
+#[derive(Clone, Debug, Serialize)]
 pub struct ChatOptions<'a> {
     pub model: &'a str,
     pub messages: Messages<'a>,
+    pub system: Option<&'a str>,
+    pub prefill: Option<&'a str>,
     pub stream: bool,
-    pub max_tokens: usize,
+    /// Left to the provider's default when `None`, except by providers that require it.
+    pub max_tokens: Option<usize>,
     pub thinking: Option<Thinking>,
     pub session_id: Option<&'a str>,
+    pub tools: &'a [Tool],
+    /// Left to the provider's default (usually [`ToolChoice::Auto`]) when `None`.
+    pub tool_choice: Option<ToolChoice>,
+    pub parallel_tool_calls: Option<bool>,
+    pub server_tools: Vec<ServerTool>,
+    pub response_format: Option<ResponseFormat>,
+    pub logprobs: bool,
+    pub top_logprobs: Option<usize>,
+    pub audio_output: Option<AudioOutput>,
+    pub timeout: Option<Duration>,
+    pub first_chunk_timeout: Option<Duration>,
+    pub include_raw: bool,
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    #[serde(skip_serializing_if = "Extensions::is_empty")]
+    pub extensions: Extensions,
+    pub overrides: RequestOverrides,
 }
 
 impl<'a> ChatOptions<'a> {
@@ -29,10 +83,73 @@ impl<'a> ChatOptions<'a> {
         Self {
             model,
             messages: Messages::Raw(&[]),
+            system: None,
+            prefill: None,
             stream: true,
-            max_tokens: 4096,
+            max_tokens: None,
             thinking: None,
             session_id: None,
+            tools: &[],
+            tool_choice: None,
+            parallel_tool_calls: None,
+            server_tools: Vec::new(),
+            response_format: None,
+            logprobs: false,
+            top_logprobs: None,
+            audio_output: None,
+            timeout: None,
+            first_chunk_timeout: None,
+            include_raw: false,
+            extra: serde_json::Map::new(),
+            extensions: Extensions::new(),
+            overrides: RequestOverrides::new(),
+        }
+    }
+
+    /// A hash of every option except secrets, stable across runs and providers,
+    /// for deduplicating requests and matching them up in logs.
+    ///
+    /// Unlike [`cache_key`](crate::providers::cache::cache_key), which is built on it,
+    /// it also covers options which don't affect the reply, such as streaming and timeouts.
+    pub fn fingerprint(&self) -> u64 {
+        // Going through `Value` sorts the keys of `extra`, of the extensions and of
+        // messages serialized elsewhere, so equal options always serialize the same.
+        let options = serde_json::to_value(self).expect("chat options serialize to JSON");
+        let fingerprint = format!("{options}\n{}", self.messages.parts_json());
+
+        // FNV-1a, as the standard library's hasher isn't stable across releases.
+        fingerprint.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    /// Options which leave the model to the provider's [`ChatProvider::default_model`].
+    ///
+    /// Providers without a default reject them with [`ChatError::InvalidOptions`].
+    pub fn default_model() -> Self {
+        Self::new("")
+    }
+
+    /// Whether the model is left to the provider's default.
+    pub fn uses_default_model(&self) -> bool {
+        self.model.is_empty()
+    }
+
+    /// These options with a model left to the provider's default set to
+    /// `default_model`, for providers, and wrappers which need to know the model,
+    /// to resolve [`ChatOptions::default_model`].
+    ///
+    /// Options naming a model, or when there is no default, are borrowed as they are.
+    pub fn or_default_model<'b>(
+        &'b self,
+        default_model: Option<&'b str>,
+    ) -> Cow<'b, ChatOptions<'b>>
+    where
+        'a: 'b,
+    {
+        match default_model {
+            Some(model) if self.uses_default_model() => Cow::Owned(self.clone().model(model)),
+            _ => Cow::Borrowed(self),
         }
     }
 
@@ -55,6 +172,22 @@ impl<'a> ChatOptions<'a> {
         self
     }
 
+    /// Sets the system prompt, kept separate from the messages.
+    /// Providers place it wherever their API expects it.
+    pub fn system(mut self, system: &'a str) -> Self {
+        self.system = Some(system);
+        self
+    }
+
+    /// Sets the start of the assistant's reply, which the model continues from.
+    /// The response only contains the continuation, not the prefill itself.
+    ///
+    /// Providers that can't continue a partial reply return [`ChatError::Unsupported`].
+    pub fn prefill(mut self, prefill: &'a str) -> Self {
+        self.prefill = Some(prefill);
+        self
+    }
+
     /// Enables or disables streaming mode.
     /// If `false` then the entire response will be returned in one chunk.
     pub fn stream(mut self, stream: bool) -> Self {
@@ -62,8 +195,9 @@ impl<'a> ChatOptions<'a> {
         self
     }
 
+    /// Caps the number of tokens generated, including any thinking.
     pub fn max_tokens(mut self, max_tokens: usize) -> Self {
-        self.max_tokens = max_tokens.max(1);
+        self.max_tokens = Some(max_tokens.max(1));
         self
     }
 
@@ -78,6 +212,263 @@ impl<'a> ChatOptions<'a> {
         self.session_id = Some(session_id);
         self
     }
+
+    /// Sets the tools the model may call.
+    pub fn tools(mut self, tools: &'a [Tool]) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Sets whether the model must call a tool, and which.
+    ///
+    /// Providers which can't constrain tool use (e.g. Ollama) ignore it.
+    pub fn tool_choice(mut self, tool_choice: ToolChoice) -> Self {
+        self.tool_choice = Some(tool_choice);
+        self
+    }
+
+    /// Sets whether the model may call several tools in one reply.
+    ///
+    /// Providers which can't limit tool calls (e.g. Ollama) ignore it.
+    pub fn parallel_tool_calls(mut self, parallel_tool_calls: bool) -> Self {
+        self.parallel_tool_calls = Some(parallel_tool_calls);
+        self
+    }
+
+    /// Enables a tool which the provider runs itself, such as web search.
+    /// Its use and results are streamed as [`ChatChunk::ServerToolUse`]
+    /// and [`ChatChunk::ServerToolResult`].
+    ///
+    /// Providers without the tool return [`ChatError::Unsupported`].
+    pub fn server_tool(mut self, server_tool: ServerTool) -> Self {
+        self.server_tools.push(server_tool);
+        self
+    }
+
+    /// Constrains the reply to JSON, optionally matching a schema.
+    ///
+    /// Providers without structured output support return [`ChatError::Unsupported`].
+    pub fn response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.response_format = Some(response_format);
+        self
+    }
+
+    /// Requests the log probability of each generated token,
+    /// streamed as [`ChatChunk::LogProbs`].
+    ///
+    /// Providers that can't report log probabilities return [`ChatError::Unsupported`].
+    pub fn logprobs(mut self, logprobs: bool) -> Self {
+        self.logprobs = logprobs;
+        self
+    }
+
+    /// Requests the `top_logprobs` most likely alternatives for each generated token.
+    /// This also enables [`ChatOptions::logprobs`].
+    pub fn top_logprobs(mut self, top_logprobs: usize) -> Self {
+        self.logprobs = true;
+        self.top_logprobs = Some(top_logprobs);
+        self
+    }
+
+    /// Asks the model to reply with audio as well as text,
+    /// streamed as [`ChatChunk::Audio`].
+    ///
+    /// Providers that can't reply with audio return [`ChatError::Unsupported`].
+    pub fn audio_output(mut self, audio_output: AudioOutput) -> Self {
+        self.audio_output = Some(audio_output);
+        self
+    }
+
+    /// Limits how long the whole response may take, including streaming it.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Limits how long it may take for the first chunk to arrive.
+    pub fn first_chunk_timeout(mut self, timeout: Duration) -> Self {
+        self.first_chunk_timeout = Some(timeout);
+        self
+    }
+
+    /// Also emits every event received from the provider as a [`ChatChunk::Raw`],
+    /// which helps when diagnosing fields that aren't parsed into other chunks.
+    pub fn include_raw(mut self, include_raw: bool) -> Self {
+        self.include_raw = include_raw;
+        self
+    }
+
+    /// Adds a provider-specific field to the request body, such as
+    /// OpenRouter's `transforms` or Ollama's `keep_alive`.
+    ///
+    /// Extra fields are sent as-is after the provider's own fields, so they shouldn't
+    /// repeat a field which the provider already sets from these options.
+    pub fn extra(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Attaches typed provider-specific options, such as `OpenAiChatExt`,
+    /// replacing any extension of the same type.
+    ///
+    /// Providers ignore the extensions of other providers.
+    pub fn extension<T: Any + fmt::Debug + Serialize + Send + Sync>(
+        mut self,
+        extension: T,
+    ) -> Self {
+        self.extensions.insert(extension);
+        self
+    }
+
+    /// Replaces the provider's API key, base URL or headers for this request only.
+    ///
+    /// Providers which don't send HTTP requests return [`ChatError::Unsupported`].
+    pub fn overrides(mut self, overrides: RequestOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Checks the options every provider rejects, so a mistake is reported before
+    /// the request is sent, rather than as the provider's error response.
+    pub fn validate(&self) -> Result<(), ChatError> {
+        self.validate_allowing_no_messages()?;
+        if self.messages.is_empty() {
+            return Err(ChatError::InvalidOptions {
+                field: "messages",
+                reason: "there must be at least one message".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Like [`ChatOptions::validate`], but accepts options without messages, for
+    /// providers which give them a meaning, such as Ollama loading the model.
+    pub fn validate_allowing_no_messages(&self) -> Result<(), ChatError> {
+        if self.uses_default_model() {
+            return Err(ChatError::InvalidOptions {
+                field: "model",
+                reason: "no model was given, and the provider has no default".to_owned(),
+            });
+        }
+        if self.max_tokens == Some(0) {
+            return Err(ChatError::InvalidOptions {
+                field: "max_tokens",
+                reason: "it must be at least 1".to_owned(),
+            });
+        }
+        match &self.tool_choice {
+            Some(ToolChoice::Required) if self.tools.is_empty() => {
+                return Err(ChatError::InvalidOptions {
+                    field: "tool_choice",
+                    reason: "a tool is required, but there are no tools".to_owned(),
+                });
+            }
+            Some(ToolChoice::Tool(name)) if !self.tools.iter().any(|tool| &tool.name == name) => {
+                return Err(ChatError::InvalidOptions {
+                    field: "tool_choice",
+                    reason: format!("there is no tool named \"{name}\""),
+                });
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Copies the borrowed options, so they can be kept or sent to another task.
+    pub fn into_owned(self) -> ChatOptionsOwned {
+        let messages = match &self.messages {
+            Messages::Raw(messages) => Some(messages.to_vec()),
+            Messages::Serialized(_) => None,
+        };
+
+        ChatOptionsOwned {
+            model: self.model.to_owned(),
+            messages,
+            system: self.system.map(str::to_owned),
+            prefill: self.prefill.map(str::to_owned),
+            session_id: self.session_id.map(str::to_owned),
+            tools: self.tools.to_vec(),
+            options: ChatOptions {
+                model: "",
+                messages: match self.messages {
+                    Messages::Raw(_) => Messages::Raw(&[]),
+                    Messages::Serialized(messages) => Messages::Serialized(messages),
+                },
+                system: None,
+                prefill: None,
+                stream: self.stream,
+                max_tokens: self.max_tokens,
+                thinking: self.thinking,
+                session_id: None,
+                tools: &[],
+                tool_choice: self.tool_choice,
+                parallel_tool_calls: self.parallel_tool_calls,
+                server_tools: self.server_tools,
+                response_format: self.response_format,
+                logprobs: self.logprobs,
+                top_logprobs: self.top_logprobs,
+                audio_output: self.audio_output,
+                timeout: self.timeout,
+                first_chunk_timeout: self.first_chunk_timeout,
+                include_raw: self.include_raw,
+                extra: self.extra,
+                extensions: self.extensions,
+                overrides: self.overrides,
+            },
+        }
+    }
+}
+
+/// [`ChatOptions`] which own their model, messages and other borrowed options,
+/// e.g. to queue a request for a background worker.
+///
+/// Made with [`ChatOptions::into_owned`], and borrowed back to send with
+/// [`ChatOptionsOwned::as_options`].
+#[derive(Clone, Debug)]
+pub struct ChatOptionsOwned {
+    model: String,
+    /// `None` if the messages are serialized, in which case `options` holds them.
+    messages: Option<Vec<Message>>,
+    system: Option<String>,
+    prefill: Option<String>,
+    session_id: Option<String>,
+    tools: Vec<Tool>,
+    /// Every other option, with the borrowed ones left empty.
+    options: ChatOptions<'static>,
+}
+
+impl ChatOptionsOwned {
+    /// Borrows the options to send them.
+    pub fn as_options(&self) -> ChatOptions<'_> {
+        let mut options = self.options.clone();
+        options.model = &self.model;
+        if let Some(messages) = &self.messages {
+            options.messages = Messages::Raw(messages);
+        }
+        options.system = self.system.as_deref();
+        options.prefill = self.prefill.as_deref();
+        options.session_id = self.session_id.as_deref();
+        options.tools = &self.tools;
+        options
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+impl From<ChatOptions<'_>> for ChatOptionsOwned {
+    fn from(options: ChatOptions<'_>) -> Self {
+        options.into_owned()
+    }
+}
+
+impl From<&ChatOptions<'_>> for ChatOptionsOwned {
+    fn from(options: &ChatOptions<'_>) -> Self {
+        options.clone().into_owned()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -86,7 +477,27 @@ pub enum Messages<'a> {
     Serialized(Box<RawValue>),
 }
 
+impl Serialize for Messages<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Messages::Raw(msgs) => msgs.serialize(serializer),
+            Messages::Serialized(raw) => raw.serialize(serializer),
+        }
+    }
+}
+
 impl Messages<'_> {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Messages::Raw(msgs) => msgs.is_empty(),
+            Messages::Serialized(raw) => raw
+                .get()
+                .trim_start()
+                .strip_prefix('[')
+                .is_some_and(|inner| inner.trim_start().starts_with(']')),
+        }
+    }
+
     /// Returns messages as a JSON string for embedding in request bodies.
     pub fn to_json(&self) -> String {
         match self {
@@ -94,14 +505,62 @@ impl Messages<'_> {
             Messages::Serialized(raw) => raw.get().to_string(),
         }
     }
+
+    /// The non-text parts of each message, which aren't part of [`Messages::to_json`],
+    /// as JSON for identifying requests. It's empty if no message has parts.
+    pub(crate) fn parts_json(&self) -> String {
+        match self {
+            Messages::Raw(msgs) if msgs.iter().any(|message| !message.parts.is_empty()) => {
+                let parts = msgs.iter().map(|message| &message.parts);
+                serde_json::to_string(&parts.collect::<Vec<_>>()).unwrap()
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Like [`Messages::to_json`], but with `system` prepended as a system message.
+    pub fn to_json_with_system(&self, system: Option<&str>) -> String {
+        self.to_json_with(system, None)
+    }
+
+    /// Like [`Messages::to_json`], but with `system` prepended as a system message
+    /// and `prefill` appended as a partial assistant message.
+    pub fn to_json_with(&self, system: Option<&str>, prefill: Option<&str>) -> String {
+        let messages = self.to_json();
+        if system.is_none() && prefill.is_none() {
+            return messages;
+        }
+
+        let inner = messages
+            .trim()
+            .strip_prefix('[')
+            .and_then(|this| this.strip_suffix(']'))
+            .unwrap_or_default()
+            .trim();
+
+        let system = system.map(|system| serde_json::to_string(&Message::system(system)).unwrap());
+        let prefill =
+            prefill.map(|prefill| serde_json::to_string(&Message::assistant(prefill)).unwrap());
+
+        let parts = system
+            .iter()
+            .map(String::as_str)
+            .chain((!inner.is_empty()).then_some(inner))
+            .chain(prefill.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+
+        format!("[{}]", parts.join(","))
+    }
 }
 
 /// Configuration for enabling model thinking/reasoning.
 ///
 /// Each variant carries exactly what its target provider needs.
 /// Providers handle the variants they understand and apply sensible
-/// defaults for the rest.
-#[derive(Clone, Debug)]
+/// defaults for the rest, unless they know the model can't honour the
+/// variant, in which case they return [`ChatError::InvalidOptions`].
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Thinking {
     /// A token budget for thinking. Used by Anthropic.
     BudgetTokens(usize),
@@ -125,31 +584,212 @@ impl Thinking {
     }
 }
 
-pub struct ChatResponse<'a>(
-    Pin<Box<dyn Stream<Item = Result<ChatChunk, ChatStreamError>> + Send + 'a>>,
-);
+/// The shape the model's reply must take.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Any valid JSON value.
+    Json,
+    /// JSON matching the given JSON schema.
+    JsonSchema(serde_json::Value),
+}
+
+impl ResponseFormat {
+    pub fn json() -> Self {
+        Self::Json
+    }
+
+    pub fn json_schema(schema: serde_json::Value) -> Self {
+        Self::JsonSchema(schema)
+    }
+}
+
+/// Whether the model must call a tool, and which.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// The model decides whether to call tools.
+    Auto,
+    /// The model doesn't call any tools, though it still knows of them.
+    None,
+    /// The model calls at least one tool.
+    Required,
+    /// The model calls the tool with this name.
+    Tool(String),
+}
+
+impl ToolChoice {
+    pub fn tool(name: impl Into<String>) -> Self {
+        Self::Tool(name.into())
+    }
+}
+
+/// A built-in tool which the provider runs server-side.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerTool {
+    /// Searches the web. `max_uses` limits the searches per request, for providers
+    /// which support a limit.
+    WebSearch { max_uses: Option<u32> },
+}
+
+impl ServerTool {
+    pub fn web_search() -> Self {
+        Self::WebSearch { max_uses: None }
+    }
+}
+
+pub struct ChatResponse<'a> {
+    stream: BoxStream<'a, Result<ChatChunk, ChatStreamError>>,
+    abort_handle: AbortHandle,
+}
 
 impl<'a> ChatResponse<'a> {
-    pub fn new(stream: impl Stream<Item = Result<ChatChunk, ChatStreamError>> + Send + 'a) -> Self {
-        Self(Box::pin(stream))
+    pub fn new(
+        stream: impl Stream<Item = Result<ChatChunk, ChatStreamError>> + MaybeSend + 'a,
+    ) -> Self {
+        let (stream, abort_handle) = futures::stream::abortable(stream);
+        Self {
+            stream: Box::pin(stream),
+            abort_handle,
+        }
+    }
+
+    /// Stops the response, dropping the underlying HTTP stream so no more is downloaded.
+    /// Any pending or later call to [`ChatResponse::next`] returns `None`.
+    pub fn abort(&mut self) {
+        self.abort_handle.abort();
+        self.stream = Box::pin(futures::stream::empty());
+    }
+
+    /// Returns a handle which can stop the response from another task.
+    ///
+    /// Aborting through the handle ends the stream promptly, but the underlying
+    /// HTTP stream is only dropped along with the response.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort_handle.clone()
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.abort_handle.is_aborted()
+    }
+
+    pub(crate) fn map_stream<S>(
+        self,
+        f: impl FnOnce(BoxStream<'a, Result<ChatChunk, ChatStreamError>>) -> S,
+    ) -> Self
+    where
+        S: Stream<Item = Result<ChatChunk, ChatStreamError>> + MaybeSend + 'a,
+    {
+        Self {
+            stream: Box::pin(f(self.stream)),
+            abort_handle: self.abort_handle,
+        }
     }
 
     pub async fn next(&mut self) -> Option<Result<ChatChunk, ChatStreamError>> {
-        self.0.next().await
+        self.stream.next().await
+    }
+
+    /// Transforms each chunk as it arrives, passing errors through.
+    /// The response can still be aborted.
+    pub fn map_chunks(self, mut f: impl FnMut(ChatChunk) -> ChatChunk + MaybeSend + 'a) -> Self {
+        self.map_stream(|stream| stream.map(move |chunk| chunk.map(&mut f)))
+    }
+
+    /// Calls `f` with each chunk as it arrives, such as to log or meter the response.
+    /// The response can still be aborted.
+    pub fn inspect(self, mut f: impl FnMut(&ChatChunk) + MaybeSend + 'a) -> Self {
+        self.map_stream(|stream| {
+            stream.inspect(move |chunk| {
+                if let Ok(chunk) = chunk {
+                    f(chunk);
+                }
+            })
+        })
+    }
+
+    /// Calls `f` once with the response's usage, merged from its usage chunks like
+    /// [`Usage::merge`], when the stream ends or the response is dropped, whichever
+    /// comes first. It isn't called if no usage was reported.
+    pub fn on_usage(self, f: impl FnOnce(&Usage) + MaybeSend + 'a) -> Self {
+        let mut total = UsageTotal {
+            usage: None,
+            f: Some(f),
+        };
+        self.map_stream(|stream| {
+            stream
+                .map(Some)
+                .chain(futures::stream::once(futures::future::ready(None)))
+                .filter_map(move |item| {
+                    match &item {
+                        Some(Ok(ChatChunk::Usage(usage))) => {
+                            total.usage.get_or_insert_with(Usage::default).merge(usage)
+                        }
+                        Some(_) => {}
+                        None => total.finish(),
+                    }
+                    futures::future::ready(item)
+                })
+        })
+    }
+
+    /// Streams only the text of the reply, skipping every other chunk but errors.
+    pub fn content_only(
+        self,
+    ) -> impl Stream<Item = Result<ByteString, ChatStreamError>> + MaybeSend + 'a {
+        self.filter_map(|chunk| {
+            futures::future::ready(match chunk {
+                Ok(ChatChunk::Content(text)) => Some(Ok(text)),
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            })
+        })
+    }
+
+    /// Streams only the model's thinking, skipping every other chunk but errors.
+    pub fn thinking_only(
+        self,
+    ) -> impl Stream<Item = Result<ByteString, ChatStreamError>> + MaybeSend + 'a {
+        self.filter_map(|chunk| {
+            futures::future::ready(match chunk {
+                Ok(ChatChunk::Thinking(text)) => Some(Ok(text)),
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            })
+        })
     }
 
     // Iterates through all remaining chunks and aggregates them.
     // If any error occurs then it will be returned instead.
     pub async fn aggregate(&mut self) -> Result<AggregatedChat, ChatStreamError> {
+        self.aggregate_with(|_| {}).await
+    }
+
+    // Iterates through all remaining chunks and aggregates them,
+    // calling `on_chunk` with each chunk as it arrives.
+    // If any error occurs then it will be returned instead.
+    pub async fn aggregate_with(
+        &mut self,
+        mut on_chunk: impl FnMut(&ChatChunk) + MaybeSend,
+    ) -> Result<AggregatedChat, ChatStreamError> {
         let mut result = AggregatedChat::default();
 
         while let Some(chunk) = self.next().await {
-            result.push(&chunk?);
+            let chunk = chunk?;
+            on_chunk(&chunk);
+            result.push(&chunk);
         }
 
         Ok(result)
     }
 
+    /// Aggregates the remaining chunks and deserializes the JSON in the reply's
+    /// content into `T`, like [`AggregatedChat::parse_json`].
+    pub async fn parse_json<T: DeserializeOwned>(&mut self) -> Result<T, ParseJsonError> {
+        self.aggregate().await?.parse_json()
+    }
+
     // Iterates through all remaining chunks and aggregates them.
     // Any errors will be ignored.
     pub async fn aggregate_lossy(&mut self) -> AggregatedChat {
@@ -163,8 +803,51 @@ impl<'a> ChatResponse<'a> {
 
         result
     }
+
+    /// Continues a reply whose stream broke off, such as after a dropped connection,
+    /// from `partial`, what was received of it with `options`.
+    ///
+    /// The received text is sent as a [`ChatOptions::prefill`] so the response only
+    /// contains the rest of the reply. Providers which can't prefill are sent the
+    /// partial reply as an assistant turn instead, followed by a request to continue it,
+    /// which they follow on a best-effort basis. Only the text of the reply is continued,
+    /// and a reply with no text yet is requested again from the start.
+    pub async fn resume<P>(
+        provider: &'a P,
+        options: &ChatOptions<'_>,
+        partial: &AggregatedChat,
+    ) -> Result<ChatResponse<'a>, ChatError>
+    where
+        P: ChatProvider + ?Sized,
+    {
+        if partial.content.is_empty() {
+            return provider.chat(options).await;
+        }
+
+        let prefill = format!("{}{}", options.prefill.unwrap_or_default(), partial.content);
+        match provider.chat(&options.clone().prefill(&prefill)).await {
+            Err(ChatError::Unsupported { feature: "prefill" }) => {}
+            result => return result,
+        }
+
+        let Messages::Raw(messages) = &options.messages else {
+            return Err(ChatError::Unsupported { feature: "prefill" });
+        };
+        let mut messages = messages.to_vec();
+        messages.push(Message::assistant(prefill));
+        messages.push(Message::user(RESUME_PROMPT));
+        let options = ChatOptions {
+            prefill: None,
+            ..options.clone().messages(&messages)
+        };
+        provider.chat(&options).await
+    }
 }
 
+/// Asks for the rest of a reply which was cut off, for providers which can't prefill.
+const RESUME_PROMPT: &str = "Your previous reply was cut off. Continue it exactly where it stopped, \
+without repeating anything or commenting on the interruption.";
+
 impl<'a> Stream for ChatResponse<'a> {
     type Item = Result<ChatChunk, ChatStreamError>;
 
@@ -172,51 +855,454 @@ impl<'a> Stream for ChatResponse<'a> {
         mut self: Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        self.0.poll_next_unpin(cx)
+        self.stream.poll_next_unpin(cx)
     }
 }
 
 impl<'a> Deref for ChatResponse<'a> {
-    type Target = Pin<Box<dyn Stream<Item = Result<ChatChunk, ChatStreamError>> + Send + 'a>>;
+    type Target = BoxStream<'a, Result<ChatChunk, ChatStreamError>>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.stream
     }
 }
 
 impl<'a> DerefMut for ChatResponse<'a> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.stream
     }
 }
 
 impl<'a> Unpin for ChatResponse<'a> {}
 
-#[derive(Debug, EnumKind)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, EnumKind)]
 #[enum_kind(ChatChunkKind)]
+#[serde(rename_all = "snake_case")]
 pub enum ChatChunk {
-    Content(String),
-    Thinking(String),
+    /// Text of the reply, held as shared bytes so it can be cloned or written out
+    /// (see [`ByteString::into_bytes`]) without copying.
+    Content(ByteString),
+    Thinking(ByteString),
+    /// The signature of the preceding thinking, sent once it ends (e.g. by Anthropic).
+    /// It must be sent back with the thinking for it to be verified on later turns.
+    ThinkingSignature(String),
+    /// Token usage and timing metadata, usually sent once near the end of the stream.
+    Usage(Usage),
+    /// A fragment of a streamed tool call.
+    /// Fragments sharing an `index` belong to the same call.
+    ToolCallDelta(ToolCallDelta),
+    /// Why the model stopped generating, sent once near the end of the stream.
+    Finish(FinishReason),
+    /// Log probabilities of the tokens in the preceding content.
+    LogProbs(Vec<TokenLogProb>),
+    /// A piece of the audio reply, encoded as requested in [`ChatOptions::audio_output`].
+    /// Its transcript is streamed as [`ChatChunk::Content`].
+    Audio(Vec<u8>),
+    /// A server tool the model used, sent once its input has streamed in.
+    ServerToolUse(ServerToolUse),
+    /// The result of a server tool, or a citation of one in the reply.
+    ServerToolResult(ServerToolResult),
+    /// An unparsed event as received from the provider, emitted before the chunks
+    /// parsed from it when [`ChatOptions::include_raw`] is set.
+    Raw {
+        provider_event: String,
+    },
+}
+
+impl ChatChunk {
+    /// The text of a [`ChatChunk::Content`] or [`ChatChunk::Thinking`] chunk.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Content(text) | Self::Thinking(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// The log probability of a generated token.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenLogProb {
+    pub token: String,
+    pub logprob: f64,
+    /// The token's UTF-8 bytes, which may be part of a multi-byte character.
+    #[serde(default)]
+    pub bytes: Option<Vec<u8>>,
+    /// The most likely tokens at this position, if requested.
+    #[serde(default)]
+    pub top_logprobs: Vec<TopLogProb>,
+}
+
+/// A likely alternative for a generated token.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopLogProb {
+    pub token: String,
+    pub logprob: f64,
+    #[serde(default)]
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// A call to a [`ServerTool`], which the provider runs itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerToolUse {
+    pub id: String,
+    pub name: String,
+    pub input: serde_json::Value,
+}
+
+/// The result of a [`ServerTool`], in the provider's format
+/// (e.g. a list of search results or a URL citation).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerToolResult {
+    /// The ID of the [`ServerToolUse`] this answers, if the provider reports it.
+    pub tool_use_id: Option<String>,
+    pub content: serde_json::Value,
+}
+
+/// Why the model stopped generating.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// The model finished its reply or hit a stop sequence.
+    Stop,
+    /// The reply was cut off by the token limit.
+    Length,
+    /// The model stopped to call tools.
+    ToolCalls,
+    /// The reply was withheld or cut off by a content filter.
+    ContentFilter,
+    /// A provider-specific reason, kept verbatim.
+    Other(String),
+}
+
+impl FinishReason {
+    /// The reason as OpenAI names it (e.g. `"tool_calls"`), or verbatim if
+    /// provider-specific.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Stop => "stop",
+            Self::Length => "length",
+            Self::ToolCalls => "tool_calls",
+            Self::ContentFilter => "content_filter",
+            Self::Other(reason) => reason,
+        }
+    }
+}
+
+/// A fragment of a tool call as it streams in.
+///
+/// The `id` and `name` are usually only present on the first fragment of a call,
+/// while `arguments` carries the next piece of the JSON argument string.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallDelta {
+    pub index: usize,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub arguments: String,
+}
+
+/// Token usage reported by the provider for a single response.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: Option<usize>,
+    pub output_tokens: Option<usize>,
+    /// How many of the input tokens were read from the provider's prompt cache,
+    /// for providers which report it (e.g. vLLM).
+    pub cached_input_tokens: Option<usize>,
+    /// Server-side timing breakdown, for providers which report it (e.g. Groq).
+    pub timing: Option<UsageTiming>,
+}
+
+impl Usage {
+    /// Merges a later report of usage into this one.
+    ///
+    /// Reports are running totals rather than increments, so each count reported
+    /// replaces the earlier one. Counts left out, as by providers reporting input and
+    /// output tokens in separate chunks, are kept.
+    pub fn merge(&mut self, usage: &Usage) {
+        self.input_tokens = usage.input_tokens.or(self.input_tokens);
+        self.output_tokens = usage.output_tokens.or(self.output_tokens);
+        self.cached_input_tokens = usage.cached_input_tokens.or(self.cached_input_tokens);
+        self.timing = usage.timing.or(self.timing);
+    }
+}
+
+/// The usage merged by [`ChatResponse::on_usage`], handed to its callback once.
+struct UsageTotal<F: FnOnce(&Usage)> {
+    usage: Option<Usage>,
+    f: Option<F>,
+}
+
+impl<F: FnOnce(&Usage)> UsageTotal<F> {
+    fn finish(&mut self) {
+        if let (Some(usage), Some(f)) = (self.usage.take(), self.f.take()) {
+            f(&usage);
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+impl<F: FnOnce(&Usage)> Drop for UsageTotal<F> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Server-side latency breakdown of a response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTiming {
+    /// Time the request spent queued before processing started.
+    pub queue_time: Option<Duration>,
+    /// Time spent processing the prompt.
+    pub prompt_time: Option<Duration>,
+    /// Time spent generating the completion.
+    pub completion_time: Option<Duration>,
+    /// Total server-side time.
+    pub total_time: Option<Duration>,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct AggregatedChat {
     pub content: String,
     pub thinking: Option<String>,
+    /// Signatures of the thinking, each ending the block of thinking it signs.
+    pub thinking_signatures: Vec<String>,
+    pub usage: Option<Usage>,
+    pub finish_reason: Option<FinishReason>,
+    /// Log probabilities of the content's tokens, if requested.
+    pub logprobs: Vec<TokenLogProb>,
+    /// The audio reply, if requested.
+    pub audio: Vec<u8>,
+    /// Tool calls, assembled from their streamed fragments.
+    pub tool_calls: Vec<ToolCall>,
+    /// Server tools the model used, such as web searches.
+    pub server_tool_uses: Vec<ServerToolUse>,
+    pub server_tool_results: Vec<ServerToolResult>,
+    /// The provider's unparsed events, if [`ChatOptions::include_raw`] was set.
+    pub raw: Vec<String>,
+    /// The order the parts of the chat were streamed in.
+    blocks: Vec<ChatBlock>,
+    /// Maps a tool call's stream index to its position in `tool_calls`.
+    tool_call_positions: HashMap<usize, usize>,
+}
+
+/// A part of an [`AggregatedChat`], which the chat lists in the order they were streamed.
+///
+/// Text is kept in the block as it was streamed, so editing the chat's `content` or
+/// `thinking` doesn't change its blocks. Everything else is referenced by its index
+/// in the chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatBlock {
+    Thinking(String),
+    ThinkingSignature(usize),
+    Content(String),
+    ToolCall(usize),
+    ServerToolUse(usize),
+    ServerToolResult(usize),
+    Raw(usize),
 }
 
 impl AggregatedChat {
     pub fn push(&mut self, chunk: &ChatChunk) {
         match chunk {
-            ChatChunk::Content(text) => self.content.push_str(text),
+            ChatChunk::Content(text) => {
+                self.content.push_str(text);
+                self.push_text_block(ChatBlock::Content(text.to_string()));
+            }
             ChatChunk::Thinking(text) => {
                 self.thinking.get_or_insert_with(String::new).push_str(text);
+                self.push_text_block(ChatBlock::Thinking(text.to_string()));
+            }
+            ChatChunk::ThinkingSignature(signature) => {
+                self.blocks
+                    .push(ChatBlock::ThinkingSignature(self.thinking_signatures.len()));
+                self.thinking_signatures.push(signature.clone());
+            }
+            ChatChunk::Usage(usage) => self.push_usage(usage),
+            ChatChunk::ToolCallDelta(delta) => self.push_tool_call_delta(delta),
+            ChatChunk::Finish(reason) => self.finish_reason = Some(reason.clone()),
+            ChatChunk::LogProbs(logprobs) => self.logprobs.extend_from_slice(logprobs),
+            ChatChunk::Audio(audio) => self.audio.extend_from_slice(audio),
+            ChatChunk::ServerToolUse(tool_use) => {
+                self.blocks
+                    .push(ChatBlock::ServerToolUse(self.server_tool_uses.len()));
+                self.server_tool_uses.push(tool_use.clone());
+            }
+            ChatChunk::ServerToolResult(result) => {
+                self.blocks
+                    .push(ChatBlock::ServerToolResult(self.server_tool_results.len()));
+                self.server_tool_results.push(result.clone());
             }
+            ChatChunk::Raw { provider_event } => {
+                self.blocks.push(ChatBlock::Raw(self.raw.len()));
+                self.raw.push(provider_event.clone());
+            }
+        }
+    }
+
+    /// The parts of the chat in the order they were streamed.
+    ///
+    /// If the chat wasn't aggregated from chunks, its thinking comes first, followed
+    /// by its content, tool calls, server tools and raw events.
+    pub fn blocks(&self) -> Vec<ChatBlock> {
+        if !self.blocks.is_empty() {
+            return self.blocks.clone();
+        }
+
+        let mut blocks = Vec::new();
+        if let Some(thinking) = &self.thinking {
+            blocks.push(ChatBlock::Thinking(thinking.clone()));
+        }
+        blocks.extend((0..self.thinking_signatures.len()).map(ChatBlock::ThinkingSignature));
+        if !self.content.is_empty() {
+            blocks.push(ChatBlock::Content(self.content.clone()));
         }
+        blocks.extend((0..self.tool_calls.len()).map(ChatBlock::ToolCall));
+        blocks.extend((0..self.server_tool_uses.len()).map(ChatBlock::ServerToolUse));
+        blocks.extend((0..self.server_tool_results.len()).map(ChatBlock::ServerToolResult));
+        blocks.extend((0..self.raw.len()).map(ChatBlock::Raw));
+        blocks
+    }
+
+    /// The reply as an assistant message, to be appended to the history.
+    ///
+    /// The message holds the content and the tool calls, which the tool results that
+    /// follow it answer. Only signed thinking is kept, as unsigned thinking can't be
+    /// sent back to the providers which take it.
+    pub fn into_message(self) -> Message {
+        let thinking = self.signed_thinking();
+        Message::assistant(self.content)
+            .tool_calls(self.tool_calls)
+            .thinking(thinking)
+    }
+
+    /// Deserializes the JSON in the reply's content, such as a reply to a chat
+    /// with a [`ResponseFormat`], into `T`.
+    ///
+    /// The JSON can be wrapped in a Markdown code fence or surrounded by other text,
+    /// in which case the first JSON object or array is used.
+    pub fn parse_json<T: DeserializeOwned>(&self) -> Result<T, ParseJsonError> {
+        parse_json(&self.content)
+    }
+
+    /// The blocks of thinking which were signed, each paired with its signature.
+    pub fn signed_thinking(&self) -> Vec<SignedThinking> {
+        let mut signed = Vec::new();
+        let mut text = String::new();
+
+        for block in self.blocks() {
+            match block {
+                ChatBlock::Thinking(thinking) => text.push_str(&thinking),
+                ChatBlock::ThinkingSignature(index) => {
+                    if let Some(signature) = self.thinking_signatures.get(index) {
+                        let thinking = std::mem::take(&mut text);
+                        signed.push(SignedThinking::new(thinking, signature.clone()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        signed
+    }
+
+    /// Splits the chat back into chunks, one per part, which aggregate back into it.
+    ///
+    /// Blocks referencing a part which was since removed from the chat are skipped.
+    pub fn into_chunks(self) -> Vec<ChatChunk> {
+        let mut chunks: Vec<_> = self
+            .blocks()
+            .into_iter()
+            .filter_map(|block| match block {
+                ChatBlock::Thinking(text) => Some(ChatChunk::Thinking(text.into())),
+                ChatBlock::ThinkingSignature(index) => self
+                    .thinking_signatures
+                    .get(index)
+                    .map(|signature| ChatChunk::ThinkingSignature(signature.clone())),
+                ChatBlock::Content(text) => Some(ChatChunk::Content(text.into())),
+                ChatBlock::ToolCall(index) => self.tool_calls.get(index).map(|tool_call| {
+                    ChatChunk::ToolCallDelta(ToolCallDelta {
+                        index,
+                        id: Some(tool_call.id.clone()),
+                        name: Some(tool_call.name.clone()),
+                        arguments: tool_call.arguments.clone(),
+                    })
+                }),
+                ChatBlock::ServerToolUse(index) => self
+                    .server_tool_uses
+                    .get(index)
+                    .map(|tool_use| ChatChunk::ServerToolUse(tool_use.clone())),
+                ChatBlock::ServerToolResult(index) => self
+                    .server_tool_results
+                    .get(index)
+                    .map(|result| ChatChunk::ServerToolResult(result.clone())),
+                ChatBlock::Raw(index) => self.raw.get(index).map(|event| ChatChunk::Raw {
+                    provider_event: event.clone(),
+                }),
+            })
+            .collect();
+
+        if !self.logprobs.is_empty() {
+            chunks.push(ChatChunk::LogProbs(self.logprobs));
+        }
+        if !self.audio.is_empty() {
+            chunks.push(ChatChunk::Audio(self.audio));
+        }
+        if let Some(usage) = self.usage {
+            chunks.push(ChatChunk::Usage(usage));
+        }
+        if let Some(reason) = self.finish_reason {
+            chunks.push(ChatChunk::Finish(reason));
+        }
+
+        chunks
+    }
+
+    /// Extends the last block if it's text of the same kind, otherwise starts a new one.
+    fn push_text_block(&mut self, block: ChatBlock) {
+        match (self.blocks.last_mut(), &block) {
+            (_, ChatBlock::Content(text) | ChatBlock::Thinking(text)) if text.is_empty() => {}
+            (Some(ChatBlock::Content(last)), ChatBlock::Content(text))
+            | (Some(ChatBlock::Thinking(last)), ChatBlock::Thinking(text)) => {
+                last.push_str(text);
+            }
+            _ => self.blocks.push(block),
+        }
+    }
+
+    /// Merges usage into what has been reported so far, as some providers
+    /// report input and output tokens in separate chunks.
+    fn push_usage(&mut self, usage: &Usage) {
+        self.usage.get_or_insert_with(Usage::default).merge(usage);
+    }
+
+    fn push_tool_call_delta(&mut self, delta: &ToolCallDelta) {
+        let position = *self
+            .tool_call_positions
+            .entry(delta.index)
+            .or_insert_with(|| {
+                self.blocks.push(ChatBlock::ToolCall(self.tool_calls.len()));
+                self.tool_calls.push(ToolCall::default());
+                self.tool_calls.len() - 1
+            });
+        let tool_call = &mut self.tool_calls[position];
+
+        if let Some(id) = &delta.id {
+            tool_call.id.clone_from(id);
+        }
+        if let Some(name) = &delta.name {
+            tool_call.name.clone_from(name);
+        }
+        tool_call.arguments.push_str(&delta.arguments);
     }
 }
 
-#[derive(Debug, Error)]
+impl From<AggregatedChat> for Message {
+    fn from(chat: AggregatedChat) -> Self {
+        chat.into_message()
+    }
+}
+
+#[derive(Debug, Error, EnumKind)]
+#[enum_kind(ChatErrorKind, derive(Hash))]
 pub enum ChatError {
     #[error("Failed to build the request: {0}.")]
     RequestBuildFailed(#[source] anyhow::Error),
@@ -226,13 +1312,813 @@ pub enum ChatError {
 
     #[error("The request failed: {0}.")]
     RequestError(#[source] anyhow::Error),
+
+    /// The provider failed to serve the request, answering with a server error
+    /// (5xx) or as overloaded (429), so repeating it later may succeed.
+    #[error("The provider is unavailable ({status}): {source}.")]
+    ProviderUnavailable {
+        status: StatusCode,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    #[error("\"{feature}\" is not supported by this provider.")]
+    Unsupported { feature: &'static str },
+
+    #[error("The request timed out after {after:?}.")]
+    Timeout { after: Duration },
+
+    #[error("No provider is registered as \"{name}\".")]
+    UnknownProvider { name: String },
+
+    #[error("The rate limit was reached, retry after {retry_after:?}.")]
+    RateLimited { retry_after: Duration },
+
+    #[error("The limit of {limit} chats in flight was reached.")]
+    TooManyInFlight { limit: usize },
+
+    /// The model failed too often, so chats aren't sent to it for now
+    /// (see [`CircuitBreakerProvider`](crate::providers::CircuitBreakerProvider)).
+    #[error("The circuit for \"{model}\" is open, retry after {retry_after:?}.")]
+    CircuitOpen {
+        model: String,
+        retry_after: Duration,
+    },
+
+    /// The chat would take the spending of `key` past its budget
+    /// (see [`BudgetedProvider`](crate::providers::BudgetedProvider)).
+    #[error("The budget of ${limit} for \"{key}\" was reached, with ${spent} spent.")]
+    BudgetExceeded { key: String, spent: f64, limit: f64 },
+
+    /// An option failed a check made before sending the request, such as
+    /// [`ChatOptions::validate`], naming the option so apps can point to it.
+    #[error("The \"{field}\" option is invalid: {reason}.")]
+    InvalidOptions { field: &'static str, reason: String },
+
+    /// The messages, plus the reply's `max_tokens`, don't fit in the model's context window.
+    #[error("The request doesn't fit in the model's context window: {0}.")]
+    ContextLengthExceeded(#[source] anyhow::Error),
 }
 
-#[derive(Debug, Error)]
+/// Phrases providers use in the errors they return when a request doesn't fit
+/// in the model's context window, in lowercase.
+const CONTEXT_LENGTH_ERRORS: &[&str] = &[
+    "context_length_exceeded",
+    "maximum context length",
+    "context window",
+    "context length",
+    "prompt is too long",
+    "input is too long",
+];
+
+impl ChatError {
+    /// The error for a request the provider rejected with `body`, which is
+    /// [`ChatError::ContextLengthExceeded`] when the body says the request was
+    /// too long, and [`ChatError::RequestError`] otherwise.
+    pub fn from_error_body(body: &[u8]) -> Self {
+        let body = String::from_utf8_lossy(body).into_owned();
+        let lowercase = body.to_lowercase();
+        if CONTEXT_LENGTH_ERRORS
+            .iter()
+            .any(|phrase| lowercase.contains(phrase))
+        {
+            Self::ContextLengthExceeded(anyhow::Error::msg(body))
+        } else {
+            Self::RequestError(anyhow::Error::msg(body))
+        }
+    }
+
+    /// The error for a request the provider rejected with `status` and `body`,
+    /// which is [`ChatError::ProviderUnavailable`] for server errors and 429s, and
+    /// the error of [`ChatError::from_error_body`] otherwise.
+    pub fn from_error_response(status: StatusCode, body: &[u8]) -> Self {
+        match Self::from_error_body(body) {
+            Self::RequestError(source)
+                if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS =>
+            {
+                Self::ProviderUnavailable { status, source }
+            }
+            error => error,
+        }
+    }
+}
+
+#[derive(Debug, Error, EnumKind)]
+#[enum_kind(ChatStreamErrorKind, derive(Hash))]
 pub enum ChatStreamError {
     #[error("This chunk contains incomplete data.")]
     IncompleteChunk,
 
     #[error("Failed to parse chunk: {0}.")]
     ParseError(#[source] anyhow::Error),
+
+    /// The provider reported an error partway through the stream.
+    #[error("The provider returned an error ({kind}): {message}.")]
+    ProviderError { kind: String, message: String },
+
+    #[error("The response timed out after {after:?}.")]
+    Timeout { after: Duration },
+
+    /// A request made partway through the stream failed, such as an
+    /// [`Agent`](crate::agent::Agent)'s request following its tool calls.
+    #[error("A follow-up request failed: {0}.")]
+    RequestFailed(#[source] anyhow::Error),
+
+    /// The model was still calling tools after the last request allowed for it.
+    #[error("The model was still calling tools after {limit} requests.")]
+    ToolLoopLimit { limit: usize },
+}
+
+/// Why a reply couldn't be deserialized by [`AggregatedChat::parse_json`],
+/// [`ChatResponse::parse_json`] or [`chat_json`](crate::providers::structured::chat_json).
+#[derive(Debug, Error)]
+pub enum ParseJsonError {
+    /// Sending the chat failed, which only [`chat_json`](crate::providers::structured::chat_json)
+    /// does itself.
+    #[error(transparent)]
+    Chat(#[from] ChatError),
+
+    #[error(transparent)]
+    Stream(#[from] ChatStreamError),
+
+    /// The reply has no JSON, or none matching the expected type.
+    #[error("The reply isn't the expected JSON: {source}.")]
+    Invalid {
+        #[source]
+        source: serde_json::Error,
+        /// The reply's content, as the model wrote it.
+        text: String,
+    },
+}
+
+/// Deserializes the first JSON object or array in `text`, looking inside
+/// the first Markdown code fence if there is one.
+fn parse_json<T: DeserializeOwned>(text: &str) -> Result<T, ParseJsonError> {
+    let invalid = |source| ParseJsonError::Invalid {
+        source,
+        text: text.to_owned(),
+    };
+    let body = fenced_block(text).unwrap_or(text);
+
+    let mut syntax_error = None;
+    for (start, _) in body.match_indices(['{', '[']) {
+        match serde_json::Deserializer::from_str(&body[start..])
+            .into_iter::<T>()
+            .next()
+        {
+            Some(Ok(value)) => return Ok(value),
+            // JSON of the wrong shape, so nested JSON isn't what was meant either.
+            Some(Err(err)) if err.is_data() => return Err(invalid(err)),
+            // Maybe just a bracket in the text before the JSON.
+            Some(Err(err)) => {
+                syntax_error.get_or_insert(err);
+            }
+            None => {}
+        }
+    }
+
+    match syntax_error {
+        Some(err) => Err(invalid(err)),
+        None => serde_json::from_str(body.trim()).map_err(invalid),
+    }
+}
+
+/// The contents of the first Markdown code fence in `text`, if any.
+fn fenced_block(text: &str) -> Option<&str> {
+    let (_, rest) = text.split_once("```")?;
+    // Skips the fence's info string, such as `json`.
+    let (_, rest) = rest.split_once('\n')?;
+    Some(rest.split_once("```").map_or(rest, |(block, _)| block))
+}
+
+impl ChatStreamError {
+    /// Whether the error is transient, so repeating the request may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::ProviderError { kind, .. } => matches!(
+                kind.as_str(),
+                "overloaded_error" | "api_error" | "rate_limit_error"
+            ),
+            Self::Timeout { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Image;
+    use crate::providers::mock::{MockChatProvider, MockReply};
+
+    #[test]
+    fn to_json_with_system_prepends_system_message() {
+        let messages = [Message::user("Hi")];
+
+        let json = Messages::Raw(&messages).to_json_with_system(Some("Be nice."));
+        let parsed: Vec<Message> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].role.as_str(), "system");
+        assert_eq!(parsed[0].content, "Be nice.");
+
+        let json = Messages::Raw(&[]).to_json_with_system(Some("Be nice."));
+        let parsed: Vec<Message> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 1);
+    }
+
+    #[test]
+    fn to_json_with_appends_prefill() {
+        let messages = [Message::user("Hi")];
+
+        let json = Messages::Raw(&messages).to_json_with(Some("Be nice."), Some("{"));
+        let parsed: Vec<Message> = serde_json::from_str(&json).unwrap();
+        let roles: Vec<_> = parsed.iter().map(|message| message.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant"]);
+        assert_eq!(parsed[2].content, "{");
+    }
+
+    #[test]
+    fn serialize_leaves_out_secrets() {
+        let messages = [Message::user("Hi")];
+        let options = ChatOptions::new("model")
+            .messages(&messages)
+            .thinking(Thinking::budget_tokens(1024))
+            .overrides(
+                RequestOverrides::new()
+                    .api_key("secret-key")
+                    .header("authorization", "Bearer secret-token"),
+            );
+
+        let json = serde_json::to_value(&options).unwrap();
+
+        assert_eq!(json["model"], "model");
+        assert_eq!(json["messages"][0]["content"], "Hi");
+        assert_eq!(
+            json["thinking"],
+            serde_json::json!({ "budget_tokens": 1024 })
+        );
+        assert_eq!(
+            json["overrides"]["headers"],
+            serde_json::json!(["authorization"])
+        );
+        assert!(!json.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn owned_options_round_trip() {
+        let owned = {
+            let model = String::from("model");
+            let messages = vec![Message::user("Hi")];
+            let tools = vec![Tool::new(
+                "get_time",
+                serde_json::json!({ "type": "object" }),
+            )];
+            ChatOptions::new(&model)
+                .messages(&messages)
+                .system("Be brief.")
+                .tools(&tools)
+                .max_tokens(100)
+                .into_owned()
+        };
+
+        let handle = std::thread::spawn(move || {
+            let options = owned.as_options();
+            assert_eq!(options.model, "model");
+            assert_eq!(options.system, Some("Be brief."));
+            assert_eq!(options.tools.len(), 1);
+            assert_eq!(options.max_tokens, Some(100));
+            options.messages.to_json()
+        });
+
+        assert_eq!(
+            handle.join().unwrap(),
+            r#"[{"content":"Hi","role":"user"}]"#
+        );
+    }
+
+    #[test]
+    fn owned_options_keep_serialized_messages() {
+        let messages = RawValue::from_string(r#"[{"role":"user","content":"Hi"}]"#.to_owned());
+        let options = ChatOptions::new("model").messages_serialized(messages.unwrap());
+        let owned = ChatOptionsOwned::from(&options);
+
+        assert_eq!(owned.as_options().fingerprint(), options.fingerprint());
+    }
+
+    #[test]
+    fn fingerprint_ignores_secrets_but_not_options() {
+        let messages = [Message::user("Hi")];
+        let options = ChatOptions::new("model").messages(&messages);
+
+        assert_eq!(
+            options.fingerprint(),
+            options
+                .clone()
+                .overrides(RequestOverrides::new().api_key("secret-key"))
+                .fingerprint()
+        );
+        assert_eq!(
+            options.fingerprint(),
+            ChatOptions::new("model")
+                .messages_serialized(
+                    RawValue::from_string(serde_json::to_string(&messages).unwrap()).unwrap()
+                )
+                .fingerprint()
+        );
+        assert_ne!(
+            options.fingerprint(),
+            options.clone().max_tokens(100).fingerprint()
+        );
+        let image = [Message::user("Hi").part(Image::new(b"PNG", "image/png"))];
+        assert_ne!(
+            options.fingerprint(),
+            options.clone().messages(&image).fingerprint()
+        );
+        assert_ne!(
+            options.fingerprint(),
+            options.clone().stream(false).fingerprint()
+        );
+    }
+
+    #[test]
+    fn fingerprint_covers_extensions() {
+        #[derive(Debug, Serialize)]
+        struct Sampling {
+            temperature: f32,
+            seed: u64,
+        }
+
+        let messages = [Message::user("Hi")];
+        let options = ChatOptions::new("model").messages(&messages);
+        let sampling = |temperature| Sampling {
+            temperature,
+            seed: 7,
+        };
+
+        let json = serde_json::to_value(options.clone().extension(sampling(0.5))).unwrap();
+        assert_eq!(
+            json["extensions"][std::any::type_name::<Sampling>()]["temperature"],
+            0.5
+        );
+        assert_eq!(
+            options.clone().extension(sampling(0.5)).fingerprint(),
+            options.clone().extension(sampling(0.5)).fingerprint()
+        );
+        assert_ne!(
+            options.clone().extension(sampling(0.5)).fingerprint(),
+            options.clone().extension(sampling(1.0)).fingerprint()
+        );
+        assert_ne!(
+            options.fingerprint(),
+            options.clone().extension(sampling(0.5)).fingerprint()
+        );
+    }
+
+    #[test]
+    fn aggregate_accumulates_tool_call_fragments() {
+        let mut result = AggregatedChat::default();
+
+        let chunks = [
+            ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 1,
+                id: Some("call_1".into()),
+                name: Some("get_weather".into()),
+                arguments: String::new(),
+            }),
+            ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 1,
+                arguments: "{\"city\":".into(),
+                ..Default::default()
+            }),
+            ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 2,
+                id: Some("call_2".into()),
+                name: Some("get_time".into()),
+                arguments: String::new(),
+            }),
+            ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 1,
+                arguments: "\"Paris\"}".into(),
+                ..Default::default()
+            }),
+        ];
+        for chunk in &chunks {
+            result.push(chunk);
+        }
+
+        assert_eq!(result.tool_calls.len(), 2);
+        assert_eq!(result.tool_calls[0].id, "call_1");
+        assert_eq!(result.tool_calls[0].name, "get_weather");
+        assert_eq!(
+            result.tool_calls[0].arguments_json().unwrap(),
+            serde_json::json!({ "city": "Paris" })
+        );
+        assert_eq!(result.tool_calls[1].name, "get_time");
+        assert_eq!(
+            result.tool_calls[1].arguments_json().unwrap(),
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn aggregate_merges_usage_and_finish_reason() {
+        let mut result = AggregatedChat::default();
+
+        let chunks = [
+            ChatChunk::Usage(Usage {
+                input_tokens: Some(25),
+                ..Default::default()
+            }),
+            ChatChunk::Finish(FinishReason::Stop),
+            ChatChunk::Usage(Usage {
+                output_tokens: Some(15),
+                ..Default::default()
+            }),
+        ];
+        for chunk in &chunks {
+            result.push(chunk);
+        }
+
+        let usage = result.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(25));
+        assert_eq!(usage.output_tokens, Some(15));
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+    }
+
+    #[test]
+    fn aggregate_retains_block_order() {
+        let chunks = [
+            ChatChunk::Raw {
+                provider_event: "{}".into(),
+            },
+            ChatChunk::Thinking("Let me ".into()),
+            ChatChunk::Thinking("check.".into()),
+            ChatChunk::Content("Checking".into()),
+            ChatChunk::Content("...".into()),
+            ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 0,
+                id: Some("call_1".into()),
+                name: Some("get_weather".into()),
+                arguments: "{}".into(),
+            }),
+            ChatChunk::Thinking("Sunny.".into()),
+            ChatChunk::Content(" It's sunny.".into()),
+            ChatChunk::Finish(FinishReason::Stop),
+        ];
+        let mut result = AggregatedChat::default();
+        for chunk in &chunks {
+            result.push(chunk);
+        }
+
+        assert_eq!(result.raw, ["{}"]);
+        assert_eq!(
+            result.blocks(),
+            [
+                ChatBlock::Raw(0),
+                ChatBlock::Thinking("Let me check.".into()),
+                ChatBlock::Content("Checking...".into()),
+                ChatBlock::ToolCall(0),
+                ChatBlock::Thinking("Sunny.".into()),
+                ChatBlock::Content(" It's sunny.".into()),
+            ]
+        );
+
+        let chunks: Vec<_> = result.clone().into_chunks();
+        assert!(matches!(&chunks[1], ChatChunk::Thinking(text) if text == "Let me check."));
+        assert!(matches!(&chunks[4], ChatChunk::Thinking(text) if text == "Sunny."));
+        assert!(matches!(&chunks[5], ChatChunk::Content(text) if text == " It's sunny."));
+
+        let mut replayed = AggregatedChat::default();
+        for chunk in &chunks {
+            replayed.push(chunk);
+        }
+        assert_eq!(replayed.blocks(), result.blocks());
+
+        // The blocks don't depend on the fields, which callers may edit.
+        result.content.clear();
+        result.thinking = None;
+        result.tool_calls.clear();
+        let chunks = result.into_chunks();
+        assert_eq!(chunks.len(), 6);
+        assert!(matches!(&chunks[4], ChatChunk::Content(text) if text == " It's sunny."));
+    }
+
+    #[test]
+    fn blocks_default_to_field_order() {
+        let chat = AggregatedChat {
+            content: "Hi".into(),
+            thinking: Some("Hmm".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            chat.blocks(),
+            [
+                ChatBlock::Thinking("Hmm".into()),
+                ChatBlock::Content("Hi".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn into_message_keeps_content_and_tool_calls() {
+        let mut chat = AggregatedChat::default();
+        for chunk in [
+            ChatChunk::Thinking("The user wants the weather.".into()),
+            ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 0,
+                id: Some("call_1".into()),
+                name: Some("get_weather".into()),
+                arguments: "{\"city\":\"Paris\"}".into(),
+            }),
+            ChatChunk::Finish(FinishReason::ToolCalls),
+        ] {
+            chat.push(&chunk);
+        }
+
+        let message = chat.into_message();
+        assert_eq!(message.role.as_str(), "assistant");
+        assert_eq!(message.content, "");
+        assert_eq!(
+            message.tool_calls,
+            [ToolCall::new(
+                "call_1",
+                "get_weather",
+                "{\"city\":\"Paris\"}"
+            )]
+        );
+        assert_eq!(message.tool_call_id, None);
+        assert!(message.thinking.is_empty());
+    }
+
+    #[test]
+    fn into_message_keeps_signed_thinking() {
+        let mut chat = AggregatedChat::default();
+        for chunk in [
+            ChatChunk::Thinking("The user wants ".into()),
+            ChatChunk::Thinking("the weather.".into()),
+            ChatChunk::ThinkingSignature("sig_1".into()),
+            ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 1,
+                id: Some("toolu_1".into()),
+                name: Some("get_weather".into()),
+                arguments: "{}".into(),
+            }),
+            ChatChunk::Thinking("Then the forecast.".into()),
+            ChatChunk::ThinkingSignature("sig_2".into()),
+            ChatChunk::Thinking("Unsigned.".into()),
+        ] {
+            chat.push(&chunk);
+        }
+
+        let mut replayed = AggregatedChat::default();
+        for chunk in &chat.clone().into_chunks() {
+            replayed.push(chunk);
+        }
+        assert_eq!(replayed.blocks(), chat.blocks());
+
+        let message = chat.into_message();
+        assert_eq!(
+            message.thinking,
+            [
+                SignedThinking::new("The user wants the weather.", "sig_1"),
+                SignedThinking::new("Then the forecast.", "sig_2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregate_with_calls_back_per_chunk() {
+        let mut response = ChatResponse::new(futures::stream::iter([
+            Ok(ChatChunk::Content("Hello".into())),
+            Ok(ChatChunk::Content(" world".into())),
+            Ok(ChatChunk::Finish(FinishReason::Stop)),
+        ]));
+
+        let mut streamed = String::new();
+        let mut chunks = 0;
+        let result = futures::executor::block_on(response.aggregate_with(|chunk| {
+            chunks += 1;
+            if let ChatChunk::Content(text) = chunk {
+                streamed.push_str(text);
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(chunks, 3);
+        assert_eq!(streamed, "Hello world");
+        assert_eq!(result.content, "Hello world");
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+    }
+
+    #[test]
+    fn map_chunks_and_inspect() {
+        let response = ChatResponse::new(futures::stream::iter([
+            Ok(ChatChunk::Content("hello".into())),
+            Err(ChatStreamError::IncompleteChunk),
+            Ok(ChatChunk::Finish(FinishReason::Stop)),
+        ]));
+
+        let mut inspected = 0;
+        let chunks: Vec<_> = futures::executor::block_on(
+            response
+                .inspect(|_| inspected += 1)
+                .map_chunks(|chunk| match chunk {
+                    ChatChunk::Content(text) => ChatChunk::Content(text.to_uppercase().into()),
+                    chunk => chunk,
+                })
+                .collect(),
+        );
+
+        assert_eq!(inspected, 2);
+        assert!(matches!(&chunks[0], Ok(ChatChunk::Content(text)) if text == "HELLO"));
+        assert!(matches!(chunks[1], Err(ChatStreamError::IncompleteChunk)));
+        assert!(matches!(
+            chunks[2],
+            Ok(ChatChunk::Finish(FinishReason::Stop))
+        ));
+    }
+
+    #[test]
+    fn content_and_thinking_only() {
+        let chunks = || {
+            futures::stream::iter([
+                Ok(ChatChunk::Thinking("Hmm.".into())),
+                Ok(ChatChunk::Content("Hello".into())),
+                Ok(ChatChunk::Usage(Usage::default())),
+                Ok(ChatChunk::Content(" world".into())),
+            ])
+        };
+
+        let content: Vec<_> = futures::executor::block_on(
+            ChatResponse::new(chunks())
+                .content_only()
+                .map(Result::unwrap)
+                .collect(),
+        );
+        let thinking: Vec<_> = futures::executor::block_on(
+            ChatResponse::new(chunks())
+                .thinking_only()
+                .map(Result::unwrap)
+                .collect(),
+        );
+
+        assert_eq!(content, ["Hello", " world"]);
+        assert_eq!(thinking, ["Hmm."]);
+    }
+
+    #[test]
+    fn chunk_text_is_shared() {
+        let text = String::from("Hello");
+        let address = text.as_ptr();
+        let chunk = ChatChunk::Content(text.into());
+
+        assert_eq!(chunk.as_str(), Some("Hello"));
+        assert_eq!(ChatChunk::Thinking("Hmm.".into()).as_str(), Some("Hmm."));
+        assert_eq!(ChatChunk::Finish(FinishReason::Stop).as_str(), None);
+
+        // Neither converting the text nor taking its bytes copies it.
+        let ChatChunk::Content(text) = chunk.clone() else {
+            unreachable!()
+        };
+        assert_eq!(text.into_bytes().as_ptr(), address);
+    }
+
+    #[test]
+    fn abort_ends_response() {
+        let mut response = ChatResponse::new(
+            futures::stream::iter([Ok(ChatChunk::Content("Hi".into()))])
+                .chain(futures::stream::pending()),
+        );
+
+        futures::executor::block_on(async {
+            assert!(response.next().await.is_some());
+
+            response.abort();
+            assert!(response.is_aborted());
+            assert!(response.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn abort_handle_wakes_pending_next() {
+        let mut response = ChatResponse::new(futures::stream::pending());
+        let abort_handle = response.abort_handle();
+
+        let aborter = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            abort_handle.abort();
+        });
+
+        assert!(futures::executor::block_on(response.next()).is_none());
+        aborter.join().unwrap();
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Weather {
+        city: String,
+        sunny: bool,
+    }
+
+    fn reply(content: &str) -> AggregatedChat {
+        let mut reply = AggregatedChat::default();
+        reply.push(&ChatChunk::Content(content.into()));
+        reply
+    }
+
+    #[test]
+    fn parse_json_strips_code_fences_and_text() {
+        let expected = Weather {
+            city: "Paris".to_owned(),
+            sunny: true,
+        };
+
+        let fenced = reply("Here you go:\n```json\n{\"city\": \"Paris\", \"sunny\": true}\n```");
+        assert_eq!(fenced.parse_json::<Weather>().unwrap(), expected);
+
+        let surrounded =
+            reply("[Note] The weather: {\"city\": \"Paris\", \"sunny\": true}. Enjoy!");
+        assert_eq!(surrounded.parse_json::<Weather>().unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_json_keeps_the_text_on_failure() {
+        let wrong_shape =
+            reply(r#"{"city": "Paris", "sunny": "yes", "inner": {"city": "Rome", "sunny": true}}"#);
+        let Err(ParseJsonError::Invalid { source, text }) = wrong_shape.parse_json::<Weather>()
+        else {
+            panic!("expected an invalid JSON error");
+        };
+        assert!(source.is_data());
+        assert!(text.starts_with(r#"{"city": "Paris""#));
+
+        let no_json = reply("I can't help with that.");
+        assert!(matches!(
+            no_json.parse_json::<Weather>(),
+            Err(ParseJsonError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn response_parse_json_aggregates_first() {
+        let mut response = ChatResponse::new(futures::stream::iter([
+            Ok(ChatChunk::Content("{\"city\": \"Paris\",".into())),
+            Ok(ChatChunk::Content(" \"sunny\": false}".into())),
+        ]));
+
+        let weather: Weather = futures::executor::block_on(response.parse_json()).unwrap();
+
+        assert_eq!(weather.city, "Paris");
+        assert!(!weather.sunny);
+    }
+
+    /// The prefill and the messages' content of the last chat sent to `provider`.
+    fn last_request(provider: &MockChatProvider) -> (Option<String>, Vec<String>) {
+        let requests = provider.requests();
+        let request = requests.last().unwrap().as_options();
+        let Messages::Raw(messages) = request.messages else {
+            unreachable!()
+        };
+        let contents = messages.iter().map(|m| m.content.clone()).collect();
+        (request.prefill.map(str::to_owned), contents)
+    }
+
+    #[test]
+    fn resume_prefills_the_partial_reply() {
+        let provider = MockChatProvider::new().reply(MockReply::text(" world"));
+        let messages = &[Message::user("Say hello world")];
+        let options = ChatOptions::new("model").messages(messages);
+        let mut partial = AggregatedChat::default();
+        partial.push(&ChatChunk::Content("Hello".into()));
+
+        futures::executor::block_on(async {
+            let mut response = ChatResponse::resume(&provider, &options, &partial)
+                .await
+                .unwrap();
+            assert_eq!(response.aggregate().await.unwrap().content, " world");
+        });
+
+        let (prefill, contents) = last_request(&provider);
+        assert_eq!(prefill.as_deref(), Some("Hello"));
+        assert_eq!(contents, ["Say hello world"]);
+    }
+
+    #[test]
+    fn resume_asks_to_continue_without_prefill() {
+        let provider = MockChatProvider::new()
+            .reply(MockReply::fail(ChatError::Unsupported {
+                feature: "prefill",
+            }))
+            .reply(MockReply::text(" world"));
+        let messages = &[Message::user("Say hello world")];
+        let options = ChatOptions::new("model").messages(messages);
+        let mut partial = AggregatedChat::default();
+        partial.push(&ChatChunk::Content("Hello".into()));
+
+        futures::executor::block_on(ChatResponse::resume(&provider, &options, &partial)).unwrap();
+
+        let (prefill, contents) = last_request(&provider);
+        assert_eq!(prefill, None);
+        assert_eq!(contents, ["Say hello world", "Hello", RESUME_PROMPT]);
+    }
 }
@@ -0,0 +1,429 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use web_time::Instant;
+
+use crate::models::Model;
+use crate::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse};
+use crate::providers::health::{HealthCheckProvider, HealthStatus};
+use crate::providers::list_models::{ListModelsError, ListModelsProvider};
+
+/// When a [`CircuitBreakerProvider`] trips, and for how long it stays open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircuitBreakerPolicy {
+    /// How many of a model's latest requests the error rate is taken over.
+    pub window: usize,
+    /// The fewest requests in the window before the breaker can trip.
+    pub min_requests: usize,
+    /// The share of failed requests in the window, from 0 to 1, which trips the breaker.
+    pub error_rate: f64,
+    /// Requests taking longer than this for their response to start count as
+    /// failed. `None` ignores latency.
+    pub slow_after: Option<Duration>,
+    /// How long the breaker stays open before letting a probe request through.
+    pub open_for: Duration,
+}
+
+impl Default for CircuitBreakerPolicy {
+    fn default() -> Self {
+        Self {
+            window: 20,
+            min_requests: 5,
+            error_rate: 0.5,
+            slow_after: None,
+            open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+/// The state of a model's circuit in a [`CircuitBreakerProvider`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Chats are sent as usual.
+    Closed,
+    /// Chats fail with [`ChatError::CircuitOpen`] without being sent.
+    Open,
+    /// A probe chat is in flight, and closes the circuit if it succeeds.
+    HalfOpen,
+}
+
+/// Stops sending chats to a model which keeps failing, so they fail fast
+/// (e.g. moving on to a fallback) rather than waiting on a dead backend.
+///
+/// Each model has its own circuit, which opens once enough of its latest
+/// requests failed or were too slow, by the [`CircuitBreakerPolicy`]. While
+/// open, chats fail with [`ChatError::CircuitOpen`]. Once
+/// [`CircuitBreakerPolicy::open_for`] has passed, the next chat is sent as a
+/// probe: if it succeeds the circuit closes, and otherwise it opens again.
+///
+/// Errors from the request itself, such as [`ChatError::InvalidOptions`], don't
+/// count as failures. A chat succeeds once its response has streamed without
+/// errors. Cloning the provider shares its circuits.
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerProvider<P> {
+    provider: P,
+    breaker: Arc<Breaker>,
+}
+
+impl<P> CircuitBreakerProvider<P> {
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            breaker: Arc::new(Breaker {
+                policy: CircuitBreakerPolicy::default(),
+                circuits: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    pub fn policy(mut self, policy: CircuitBreakerPolicy) -> Self {
+        self.breaker = Arc::new(Breaker {
+            policy,
+            circuits: Mutex::new(HashMap::new()),
+        });
+        self
+    }
+
+    pub fn state(&self, model: &str) -> CircuitState {
+        let circuits = self.breaker.circuits.lock().unwrap();
+        match circuits.get(model).map(|circuit| circuit.state) {
+            None | Some(State::Closed) => CircuitState::Closed,
+            Some(State::Open { .. }) => CircuitState::Open,
+            Some(State::HalfOpen) => CircuitState::HalfOpen,
+        }
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn into_inner(self) -> P {
+        self.provider
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider> ChatProvider for CircuitBreakerProvider<P> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let options: &ChatOptions = &options.or_default_model(self.provider.default_model());
+        let probe = self.breaker.acquire(options.model)?;
+        // Recorded when dropped, so a cancelled probe lets another through.
+        let mut outcome = Outcome {
+            breaker: self.breaker.clone(),
+            model: options.model.to_owned(),
+            probe,
+            failed: None,
+        };
+
+        let sent = Instant::now();
+        let response = match self.provider.chat(options).await {
+            Ok(response) => response,
+            Err(error) => {
+                if is_failure(&error) {
+                    outcome.failed = Some(true);
+                }
+                return Err(error);
+            }
+        };
+
+        let slow = self
+            .breaker
+            .policy
+            .slow_after
+            .is_some_and(|slow_after| sent.elapsed() > slow_after);
+        if slow {
+            outcome.failed = Some(true);
+            return Ok(response);
+        }
+
+        outcome.failed = Some(false);
+        Ok(response.map_stream(|stream| {
+            stream.map(move |item| {
+                // Borrows the whole outcome, so it moves into the stream and is only
+                // recorded once the stream is dropped, rather than copying `failed`.
+                let outcome = &mut outcome;
+                if item.is_err() {
+                    outcome.failed = Some(true);
+                }
+                item
+            })
+        }))
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.provider.default_model()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ListModelsProvider> ListModelsProvider for CircuitBreakerProvider<P> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        self.provider.list_models().await
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.provider.get_model(id).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: HealthCheckProvider> HealthCheckProvider for CircuitBreakerProvider<P> {
+    async fn health(&self) -> HealthStatus {
+        self.provider.health().await
+    }
+}
+
+/// Whether `error` says the backend is in trouble, rather than the request
+/// being at fault, as rejected requests (such as 400s and 401s) are.
+fn is_failure(error: &ChatError) -> bool {
+    matches!(
+        error,
+        ChatError::ResponseFetchFailed(_)
+            | ChatError::ProviderUnavailable { .. }
+            | ChatError::Timeout { .. }
+            | ChatError::RateLimited { .. }
+    )
+}
+
+#[derive(Debug)]
+struct Breaker {
+    policy: CircuitBreakerPolicy,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    state: State,
+    /// Whether each of the latest requests failed, oldest first.
+    outcomes: VecDeque<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+enum State {
+    #[default]
+    Closed,
+    Open {
+        until: Instant,
+    },
+    /// A probe is in flight.
+    HalfOpen,
+}
+
+impl Breaker {
+    /// Lets a chat to `model` through, returning whether it is the probe of a
+    /// half-open circuit.
+    fn acquire(&self, model: &str) -> Result<bool, ChatError> {
+        let mut circuits = self.circuits.lock().unwrap();
+        let Some(circuit) = circuits.get_mut(model) else {
+            return Ok(false);
+        };
+
+        match circuit.state {
+            State::Closed => Ok(false),
+            State::Open { until } => {
+                let now = Instant::now();
+                if now < until {
+                    return Err(ChatError::CircuitOpen {
+                        model: model.to_owned(),
+                        retry_after: until - now,
+                    });
+                }
+                circuit.state = State::HalfOpen;
+                Ok(true)
+            }
+            State::HalfOpen => Err(ChatError::CircuitOpen {
+                model: model.to_owned(),
+                retry_after: Duration::ZERO,
+            }),
+        }
+    }
+
+    /// Records whether a chat to `model` failed, or `None` if it was cancelled
+    /// or failed through no fault of the backend.
+    fn record(&self, model: &str, probe: bool, failed: Option<bool>) {
+        let mut circuits = self.circuits.lock().unwrap();
+
+        if probe {
+            let circuit = circuits.entry(model.to_owned()).or_default();
+            circuit.state = match failed {
+                Some(false) => {
+                    circuit.outcomes.clear();
+                    State::Closed
+                }
+                Some(true) => State::Open {
+                    until: Instant::now() + self.policy.open_for,
+                },
+                // Let the next chat probe instead.
+                None => State::Open {
+                    until: Instant::now(),
+                },
+            };
+            return;
+        }
+
+        let Some(failed) = failed else {
+            return;
+        };
+        let circuit = circuits.entry(model.to_owned()).or_default();
+        // Chats sent before the circuit opened don't count.
+        if !matches!(circuit.state, State::Closed) {
+            return;
+        }
+
+        circuit.outcomes.push_back(failed);
+        while circuit.outcomes.len() > self.policy.window {
+            circuit.outcomes.pop_front();
+        }
+
+        let requests = circuit.outcomes.len();
+        let failures = circuit.outcomes.iter().filter(|failed| **failed).count();
+        if requests >= self.policy.min_requests
+            && failures as f64 >= self.policy.error_rate * requests as f64
+        {
+            circuit.outcomes.clear();
+            circuit.state = State::Open {
+                until: Instant::now() + self.policy.open_for,
+            };
+        }
+    }
+}
+
+/// The outcome of a chat, recorded once it's dropped.
+struct Outcome {
+    breaker: Arc<Breaker>,
+    model: String,
+    probe: bool,
+    failed: Option<bool>,
+}
+
+impl Drop for Outcome {
+    fn drop(&mut self) {
+        self.breaker.record(&self.model, self.probe, self.failed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::chat::ChatStreamError;
+    use crate::providers::mock::{MockChatProvider, MockReply};
+    use futures::executor::block_on;
+
+    fn failure() -> MockReply {
+        MockReply::fail(ChatError::ProviderUnavailable {
+            status: http::StatusCode::SERVICE_UNAVAILABLE,
+            source: anyhow::anyhow!("overloaded"),
+        })
+    }
+
+    fn send(
+        provider: &CircuitBreakerProvider<MockChatProvider>,
+        model: &str,
+    ) -> Result<(), ChatError> {
+        block_on(async {
+            let mut response = provider.chat(&ChatOptions::new(model)).await?;
+            while response.next().await.is_some() {}
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn opens_once_the_error_rate_is_reached() {
+        let provider = CircuitBreakerProvider::new(
+            MockChatProvider::new()
+                .reply(MockReply::text("Hello"))
+                .reply(failure())
+                .reply(MockReply::new().error(ChatStreamError::IncompleteChunk))
+                .reply(MockReply::text("Hello")),
+        )
+        .policy(CircuitBreakerPolicy {
+            window: 4,
+            min_requests: 3,
+            error_rate: 0.6,
+            open_for: Duration::from_secs(60),
+            ..Default::default()
+        });
+
+        assert!(send(&provider, "llama3").is_ok());
+        assert!(send(&provider, "llama3").is_err());
+        assert_eq!(provider.state("llama3"), CircuitState::Closed);
+        assert!(send(&provider, "llama3").is_ok());
+        assert_eq!(provider.state("llama3"), CircuitState::Open);
+
+        assert!(matches!(
+            send(&provider, "llama3"),
+            Err(ChatError::CircuitOpen { model, .. }) if model == "llama3"
+        ));
+        assert_eq!(provider.inner().calls(), 3);
+
+        assert!(send(&provider, "qwen3").is_ok());
+    }
+
+    #[test]
+    fn a_successful_probe_closes_the_circuit() {
+        let provider = CircuitBreakerProvider::new(
+            MockChatProvider::new()
+                .reply(failure())
+                .reply(failure())
+                .reply(MockReply::text("Hello")),
+        )
+        .policy(CircuitBreakerPolicy {
+            min_requests: 1,
+            open_for: Duration::ZERO,
+            ..Default::default()
+        });
+
+        assert!(send(&provider, "llama3").is_err());
+        assert_eq!(provider.state("llama3"), CircuitState::Open);
+
+        // The failed probe opens the circuit again.
+        assert!(matches!(
+            send(&provider, "llama3"),
+            Err(ChatError::ProviderUnavailable { .. })
+        ));
+        assert_eq!(provider.state("llama3"), CircuitState::Open);
+
+        let response = block_on(provider.chat(&ChatOptions::new("llama3"))).unwrap();
+        assert_eq!(provider.state("llama3"), CircuitState::HalfOpen);
+        assert!(matches!(
+            send(&provider, "llama3"),
+            Err(ChatError::CircuitOpen { .. })
+        ));
+
+        drop(response);
+        assert_eq!(provider.state("llama3"), CircuitState::Closed);
+    }
+
+    #[test]
+    fn invalid_requests_dont_count() {
+        let provider = CircuitBreakerProvider::new(
+            MockChatProvider::new()
+                .reply(MockReply::fail(ChatError::InvalidOptions {
+                    field: "temperature",
+                    reason: "must be at most 2".to_owned(),
+                }))
+                .reply(MockReply::fail(ChatError::from_error_response(
+                    http::StatusCode::UNAUTHORIZED,
+                    b"invalid api key",
+                ))),
+        )
+        .policy(CircuitBreakerPolicy {
+            min_requests: 1,
+            ..Default::default()
+        });
+
+        assert!(send(&provider, "llama3").is_err());
+        assert!(matches!(
+            send(&provider, "llama3"),
+            Err(ChatError::RequestError(_))
+        ));
+        assert_eq!(provider.state("llama3"), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::maybe_send::{MaybeSend, MaybeSync};
+use crate::providers::chat::{ChatError, ChatResponse};
+
+/// A provider that continues a raw prompt, without applying a chat template.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait CompletionProvider: MaybeSend + MaybeSync {
+    async fn complete(&self, options: &CompletionOptions<'_>) -> Result<ChatResponse, ChatError>;
+}
+
+#[derive(Clone, Debug)]
+pub struct CompletionOptions<'a> {
+    pub model: &'a str,
+    pub prompt: &'a str,
+    pub suffix: Option<&'a str>,
+    pub system: Option<&'a str>,
+    pub raw: bool,
+    pub stream: bool,
+    /// Left to the provider's default when `None`.
+    pub max_tokens: Option<usize>,
+}
+
+impl<'a> CompletionOptions<'a> {
+    pub fn new(model: &'a str, prompt: &'a str) -> Self {
+        Self {
+            model,
+            prompt,
+            suffix: None,
+            system: None,
+            raw: false,
+            stream: true,
+            max_tokens: None,
+        }
+    }
+
+    /// Sets the model to be used for the completion.
+    pub fn model(mut self, model: &'a str) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Sets the text the model continues from.
+    pub fn prompt(mut self, prompt: &'a str) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    /// Sets the text after the completion, for fill-in-the-middle.
+    pub fn suffix(mut self, suffix: &'a str) -> Self {
+        self.suffix = Some(suffix);
+        self
+    }
+
+    /// Sets the system prompt. Ignored in raw mode.
+    pub fn system(mut self, system: &'a str) -> Self {
+        self.system = Some(system);
+        self
+    }
+
+    /// Sends the prompt exactly as given, skipping the model's prompt template.
+    /// The prompt must then include any special tokens the model expects.
+    pub fn raw(mut self, raw: bool) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// Enables or disables streaming mode.
+    /// If `false` then the entire response will be returned in one chunk.
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    /// Caps the number of tokens generated.
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens.max(1));
+        self
+    }
+}
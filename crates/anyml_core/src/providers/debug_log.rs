@@ -0,0 +1,233 @@
+use std::{fmt, sync::Arc};
+
+use http::{HeaderMap, HeaderName, HeaderValue, Method, Request, Uri};
+use serde_json::Value;
+
+use crate::providers::chat::ChatChunk;
+use crate::providers::interceptor::ChatInterceptor;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Something recorded by a [`DebugLogger`].
+#[derive(Debug)]
+pub enum DebugEntry<'a> {
+    /// A request as sent, with sensitive headers and fields redacted.
+    Request {
+        method: &'a Method,
+        uri: &'a Uri,
+        headers: &'a HeaderMap,
+        body: &'a str,
+    },
+    /// A response frame as received from the provider.
+    Frame(&'a str),
+}
+
+impl fmt::Display for DebugEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request {
+                method,
+                uri,
+                headers,
+                body,
+            } => {
+                writeln!(f, "--> {method} {uri}")?;
+                for (name, value) in headers.iter() {
+                    writeln!(f, "{name}: {}", String::from_utf8_lossy(value.as_bytes()))?;
+                }
+                write!(f, "\n{body}")
+            }
+            Self::Frame(frame) => write!(f, "<-- {frame}"),
+        }
+    }
+}
+
+/// A [`ChatInterceptor`] which records every request body and response frame,
+/// for debugging what is actually sent to and received from a provider.
+///
+/// The `Authorization` and `x-api-key` headers are always redacted, other headers
+/// and JSON body fields are redacted once added with [`DebugLogger::redact_header`]
+/// and [`DebugLogger::redact_field`].
+///
+/// Requests are recorded when the logger wraps the provider's [`HttpClient`](anyhttp::HttpClient)
+/// and frames when it wraps the provider itself (see [`Intercepted`](crate::providers::Intercepted)).
+/// Frames are only received for requests made with [`ChatOptions::include_raw`](crate::providers::ChatOptions::include_raw).
+#[derive(Clone)]
+pub struct DebugLogger {
+    sink: Arc<dyn Fn(&DebugEntry<'_>) + Send + Sync>,
+    headers: Vec<HeaderName>,
+    fields: Vec<String>,
+}
+
+impl DebugLogger {
+    /// Creates a logger which writes every entry to stderr.
+    pub fn new() -> Self {
+        Self::with_sink(|entry| eprintln!("{entry}"))
+    }
+
+    /// Creates a logger which passes every entry to `sink`.
+    pub fn with_sink(sink: impl Fn(&DebugEntry<'_>) + Send + Sync + 'static) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            headers: vec![
+                http::header::AUTHORIZATION,
+                HeaderName::from_static("x-api-key"),
+            ],
+            fields: Vec::new(),
+        }
+    }
+
+    /// Redacts the header `name` from logged requests.
+    pub fn redact_header(mut self, name: HeaderName) -> Self {
+        self.headers.push(name);
+        self
+    }
+
+    /// Redacts every JSON field called `name` from logged request bodies, at any depth.
+    ///
+    /// Bodies are logged byte for byte unless they have a field to redact,
+    /// in which case they are logged re-serialized.
+    pub fn redact_field(mut self, name: impl Into<String>) -> Self {
+        self.fields.push(name.into());
+        self
+    }
+
+    fn redact_body(&self, body: &[u8]) -> String {
+        if !self.fields.is_empty()
+            && let Ok(mut json) = serde_json::from_slice::<Value>(body)
+        {
+            self.redact_value(&mut json);
+            return json.to_string();
+        }
+
+        String::from_utf8_lossy(body).into_owned()
+    }
+
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object.iter_mut() {
+                    if self.fields.contains(key) {
+                        *value = Value::String(REDACTED.to_owned());
+                    } else {
+                        self.redact_value(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.redact_value(value)),
+            _ => {}
+        }
+    }
+}
+
+impl Default for DebugLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for DebugLogger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugLogger")
+            .field("headers", &self.headers)
+            .field("fields", &self.fields)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ChatInterceptor for DebugLogger {
+    fn before_request(&self, request: &mut Request<Vec<u8>>) {
+        let mut headers = request.headers().clone();
+        for name in &self.headers {
+            if let http::header::Entry::Occupied(mut entry) = headers.entry(name) {
+                entry.insert(HeaderValue::from_static(REDACTED));
+            }
+        }
+
+        (self.sink)(&DebugEntry::Request {
+            method: request.method(),
+            uri: request.uri(),
+            headers: &headers,
+            body: &self.redact_body(request.body()),
+        });
+    }
+
+    fn on_chunk(&self, chunk: &ChatChunk) {
+        if let ChatChunk::Raw { provider_event } = chunk {
+            (self.sink)(&DebugEntry::Frame(provider_event));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn recording_logger() -> (DebugLogger, Arc<Mutex<Vec<String>>>) {
+        let entries = Arc::new(Mutex::new(Vec::new()));
+        let logger = DebugLogger::with_sink({
+            let entries = entries.clone();
+            move |entry| entries.lock().unwrap().push(entry.to_string())
+        });
+        (logger, entries)
+    }
+
+    #[test]
+    fn redacts_request() {
+        let (logger, entries) = recording_logger();
+        let logger = logger
+            .redact_header(HeaderName::from_static("x-team"))
+            .redact_field("user");
+
+        let mut request = Request::post("https://api.openai.com/v1/chat/completions")
+            .header("authorization", "Bearer sk-secret")
+            .header("x-team", "research")
+            .header("content-type", "application/json")
+            .body(br#"{"messages":[{"role":"user","user":"jane"}],"model":"gpt-4o"}"#.to_vec())
+            .unwrap();
+        logger.before_request(&mut request);
+
+        assert_eq!(
+            entries.lock().unwrap().as_slice(),
+            ["--> POST https://api.openai.com/v1/chat/completions\n\
+              authorization: [REDACTED]\n\
+              x-team: [REDACTED]\n\
+              content-type: application/json\n\
+              \n\
+              {\"messages\":[{\"role\":\"user\",\"user\":\"[REDACTED]\"}],\"model\":\"gpt-4o\"}"]
+        );
+        // The request itself is sent unredacted.
+        assert_eq!(request.headers()["authorization"], "Bearer sk-secret");
+    }
+
+    #[test]
+    fn keeps_exact_body_without_fields() {
+        let (logger, entries) = recording_logger();
+
+        let mut request = Request::post("http://localhost:11434/api/chat")
+            .body(b"{ \"model\": \"llama3\" }".to_vec())
+            .unwrap();
+        logger.before_request(&mut request);
+
+        assert_eq!(
+            entries.lock().unwrap().as_slice(),
+            ["--> POST http://localhost:11434/api/chat\n\n{ \"model\": \"llama3\" }"]
+        );
+    }
+
+    #[test]
+    fn logs_raw_frames() {
+        let (logger, entries) = recording_logger();
+
+        logger.on_chunk(&ChatChunk::Content("Hi".into()));
+        logger.on_chunk(&ChatChunk::Raw {
+            provider_event: "data: {\"done\":true}".to_owned(),
+        });
+
+        assert_eq!(
+            entries.lock().unwrap().as_slice(),
+            ["<-- data: {\"done\":true}"]
+        );
+    }
+}
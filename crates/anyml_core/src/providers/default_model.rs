@@ -0,0 +1,120 @@
+use std::borrow::Cow;
+
+use crate::models::Model;
+use crate::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse};
+use crate::providers::health::{HealthCheckProvider, HealthStatus};
+use crate::providers::list_models::{ListModelsError, ListModelsProvider};
+
+/// Gives a provider a default model, for chats sent with
+/// [`ChatOptions::default_model`].
+///
+/// Chats naming a model are sent to it as usual.
+#[derive(Debug, Clone)]
+pub struct DefaultModelProvider<P> {
+    provider: P,
+    model: Cow<'static, str>,
+}
+
+impl<P> DefaultModelProvider<P> {
+    pub fn new(provider: P, model: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+        }
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn into_inner(self) -> P {
+        self.provider
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider> ChatProvider for DefaultModelProvider<P> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        if !options.uses_default_model() {
+            return self.provider.chat(options).await;
+        }
+
+        let options = options.clone().model(&self.model);
+        self.provider.chat(&options).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ListModelsProvider> ListModelsProvider for DefaultModelProvider<P> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        self.provider.list_models().await
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.provider.get_model(id).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: HealthCheckProvider> HealthCheckProvider for DefaultModelProvider<P> {
+    async fn health(&self) -> HealthStatus {
+        self.provider.health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use crate::providers::mock::{MockChatProvider, MockReply};
+    use futures::executor::block_on;
+
+    fn chat(provider: &dyn ChatProvider, options: &ChatOptions<'_>) {
+        block_on(async {
+            let mut response = provider.chat(options).await.unwrap();
+            response.aggregate().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn fills_in_the_default_model() {
+        let provider = DefaultModelProvider::new(
+            MockChatProvider::new()
+                .reply(MockReply::text("Hi"))
+                .reply(MockReply::text("Hi")),
+            "llama3.2",
+        );
+        let messages = &[Message::user("Hello")];
+
+        chat(&provider, &ChatOptions::default_model().messages(messages));
+        chat(&provider, &ChatOptions::new("qwen3").messages(messages));
+
+        let models: Vec<_> = provider
+            .inner()
+            .requests()
+            .iter()
+            .map(|request| request.model().to_owned())
+            .collect();
+        assert_eq!(models, ["llama3.2", "qwen3"]);
+        assert_eq!(provider.default_model(), Some("llama3.2"));
+    }
+
+    #[test]
+    fn providers_without_a_default_reject_the_marker() {
+        let messages = &[Message::user("Hello")];
+        let options = ChatOptions::default_model().messages(messages);
+
+        assert_eq!(MockChatProvider::new().default_model(), None);
+        assert!(matches!(
+            options.validate(),
+            Err(ChatError::InvalidOptions { field: "model", .. })
+        ));
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+};
+
+use serde::{Serialize, Serializer, ser::Error};
+
+/// Typed, provider-specific options attached to [`ChatOptions`](crate::providers::ChatOptions),
+/// holding at most one value per type.
+///
+/// Providers look up the extension type they define (such as `OpenAiChatExt`)
+/// and ignore the others, so generic code can pass the same options to any provider.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Arc<dyn Extension>>,
+}
+
+trait Extension: Any + fmt::Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    fn type_name(&self) -> &'static str;
+
+    fn to_json(&self) -> Result<serde_json::Value, serde_json::Error>;
+}
+
+impl<T: Any + fmt::Debug + Serialize + Send + Sync> Extension for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn to_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `extension`, replacing any extension of the same type.
+    pub fn insert<T: Any + fmt::Debug + Serialize + Send + Sync>(&mut self, extension: T) {
+        self.map.insert(TypeId::of::<T>(), Arc::new(extension));
+    }
+
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            // Derefs the `Arc` first, as it is an `Extension` itself.
+            .and_then(|extension| (**extension).as_any().downcast_ref())
+    }
+
+    pub fn remove<T: Any>(&mut self) {
+        self.map.remove(&TypeId::of::<T>());
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// Serializes as a map from each extension's type name to the extension, so
+/// the keys are in a stable order.
+impl Serialize for Extensions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.map
+            .values()
+            .map(|extension| Ok((extension.type_name(), extension.to_json()?)))
+            .collect::<Result<BTreeMap<_, _>, serde_json::Error>>()
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+/// Lists the extensions in a stable order.
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut extensions = self
+            .map
+            .values()
+            .map(|extension| format!("{extension:?}"))
+            .collect::<Vec<_>>();
+        extensions.sort();
+
+        write!(f, "{{{}}}", extensions.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize)]
+    struct Seed(u64);
+
+    #[derive(Debug, PartialEq, Serialize)]
+    struct User(&'static str);
+
+    #[test]
+    fn one_extension_per_type() {
+        let mut extensions = Extensions::new();
+        extensions.insert(Seed(1));
+        extensions.insert(User("jane"));
+        extensions.insert(Seed(2));
+
+        assert_eq!(extensions.len(), 2);
+        assert_eq!(extensions.get::<Seed>(), Some(&Seed(2)));
+        assert_eq!(extensions.get::<User>(), Some(&User("jane")));
+        assert_eq!(format!("{extensions:?}"), "{Seed(2), User(\"jane\")}");
+
+        extensions.remove::<Seed>();
+        assert_eq!(extensions.get::<Seed>(), None);
+    }
+
+    #[test]
+    fn serializes_by_type_name() {
+        let mut extensions = Extensions::new();
+        extensions.insert(User("jane"));
+        extensions.insert(Seed(2));
+
+        assert_eq!(
+            serde_json::to_value(&extensions).unwrap(),
+            serde_json::json!({
+                std::any::type_name::<Seed>(): 2,
+                std::any::type_name::<User>(): "jane",
+            })
+        );
+    }
+}
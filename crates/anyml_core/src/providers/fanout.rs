@@ -0,0 +1,193 @@
+use futures::{StreamExt, stream::FuturesUnordered};
+use thiserror::Error;
+
+use crate::providers::chat::{
+    AggregatedChat, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError,
+};
+
+/// Sends the same chat to several providers, or models of a provider, at once.
+///
+/// [`FanOut::all`] returns every response, e.g. to compare models side by side,
+/// and [`FanOut::first`] hedges a chat by keeping only the first reply to complete.
+#[derive(Clone, Default)]
+pub struct FanOut<'a> {
+    targets: Vec<Target<'a>>,
+}
+
+#[derive(Clone, Copy)]
+struct Target<'a> {
+    provider: &'a dyn ChatProvider,
+    model: Option<&'a str>,
+}
+
+impl<'a> FanOut<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `provider`, sent the model of the chat's options.
+    pub fn provider(mut self, provider: &'a dyn ChatProvider) -> Self {
+        self.targets.push(Target {
+            provider,
+            model: None,
+        });
+        self
+    }
+
+    /// Adds `provider`, sent `model` instead of the model of the chat's options.
+    pub fn model(mut self, provider: &'a dyn ChatProvider, model: &'a str) -> Self {
+        self.targets.push(Target {
+            provider,
+            model: Some(model),
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Sends the chat to every target concurrently, returning their responses
+    /// in the order the targets were added.
+    pub async fn all(&self, options: &ChatOptions<'_>) -> Vec<Result<ChatResponse<'a>, ChatError>> {
+        futures::future::join_all(self.targets.iter().map(|target| async move {
+            let options = target.options(options);
+            target.provider.chat(&options).await
+        }))
+        .await
+    }
+
+    /// Sends the chat to every target concurrently and returns the first reply
+    /// to complete, along with the index of its target.
+    ///
+    /// The other chats are cancelled as soon as one completes. If they all fail,
+    /// their errors are returned in the order the targets were added.
+    pub async fn first(
+        &self,
+        options: &ChatOptions<'_>,
+    ) -> Result<(usize, AggregatedChat), Vec<FanOutError>> {
+        let mut chats = self
+            .targets
+            .iter()
+            .enumerate()
+            .map(|(index, target)| async move {
+                let options = target.options(options);
+                let reply = async {
+                    let mut response = target.provider.chat(&options).await?;
+                    Ok::<_, FanOutError>(response.aggregate().await?)
+                };
+                (index, reply.await)
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut errors = Vec::new();
+        while let Some((index, reply)) = chats.next().await {
+            match reply {
+                Ok(reply) => return Ok((index, reply)),
+                Err(err) => errors.push((index, err)),
+            }
+        }
+
+        errors.sort_by_key(|(index, _)| *index);
+        Err(errors.into_iter().map(|(_, err)| err).collect())
+    }
+}
+
+impl<'a> Target<'a> {
+    fn options<'o>(&self, options: &ChatOptions<'o>) -> ChatOptions<'o>
+    where
+        'a: 'o,
+    {
+        match self.model {
+            Some(model) => ChatOptions {
+                model,
+                ..options.clone()
+            },
+            None => options.clone(),
+        }
+    }
+}
+
+/// Why one of the chats sent by [`FanOut::first`] failed.
+#[derive(Debug, Error)]
+pub enum FanOutError {
+    #[error(transparent)]
+    Chat(#[from] ChatError),
+
+    #[error(transparent)]
+    Stream(#[from] ChatStreamError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use crate::providers::mock::{MockChatProvider, MockReply};
+    use futures::executor::block_on;
+    use std::time::Duration;
+
+    #[test]
+    fn all_returns_every_response_in_order() {
+        let fast = MockChatProvider::new().reply(MockReply::text("Fast"));
+        let slow = MockChatProvider::new()
+            .reply(MockReply::text("Slow").interval(Duration::from_millis(20)));
+        let messages = &[Message::user("Hi")];
+        let options = ChatOptions::new("model").messages(messages);
+
+        let contents = block_on(async {
+            let mut contents = Vec::new();
+            for response in FanOut::new()
+                .provider(&slow)
+                .provider(&fast)
+                .all(&options)
+                .await
+            {
+                contents.push(response.unwrap().aggregate().await.unwrap().content);
+            }
+            contents
+        });
+
+        assert_eq!(contents, ["Slow", "Fast"]);
+    }
+
+    #[test]
+    fn first_returns_the_first_reply_to_complete() {
+        let slow = MockChatProvider::new()
+            .reply(MockReply::text("Slow").interval(Duration::from_millis(50)));
+        let failing = MockChatProvider::new();
+        let fast = MockChatProvider::new().reply(MockReply::text("Fast"));
+        let messages = &[Message::user("Hi")];
+        let options = ChatOptions::new("model").messages(messages);
+
+        let fan_out = FanOut::new()
+            .provider(&slow)
+            .provider(&failing)
+            .model(&fast, "other-model");
+        let (index, reply) = block_on(fan_out.first(&options)).unwrap();
+
+        assert_eq!(index, 2);
+        assert_eq!(reply.content, "Fast");
+    }
+
+    #[test]
+    fn first_returns_every_error_when_all_fail() {
+        let failing = MockChatProvider::new();
+        let messages = &[Message::user("Hi")];
+        let options = ChatOptions::new("model").messages(messages);
+
+        let errors = block_on(
+            FanOut::new()
+                .provider(&failing)
+                .provider(&failing)
+                .first(&options),
+        )
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], FanOutError::Chat(_)));
+    }
+}
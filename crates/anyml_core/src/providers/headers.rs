@@ -0,0 +1,86 @@
+use std::borrow::Cow;
+
+use http::request::Builder;
+
+/// Headers a provider adds to every request it sends, such as
+/// attribution, gateway or proxy headers.
+#[derive(Debug, Clone, Default)]
+pub struct DefaultHeaders {
+    headers: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+}
+
+impl DefaultHeaders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a header, keeping any already added under the same name.
+    pub fn insert(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) {
+        self.headers.push((name.into(), value.into()));
+    }
+
+    /// Adds the headers to a request.
+    ///
+    /// Invalid names or values surface as an error when the request is built.
+    pub fn apply(&self, mut request: Builder) -> Builder {
+        for (name, value) in &self.headers {
+            request = request.header(name.as_ref(), value.as_ref());
+        }
+        request
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name.as_ref(), value.as_ref()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Request;
+
+    use super::*;
+
+    #[test]
+    fn apply_adds_every_header() {
+        let mut headers = DefaultHeaders::new();
+        headers.insert("x-team", "research");
+        headers.insert("x-tag", "a");
+        headers.insert("x-tag", "b");
+
+        let request = headers
+            .apply(Request::get("http://localhost"))
+            .body(())
+            .unwrap();
+
+        assert_eq!(request.headers()["x-team"], "research");
+        let tags: Vec<_> = request.headers().get_all("x-tag").iter().collect();
+        assert_eq!(tags, ["a", "b"]);
+    }
+
+    #[test]
+    fn apply_defers_invalid_headers_to_build() {
+        let mut headers = DefaultHeaders::new();
+        headers.insert("bad header", "value");
+
+        assert!(
+            headers
+                .apply(Request::get("http://localhost"))
+                .body(())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use anyhttp::HttpClient;
+use http::Request;
+
+use crate::maybe_send::{MaybeSend, MaybeSync};
+
+/// Checks whether a provider can currently serve requests, e.g. so a dashboard
+/// can show it or requests can be steered away from it before they fail.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait HealthCheckProvider: MaybeSend + MaybeSync {
+    /// Makes the cheapest request the provider offers which proves it works,
+    /// including that its API key is accepted.
+    async fn health(&self) -> HealthStatus;
+}
+
+/// The outcome of a [`HealthCheckProvider::health`] check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthStatus {
+    Healthy,
+    /// The provider answered, but with an error, e.g. rejecting the API key.
+    Unhealthy {
+        reason: String,
+    },
+    /// The provider couldn't be reached at all.
+    Unreachable {
+        reason: String,
+    },
+}
+
+impl HealthStatus {
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Self::Healthy)
+    }
+
+    /// Sends `request` and reports the provider healthy if it succeeds.
+    ///
+    /// Used by HTTP providers, with a request to a cheap endpoint.
+    pub async fn probe<C: HttpClient>(client: &C, request: Request<Vec<u8>>) -> Self {
+        let response = match client.execute(request).await {
+            Ok(response) => response,
+            Err(err) => {
+                return Self::Unreachable {
+                    reason: format!("{err:#}"),
+                };
+            }
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return Self::Healthy;
+        }
+
+        let body = response.bytes().await.unwrap_or_default();
+        Self::Unhealthy {
+            reason: format!("{status}: {}", String::from_utf8_lossy(&body)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use futures::executor::block_on;
+    use http::StatusCode;
+
+    fn probe(client: &MockHttpClient) -> HealthStatus {
+        let request = Request::get("http://localhost/health")
+            .body(Vec::new())
+            .unwrap();
+        block_on(HealthStatus::probe(client, request))
+    }
+
+    #[test]
+    fn probe_reports_the_response() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK))
+            .with_response(MockResponse::new(StatusCode::UNAUTHORIZED).body("invalid api key"));
+
+        assert_eq!(probe(&client), HealthStatus::Healthy);
+        assert_eq!(
+            probe(&client),
+            HealthStatus::Unhealthy {
+                reason: "401 Unauthorized: invalid api key".to_owned()
+            }
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use anyhttp::{HttpClient, Response};
+use futures::StreamExt;
+use http::Request;
+
+use crate::maybe_send::{MaybeSend, MaybeSync};
+use crate::models::Model;
+use crate::providers::chat::{
+    ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError,
+};
+use crate::providers::health::{HealthCheckProvider, HealthStatus};
+use crate::providers::list_models::{ListModelsError, ListModelsProvider};
+
+/// Hooks run by [`Intercepted`], e.g. for auth header rotation, logging or metrics.
+///
+/// Every hook does nothing by default, so only the ones needed have to be implemented.
+pub trait ChatInterceptor: MaybeSend + MaybeSync {
+    /// Called before each HTTP request is sent.
+    fn before_request(&self, _request: &mut Request<Vec<u8>>) {}
+
+    /// Called for each chunk of a response, as it is streamed.
+    fn on_chunk(&self, _chunk: &ChatChunk) {}
+
+    /// Called when a chat request fails before its response starts streaming.
+    fn on_error(&self, _error: &ChatError) {}
+
+    /// Called when a response fails while streaming.
+    fn on_stream_error(&self, _error: &ChatStreamError) {}
+}
+
+impl<I: ChatInterceptor + ?Sized> ChatInterceptor for Arc<I> {
+    fn before_request(&self, request: &mut Request<Vec<u8>>) {
+        (**self).before_request(request)
+    }
+
+    fn on_chunk(&self, chunk: &ChatChunk) {
+        (**self).on_chunk(chunk)
+    }
+
+    fn on_error(&self, error: &ChatError) {
+        (**self).on_error(error)
+    }
+
+    fn on_stream_error(&self, error: &ChatStreamError) {
+        (**self).on_stream_error(error)
+    }
+}
+
+/// Runs [`ChatInterceptor`]s around an [`HttpClient`] or a [`ChatProvider`],
+/// in the order they were added.
+///
+/// Wrapping a client runs [`ChatInterceptor::before_request`], while wrapping
+/// a provider runs the other hooks. To run all of them, share the interceptor
+/// between both:
+///
+/// ```ignore
+/// let metrics = Arc::new(Metrics::default());
+/// let client = Intercepted::new(reqwest::Client::new()).with(metrics.clone());
+/// let provider = Intercepted::new(OpenAiProvider::new(client, api_key)).with(metrics);
+/// ```
+#[derive(Clone)]
+pub struct Intercepted<T> {
+    inner: T,
+    interceptors: Vec<Arc<dyn ChatInterceptor>>,
+}
+
+impl<T> Intercepted<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            interceptors: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, interceptor: impl ChatInterceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> HttpClient for Intercepted<C> {
+    async fn execute(&self, mut request: Request<Vec<u8>>) -> Result<Response, anyhow::Error> {
+        for interceptor in &self.interceptors {
+            interceptor.before_request(&mut request);
+        }
+
+        self.inner.execute(request).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider> ChatProvider for Intercepted<P> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let response = self.inner.chat(options).await.inspect_err(|error| {
+            for interceptor in &self.interceptors {
+                interceptor.on_error(error);
+            }
+        })?;
+
+        let interceptors = self.interceptors.clone();
+        Ok(response.map_stream(|stream| {
+            stream.inspect(move |item| {
+                for interceptor in &interceptors {
+                    match item {
+                        Ok(chunk) => interceptor.on_chunk(chunk),
+                        Err(error) => interceptor.on_stream_error(error),
+                    }
+                }
+            })
+        }))
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.inner.default_model()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ListModelsProvider> ListModelsProvider for Intercepted<P> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        self.inner.list_models().await
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.inner.get_model(id).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: HealthCheckProvider> HealthCheckProvider for Intercepted<P> {
+    async fn health(&self) -> HealthStatus {
+        self.inner.health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockChatProvider, MockReply, Recorder};
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use futures::executor::block_on;
+    use http::StatusCode;
+
+    #[test]
+    fn before_request() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK));
+        let intercepted = Intercepted::new(client.clone()).with(Recorder::default());
+
+        let request = Request::get("http://localhost").body(Vec::new()).unwrap();
+        block_on(intercepted.execute(request)).unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.headers()["x-api-key"], "rotated");
+    }
+
+    #[test]
+    fn on_chunk_and_stream_error() {
+        let recorder = Arc::new(Recorder::default());
+        let provider = MockChatProvider::new().reply(
+            MockReply::new()
+                .chunk(ChatChunk::Content("Hi".into()))
+                .error(ChatStreamError::IncompleteChunk),
+        );
+        let provider = Intercepted::new(provider).with(recorder.clone());
+
+        let mut response = block_on(provider.chat(&ChatOptions::new("test"))).unwrap();
+        block_on(response.aggregate_lossy());
+
+        assert_eq!(
+            recorder.events(),
+            [
+                "chunk Content(\"Hi\")",
+                "stream error This chunk contains incomplete data.",
+            ]
+        );
+    }
+
+    #[test]
+    fn on_error() {
+        let recorder = Arc::new(Recorder::default());
+        let provider = MockChatProvider::new()
+            .reply(MockReply::fail(ChatError::Unsupported { feature: "tools" }));
+        let provider = Intercepted::new(provider).with(recorder.clone());
+
+        let result = block_on(provider.chat(&ChatOptions::new("test")));
+
+        assert!(matches!(result, Err(ChatError::Unsupported { .. })));
+        assert_eq!(
+            recorder.events(),
+            ["error \"tools\" is not supported by this provider."]
+        );
+    }
+}
@@ -1,10 +1,24 @@
 use thiserror::Error;
 
+use crate::maybe_send::{MaybeSend, MaybeSync};
 use crate::models::Model;
 
-#[async_trait::async_trait]
-pub trait ListModelsProvider: Send + Sync {
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait ListModelsProvider: MaybeSend + MaybeSync {
     async fn list_models(&self) -> Result<Vec<Model>, ListModelsError>;
+
+    /// Fetches a single model by its ID.
+    ///
+    /// Lists every model and picks the matching one by default,
+    /// providers which can look up a model directly override this.
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.list_models()
+            .await?
+            .into_iter()
+            .find(|model| model.id == id)
+            .ok_or_else(|| ListModelsError::NotFound { id: id.to_owned() })
+    }
 }
 
 #[derive(Debug, Error)]
@@ -17,4 +31,7 @@ pub enum ListModelsError {
 
     #[error("Failed to parse response: {0}.")]
     ParseError(#[source] anyhow::Error),
+
+    #[error("The model \"{id}\" was not found.")]
+    NotFound { id: String },
 }
@@ -0,0 +1,300 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::{StreamExt, future::ready};
+use web_time::Instant;
+
+use crate::maybe_send::{MaybeSend, MaybeSync};
+use crate::models::Model;
+use crate::providers::chat::{
+    ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError, Usage,
+};
+use crate::providers::health::{HealthCheckProvider, HealthStatus};
+use crate::providers::list_models::{ListModelsError, ListModelsProvider};
+
+/// Identifies what a metric was recorded for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MetricLabels<'a> {
+    /// The name the provider was given in [`Metered::new`].
+    pub provider: &'a str,
+    pub model: &'a str,
+}
+
+/// Receives the metrics recorded by [`Metered`], e.g. to export them to Prometheus.
+///
+/// Every method does nothing by default, so only the ones needed have to be implemented.
+/// Errors can be counted by type through [`ChatErrorKind`](crate::providers::chat::ChatErrorKind)
+/// and [`ChatStreamErrorKind`](crate::providers::chat::ChatStreamErrorKind).
+pub trait Metrics: MaybeSend + MaybeSync {
+    /// Called when a chat request is sent.
+    fn record_request(&self, _labels: MetricLabels<'_>) {}
+
+    /// Called when a chat request fails before its response starts streaming.
+    fn record_error(&self, _labels: MetricLabels<'_>, _error: &ChatError) {}
+
+    /// Called when a response fails while streaming.
+    fn record_stream_error(&self, _labels: MetricLabels<'_>, _error: &ChatStreamError) {}
+
+    /// Called once a request has failed, or its response has been fully streamed
+    /// or dropped, with the time since it was sent.
+    fn record_latency(&self, _labels: MetricLabels<'_>, _latency: Duration) {}
+
+    /// Called once a response has been fully streamed or dropped, with the usage
+    /// it reported, merged from its usage chunks like [`Usage::merge`].
+    fn record_tokens(&self, _labels: MetricLabels<'_>, _usage: &Usage) {}
+}
+
+/// Records no metrics, the default of [`Metered`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+impl<M: Metrics + ?Sized> Metrics for Arc<M> {
+    fn record_request(&self, labels: MetricLabels<'_>) {
+        (**self).record_request(labels)
+    }
+
+    fn record_error(&self, labels: MetricLabels<'_>, error: &ChatError) {
+        (**self).record_error(labels, error)
+    }
+
+    fn record_stream_error(&self, labels: MetricLabels<'_>, error: &ChatStreamError) {
+        (**self).record_stream_error(labels, error)
+    }
+
+    fn record_latency(&self, labels: MetricLabels<'_>, latency: Duration) {
+        (**self).record_latency(labels, latency)
+    }
+
+    fn record_tokens(&self, labels: MetricLabels<'_>, usage: &Usage) {
+        (**self).record_tokens(labels, usage)
+    }
+}
+
+/// Records [`Metrics`] for every chat request sent through a provider,
+/// labelled with the provider's name and the requested model.
+#[derive(Clone)]
+pub struct Metered<P> {
+    name: String,
+    provider: P,
+    metrics: Arc<dyn Metrics>,
+}
+
+impl<P> Metered<P> {
+    pub fn new(name: impl Into<String>, provider: P) -> Self {
+        Self {
+            name: name.into(),
+            provider,
+            metrics: Arc::new(NoopMetrics),
+        }
+    }
+
+    pub fn metrics(mut self, metrics: impl Metrics + 'static) -> Self {
+        self.metrics = Arc::new(metrics);
+        self
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn into_inner(self) -> P {
+        self.provider
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider> ChatProvider for Metered<P> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let options: &ChatOptions = &options.or_default_model(self.provider.default_model());
+        let labels = MetricLabels {
+            provider: &self.name,
+            model: options.model,
+        };
+        let sent = Instant::now();
+        self.metrics.record_request(labels);
+
+        let response = match self.provider.chat(options).await {
+            Ok(response) => response,
+            Err(error) => {
+                self.metrics.record_error(labels, &error);
+                self.metrics.record_latency(labels, sent.elapsed());
+                return Err(error);
+            }
+        };
+
+        let mut recorder = StreamRecorder {
+            metrics: self.metrics.clone(),
+            name: self.name.clone(),
+            model: options.model.to_owned(),
+            sent: Some(sent),
+            usage: None,
+        };
+        Ok(response.map_stream(|stream| {
+            stream
+                .map(Some)
+                .chain(futures::stream::once(ready(None)))
+                .filter_map(move |item| {
+                    match &item {
+                        Some(item) => recorder.record(item),
+                        None => recorder.finish(),
+                    }
+                    ready(item)
+                })
+        }))
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.provider.default_model()
+    }
+}
+
+/// Records the metrics of a response as it streams, and its usage and latency
+/// once it has been fully streamed or is dropped, whichever comes first.
+struct StreamRecorder {
+    metrics: Arc<dyn Metrics>,
+    name: String,
+    model: String,
+    sent: Option<Instant>,
+    usage: Option<Usage>,
+}
+
+impl StreamRecorder {
+    fn labels(&self) -> MetricLabels<'_> {
+        MetricLabels {
+            provider: &self.name,
+            model: &self.model,
+        }
+    }
+
+    fn record(&mut self, item: &Result<ChatChunk, ChatStreamError>) {
+        match item {
+            Ok(ChatChunk::Usage(usage)) => {
+                self.usage.get_or_insert_with(Usage::default).merge(usage)
+            }
+            Ok(_) => {}
+            Err(error) => self.metrics.record_stream_error(self.labels(), error),
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(usage) = self.usage.take() {
+            self.metrics.record_tokens(self.labels(), &usage);
+        }
+        if let Some(sent) = self.sent.take() {
+            self.metrics.record_latency(self.labels(), sent.elapsed());
+        }
+    }
+}
+
+impl Drop for StreamRecorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ListModelsProvider> ListModelsProvider for Metered<P> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        self.provider.list_models().await
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.provider.get_model(id).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: HealthCheckProvider> HealthCheckProvider for Metered<P> {
+    async fn health(&self) -> HealthStatus {
+        self.provider.health().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockChatProvider, MockReply, Recorder};
+    use futures::executor::block_on;
+
+    /// Streams usage, as running totals, followed by an error.
+    fn flaky_reply() -> MockReply {
+        MockReply::new()
+            .chunk(ChatChunk::Usage(Usage {
+                output_tokens: Some(5),
+                ..Default::default()
+            }))
+            .chunk(ChatChunk::Content("Hi".into()))
+            .chunk(ChatChunk::Usage(Usage {
+                output_tokens: Some(12),
+                ..Default::default()
+            }))
+            .error(ChatStreamError::IncompleteChunk)
+    }
+
+    #[test]
+    fn records_streamed_response() {
+        let recorder = Arc::new(Recorder::default());
+        let provider = Metered::new("flaky", MockChatProvider::new().reply(flaky_reply()))
+            .metrics(recorder.clone());
+
+        let mut response = block_on(provider.chat(&ChatOptions::new("gpt-4o"))).unwrap();
+        let result = block_on(response.aggregate_lossy());
+
+        assert_eq!(result.content, "Hi");
+        assert_eq!(
+            recorder.events(),
+            [
+                "flaky/gpt-4o request",
+                "flaky/gpt-4o stream error IncompleteChunk",
+                "flaky/gpt-4o tokens 12",
+                "flaky/gpt-4o latency",
+            ]
+        );
+    }
+
+    #[test]
+    fn records_latency_of_dropped_response() {
+        let recorder = Arc::new(Recorder::default());
+        let provider = Metered::new("flaky", MockChatProvider::new().reply(flaky_reply()))
+            .metrics(recorder.clone());
+
+        let mut response = block_on(provider.chat(&ChatOptions::new("gpt-4o"))).unwrap();
+        block_on(response.next());
+        assert_eq!(recorder.events(), ["flaky/gpt-4o request"]);
+
+        drop(response);
+        assert_eq!(
+            recorder.events(),
+            [
+                "flaky/gpt-4o request",
+                "flaky/gpt-4o tokens 5",
+                "flaky/gpt-4o latency"
+            ]
+        );
+    }
+
+    #[test]
+    fn records_failed_request() {
+        let recorder = Arc::new(Recorder::default());
+        let provider = MockChatProvider::new().reply(MockReply::fail(ChatError::UnknownProvider {
+            name: "gpt-4o".to_owned(),
+        }));
+        let provider = Metered::new("flaky", provider).metrics(recorder.clone());
+
+        let result = block_on(provider.chat(&ChatOptions::new("gpt-4o")));
+
+        assert!(result.is_err());
+        assert_eq!(
+            recorder.events(),
+            [
+                "flaky/gpt-4o request",
+                "flaky/gpt-4o error UnknownProvider",
+                "flaky/gpt-4o latency",
+            ]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::{collections::VecDeque, sync::Mutex, time::Duration};
+
+use anyhow::anyhow;
+use bytestring::ByteString;
+use futures::StreamExt;
+use futures_timer::Delay;
+
+use crate::providers::chat::{
+    ChatChunk, ChatError, ChatOptions, ChatOptionsOwned, ChatProvider, ChatResponse,
+    ChatStreamError, FinishReason,
+};
+#[cfg(test)]
+use crate::providers::{
+    chat::{ChatErrorKind, ChatStreamErrorKind, Usage},
+    interceptor::ChatInterceptor,
+    metrics::{MetricLabels, Metrics},
+};
+
+/// A scripted reply of a [`MockChatProvider`].
+#[derive(Debug, Default)]
+pub struct MockReply {
+    result: Option<ChatError>,
+    steps: Vec<Step>,
+    interval: Duration,
+}
+
+#[derive(Debug)]
+enum Step {
+    Item(Result<ChatChunk, ChatStreamError>),
+    Delay(Duration),
+}
+
+impl MockReply {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A reply streaming `content` in a single chunk, then finishing.
+    pub fn text(content: impl Into<ByteString>) -> Self {
+        Self::new()
+            .chunk(ChatChunk::Content(content.into()))
+            .chunk(ChatChunk::Finish(FinishReason::Stop))
+    }
+
+    /// A reply which fails the chat itself with `error`, before any stream.
+    pub fn fail(error: ChatError) -> Self {
+        Self {
+            result: Some(error),
+            ..Self::default()
+        }
+    }
+
+    pub fn chunk(mut self, chunk: ChatChunk) -> Self {
+        self.steps.push(Step::Item(Ok(chunk)));
+        self
+    }
+
+    pub fn error(mut self, error: ChatStreamError) -> Self {
+        self.steps.push(Step::Item(Err(error)));
+        self
+    }
+
+    /// Pauses the stream for `delay` before the next item.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.steps.push(Step::Delay(delay));
+        self
+    }
+
+    /// Pauses the stream for `interval` before every item.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// A [`ChatProvider`] replying with scripted [`MockReply`]s, one per chat in the
+/// order they were added, for testing code which consumes chat streams.
+///
+/// Chats sent once every reply was used fail with [`ChatError::RequestError`].
+#[derive(Debug, Default)]
+pub struct MockChatProvider {
+    replies: Mutex<VecDeque<MockReply>>,
+    requests: Mutex<Vec<ChatOptionsOwned>>,
+}
+
+impl MockChatProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reply(self, reply: MockReply) -> Self {
+        self.push_reply(reply);
+        self
+    }
+
+    /// Adds a reply to an already shared provider.
+    pub fn push_reply(&self, reply: MockReply) {
+        self.replies.lock().unwrap().push_back(reply);
+    }
+
+    /// How many chats were sent to the provider.
+    pub fn calls(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// The options of every chat sent to the provider, in the order they were sent.
+    pub fn requests(&self) -> Vec<ChatOptionsOwned> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// How many replies are left.
+    pub fn remaining(&self) -> usize {
+        self.replies.lock().unwrap().len()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl ChatProvider for MockChatProvider {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push(options.clone().into_owned());
+        let reply = self.replies.lock().unwrap().pop_front();
+        let reply = reply.ok_or_else(|| {
+            ChatError::RequestError(anyhow!(
+                "the mock provider has no reply left for \"{}\"",
+                options.model
+            ))
+        })?;
+
+        if let Some(error) = reply.result {
+            return Err(error);
+        }
+
+        let interval = reply.interval;
+        let stream = futures::stream::iter(reply.steps).filter_map(move |step| async move {
+            if !interval.is_zero() {
+                Delay::new(interval).await;
+            }
+            match step {
+                Step::Item(item) => Some(item),
+                Step::Delay(delay) => {
+                    Delay::new(delay).await;
+                    None
+                }
+            }
+        });
+
+        Ok(ChatResponse::new(stream))
+    }
+}
+
+/// Records every [`ChatInterceptor`] hook and [`Metrics`] call it receives, except
+/// latencies' values which vary, for the tests of the wrappers calling them.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct Recorder {
+    events: Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl Recorder {
+    pub(crate) fn events(&self) -> Vec<String> {
+        self.events.lock().unwrap().clone()
+    }
+
+    fn push(&self, event: String) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    fn push_labelled(&self, labels: MetricLabels<'_>, event: &str) {
+        self.push(format!("{}/{} {event}", labels.provider, labels.model));
+    }
+}
+
+#[cfg(test)]
+impl ChatInterceptor for Recorder {
+    fn before_request(&self, request: &mut http::Request<Vec<u8>>) {
+        request
+            .headers_mut()
+            .insert("x-api-key", http::HeaderValue::from_static("rotated"));
+    }
+
+    fn on_chunk(&self, chunk: &ChatChunk) {
+        self.push(format!("chunk {chunk:?}"));
+    }
+
+    fn on_error(&self, error: &ChatError) {
+        self.push(format!("error {error}"));
+    }
+
+    fn on_stream_error(&self, error: &ChatStreamError) {
+        self.push(format!("stream error {error}"));
+    }
+}
+
+#[cfg(test)]
+impl Metrics for Recorder {
+    fn record_request(&self, labels: MetricLabels<'_>) {
+        self.push_labelled(labels, "request");
+    }
+
+    fn record_error(&self, labels: MetricLabels<'_>, error: &ChatError) {
+        let kind = ChatErrorKind::from(error);
+        self.push_labelled(labels, &format!("error {kind:?}"));
+    }
+
+    fn record_stream_error(&self, labels: MetricLabels<'_>, error: &ChatStreamError) {
+        let kind = ChatStreamErrorKind::from(error);
+        self.push_labelled(labels, &format!("stream error {kind:?}"));
+    }
+
+    fn record_latency(&self, labels: MetricLabels<'_>, _latency: Duration) {
+        self.push_labelled(labels, "latency");
+    }
+
+    fn record_tokens(&self, labels: MetricLabels<'_>, usage: &Usage) {
+        let tokens = usage.output_tokens.unwrap_or_default();
+        self.push_labelled(labels, &format!("tokens {tokens}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::time::Instant;
+
+    #[test]
+    fn replies_in_order() {
+        let provider = MockChatProvider::new()
+            .reply(MockReply::text("Hello"))
+            .reply(MockReply::fail(ChatError::Unsupported { feature: "tools" }));
+        let options = ChatOptions::new("llama3");
+
+        let mut response = block_on(provider.chat(&options)).unwrap();
+        let chat = block_on(response.aggregate_lossy());
+        assert_eq!(chat.content, "Hello");
+        assert_eq!(chat.finish_reason, Some(FinishReason::Stop));
+
+        assert!(matches!(
+            block_on(provider.chat(&options)),
+            Err(ChatError::Unsupported { feature: "tools" })
+        ));
+        assert!(matches!(
+            block_on(provider.chat(&options)),
+            Err(ChatError::RequestError(_))
+        ));
+        assert_eq!(provider.calls(), 3);
+        assert_eq!(provider.requests()[0].model(), "llama3");
+    }
+
+    #[test]
+    fn streams_errors_with_delays() {
+        let provider = MockChatProvider::new().reply(
+            MockReply::new()
+                .chunk(ChatChunk::Content("Hel".into()))
+                .delay(Duration::from_millis(20))
+                .error(ChatStreamError::IncompleteChunk)
+                .chunk(ChatChunk::Content("lo".into())),
+        );
+
+        let started = Instant::now();
+        let response = block_on(provider.chat(&ChatOptions::new("llama3"))).unwrap();
+        let items: Vec<_> = block_on(response.collect());
+
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert!(matches!(
+            items.as_slice(),
+            [
+                Ok(ChatChunk::Content(_)),
+                Err(ChatStreamError::IncompleteChunk),
+                Ok(ChatChunk::Content(_)),
+            ]
+        ));
+    }
+}
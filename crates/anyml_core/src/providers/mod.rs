@@ -1,5 +1,73 @@
+pub mod api_key;
+pub mod budget;
+pub mod cache;
+#[cfg(any(test, feature = "test-util"))]
+pub mod cassette;
+pub mod catalog;
 pub mod chat;
+pub mod circuit_breaker;
+pub mod completion;
+pub mod debug_log;
+pub mod default_model;
+pub mod extensions;
+pub mod fanout;
+pub mod headers;
+pub mod health;
+pub mod interceptor;
 pub mod list_models;
+pub mod metrics;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
+pub mod openai_compat;
+pub mod overrides;
+pub mod rate_limit;
+pub mod rechunk;
+pub mod registry;
+pub mod split;
+pub mod structured;
+pub mod throttle;
+mod timeout;
+pub mod transcription;
+pub mod validate;
 
-pub use chat::{AggregatedChat, ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError, Thinking};
+pub use api_key::ApiKeyProvider;
+pub use budget::{Budget, BudgetEvent, BudgetKey, BudgetedProvider, OverBudget};
+pub use cache::{CacheStore, CachedProvider, MemoryCache, cache_key};
+#[cfg(any(test, feature = "test-util"))]
+pub use cassette::{
+    Cassette, Interaction, RecordedRequest, RecordedResponse, RecordingClient, ReplayClient,
+};
+pub use catalog::{CombinedModels, ModelCatalog, ModelChanges};
+pub use chat::{
+    AbortHandle, AggregatedChat, ChatBlock, ChatChunk, ChatError, ChatOptions, ChatOptionsOwned,
+    ChatProvider, ChatResponse, ChatStreamError, FinishReason, ParseJsonError, ResponseFormat,
+    ServerTool, ServerToolResult, ServerToolUse, Thinking, TokenLogProb, ToolCallDelta, ToolChoice,
+    TopLogProb, Usage, UsageTiming,
+};
+pub use circuit_breaker::{CircuitBreakerPolicy, CircuitBreakerProvider, CircuitState};
+pub use completion::{CompletionOptions, CompletionProvider};
+pub use debug_log::{DebugEntry, DebugLogger};
+pub use default_model::DefaultModelProvider;
+pub use extensions::Extensions;
+pub use fanout::{FanOut, FanOutError};
+pub use headers::DefaultHeaders;
+pub use health::{HealthCheckProvider, HealthStatus};
+pub use interceptor::{ChatInterceptor, Intercepted};
 pub use list_models::{ListModelsError, ListModelsProvider};
+pub use metrics::{Metered, MetricLabels, Metrics, NoopMetrics};
+#[cfg(any(test, feature = "test-util"))]
+pub use mock::{MockChatProvider, MockReply};
+pub use overrides::RequestOverrides;
+pub use rate_limit::{
+    RateLimit, RateLimitHeaders, RateLimitPolicy, RateLimitedProvider, RateLimiter,
+};
+pub use rechunk::ChunkBoundary;
+pub use registry::ProviderRegistry;
+pub use split::SplitStream;
+pub use structured::chat_json;
+pub use throttle::{OverflowPolicy, ThrottledProvider};
+pub use transcription::{
+    Transcription, TranscriptionError, TranscriptionOptions, TranscriptionProvider,
+    TranscriptionSegment,
+};
+pub use validate::{MessageRules, coalesce_messages};
@@ -0,0 +1,710 @@
+//! Helpers for providers whose APIs follow OpenAI's formats, such as its chat
+//! completions and audio transcriptions.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use anyhttp::HttpClient;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use futures::StreamExt;
+use http::Request;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::decode::SseDecoder;
+use crate::models::{ContentPart, Message, Tool};
+use crate::multipart::Multipart;
+use crate::providers::chat::{
+    ChatChunk, ChatError, ChatResponse, ChatStreamError, FinishReason, Messages, ResponseFormat,
+    ServerToolResult, TokenLogProb, ToolCallDelta, ToolChoice, Usage, UsageTiming,
+};
+use crate::providers::transcription::{
+    Transcription, TranscriptionError, TranscriptionOptions, TranscriptionSegment,
+};
+
+/// Serializes messages into OpenAI's format, with `system` prepended as a system
+/// message and `prefill` appended as a partial assistant message.
+///
+/// Messages with non-text parts have their content sent as an array of content
+/// parts: images as `image_url`s holding a data URL, and audio as `input_audio`.
+pub fn messages_json(
+    messages: &Messages<'_>,
+    system: Option<&str>,
+    prefill: Option<&str>,
+) -> Result<String, ChatError> {
+    let Messages::Raw(raw) = messages else {
+        return Ok(messages.to_json_with(system, prefill));
+    };
+    if raw.iter().all(|message| message.parts.is_empty()) {
+        return Ok(messages.to_json_with(system, prefill));
+    }
+
+    let system = system.map(Message::system);
+    let prefill = prefill.map(Message::assistant);
+    let messages = system
+        .iter()
+        .chain(raw.iter())
+        .chain(&prefill)
+        .map(message_json)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+    Ok(Value::from(messages).to_string())
+}
+
+fn message_json(message: &Message) -> Result<Value, serde_json::Error> {
+    let mut json = serde_json::to_value(message)?;
+    if message.parts.is_empty() {
+        return Ok(json);
+    }
+
+    let text = (!message.content.is_empty())
+        .then(|| serde_json::json!({ "type": "text", "text": message.content }));
+    let parts = message.parts.iter().map(|part| match part {
+        ContentPart::Audio(audio) => serde_json::json!({
+            "type": "input_audio",
+            "input_audio": { "data": audio.data, "format": audio.format },
+        }),
+        ContentPart::Image(image) => serde_json::json!({
+            "type": "image_url",
+            "image_url": { "url": format!("data:{};base64,{}", image.media_type, image.data) },
+        }),
+    });
+    json["content"] = text.into_iter().chain(parts).collect::<Vec<_>>().into();
+
+    Ok(json)
+}
+
+/// Serializes tools into the `tools` array of function definitions, or `None` if there are none.
+pub fn tools_json(tools: &[Tool]) -> Option<String> {
+    if tools.is_empty() {
+        return None;
+    }
+
+    let tools = tools
+        .iter()
+        .map(|tool| {
+            serde_json::json!({
+                "type": "function",
+                "function": tool,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Some(Value::from(tools).to_string())
+}
+
+/// Serializes the tool choice into OpenAI's format, where a specific tool is an object.
+pub fn tool_choice_json(tool_choice: &ToolChoice) -> Value {
+    match tool_choice {
+        ToolChoice::Auto => "auto".into(),
+        ToolChoice::None => "none".into(),
+        ToolChoice::Required => "required".into(),
+        ToolChoice::Tool(name) => serde_json::json!({
+            "type": "function",
+            "function": { "name": name },
+        }),
+    }
+}
+
+/// Serializes the response format into OpenAI's `response_format` object.
+pub fn response_format_json(response_format: &ResponseFormat) -> Value {
+    match response_format {
+        ResponseFormat::Json => serde_json::json!({ "type": "json_object" }),
+        ResponseFormat::JsonSchema(schema) => serde_json::json!({
+            "type": "json_schema",
+            "json_schema": { "name": "response", "schema": schema },
+        }),
+    }
+}
+
+/// Maps OpenAI's finish reasons, and TGI's own `eos_token` and `stop_sequence`.
+pub fn finish_reason(reason: String) -> FinishReason {
+    match reason.as_str() {
+        "stop" | "eos_token" | "stop_sequence" => FinishReason::Stop,
+        "length" => FinishReason::Length,
+        "tool_calls" => FinishReason::ToolCalls,
+        "content_filter" => FinishReason::ContentFilter,
+        _ => FinishReason::Other(reason),
+    }
+}
+
+/// Sends a chat completions request, streaming the reply from its server-sent
+/// `chat.completion.chunk` events, with each event as a [`ChatChunk::Raw`] too if
+/// `include_raw`.
+pub async fn send_chat<C: HttpClient>(
+    client: &C,
+    request: Request<Vec<u8>>,
+    include_raw: bool,
+) -> Result<ChatResponse<'_>, ChatError> {
+    let response = client
+        .execute(request)
+        .await
+        .map_err(ChatError::ResponseFetchFailed)?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let err_body = response.bytes().await;
+        let err_body = err_body.as_deref().unwrap_or(b"<failed to read>");
+
+        return Err(ChatError::from_error_response(status, err_body));
+    }
+
+    let stream = response.bytes_stream();
+
+    Ok(ChatResponse::new(
+        stream
+            .scan(SseDecoder::new(), move |events, chunk| {
+                let chunks = parse_sse_batch(chunk, events, include_raw);
+                futures::future::ready(Some(chunks))
+            })
+            .flat_map(futures::stream::iter)
+            // Ends at `[DONE]`, without waiting for the connection to close.
+            .take_while(|chunk| futures::future::ready(chunk.is_some()))
+            .filter_map(futures::future::ready),
+    ))
+}
+
+/// Parses every SSE event completed by the new chunk, keeping any trailing
+/// partial event in `events` for the next call. `None` marks the end of the stream.
+fn parse_sse_batch<B: AsRef<[u8]>>(
+    chunk: Result<B, anyhow::Error>,
+    events: &mut SseDecoder,
+    include_raw: bool,
+) -> Vec<Option<Result<ChatChunk, ChatStreamError>>> {
+    let chunk = match chunk {
+        Ok(chunk) => chunk,
+        Err(err) => return vec![Some(Err(ChatStreamError::ParseError(err)))],
+    };
+    events.push(chunk.as_ref());
+
+    let mut results = Vec::new();
+    let mut chunks = Vec::new();
+
+    while let Some(event) = events.next_event() {
+        let event = event.trim();
+        if include_raw && !event.is_empty() {
+            results.push(Some(Ok(ChatChunk::Raw {
+                provider_event: event.to_owned(),
+            })));
+        }
+        if event
+            .strip_prefix("data:")
+            .is_some_and(|event_body| event_body.trim() == "[DONE]")
+        {
+            results.push(None);
+            break;
+        }
+        process_event(event, &mut chunks);
+        results.extend(chunks.drain(..).map(Some));
+    }
+
+    results
+}
+
+fn process_event(event: &str, results: &mut Vec<Result<ChatChunk, ChatStreamError>>) {
+    // Lines starting with `:` (e.g. `: OPENROUTER PROCESSING`) are keep-alive comments.
+    let Some(event_body) = event.strip_prefix("data:") else {
+        return;
+    };
+    let event_body = event_body.trim();
+
+    let parsed_event = match serde_json::from_str::<ChunkResponse>(event_body) {
+        Ok(parsed_event) => parsed_event,
+        Err(err) => {
+            results.push(Err(ChatStreamError::ParseError(anyhow::Error::new(err))));
+            return;
+        }
+    };
+
+    if let Some(error) = parsed_event.error {
+        let (kind, message) = match error {
+            StreamError::Message(message) => (parsed_event.error_type, message),
+            StreamError::Object { message, kind } => (kind, message),
+        };
+        results.push(Err(ChatStreamError::ProviderError {
+            kind: kind.unwrap_or_default(),
+            message,
+        }));
+        return;
+    }
+
+    if let Some(choice) = parsed_event.choices.into_iter().next() {
+        if let Some(reasoning) = choice
+            .delta
+            .reasoning_content
+            .filter(|this| !this.is_empty())
+        {
+            results.push(Ok(ChatChunk::Thinking(reasoning.into())));
+        }
+        if let Some(content) = choice.delta.content.filter(|this| !this.is_empty()) {
+            results.push(Ok(ChatChunk::Content(content.into())));
+        }
+        if let Some(audio) = choice.delta.audio {
+            if let Some(transcript) = audio.transcript.filter(|this| !this.is_empty()) {
+                results.push(Ok(ChatChunk::Content(transcript.into())));
+            }
+            if let Some(data) = audio.data.filter(|this| !this.is_empty()) {
+                results.push(
+                    STANDARD
+                        .decode(data)
+                        .map(ChatChunk::Audio)
+                        .map_err(|this| ChatStreamError::ParseError(anyhow::Error::new(this))),
+                );
+            }
+        }
+        for tool_call in choice.delta.tool_calls {
+            results.push(Ok(ChatChunk::ToolCallDelta(tool_call.into())));
+        }
+        for annotation in choice.delta.annotations {
+            results.push(Ok(ChatChunk::ServerToolResult(ServerToolResult {
+                tool_use_id: None,
+                content: annotation,
+            })));
+        }
+        if let Some(logprobs) = choice
+            .logprobs
+            .and_then(|this| this.content)
+            .filter(|this| !this.is_empty())
+        {
+            results.push(Ok(ChatChunk::LogProbs(logprobs)));
+        }
+        if let Some(reason) = choice.finish_reason {
+            results.push(Ok(ChatChunk::Finish(finish_reason(reason))));
+        }
+    }
+
+    if let Some(usage) = parsed_event.usage {
+        results.push(Ok(ChatChunk::Usage(usage.into())));
+    }
+    if let Some(usage) = parsed_event.x_groq.and_then(|x_groq| x_groq.usage) {
+        results.push(Ok(ChatChunk::Usage(usage.into())));
+    }
+}
+
+#[derive(Deserialize)]
+struct ChunkResponse {
+    #[serde(default)]
+    choices: Vec<ChunkResponseChoice>,
+    #[serde(default)]
+    usage: Option<ChunkUsage>,
+    #[serde(default)]
+    x_groq: Option<GroqExtensions>,
+    #[serde(default)]
+    error: Option<StreamError>,
+    /// The kind of an error given as a message, as by TGI.
+    #[serde(default)]
+    error_type: Option<String>,
+}
+
+/// An error partway through the stream, sent as an event of its own: an object
+/// by vLLM, and a message by TGI.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StreamError {
+    Message(String),
+    Object {
+        message: String,
+        #[serde(default, rename = "type")]
+        kind: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+struct ChunkResponseChoice {
+    delta: ChunkResponseDelta,
+    #[serde(default)]
+    logprobs: Option<ChunkLogProbs>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChunkLogProbs {
+    #[serde(default)]
+    content: Option<Vec<TokenLogProb>>,
+}
+
+#[derive(Deserialize)]
+struct ChunkResponseDelta {
+    #[serde(default)]
+    content: Option<String>,
+    /// Renamed to `reasoning` in newer versions of vLLM.
+    #[serde(default, alias = "reasoning")]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ChunkToolCallDelta>,
+    #[serde(default)]
+    audio: Option<ChunkAudioDelta>,
+    /// Citations of web search results, such as `url_citation`s.
+    #[serde(default)]
+    annotations: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct ChunkAudioDelta {
+    #[serde(default)]
+    data: Option<String>,
+    #[serde(default)]
+    transcript: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChunkToolCallDelta {
+    index: usize,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<ChunkFunctionDelta>,
+}
+
+#[derive(Deserialize)]
+struct ChunkFunctionDelta {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+impl From<ChunkToolCallDelta> for ToolCallDelta {
+    fn from(delta: ChunkToolCallDelta) -> Self {
+        let (name, arguments) = delta
+            .function
+            .map(|function| (function.name, function.arguments.unwrap_or_default()))
+            .unwrap_or_default();
+
+        ToolCallDelta {
+            index: delta.index,
+            id: delta.id,
+            name,
+            arguments,
+        }
+    }
+}
+
+/// Token counts, including the prompt tokens served from a prefix cache, which
+/// vLLM reports when started with `--enable-prompt-tokens-details`.
+#[derive(Deserialize)]
+struct ChunkUsage {
+    prompt_tokens: Option<usize>,
+    completion_tokens: Option<usize>,
+    #[serde(default)]
+    prompt_tokens_details: Option<PromptTokensDetails>,
+}
+
+#[derive(Deserialize)]
+struct PromptTokensDetails {
+    #[serde(default)]
+    cached_tokens: Option<usize>,
+}
+
+impl From<ChunkUsage> for Usage {
+    fn from(usage: ChunkUsage) -> Self {
+        Usage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+            cached_input_tokens: usage
+                .prompt_tokens_details
+                .and_then(|details| details.cached_tokens),
+            timing: None,
+        }
+    }
+}
+
+/// The Groq-specific `x_groq` object attached to stream chunks.
+#[derive(Deserialize)]
+struct GroqExtensions {
+    #[serde(default)]
+    usage: Option<GroqUsage>,
+}
+
+/// Token counts and timings (in seconds) reported in `x_groq.usage`.
+#[derive(Deserialize)]
+struct GroqUsage {
+    prompt_tokens: Option<usize>,
+    completion_tokens: Option<usize>,
+    queue_time: Option<f64>,
+    prompt_time: Option<f64>,
+    completion_time: Option<f64>,
+    total_time: Option<f64>,
+}
+
+impl From<GroqUsage> for Usage {
+    fn from(usage: GroqUsage) -> Self {
+        let seconds = |secs: Option<f64>| secs.map(Duration::from_secs_f64);
+
+        Usage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+            cached_input_tokens: None,
+            timing: Some(UsageTiming {
+                queue_time: seconds(usage.queue_time),
+                prompt_time: seconds(usage.prompt_time),
+                completion_time: seconds(usage.completion_time),
+                total_time: seconds(usage.total_time),
+            }),
+        }
+    }
+}
+
+/// Transcribes audio with an OpenAI-compatible `/audio/transcriptions` endpoint.
+///
+/// `request` is a POST to the endpoint with the provider's auth, to which the
+/// multipart form is added.
+pub async fn transcribe<C: HttpClient>(
+    client: &C,
+    request: http::request::Builder,
+    options: &TranscriptionOptions<'_>,
+) -> Result<Transcription, TranscriptionError> {
+    let mut form = Multipart::new()
+        .file(
+            "file",
+            &format!("audio.{}", options.format),
+            &format!("audio/{}", options.format),
+            options.audio,
+        )
+        .text("model", options.model);
+    if let Some(language) = options.language {
+        form = form.text("language", language);
+    }
+    if let Some(prompt) = options.prompt {
+        form = form.text("prompt", prompt);
+    }
+    // Only the Whisper models support `verbose_json`, so it's only requested for timestamps.
+    form = if options.timestamps {
+        form.text("response_format", "verbose_json")
+            .text("timestamp_granularities[]", "segment")
+    } else {
+        form.text("response_format", "json")
+    };
+    let (content_type, body) = form.finish();
+
+    let request = request
+        .header("Content-Type", content_type)
+        .body(body)
+        .map_err(|this| TranscriptionError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+    let response = client
+        .execute(request)
+        .await
+        .map_err(TranscriptionError::ResponseFetchFailed)?;
+
+    if !response.status().is_success() {
+        let err_body = match response.bytes().await {
+            Ok(body) => String::from_utf8_lossy(&body).into_owned(),
+            Err(_) => "<failed to read>".to_owned(),
+        };
+
+        return Err(TranscriptionError::RequestError(anyhow!(err_body)));
+    }
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(TranscriptionError::ResponseFetchFailed)?;
+
+    let transcription: OpenAiTranscription = serde_json::from_slice(&body)
+        .map_err(|this| TranscriptionError::ParseError(anyhow::Error::new(this)))?;
+
+    Ok(transcription.into())
+}
+
+/// A transcription as `json`, or as `verbose_json` with its language, duration
+/// and segments.
+#[derive(Deserialize)]
+struct OpenAiTranscription {
+    text: String,
+    #[serde(default)]
+    language: Option<String>,
+    /// In seconds.
+    #[serde(default)]
+    duration: Option<f64>,
+    #[serde(default)]
+    segments: Vec<OpenAiSegment>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiSegment {
+    start: f64,
+    end: f64,
+    text: String,
+}
+
+impl From<OpenAiTranscription> for Transcription {
+    fn from(transcription: OpenAiTranscription) -> Self {
+        Transcription {
+            text: transcription.text,
+            language: transcription.language,
+            duration: transcription.duration.map(Duration::from_secs_f64),
+            segments: transcription
+                .segments
+                .into_iter()
+                .map(|segment| TranscriptionSegment {
+                    start: Duration::from_secs_f64(segment.start),
+                    end: Duration::from_secs_f64(segment.end),
+                    text: segment.text,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Audio, Image};
+    use serde_json::json;
+
+    #[test]
+    fn sends_parts_as_content_arrays() {
+        let messages = [
+            Message::user("What's this?").part(Image::new(b"png", "image/png")),
+            Message::user("").part(Audio::new(b"wav", "wav")),
+        ];
+
+        let json = messages_json(&Messages::Raw(&messages), Some("Be brief."), None).unwrap();
+
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json[0], json!({ "role": "system", "content": "Be brief." }));
+        assert_eq!(
+            json[1]["content"],
+            json!([
+                { "type": "text", "text": "What's this?" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,cG5n" } },
+            ])
+        );
+        assert_eq!(
+            json[2]["content"],
+            json!([{ "type": "input_audio", "input_audio": { "data": "d2F2", "format": "wav" } }])
+        );
+        assert!(json[1].get("parts").is_none());
+    }
+
+    #[test]
+    fn leaves_text_messages_as_they_are() {
+        let messages = [Message::user("Hi")];
+
+        let json = messages_json(&Messages::Raw(&messages), None, Some("Hello")).unwrap();
+
+        assert_eq!(
+            json,
+            r#"[{"content":"Hi","role":"user"},{"content":"Hello","role":"assistant"}]"#
+        );
+    }
+
+    #[test]
+    fn parses_chunks() {
+        let mut results = Vec::new();
+
+        process_event(
+            "data: {\"choices\":[{\"delta\":{\"reasoning\":\"Hmm\",\"content\":\"Hi\"},\"finish_reason\":\"eos_token\"}],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2,\"prompt_tokens_details\":{\"cached_tokens\":8}}}",
+            &mut results,
+        );
+
+        assert!(matches!(
+            results.as_slice(),
+            [
+                Ok(ChatChunk::Thinking(thinking)),
+                Ok(ChatChunk::Content(content)),
+                Ok(ChatChunk::Finish(FinishReason::Stop)),
+                Ok(ChatChunk::Usage(Usage { cached_input_tokens: Some(8), .. })),
+            ] if thinking == "Hmm" && content == "Hi"
+        ));
+    }
+
+    #[test]
+    fn parses_stream_errors() {
+        let mut results = Vec::new();
+
+        // vLLM's errors are objects, and TGI's messages.
+        process_event(
+            "data: {\"error\":{\"object\":\"error\",\"message\":\"The model does not exist.\",\"type\":\"NotFoundError\",\"code\":404}}",
+            &mut results,
+        );
+        process_event(
+            "data: {\"error\":\"Input validation error\",\"error_type\":\"validation\"}",
+            &mut results,
+        );
+
+        assert!(matches!(
+            results.as_slice(),
+            [
+                Err(ChatStreamError::ProviderError { kind: vllm, message: vllm_message }),
+                Err(ChatStreamError::ProviderError { kind: tgi, message: tgi_message }),
+            ] if vllm == "NotFoundError"
+                && vllm_message == "The model does not exist."
+                && tgi == "validation"
+                && tgi_message == "Input validation error"
+        ));
+    }
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let body = "data: {\"choices\":[{\"delta\":{\"content\":\"こんにちは\"},\"index\":0}]}\n\n";
+        // Splits inside the first character, before the event is complete.
+        let split = body.find('こ').unwrap() + 1;
+
+        let mut events = SseDecoder::new();
+        let first = parse_sse_batch(Ok(&body.as_bytes()[..split]), &mut events, false);
+        let second = parse_sse_batch(Ok(&body.as_bytes()[split..]), &mut events, false);
+
+        assert!(first.is_empty());
+        assert!(matches!(
+            second.as_slice(),
+            [Some(Ok(ChatChunk::Content(content)))] if content == "こんにちは"
+        ));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn ends_at_done() {
+        let mut events = SseDecoder::new();
+
+        let results = parse_sse_batch(
+            Ok(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\ndata: [DONE]\n\ndata: {}\n\n",
+            ),
+            &mut events,
+            false,
+        );
+
+        assert!(matches!(
+            results.as_slice(),
+            [Some(Ok(ChatChunk::Content(content))), None] if content == "Hi"
+        ));
+    }
+
+    #[test]
+    fn parses_groq_usage() {
+        let mut results = Vec::new();
+
+        process_event(
+            "data: {\"choices\":[{\"delta\":{}}],\"x_groq\":{\"id\":\"req_1\",\"usage\":{\"queue_time\":0.25,\"prompt_tokens\":12,\"prompt_time\":0.5,\"completion_tokens\":3,\"completion_time\":0.125,\"total_time\":0.625}}}",
+            &mut results,
+        );
+
+        let [Ok(ChatChunk::Usage(usage))] = results.as_slice() else {
+            panic!("expected usage, got {results:?}");
+        };
+        assert_eq!(usage.input_tokens, Some(12));
+        assert_eq!(
+            usage.timing.as_ref().unwrap().total_time,
+            Some(Duration::from_millis(625))
+        );
+    }
+
+    #[test]
+    fn serializes_response_formats() {
+        assert_eq!(
+            response_format_json(&ResponseFormat::Json),
+            json!({ "type": "json_object" })
+        );
+        assert_eq!(
+            response_format_json(&ResponseFormat::json_schema(json!({ "type": "object" }))),
+            json!({
+                "type": "json_schema",
+                "json_schema": { "name": "response", "schema": { "type": "object" } },
+            })
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::borrow::Cow;
+
+use secrecy::SecretString;
+use serde::{Serialize, ser::SerializeStruct};
+
+use crate::providers::api_key::ApiKeyProvider;
+use crate::providers::headers::DefaultHeaders;
+
+/// Settings which replace a provider's own for a single request, so one
+/// long-lived provider can serve many tenants.
+#[derive(Debug, Clone, Default)]
+pub struct RequestOverrides {
+    pub api_key: Option<SecretString>,
+    pub url: Option<Cow<'static, str>>,
+    /// Sent after the provider's own default headers.
+    pub headers: DefaultHeaders,
+}
+
+impl RequestOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends this API key instead of the provider's.
+    pub fn api_key(mut self, api_key: impl Into<SecretString>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Sends the request to this base URL instead of the provider's.
+    pub fn url(mut self, url: impl Into<Cow<'static, str>>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Adds a header to the request, as well as the provider's default headers.
+    pub fn header(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.api_key.is_none() && self.url.is_none() && self.headers.is_empty()
+    }
+
+    /// Returns the overriding API key, or else the provider's current one.
+    pub async fn api_key_or(&self, provider: &dyn ApiKeyProvider) -> anyhow::Result<SecretString> {
+        match &self.api_key {
+            Some(api_key) => Ok(api_key.clone()),
+            None => provider.current_key().await,
+        }
+    }
+
+    /// Returns the overriding base URL, or else `url`.
+    pub fn url_or<'a>(&'a self, url: &'a str) -> &'a str {
+        self.url.as_deref().unwrap_or(url)
+    }
+}
+
+/// Serializes the URL and the names of the headers, but neither the API key nor
+/// the values of the headers, as they may hold credentials.
+impl Serialize for RequestOverrides {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let headers: Vec<&str> = self.headers.iter().map(|(name, _)| name).collect();
+
+        let mut overrides = serializer.serialize_struct("RequestOverrides", 2)?;
+        overrides.serialize_field("url", &self.url)?;
+        overrides.serialize_field("headers", &headers)?;
+        overrides.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use secrecy::ExposeSecret;
+
+    use super::*;
+
+    #[test]
+    fn falls_back_to_the_provider() {
+        let provider_key = SecretString::from("provider-key");
+        let overrides = RequestOverrides::new();
+
+        let api_key = block_on(overrides.api_key_or(&provider_key)).unwrap();
+
+        assert_eq!(api_key.expose_secret(), "provider-key");
+        assert_eq!(
+            overrides.url_or("https://api.example.com"),
+            "https://api.example.com"
+        );
+        assert!(overrides.is_empty());
+    }
+
+    #[test]
+    fn prefers_the_overrides() {
+        let provider_key = SecretString::from("provider-key");
+        let overrides = RequestOverrides::new()
+            .api_key("tenant-key")
+            .url("https://tenant.example.com");
+
+        let api_key = block_on(overrides.api_key_or(&provider_key)).unwrap();
+
+        assert_eq!(api_key.expose_secret(), "tenant-key");
+        assert_eq!(
+            overrides.url_or("https://api.example.com"),
+            "https://tenant.example.com"
+        );
+        assert!(!overrides.is_empty());
+    }
+}
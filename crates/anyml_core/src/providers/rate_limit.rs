@@ -0,0 +1,501 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhttp::{HttpClient, Response};
+use futures_timer::Delay;
+use http::{HeaderMap, Request};
+use serde::Deserialize;
+use web_time::Instant;
+
+use crate::models::Model;
+use crate::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse};
+use crate::providers::health::{HealthCheckProvider, HealthStatus};
+use crate::providers::list_models::{ListModelsError, ListModelsProvider};
+
+/// The headers the remaining requests are read from, for OpenAI and Anthropic.
+const REMAINING_REQUESTS_HEADERS: [&str; 2] = [
+    "x-ratelimit-remaining-requests",
+    "anthropic-ratelimit-requests-remaining",
+];
+
+/// The headers the remaining tokens are read from, for OpenAI and Anthropic.
+const REMAINING_TOKENS_HEADERS: [&str; 2] = [
+    "x-ratelimit-remaining-tokens",
+    "anthropic-ratelimit-tokens-remaining",
+];
+
+/// How many requests and tokens may be used per minute. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u32>,
+}
+
+impl RateLimit {
+    pub fn per_minute(requests: u32, tokens: u32) -> Self {
+        Self {
+            requests_per_minute: Some(requests),
+            tokens_per_minute: Some(tokens),
+        }
+    }
+
+    pub fn requests_per_minute(requests: u32) -> Self {
+        Self {
+            requests_per_minute: Some(requests),
+            tokens_per_minute: None,
+        }
+    }
+
+    pub fn tokens_per_minute(tokens: u32) -> Self {
+        Self {
+            requests_per_minute: None,
+            tokens_per_minute: Some(tokens),
+        }
+    }
+}
+
+/// What a [`RateLimiter`] does with requests over its limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RateLimitPolicy {
+    /// Waits until the request fits within the limit.
+    #[default]
+    Wait,
+    /// Fails the request with [`ChatError::RateLimited`].
+    Reject,
+}
+
+/// Token buckets tracking the requests and tokens used per model.
+///
+/// Requests take one request from the bucket before they are sent and,
+/// as the token count of a request isn't known up front, its tokens are taken
+/// once its response reports its usage. Requests are held back while either
+/// bucket is empty.
+///
+/// Cloning a limiter shares its buckets, so it can be shared between a
+/// [`RateLimitedProvider`] and the client passed to [`RateLimiter::track_headers`].
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+    default: RateLimit,
+    models: HashMap<String, RateLimit>,
+    policy: RateLimitPolicy,
+    buckets: HashMap<String, Buckets>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter applying `limit` to each model.
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                default: limit,
+                models: HashMap::new(),
+                policy: RateLimitPolicy::default(),
+                buckets: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Applies `limit` to `model` instead of the default limit.
+    pub fn model(self, model: impl Into<String>, limit: RateLimit) -> Self {
+        let model = model.into();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.buckets.remove(&model);
+            state.models.insert(model, limit);
+        }
+        self
+    }
+
+    pub fn policy(self, policy: RateLimitPolicy) -> Self {
+        self.state.lock().unwrap().policy = policy;
+        self
+    }
+
+    /// Wraps `client` so that the `x-ratelimit-remaining-*` headers of OpenAI and
+    /// the `anthropic-ratelimit-*-remaining` headers of Anthropic lower the
+    /// remaining budget whenever the API reports less than the limiter expects.
+    pub fn track_headers<C: HttpClient>(&self, client: C) -> RateLimitHeaders<C> {
+        RateLimitHeaders {
+            client,
+            limiter: self.clone(),
+        }
+    }
+
+    /// Takes a request for `model`, waiting or failing as per the policy
+    /// if there is none left.
+    pub async fn acquire(&self, model: &str) -> Result<(), ChatError> {
+        loop {
+            let (result, policy) = {
+                let mut state = self.state.lock().unwrap();
+                let policy = state.policy;
+                (state.buckets_for(model).map(Buckets::try_acquire), policy)
+            };
+
+            match (result, policy) {
+                (None | Some(Ok(())), _) => return Ok(()),
+                (Some(Err(wait)), RateLimitPolicy::Wait) => Delay::new(wait).await,
+                (Some(Err(retry_after)), RateLimitPolicy::Reject) => {
+                    return Err(ChatError::RateLimited { retry_after });
+                }
+            }
+        }
+    }
+
+    /// Takes `tokens` used by a request for `model` from its budget.
+    pub fn consume_tokens(&self, model: &str, tokens: usize) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(tokens_bucket) = state
+            .buckets_for(model)
+            .and_then(|buckets| buckets.tokens.as_mut())
+        {
+            tokens_bucket.take(tokens as f64);
+        }
+    }
+
+    fn update_from_headers(&self, model: &str, headers: &HeaderMap) {
+        let remaining = |names: [&str; 2]| {
+            names
+                .iter()
+                .find_map(|name| headers.get(*name)?.to_str().ok()?.parse::<f64>().ok())
+        };
+        let (requests, tokens) = (
+            remaining(REMAINING_REQUESTS_HEADERS),
+            remaining(REMAINING_TOKENS_HEADERS),
+        );
+
+        let mut state = self.state.lock().unwrap();
+        let Some(buckets) = state.buckets_for(model) else {
+            return;
+        };
+        if let (Some(bucket), Some(remaining)) = (&mut buckets.requests, requests) {
+            bucket.lower_to(remaining);
+        }
+        if let (Some(bucket), Some(remaining)) = (&mut buckets.tokens, tokens) {
+            bucket.lower_to(remaining);
+        }
+    }
+}
+
+impl State {
+    /// The buckets of `model`, or `None` if it is unlimited.
+    fn buckets_for(&mut self, model: &str) -> Option<&mut Buckets> {
+        if !self.buckets.contains_key(model) {
+            let limit = self.models.get(model).copied().unwrap_or(self.default);
+            if limit == RateLimit::default() {
+                return None;
+            }
+            self.buckets.insert(model.to_owned(), Buckets::new(limit));
+        }
+
+        self.buckets.get_mut(model)
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+}
+
+impl Buckets {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            requests: limit.requests_per_minute.map(Bucket::per_minute),
+            tokens: limit.tokens_per_minute.map(Bucket::per_minute),
+        }
+    }
+
+    /// Takes a request if neither bucket is empty, otherwise returns how long
+    /// until both have refilled enough.
+    fn try_acquire(&mut self) -> Result<(), Duration> {
+        let wait = [&mut self.requests, &mut self.tokens]
+            .into_iter()
+            .flatten()
+            .map(|bucket| bucket.time_until(1.0))
+            .max()
+            .unwrap_or_default();
+
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        if let Some(requests) = &mut self.requests {
+            requests.take(1.0);
+        }
+        Ok(())
+    }
+}
+
+/// A token bucket which refills its capacity once a minute.
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn per_minute(capacity: u32) -> Self {
+        Self {
+            capacity: capacity as f64,
+            available: capacity as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let refilled = (now - self.refilled_at).as_secs_f64() * self.capacity / 60.0;
+        self.available = (self.available + refilled).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    /// How long until `amount` is available.
+    fn time_until(&mut self, amount: f64) -> Duration {
+        self.refill();
+        let missing = amount - self.available;
+        if missing <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(missing * 60.0 / self.capacity)
+    }
+
+    /// Takes `amount`, which may leave the bucket in debt.
+    fn take(&mut self, amount: f64) {
+        self.refill();
+        self.available -= amount;
+    }
+
+    fn lower_to(&mut self, remaining: f64) {
+        self.refill();
+        self.available = self.available.min(remaining);
+    }
+}
+
+/// Limits the chat requests sent through a provider with a [`RateLimiter`],
+/// before they reach the API.
+#[derive(Debug, Clone)]
+pub struct RateLimitedProvider<P> {
+    provider: P,
+    limiter: RateLimiter,
+}
+
+impl<P> RateLimitedProvider<P> {
+    pub fn new(provider: P, limiter: RateLimiter) -> Self {
+        Self { provider, limiter }
+    }
+
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn into_inner(self) -> P {
+        self.provider
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider> ChatProvider for RateLimitedProvider<P> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let options: &ChatOptions = &options.or_default_model(self.provider.default_model());
+        self.limiter.acquire(options.model).await?;
+
+        let response = self.provider.chat(options).await?;
+
+        let (limiter, model) = (self.limiter.clone(), options.model.to_owned());
+        Ok(response.on_usage(move |usage| {
+            let tokens =
+                usage.input_tokens.unwrap_or_default() + usage.output_tokens.unwrap_or_default();
+            limiter.consume_tokens(&model, tokens);
+        }))
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.provider.default_model()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ListModelsProvider> ListModelsProvider for RateLimitedProvider<P> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        self.provider.list_models().await
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.provider.get_model(id).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: HealthCheckProvider> HealthCheckProvider for RateLimitedProvider<P> {
+    async fn health(&self) -> HealthStatus {
+        self.provider.health().await
+    }
+}
+
+/// Updates a [`RateLimiter`] from the rate limit headers of each response,
+/// created by [`RateLimiter::track_headers`].
+#[derive(Debug, Clone)]
+pub struct RateLimitHeaders<C> {
+    client: C,
+    limiter: RateLimiter,
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> HttpClient for RateLimitHeaders<C> {
+    async fn execute(&self, request: Request<Vec<u8>>) -> Result<Response, anyhow::Error> {
+        #[derive(Deserialize)]
+        struct Body {
+            model: Option<String>,
+        }
+
+        let model = serde_json::from_slice::<Body>(request.body())
+            .ok()
+            .and_then(|body| body.model);
+
+        let response = self.client.execute(request).await?;
+
+        if let Some(model) = model {
+            self.limiter.update_from_headers(&model, response.headers());
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::chat::{ChatChunk, Usage};
+    use crate::providers::mock::{MockChatProvider, MockReply};
+    use futures::executor::block_on;
+    use http::HeaderValue;
+
+    fn provider(limiter: RateLimiter) -> RateLimitedProvider<MockChatProvider> {
+        RateLimitedProvider::new(MockChatProvider::new(), limiter)
+    }
+
+    /// Chats with `model`, which replies using 100 tokens, reported like Anthropic:
+    /// the input first, then the running totals.
+    fn chat(
+        provider: &RateLimitedProvider<MockChatProvider>,
+        model: &str,
+    ) -> Result<(), ChatError> {
+        provider.inner().push_reply(
+            MockReply::text("Hi")
+                .chunk(ChatChunk::Usage(Usage {
+                    input_tokens: Some(60),
+                    ..Default::default()
+                }))
+                .chunk(ChatChunk::Usage(Usage {
+                    input_tokens: Some(60),
+                    output_tokens: Some(40),
+                    ..Default::default()
+                })),
+        );
+        let mut response = block_on(provider.chat(&ChatOptions::new(model)))?;
+        block_on(response.aggregate()).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_over_request_limit() {
+        let limiter =
+            RateLimiter::new(RateLimit::requests_per_minute(2)).policy(RateLimitPolicy::Reject);
+        let provider = provider(limiter);
+
+        assert!(chat(&provider, "gpt-4o").is_ok());
+        assert!(chat(&provider, "gpt-4o").is_ok());
+        assert!(matches!(
+            chat(&provider, "gpt-4o"),
+            Err(ChatError::RateLimited { retry_after }) if retry_after <= Duration::from_secs(30)
+        ));
+    }
+
+    #[test]
+    fn rejects_over_token_limit() {
+        let limiter =
+            RateLimiter::new(RateLimit::tokens_per_minute(100)).policy(RateLimitPolicy::Reject);
+        let provider = provider(limiter);
+
+        assert!(chat(&provider, "gpt-4o").is_ok());
+        assert!(matches!(
+            chat(&provider, "gpt-4o"),
+            Err(ChatError::RateLimited { .. })
+        ));
+    }
+
+    #[test]
+    fn consumes_the_merged_usage_once() {
+        let limiter =
+            RateLimiter::new(RateLimit::tokens_per_minute(101)).policy(RateLimitPolicy::Reject);
+        let provider = provider(limiter);
+
+        assert!(chat(&provider, "gpt-4o").is_ok());
+        assert!(chat(&provider, "gpt-4o").is_ok());
+    }
+
+    #[test]
+    fn limits_per_model() {
+        let limiter = RateLimiter::new(RateLimit::default())
+            .model("gpt-4o", RateLimit::requests_per_minute(1))
+            .policy(RateLimitPolicy::Reject);
+        let provider = provider(limiter);
+
+        assert!(chat(&provider, "gpt-4o").is_ok());
+        assert!(chat(&provider, "gpt-4o").is_err());
+        assert!(chat(&provider, "gpt-4o-mini").is_ok());
+        assert!(chat(&provider, "gpt-4o-mini").is_ok());
+    }
+
+    #[test]
+    fn waits_for_refill() {
+        // A request is refilled every 10ms.
+        let limiter = RateLimiter::new(RateLimit::requests_per_minute(6000));
+        let provider = provider(limiter.clone());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-remaining-requests",
+            HeaderValue::from_static("0"),
+        );
+        limiter.update_from_headers("gpt-4o", &headers);
+
+        let started = Instant::now();
+        assert!(chat(&provider, "gpt-4o").is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn lowers_budget_from_headers() {
+        let limiter =
+            RateLimiter::new(RateLimit::per_minute(100, 10000)).policy(RateLimitPolicy::Reject);
+        let provider = provider(limiter.clone());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "anthropic-ratelimit-requests-remaining",
+            HeaderValue::from_static("0"),
+        );
+        limiter.update_from_headers("claude-sonnet-4-6", &headers);
+
+        assert!(matches!(
+            chat(&provider, "claude-sonnet-4-6"),
+            Err(ChatError::RateLimited { .. })
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+use futures::{StreamExt, future::ready, stream};
+
+use crate::providers::chat::{ChatChunk, ChatResponse};
+
+/// Where [`ChatResponse::rechunk`] splits the content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkBoundary {
+    /// After each word, along with the whitespace following it.
+    Word,
+    /// After each sentence or line, along with the whitespace following it.
+    Sentence,
+}
+
+impl<'a> ChatResponse<'a> {
+    /// Re-buffers the content so each [`ChatChunk::Content`] holds exactly one word or
+    /// sentence, smoothing out the uneven deltas providers send, such as for speech
+    /// synthesis or a typewriter effect.
+    ///
+    /// Other chunks pass straight through, except that the buffered content is flushed
+    /// before a [`ChatChunk::Finish`] or an error, and once the response ends.
+    pub fn rechunk(self, boundary: ChunkBoundary) -> Self {
+        self.map_stream(move |stream| {
+            stream
+                .map(Some)
+                .chain(stream::once(ready(None)))
+                .scan(String::new(), move |buffer, chunk| {
+                    let mut chunks = Vec::new();
+                    match chunk {
+                        Some(Ok(ChatChunk::Content(text))) => {
+                            buffer.push_str(&text);
+                            while let Some(end) = boundary.find_end(buffer) {
+                                let unit: String = buffer.drain(..end).collect();
+                                chunks.push(Ok(ChatChunk::Content(unit.into())));
+                            }
+                        }
+                        Some(chunk) => {
+                            if matches!(chunk, Ok(ChatChunk::Finish(_)) | Err(_)) {
+                                flush(buffer, &mut chunks);
+                            }
+                            chunks.push(chunk);
+                        }
+                        None => flush(buffer, &mut chunks),
+                    }
+                    ready(Some(chunks))
+                })
+                .flat_map(stream::iter)
+        })
+    }
+}
+
+fn flush<E>(buffer: &mut String, chunks: &mut Vec<Result<ChatChunk, E>>) {
+    if !buffer.is_empty() {
+        chunks.push(Ok(ChatChunk::Content(std::mem::take(buffer).into())));
+    }
+}
+
+impl ChunkBoundary {
+    /// Returns where the first complete word or sentence of `text` ends, if any.
+    fn find_end(self, text: &str) -> Option<usize> {
+        let unit_end = match self {
+            Self::Word => {
+                let start = text.find(|c: char| !c.is_whitespace())?;
+                start + text[start..].find(char::is_whitespace)?
+            }
+            Self::Sentence => sentence_end(text)?,
+        };
+
+        // Includes the whitespace after the unit, so no text is lost between chunks.
+        Some(
+            text[unit_end..]
+                .find(|c: char| !c.is_whitespace())
+                .map_or(text.len(), |offset| unit_end + offset),
+        )
+    }
+}
+
+/// Returns where the first sentence of `text` ends, before the whitespace following it.
+fn sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '\n' | '。' | '！' | '？' => return Some(index + char.len_utf8()),
+            '.' | '!' | '?' | '…' => {
+                let mut end = index + char.len_utf8();
+                // Closing quotes and brackets belong to the sentence they end.
+                while let Some(&(index, char)) = chars.peek() {
+                    if !matches!(char, '"' | '\'' | '”' | '’' | ')' | ']') {
+                        break;
+                    }
+                    end = index + char.len_utf8();
+                    chars.next();
+                }
+
+                // Without whitespace after it, the period may be part of a number or
+                // abbreviation, or the whitespace may not have arrived yet.
+                if chars.peek().is_some_and(|&(_, char)| char.is_whitespace()) {
+                    return Some(end);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::chat::{ChatStreamError, FinishReason};
+    use futures::executor::block_on;
+
+    fn content(response: ChatResponse<'_>) -> Vec<String> {
+        block_on(
+            response
+                .map(|chunk| match chunk.unwrap() {
+                    ChatChunk::Content(text) => text.into(),
+                    chunk => format!("<{chunk:?}>"),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn rechunks_words() {
+        let response = ChatResponse::new(stream::iter(
+            ["He", "llo wo", "rld,  how", " are you?"]
+                .map(|text| Ok::<_, ChatStreamError>(ChatChunk::Content(text.into()))),
+        ));
+
+        assert_eq!(
+            content(response.rechunk(ChunkBoundary::Word)),
+            ["Hello ", "world,  ", "how ", "are ", "you?"]
+        );
+    }
+
+    #[test]
+    fn rechunks_sentences() {
+        let response = ChatResponse::new(stream::iter(
+            [
+                "Pi is 3.",
+                "14. It's \"irrational.\" ",
+                "Really?! Yes\nIndeed",
+            ]
+            .map(|text| Ok::<_, ChatStreamError>(ChatChunk::Content(text.into()))),
+        ));
+
+        assert_eq!(
+            content(response.rechunk(ChunkBoundary::Sentence)),
+            [
+                "Pi is 3.14. ",
+                "It's \"irrational.\" ",
+                "Really?! ",
+                "Yes\n",
+                "Indeed"
+            ]
+        );
+    }
+
+    #[test]
+    fn flushes_before_finish() {
+        let response = ChatResponse::new(stream::iter([
+            Ok(ChatChunk::Thinking("Hmm.".into())),
+            Ok(ChatChunk::Content("Hello wor".into())),
+            Ok(ChatChunk::Content("ld".into())),
+            Ok(ChatChunk::Finish(FinishReason::Stop)),
+        ]));
+
+        assert_eq!(
+            content(response.rechunk(ChunkBoundary::Word)),
+            ["<Thinking(\"Hmm.\")>", "Hello ", "world", "<Finish(Stop)>"]
+        );
+    }
+}
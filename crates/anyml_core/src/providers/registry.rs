@@ -0,0 +1,202 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use crate::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse};
+
+/// Chat providers registered under names such as `"openai"` or `"anthropic"`,
+/// so that the provider for a request can be picked at runtime.
+///
+/// Models can be prefixed with a provider's name (e.g. `"openai/gpt-4o"`)
+/// to route them to that provider, which is what the registry's own
+/// [`ChatProvider`] implementation does.
+#[derive(Clone, Default)]
+pub struct ProviderRegistry {
+    providers: HashMap<String, Arc<dyn ChatProvider>>,
+    default: Option<String>,
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `provider` under `name`, replacing any provider already registered under it.
+    pub fn register(
+        mut self,
+        name: impl Into<String>,
+        provider: impl ChatProvider + 'static,
+    ) -> Self {
+        self.insert(name, provider);
+        self
+    }
+
+    /// Sets the provider used for models without a registered prefix.
+    pub fn default_provider(mut self, name: impl Into<String>) -> Self {
+        self.default = Some(name.into());
+        self
+    }
+
+    /// Registers `provider` under `name`, returning the provider it replaced.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        provider: impl ChatProvider + 'static,
+    ) -> Option<Arc<dyn ChatProvider>> {
+        self.providers.insert(name.into(), Arc::new(provider))
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Arc<dyn ChatProvider>> {
+        self.providers.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn ChatProvider> {
+        self.providers.get(name).map(|provider| provider.as_ref())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.providers.contains_key(name)
+    }
+
+    /// The names of all registered providers, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.providers.keys().map(String::as_str)
+    }
+
+    /// Sends a chat request to the provider registered under `name`.
+    pub async fn chat(
+        &self,
+        name: &str,
+        options: &ChatOptions<'_>,
+    ) -> Result<ChatResponse<'_>, ChatError> {
+        let provider = self.get(name).ok_or_else(|| ChatError::UnknownProvider {
+            name: name.to_owned(),
+        })?;
+
+        provider.chat(options).await
+    }
+
+    /// Resolves which provider serves `model`, returning it along with the
+    /// model name that provider expects.
+    ///
+    /// A model prefixed with a registered name (e.g. `"openai/gpt-4o"`) resolves
+    /// to that provider with the prefix stripped. Any other model resolves to the
+    /// default provider unchanged, if there is one.
+    pub fn route<'m>(&self, model: &'m str) -> Result<(&dyn ChatProvider, &'m str), ChatError> {
+        if let Some((name, rest)) = model.split_once('/')
+            && let Some(provider) = self.get(name)
+        {
+            return Ok((provider, rest));
+        }
+
+        let name = match &self.default {
+            Some(name) => name.as_str(),
+            None => model.split_once('/').map_or(model, |(name, _)| name),
+        };
+        let provider = self.get(name).ok_or_else(|| ChatError::UnknownProvider {
+            name: name.to_owned(),
+        })?;
+
+        Ok((provider, model))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl ChatProvider for ProviderRegistry {
+    /// Sends the request to the provider resolved by [`ProviderRegistry::route`].
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let (provider, model) = self.route(options.model)?;
+
+        provider.chat(&options.clone().model(model)).await
+    }
+}
+
+impl fmt::Debug for ProviderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProviderRegistry")
+            .field("providers", &self.providers.keys().collect::<Vec<_>>())
+            .field("default", &self.default)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::chat::ChatChunk;
+    use futures::executor::block_on;
+
+    /// Replies with its own name and the requested model.
+    struct NamedProvider(&'static str);
+
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+    #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+    impl ChatProvider for NamedProvider {
+        async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+            let reply = format!("{}:{}", self.0, options.model);
+            Ok(ChatResponse::new(futures::stream::iter([Ok(
+                ChatChunk::Content(reply.into()),
+            )])))
+        }
+    }
+
+    fn registry() -> ProviderRegistry {
+        ProviderRegistry::new()
+            .register("openai", NamedProvider("openai"))
+            .register("anthropic", NamedProvider("anthropic"))
+    }
+
+    fn reply(response: Result<ChatResponse, ChatError>) -> String {
+        block_on(response.unwrap().aggregate()).unwrap().content
+    }
+
+    #[test]
+    fn chat_by_name() {
+        let registry = registry();
+        let options = ChatOptions::new("claude-sonnet-4-5");
+
+        let response = block_on(registry.chat("anthropic", &options));
+
+        assert_eq!(reply(response), "anthropic:claude-sonnet-4-5");
+    }
+
+    #[test]
+    fn chat_unknown_name() {
+        let registry = registry();
+        let options = ChatOptions::new("llama3");
+
+        let result = block_on(registry.chat("ollama", &options));
+
+        assert!(matches!(result, Err(ChatError::UnknownProvider { name }) if name == "ollama"));
+    }
+
+    #[test]
+    fn routes_by_model_prefix() {
+        let registry = registry();
+        let options = ChatOptions::new("openai/gpt-4o");
+
+        let response = block_on(ChatProvider::chat(&registry, &options));
+
+        assert_eq!(reply(response), "openai:gpt-4o");
+    }
+
+    #[test]
+    fn routes_unknown_prefix_to_default() {
+        let registry = registry()
+            .register("openrouter", NamedProvider("openrouter"))
+            .default_provider("openrouter");
+        let options = ChatOptions::new("meta-llama/llama-3-70b");
+
+        let response = block_on(ChatProvider::chat(&registry, &options));
+
+        assert_eq!(reply(response), "openrouter:meta-llama/llama-3-70b");
+    }
+
+    #[test]
+    fn routes_unknown_prefix_without_default() {
+        let registry = registry();
+
+        let result = registry.route("ollama/llama3").map(|(_, model)| model);
+
+        assert!(matches!(result, Err(ChatError::UnknownProvider { name }) if name == "ollama"));
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use anyhow::anyhow;
+use bytestring::ByteString;
+use futures::{
+    Stream, StreamExt,
+    task::{ArcWake, waker},
+};
+
+use crate::providers::chat::{ChatChunk, ChatResponse, ChatStreamError};
+
+impl<'a> ChatResponse<'a> {
+    /// Splits the response into a stream of its content and a stream of its thinking,
+    /// which can be consumed independently, such as by separate panes of a UI.
+    ///
+    /// Whichever stream is polled drives the response, buffering the other stream's
+    /// text until it is polled. Errors are sent to both streams, and other chunks
+    /// are dropped, so use [`ChatResponse::inspect`] beforehand to observe them.
+    pub fn split(self) -> (SplitStream<'a>, SplitStream<'a>) {
+        let shared = Arc::new(Mutex::new(Shared {
+            response: self,
+            ended: false,
+            queues: [VecDeque::new(), VecDeque::new()],
+        }));
+        let wakers = Arc::new(Wakers::default());
+
+        let content = SplitStream {
+            shared: shared.clone(),
+            wakers: wakers.clone(),
+            side: Side::Content,
+        };
+        let thinking = SplitStream {
+            shared,
+            wakers,
+            side: Side::Thinking,
+        };
+
+        (content, thinking)
+    }
+}
+
+/// One half of a [`ChatResponse::split`], streaming either its content or its thinking.
+pub struct SplitStream<'a> {
+    shared: Arc<Mutex<Shared<'a>>>,
+    wakers: Arc<Wakers>,
+    side: Side,
+}
+
+struct Shared<'a> {
+    response: ChatResponse<'a>,
+    ended: bool,
+    /// Text and errors not yet taken by each side, indexed by [`Side`].
+    queues: [VecDeque<Result<ByteString, ChatStreamError>>; 2],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Content = 0,
+    Thinking = 1,
+}
+
+impl Side {
+    fn other(self) -> Self {
+        match self {
+            Self::Content => Self::Thinking,
+            Self::Thinking => Self::Content,
+        }
+    }
+}
+
+/// Wakes both sides when the response is ready, as either may be the one waiting on it.
+#[derive(Default)]
+struct Wakers([Mutex<Option<Waker>>; 2]);
+
+impl Wakers {
+    fn register(&self, side: Side, waker: &Waker) {
+        *self.0[side as usize].lock().unwrap() = Some(waker.clone());
+    }
+
+    fn wake_side(&self, side: Side) {
+        if let Some(waker) = self.0[side as usize].lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+impl ArcWake for Wakers {
+    fn wake_by_ref(this: &Arc<Self>) {
+        this.wake_side(Side::Content);
+        this.wake_side(Side::Thinking);
+    }
+}
+
+impl Stream for SplitStream<'_> {
+    type Item = Result<ByteString, ChatStreamError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let side = self.side;
+        let mut shared = self.shared.lock().unwrap();
+        self.wakers.register(side, cx.waker());
+        let response_waker = waker(self.wakers.clone());
+
+        loop {
+            if let Some(item) = shared.queues[side as usize].pop_front() {
+                return Poll::Ready(Some(item));
+            }
+            if shared.ended {
+                return Poll::Ready(None);
+            }
+
+            let chunk = match shared
+                .response
+                .poll_next_unpin(&mut Context::from_waker(&response_waker))
+            {
+                Poll::Ready(chunk) => chunk,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match chunk {
+                Some(Ok(ChatChunk::Content(text))) => {
+                    shared.queues[Side::Content as usize].push_back(Ok(text));
+                }
+                Some(Ok(ChatChunk::Thinking(text))) => {
+                    shared.queues[Side::Thinking as usize].push_back(Ok(text));
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => {
+                    shared.queues[side.other() as usize].push_back(Err(duplicate(&err)));
+                    shared.queues[side as usize].push_back(Err(err));
+                }
+                None => shared.ended = true,
+            }
+            self.wakers.wake_side(side.other());
+        }
+    }
+}
+
+impl Drop for SplitStream<'_> {
+    fn drop(&mut self) {
+        // The other side may have been waiting for this one to drive the response.
+        self.wakers.wake_side(self.side.other());
+    }
+}
+
+/// Copies an error for the other side, as errors can't be cloned.
+fn duplicate(err: &ChatStreamError) -> ChatStreamError {
+    match err {
+        ChatStreamError::IncompleteChunk => ChatStreamError::IncompleteChunk,
+        ChatStreamError::ParseError(err) => ChatStreamError::ParseError(anyhow!("{err:#}")),
+        ChatStreamError::ProviderError { kind, message } => ChatStreamError::ProviderError {
+            kind: kind.clone(),
+            message: message.clone(),
+        },
+        ChatStreamError::Timeout { after } => ChatStreamError::Timeout { after: *after },
+        ChatStreamError::RequestFailed(err) => ChatStreamError::RequestFailed(anyhow!("{err:#}")),
+        ChatStreamError::ToolLoopLimit { limit } => {
+            ChatStreamError::ToolLoopLimit { limit: *limit }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::chat::Usage;
+    use futures::executor::block_on;
+
+    #[test]
+    fn splits_content_and_thinking() {
+        let response = ChatResponse::new(futures::stream::iter([
+            Ok(ChatChunk::Thinking("Let me ".into())),
+            Ok(ChatChunk::Thinking("think.".into())),
+            Ok(ChatChunk::Content("Hello".into())),
+            Ok(ChatChunk::Usage(Usage::default())),
+            Ok(ChatChunk::Content(" world".into())),
+        ]));
+
+        let (content, thinking) = response.split();
+        // Draining the content first buffers all of the thinking.
+        let content: Vec<_> = block_on(content.map(Result::unwrap).collect());
+        let thinking: Vec<_> = block_on(thinking.map(Result::unwrap).collect());
+
+        assert_eq!(content, ["Hello", " world"]);
+        assert_eq!(thinking, ["Let me ", "think."]);
+    }
+
+    #[test]
+    fn sends_errors_to_both_sides() {
+        let response = ChatResponse::new(futures::stream::iter([
+            Ok(ChatChunk::Content("Hi".into())),
+            Err(ChatStreamError::ProviderError {
+                kind: "overloaded_error".into(),
+                message: "Overloaded".into(),
+            }),
+        ]));
+
+        let (content, thinking) = response.split();
+        let thinking: Vec<_> = block_on(thinking.collect());
+        let content: Vec<_> = block_on(content.collect());
+
+        assert!(matches!(&content[0], Ok(text) if text == "Hi"));
+        assert!(matches!(
+            content[1],
+            Err(ChatStreamError::ProviderError { .. })
+        ));
+        assert_eq!(thinking.len(), 1);
+        assert!(matches!(
+            thinking[0],
+            Err(ChatStreamError::ProviderError { .. })
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+use serde::de::DeserializeOwned;
+
+use crate::models::Message;
+use crate::providers::chat::{ChatOptions, ChatProvider, Messages, ParseJsonError, ResponseFormat};
+
+/// Sends a chat and deserializes the JSON in its reply into `T`, like
+/// [`ChatResponse::parse_json`](crate::providers::chat::ChatResponse::parse_json).
+///
+/// Replies which can't be deserialized are sent back to the model along with
+/// the error, and the [`ResponseFormat::JsonSchema`] schema if there is one,
+/// asking it to correct them, up to `max_repairs` times. Chats with
+/// [`Messages::Serialized`] messages can't be extended, so they aren't repaired.
+pub async fn chat_json<T, P>(
+    provider: &P,
+    options: &ChatOptions<'_>,
+    max_repairs: usize,
+) -> Result<T, ParseJsonError>
+where
+    T: DeserializeOwned,
+    P: ChatProvider + ?Sized,
+{
+    let mut result = provider.chat(options).await?.parse_json().await;
+    let Messages::Raw(messages) = &options.messages else {
+        return result;
+    };
+
+    let mut messages = messages.to_vec();
+    for _ in 0..max_repairs {
+        let Err(ParseJsonError::Invalid { source, text }) = &result else {
+            return result;
+        };
+        messages.push(Message::assistant(text.as_str()));
+        messages.push(Message::user(repair_prompt(source, options)));
+
+        let options = options.clone().messages(&messages);
+        result = provider.chat(&options).await?.parse_json().await;
+    }
+
+    result
+}
+
+/// Asks the model to correct a reply which failed to deserialize with `error`.
+fn repair_prompt(error: &serde_json::Error, options: &ChatOptions<'_>) -> String {
+    let mut prompt = format!("Your reply couldn't be parsed as the expected JSON: {error}.");
+    if let Some(ResponseFormat::JsonSchema(schema)) = &options.response_format {
+        prompt.push_str(&format!(" It must match this JSON schema:\n{schema}\n"));
+    }
+    prompt.push_str(" Reply again with only the corrected JSON.");
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::chat::ChatError;
+    use crate::providers::mock::{MockChatProvider, MockReply};
+    use futures::executor::block_on;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Weather {
+        city: String,
+    }
+
+    #[test]
+    fn repairs_invalid_replies() {
+        let provider = MockChatProvider::new()
+            .reply(MockReply::text(r#"{"town": "Paris"}"#))
+            .reply(MockReply::text(r#"{"city": "Paris"}"#));
+        let messages = &[Message::user("What's the weather in Paris?")];
+        let options = ChatOptions::new("model").messages(messages);
+
+        let weather: Weather = block_on(chat_json(&provider, &options, 1)).unwrap();
+
+        assert_eq!(weather.city, "Paris");
+        assert_eq!(provider.calls(), 2);
+    }
+
+    #[test]
+    fn gives_up_after_max_repairs() {
+        let provider = MockChatProvider::new()
+            .reply(MockReply::text("Sunny"))
+            .reply(MockReply::text("Still sunny"));
+        let messages = &[Message::user("What's the weather in Paris?")];
+        let options = ChatOptions::new("model").messages(messages);
+
+        let result = block_on(chat_json::<Weather, _>(&provider, &options, 1));
+
+        let Err(ParseJsonError::Invalid { text, .. }) = result else {
+            panic!("expected an invalid JSON error");
+        };
+        assert_eq!(text, "Still sunny");
+        assert_eq!(provider.calls(), 2);
+    }
+
+    #[test]
+    fn repair_prompt_includes_the_schema() {
+        let schema = serde_json::json!({ "type": "object", "required": ["city"] });
+        let options =
+            ChatOptions::new("model").response_format(ResponseFormat::json_schema(schema.clone()));
+        let error = serde_json::from_str::<Weather>("{}").unwrap_err();
+
+        let prompt = repair_prompt(&error, &options);
+
+        assert!(prompt.contains("missing field `city`"));
+        assert!(prompt.contains(&schema.to_string()));
+    }
+
+    #[test]
+    fn request_errors_are_returned() {
+        let provider = MockChatProvider::new();
+        let messages = &[Message::user("Hi")];
+        let options = ChatOptions::new("model").messages(messages);
+
+        let result = block_on(chat_json::<Weather, _>(&provider, &options, 3));
+
+        assert!(matches!(
+            result,
+            Err(ParseJsonError::Chat(ChatError::RequestError(_)))
+        ));
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use futures::StreamExt;
+
+use crate::models::Model;
+use crate::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse};
+use crate::providers::health::{HealthCheckProvider, HealthStatus};
+use crate::providers::list_models::{ListModelsError, ListModelsProvider};
+
+/// What a [`ThrottledProvider`] does with chats sent while all its slots are taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Queues the chat until a slot frees up, first come first served.
+    #[default]
+    Wait,
+    /// Fails the chat with [`ChatError::TooManyInFlight`].
+    FailFast,
+}
+
+/// Limits how many chats can be in flight through a provider at once.
+///
+/// A chat is in flight from when it is sent until its response is dropped.
+/// Cloning the provider shares its limit.
+#[derive(Debug, Clone)]
+pub struct ThrottledProvider<P> {
+    provider: P,
+    semaphore: Arc<Semaphore>,
+    policy: OverflowPolicy,
+}
+
+impl<P> ThrottledProvider<P> {
+    /// Fails with [`ChatError::InvalidOptions`] if `max_in_flight` is 0, as no chat
+    /// could ever be sent.
+    pub fn new(provider: P, max_in_flight: usize) -> Result<Self, ChatError> {
+        if max_in_flight == 0 {
+            return Err(ChatError::InvalidOptions {
+                field: "max_in_flight",
+                reason: "it must be at least 1".to_owned(),
+            });
+        }
+
+        Ok(Self {
+            provider,
+            semaphore: Arc::new(Semaphore::new(max_in_flight)),
+            policy: OverflowPolicy::default(),
+        })
+    }
+
+    pub fn policy(mut self, policy: OverflowPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// How many more chats can be sent before they are queued or rejected.
+    pub fn available(&self) -> usize {
+        self.semaphore.state.lock().unwrap().available
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.provider
+    }
+
+    pub fn into_inner(self) -> P {
+        self.provider
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ChatProvider> ChatProvider for ThrottledProvider<P> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let permit = match self.policy {
+            OverflowPolicy::Wait => self.semaphore.clone().acquire().await,
+            OverflowPolicy::FailFast => {
+                self.semaphore
+                    .clone()
+                    .try_acquire()
+                    .ok_or(ChatError::TooManyInFlight {
+                        limit: self.semaphore.permits,
+                    })?
+            }
+        };
+
+        let response = self.provider.chat(options).await?;
+
+        // The permit is released once the stream is dropped.
+        Ok(response.map_stream(|stream| {
+            stream.map(move |item| {
+                let _ = &permit;
+                item
+            })
+        }))
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.provider.default_model()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: ListModelsProvider> ListModelsProvider for ThrottledProvider<P> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        self.provider.list_models().await
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        self.provider.get_model(id).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<P: HealthCheckProvider> HealthCheckProvider for ThrottledProvider<P> {
+    async fn health(&self) -> HealthStatus {
+        self.provider.health().await
+    }
+}
+
+/// A semaphore which hands out permits in the order they were asked for.
+#[derive(Debug)]
+struct Semaphore {
+    permits: usize,
+    state: Mutex<SemaphoreState>,
+}
+
+#[derive(Debug)]
+struct SemaphoreState {
+    available: usize,
+    /// The waiting acquires, by their ID.
+    queue: VecDeque<(u64, Option<Waker>)>,
+    next_id: u64,
+}
+
+impl Semaphore {
+    fn new(permits: usize) -> Self {
+        Self {
+            permits,
+            state: Mutex::new(SemaphoreState {
+                available: permits,
+                queue: VecDeque::new(),
+                next_id: 0,
+            }),
+        }
+    }
+
+    fn try_acquire(self: Arc<Self>) -> Option<Permit> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.available == 0 || !state.queue.is_empty() {
+                return None;
+            }
+            state.available -= 1;
+        }
+
+        Some(Permit { semaphore: self })
+    }
+
+    fn acquire(self: Arc<Self>) -> Acquire {
+        Acquire {
+            semaphore: self,
+            id: None,
+        }
+    }
+
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.available += 1;
+        state.wake_front();
+    }
+}
+
+impl SemaphoreState {
+    fn wake_front(&mut self) {
+        if let Some((_, Some(waker))) = self.queue.front_mut() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+/// Held while a chat is in flight, releasing its slot when dropped.
+#[derive(Debug)]
+struct Permit {
+    semaphore: Arc<Semaphore>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.semaphore.release();
+    }
+}
+
+/// Waits for a [`Permit`], in its turn.
+struct Acquire {
+    semaphore: Arc<Semaphore>,
+    /// The ID of this acquire in the queue, once it had to wait.
+    id: Option<u64>,
+}
+
+impl Future for Acquire {
+    type Output = Permit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let semaphore = self.semaphore.clone();
+        let mut state = semaphore.state.lock().unwrap();
+
+        let first = match self.id {
+            Some(id) => state.queue.front().is_some_and(|(front, _)| *front == id),
+            None => state.queue.is_empty(),
+        };
+        if first && state.available > 0 {
+            state.available -= 1;
+            if self.id.take().is_some() {
+                state.queue.pop_front();
+                // Several permits may have been released at once.
+                if state.available > 0 {
+                    state.wake_front();
+                }
+            }
+            drop(state);
+            return Poll::Ready(Permit { semaphore });
+        }
+
+        match self.id {
+            Some(id) => {
+                if let Some((_, waker)) = state.queue.iter_mut().find(|(queued, _)| *queued == id) {
+                    *waker = Some(cx.waker().clone());
+                }
+            }
+            None => {
+                let id = state.next_id;
+                state.next_id += 1;
+                state.queue.push_back((id, Some(cx.waker().clone())));
+                self.id = Some(id);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        let Some(id) = self.id else {
+            return;
+        };
+
+        let mut state = self.semaphore.state.lock().unwrap();
+        state.queue.retain(|(queued, _)| *queued != id);
+        // Let the next in line take the permit this acquire was woken for.
+        if state.available > 0 {
+            state.wake_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockChatProvider, MockReply};
+    use futures::{FutureExt, executor::block_on, task::noop_waker_ref};
+
+    fn hello(chats: usize) -> MockChatProvider {
+        let provider = MockChatProvider::new();
+        for _ in 0..chats {
+            provider.push_reply(MockReply::text("Hello"));
+        }
+        provider
+    }
+
+    #[test]
+    fn fail_fast_when_full() {
+        let provider = ThrottledProvider::new(hello(2), 1)
+            .unwrap()
+            .policy(OverflowPolicy::FailFast);
+        let options = ChatOptions::new("llama3");
+
+        let response = block_on(provider.chat(&options)).unwrap();
+        assert_eq!(provider.available(), 0);
+        assert!(matches!(
+            block_on(provider.chat(&options)),
+            Err(ChatError::TooManyInFlight { limit: 1 })
+        ));
+
+        drop(response);
+        assert_eq!(provider.available(), 1);
+        assert!(block_on(provider.chat(&options)).is_ok());
+        assert_eq!(provider.inner().calls(), 2);
+    }
+
+    #[test]
+    fn waits_for_slot() {
+        let provider = ThrottledProvider::new(hello(2), 1).unwrap();
+        let options = ChatOptions::new("llama3");
+
+        let response = block_on(provider.chat(&options)).unwrap();
+        let mut waiting = provider.chat(&options);
+        assert!((&mut waiting).now_or_never().is_none());
+
+        drop(response);
+        assert!(block_on(waiting).is_ok());
+    }
+
+    #[test]
+    fn rejects_no_slots() {
+        assert!(matches!(
+            ThrottledProvider::new(hello(0), 0),
+            Err(ChatError::InvalidOptions {
+                field: "max_in_flight",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn hands_out_permits_in_order() {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let held = semaphore.clone().try_acquire().unwrap();
+        let mut first = semaphore.clone().acquire();
+        let mut second = semaphore.clone().acquire();
+        assert!(first.poll_unpin(&mut cx).is_pending());
+        assert!(second.poll_unpin(&mut cx).is_pending());
+        // Later arrivals queue up behind the waiting acquires.
+        assert!(semaphore.clone().try_acquire().is_none());
+
+        drop(held);
+        assert!(second.poll_unpin(&mut cx).is_pending());
+        let held = match first.poll_unpin(&mut cx) {
+            Poll::Ready(permit) => permit,
+            Poll::Pending => panic!("the first acquire should get the permit"),
+        };
+
+        drop(held);
+        assert!(second.poll_unpin(&mut cx).is_ready());
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    future::Future,
+    pin::{Pin, pin},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{
+    FutureExt, Stream, StreamExt,
+    future::{Either, select},
+};
+use futures_timer::Delay;
+use web_time::Instant;
+
+use crate::providers::chat::{ChatChunk, ChatError, ChatOptions, ChatResponse, ChatStreamError};
+
+impl ChatOptions<'_> {
+    /// Runs a provider's request, enforcing [`ChatOptions::timeout`] and
+    /// [`ChatOptions::first_chunk_timeout`].
+    ///
+    /// If either runs out before the response arrives, [`ChatError::Timeout`] is returned.
+    /// Otherwise the rest of the time is enforced on the stream, which ends with
+    /// [`ChatStreamError::Timeout`] if it runs out.
+    pub async fn with_timeouts<'r>(
+        &self,
+        request: impl Future<Output = Result<ChatResponse<'r>, ChatError>>,
+    ) -> Result<ChatResponse<'r>, ChatError> {
+        if self.timeout.is_none() && self.first_chunk_timeout.is_none() {
+            return request.await;
+        }
+
+        let started = Instant::now();
+        let limit = self
+            .timeout
+            .into_iter()
+            .chain(self.first_chunk_timeout)
+            .min()
+            .unwrap_or_default();
+
+        let response = match select(pin!(request), Delay::new(limit)).await {
+            Either::Left((response, _)) => response?,
+            Either::Right(_) => return Err(ChatError::Timeout { after: limit }),
+        };
+
+        let remaining = |timeout: Option<Duration>| {
+            timeout.map(|timeout| (timeout, timeout.saturating_sub(started.elapsed())))
+        };
+        Ok(response.map_stream(|stream| Deadlines {
+            stream,
+            timeout: remaining(self.timeout).map(|(after, left)| (after, Delay::new(left))),
+            first_chunk_timeout: remaining(self.first_chunk_timeout)
+                .map(|(after, left)| (after, Delay::new(left))),
+            expired: false,
+        }))
+    }
+}
+
+/// Ends a stream with [`ChatStreamError::Timeout`] once a deadline passes.
+struct Deadlines<S> {
+    stream: S,
+    /// The overall timeout and the delay until it's reached.
+    timeout: Option<(Duration, Delay)>,
+    /// Cleared once the first chunk arrives.
+    first_chunk_timeout: Option<(Duration, Delay)>,
+    expired: bool,
+}
+
+impl<S> Stream for Deadlines<S>
+where
+    S: Stream<Item = Result<ChatChunk, ChatStreamError>> + Unpin,
+{
+    type Item = Result<ChatChunk, ChatStreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.expired {
+            return Poll::Ready(None);
+        }
+
+        match self.stream.poll_next_unpin(cx) {
+            Poll::Ready(Some(item)) => {
+                self.first_chunk_timeout = None;
+                return Poll::Ready(Some(item));
+            }
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => {}
+        }
+
+        let this = &mut *self;
+        for (after, delay) in [&mut this.timeout, &mut this.first_chunk_timeout]
+            .into_iter()
+            .flatten()
+        {
+            if delay.poll_unpin(cx).is_ready() {
+                let after = *after;
+                this.expired = true;
+                return Poll::Ready(Some(Err(ChatStreamError::Timeout { after })));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, stream};
+
+    const SHORT: Duration = Duration::from_millis(20);
+
+    fn pending_response() -> ChatResponse<'static> {
+        ChatResponse::new(stream::pending())
+    }
+
+    #[test]
+    fn no_timeouts_passes_response_through() {
+        let options = ChatOptions::new("model");
+
+        let mut response = block_on(options.with_timeouts(async {
+            Ok(ChatResponse::new(stream::iter([Ok(ChatChunk::Content(
+                "Hi".into(),
+            ))])))
+        }))
+        .unwrap();
+
+        assert!(block_on(response.next()).unwrap().is_ok());
+        assert!(block_on(response.next()).is_none());
+    }
+
+    #[test]
+    fn request_timeout() {
+        let options = ChatOptions::new("model").timeout(SHORT);
+
+        let result = block_on(options.with_timeouts(futures::future::pending()));
+
+        assert!(matches!(result, Err(ChatError::Timeout { after }) if after == SHORT));
+    }
+
+    #[test]
+    fn first_chunk_timeout() {
+        let options = ChatOptions::new("model")
+            .timeout(Duration::from_secs(60))
+            .first_chunk_timeout(SHORT);
+
+        let mut response =
+            block_on(options.with_timeouts(async { Ok(pending_response()) })).unwrap();
+
+        let chunk = block_on(response.next()).unwrap();
+        assert!(matches!(chunk, Err(ChatStreamError::Timeout { after }) if after == SHORT));
+        assert!(block_on(response.next()).is_none());
+    }
+
+    #[test]
+    fn first_chunk_timeout_cleared_by_first_chunk() {
+        let options = ChatOptions::new("model").first_chunk_timeout(SHORT);
+
+        let mut response = block_on(options.with_timeouts(async {
+            Ok(ChatResponse::new(
+                stream::iter([Ok(ChatChunk::Content("Hi".into()))]).chain(
+                    stream::once(Delay::new(SHORT * 3))
+                        .map(|_| Ok(ChatChunk::Content("there".into()))),
+                ),
+            ))
+        }))
+        .unwrap();
+
+        let aggregated = block_on(response.aggregate()).unwrap();
+        assert_eq!(aggregated.content, "Hithere");
+    }
+
+    #[test]
+    fn stream_timeout() {
+        let options = ChatOptions::new("model").timeout(SHORT);
+
+        let mut response = block_on(options.with_timeouts(async {
+            Ok(ChatResponse::new(
+                stream::iter([Ok(ChatChunk::Content("Hi".into()))]).chain(stream::pending()),
+            ))
+        }))
+        .unwrap();
+
+        assert!(block_on(response.next()).unwrap().is_ok());
+        let chunk = block_on(response.next()).unwrap();
+        assert!(matches!(chunk, Err(ChatStreamError::Timeout { .. })));
+        assert!(chunk.unwrap_err().is_retryable());
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::maybe_send::{MaybeSend, MaybeSync};
+
+/// A provider that transcribes speech into text.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+pub trait TranscriptionProvider: MaybeSend + MaybeSync {
+    async fn transcribe(
+        &self,
+        options: &TranscriptionOptions<'_>,
+    ) -> Result<Transcription, TranscriptionError>;
+}
+
+#[derive(Clone, Debug)]
+pub struct TranscriptionOptions<'a> {
+    pub model: &'a str,
+    pub audio: &'a [u8],
+    /// The encoding of the audio (e.g. `"wav"`, `"mp3"`).
+    pub format: &'a str,
+    pub language: Option<&'a str>,
+    pub prompt: Option<&'a str>,
+    pub timestamps: bool,
+}
+
+impl<'a> TranscriptionOptions<'a> {
+    pub fn new(model: &'a str, audio: &'a [u8], format: &'a str) -> Self {
+        Self {
+            model,
+            audio,
+            format,
+            language: None,
+            prompt: None,
+            timestamps: false,
+        }
+    }
+
+    /// Sets the model to be used for the transcription.
+    pub fn model(mut self, model: &'a str) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Sets the spoken language as an ISO-639-1 code (e.g. `"en"`),
+    /// rather than letting the model detect it.
+    pub fn language(mut self, language: &'a str) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Sets text which guides the transcription's style or spelling of unusual words.
+    pub fn prompt(mut self, prompt: &'a str) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+
+    /// Requests the timestamps of each segment in [`Transcription::segments`].
+    pub fn timestamps(mut self, timestamps: bool) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcription {
+    pub text: String,
+    /// The spoken language, if the provider reports it.
+    pub language: Option<String>,
+    /// The length of the audio, if the provider reports it.
+    pub duration: Option<Duration>,
+    /// The timed segments of the transcription, if timestamps were requested.
+    pub segments: Vec<TranscriptionSegment>,
+}
+
+/// A span of the transcription and when it was spoken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionSegment {
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+}
+
+#[derive(Debug, Error)]
+pub enum TranscriptionError {
+    #[error("Failed to build the request: {0}.")]
+    RequestBuildFailed(#[source] anyhow::Error),
+
+    #[error("Failed to retrieve the response: {0}.")]
+    ResponseFetchFailed(#[source] anyhow::Error),
+
+    #[error("The request failed: {0}.")]
+    RequestError(#[source] anyhow::Error),
+
+    #[error("Failed to parse response: {0}.")]
+    ParseError(#[source] anyhow::Error),
+}
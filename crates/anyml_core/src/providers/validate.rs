@@ -0,0 +1,182 @@
+use crate::models::{Message, MessageRole};
+use crate::providers::chat::{ChatError, Messages};
+
+/// The rules a provider places on the order of messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MessageRules {
+    /// Messages may come in any order.
+    #[default]
+    Any,
+    /// The conversation must start with a user message and then alternate
+    /// between the user and the assistant.
+    ///
+    /// Required by Anthropic, and by the chat templates of many open models
+    /// (e.g. Mistral's) served through Hugging Face or vLLM.
+    Alternating,
+}
+
+impl Messages<'_> {
+    /// Checks the messages follow a provider's `rules`, so a request doesn't fail
+    /// on history produced by another provider.
+    ///
+    /// [`MessageRules::Alternating`] requires a non-empty user message first, and
+    /// no two user or assistant messages in a row (see [`coalesce_messages`]).
+    /// System messages are ignored, as are tool results, which are sent on the
+    /// user's side. Serialized messages aren't checked.
+    pub fn validate_for(&self, rules: impl Into<MessageRules>) -> Result<(), ChatError> {
+        let Messages::Raw(messages) = self else {
+            return Ok(());
+        };
+        if rules.into() == MessageRules::Any {
+            return Ok(());
+        }
+
+        let invalid = |reason: String| ChatError::InvalidOptions {
+            field: "messages",
+            reason,
+        };
+
+        let mut previous: Option<(usize, &MessageRole)> = None;
+        for (index, message) in messages.iter().enumerate() {
+            if matches!(message.role, MessageRole::System) {
+                continue;
+            }
+
+            match previous {
+                None if !matches!(message.role, MessageRole::User) => {
+                    return Err(invalid(format!(
+                        "the first message must be from the user, not a {} message",
+                        message.role.as_str()
+                    )));
+                }
+                None if message.content.trim().is_empty() && message.parts.is_empty() => {
+                    return Err(invalid(
+                        "the first user message must not be empty".to_owned(),
+                    ));
+                }
+                Some((previous_index, previous_role))
+                    if previous_role.as_str() == message.role.as_str()
+                        && !matches!(message.role, MessageRole::Tool) =>
+                {
+                    return Err(invalid(format!(
+                        "roles must alternate, but messages {previous_index} and {index} are both {} messages",
+                        message.role.as_str()
+                    )));
+                }
+                _ => {}
+            }
+            previous = Some((index, &message.role));
+        }
+
+        Ok(())
+    }
+}
+
+/// Merges each run of consecutive messages with the same role into one, for
+/// providers which require alternating roles.
+///
+/// The contents of merged messages are joined by a blank line, and their parts,
+/// tool calls and thinking are kept in order. Tool results are never merged, as
+/// each answers its own tool call.
+pub fn coalesce_messages(messages: &[Message]) -> Vec<Message> {
+    let mut coalesced: Vec<Message> = Vec::with_capacity(messages.len());
+    for message in messages {
+        match coalesced.last_mut() {
+            Some(last)
+                if last.role.as_str() == message.role.as_str()
+                    && !matches!(message.role, MessageRole::Tool) =>
+            {
+                if !message.content.is_empty() {
+                    if !last.content.is_empty() {
+                        last.content.push_str("\n\n");
+                    }
+                    last.content.push_str(&message.content);
+                }
+                last.parts.extend(message.parts.iter().cloned());
+                last.tool_calls.extend(message.tool_calls.iter().cloned());
+                last.thinking.extend(message.thinking.iter().cloned());
+            }
+            _ => coalesced.push(message.clone()),
+        }
+    }
+    coalesced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ToolCall;
+
+    #[test]
+    fn coalesces_consecutive_messages_with_the_same_role() {
+        let messages = [
+            Message::system("Be brief."),
+            Message::user("Hi"),
+            Message::user("Are you there?"),
+            Message::assistant("Yes.").tool_calls(vec![ToolCall::new("call_1", "get_time", "{}")]),
+            Message::tool("call_1", "12:00"),
+            Message::tool("call_2", "13:00"),
+            Message::assistant(""),
+            Message::assistant("It's noon."),
+        ];
+
+        let coalesced = coalesce_messages(&messages);
+
+        let roles: Vec<_> = coalesced
+            .iter()
+            .map(|message| message.role.as_str())
+            .collect();
+        assert_eq!(
+            roles,
+            ["system", "user", "assistant", "tool", "tool", "assistant"]
+        );
+        assert_eq!(coalesced[1].content, "Hi\n\nAre you there?");
+        assert_eq!(coalesced[2].tool_calls.len(), 1);
+        assert_eq!(coalesced[5].content, "It's noon.");
+    }
+
+    #[test]
+    fn validates_alternation_where_required() {
+        let messages = [
+            Message::system("Be brief."),
+            Message::user("Hi"),
+            Message::assistant("Hello."),
+            Message::assistant("How can I help?"),
+        ];
+        let messages = Messages::Raw(&messages);
+
+        assert!(messages.validate_for(MessageRules::Any).is_ok());
+        assert!(matches!(
+            messages.validate_for(MessageRules::Alternating),
+            Err(ChatError::InvalidOptions {
+                field: "messages",
+                ..
+            })
+        ));
+
+        let coalesced = coalesce_messages(&[
+            Message::user("Hi"),
+            Message::assistant("Hello."),
+            Message::assistant("How can I help?"),
+        ]);
+        assert!(
+            Messages::Raw(&coalesced)
+                .validate_for(MessageRules::Alternating)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn validates_the_first_message() {
+        for messages in [
+            vec![Message::assistant("Hello.")],
+            vec![Message::system("Be brief."), Message::user(" ")],
+        ] {
+            assert!(
+                Messages::Raw(&messages)
+                    .validate_for(MessageRules::Alternating)
+                    .is_err()
+            );
+        }
+    }
+}
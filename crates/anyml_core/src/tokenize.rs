@@ -0,0 +1,94 @@
+//! Approximate token counts, made offline.
+//!
+//! With the `tiktoken` feature, text for OpenAI models (and other models
+//! known to tiktoken) is counted with the model's own BPE encoding. Other
+//! models, or all of them without the feature, get an estimate of about
+//! four characters per token, which is close for English text.
+
+use crate::models::Message;
+
+/// Tokens added for each message's role and framing, on top of its text.
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Counts the tokens `text` takes up for `model`, such as `"gpt-4o"`.
+///
+/// Models prefixed with a provider's name (e.g. `"openai/gpt-4o"`) are looked up
+/// without the prefix.
+pub fn count_tokens(model: &str, text: &str) -> usize {
+    #[cfg(feature = "tiktoken")]
+    if let Some(bpe) = bpe::for_model(model) {
+        return bpe.encode_with_special_tokens(text).len();
+    }
+
+    #[cfg(not(feature = "tiktoken"))]
+    let _ = model;
+    estimate_tokens(text)
+}
+
+/// Estimates the tokens `text` takes up, at about four characters each.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Counts the tokens `message` takes up for `model`, including its tool calls
+/// and a few tokens for its framing.
+pub fn count_message_tokens(model: &str, message: &Message) -> usize {
+    let tool_calls: usize = message
+        .tool_calls
+        .iter()
+        .map(|tool_call| {
+            count_tokens(model, &tool_call.name) + count_tokens(model, &tool_call.arguments)
+        })
+        .sum();
+    count_tokens(model, &message.content) + tool_calls + MESSAGE_OVERHEAD
+}
+
+#[cfg(feature = "tiktoken")]
+mod bpe {
+    use tiktoken_rs::{CoreBPE, tokenizer::Tokenizer};
+
+    /// The encoding `model` was trained with, if tiktoken knows it.
+    pub(super) fn for_model(model: &str) -> Option<&'static CoreBPE> {
+        let model = model.rsplit('/').next().unwrap_or(model);
+        match tiktoken_rs::tokenizer::get_tokenizer(model)? {
+            Tokenizer::O200kBase => Some(tiktoken_rs::o200k_base_singleton()),
+            Tokenizer::Cl100kBase => Some(tiktoken_rs::cl100k_base_singleton()),
+            Tokenizer::P50kBase => Some(tiktoken_rs::p50k_base_singleton()),
+            Tokenizer::R50kBase | Tokenizer::Gpt2 => Some(tiktoken_rs::r50k_base_singleton()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ToolCall;
+
+    #[test]
+    fn estimates_four_characters_per_token() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("Hello"), 2);
+        assert_eq!(estimate_tokens("héllo wörld!"), 3);
+    }
+
+    #[test]
+    fn unknown_models_are_estimated() {
+        assert_eq!(count_tokens("llama3.2", "Hello world!"), 3);
+    }
+
+    #[test]
+    fn messages_count_tool_calls_and_framing() {
+        let message =
+            Message::assistant("").tool_calls(vec![ToolCall::new("call_1", "get", r#"{"a":1}"#)]);
+
+        assert_eq!(count_message_tokens("llama3.2", &message), 1 + 2 + 4);
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn openai_models_are_counted_with_their_encoding() {
+        assert_eq!(count_tokens("gpt-4o", "Hello world!"), 3);
+        assert_eq!(count_tokens("openai/gpt-4o", "Hello world!"), 3);
+    }
+}
@@ -0,0 +1,37 @@
+use std::env;
+
+use anyml_core::providers::chat::{ChatChunk, ChatOptions, ChatProvider};
+use anyml_groq::GroqProvider;
+
+const MODEL: &str = "llama-3.1-8b-instant";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::from_filename(".env.test").ok();
+    let api_key = env::var("GROQ_API_KEY").expect("GROQ_API_KEY not set");
+
+    let provider = GroqProvider::new(reqwest::Client::new(), api_key);
+
+    let messages = &["Write me a short poem".into()];
+    let options = ChatOptions::new(MODEL).messages(messages);
+
+    let mut response = provider.chat(&options).await.map_err(anyhow::Error::new)?;
+
+    while let Some(chunk) = response.next().await {
+        match chunk? {
+            ChatChunk::Content(text) => print!("{text}"),
+            ChatChunk::Usage(usage) => {
+                println!("\n\nInput tokens: {:?}", usage.input_tokens);
+                println!("Output tokens: {:?}", usage.output_tokens);
+                if let Some(timing) = usage.timing {
+                    println!("Queue time: {:?}", timing.queue_time);
+                    println!("Prompt time: {:?}", timing.prompt_time);
+                    println!("Completion time: {:?}", timing.completion_time);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
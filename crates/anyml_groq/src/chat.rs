@@ -0,0 +1,302 @@
+use anyhttp::HttpClient;
+use anyml_core::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse, Thinking};
+use anyml_core::providers::openai_compat;
+use anyml_macros::json_string;
+use http::Request;
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::GroqProvider;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ChatProvider for GroqProvider<C> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let options = options.or_default_model(self.default_model.as_deref());
+        options.with_timeouts(self.send_chat(&options)).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.default_model.as_deref()
+    }
+}
+
+impl<C: HttpClient> GroqProvider<C> {
+    /// An empty key is only rejected for the hosted API, as other servers may not need one.
+    fn check_api_key(&self, api_key: &SecretString, url: &str) -> Result<(), ChatError> {
+        if api_key.expose_secret().is_empty() && url == crate::DEFAULT_URL {
+            return Err(ChatError::InvalidOptions {
+                field: "api_key",
+                reason: "it is empty".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn send_chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse<'_>, ChatError> {
+        let overrides = &options.overrides;
+        let url = overrides.url_or(&self.url);
+        let api_key = overrides
+            .api_key_or(&*self.api_key)
+            .await
+            .map_err(ChatError::RequestBuildFailed)?;
+        self.check_api_key(&api_key, url)?;
+        options.validate()?;
+        if options.response_format.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "response_format",
+            });
+        }
+        if options.logprobs {
+            return Err(ChatError::Unsupported {
+                feature: "logprobs",
+            });
+        }
+        if options.audio_output.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "audio_output",
+            });
+        }
+        if !options.server_tools.is_empty() {
+            return Err(ChatError::Unsupported {
+                feature: "server_tools",
+            });
+        }
+
+        let messages_json =
+            openai_compat::messages_json(&options.messages, options.system, options.prefill)?;
+        let tools_json = openai_compat::tools_json(options.tools);
+
+        let body: String = json_string! {
+            "model": options.model,
+            "messages": @raw messages_json,
+            "stream": options.stream,
+            "max_completion_tokens"?: options.max_tokens,
+            if let Some(Thinking::Effort(effort)) = &options.thinking {
+                "reasoning_effort": effort,
+                "reasoning_format": "parsed"
+            } else if options.thinking.is_some() {
+                "reasoning_format": "parsed"
+            }
+            if let Some(tools) = &tools_json {
+                "tools": @raw tools,
+                "tool_choice"?: options.tool_choice.as_ref().map(openai_compat::tool_choice_json),
+                "parallel_tool_calls"?: options.parallel_tool_calls
+            }
+            @merge &options.extra
+        };
+
+        let request = self
+            .headers
+            .apply(Request::post(format!("{url}{}", self.chat_path)));
+        let request = overrides
+            .headers
+            .apply(request)
+            .header(
+                "Authorization",
+                format!("Bearer {}", api_key.expose_secret()),
+            )
+            .body(body.into_bytes())
+            .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        openai_compat::send_chat(&self.client, request, options.include_raw).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::models::{Image, Message, Tool};
+    use anyml_core::providers::chat::ChatChunk;
+    use http::StatusCode;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_chat_success() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"Hello!\"}}]}\n\n"),
+        );
+
+        let provider = GroqProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama-3.1-8b-instant").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let chunk = response.next().await.unwrap().unwrap();
+
+        assert!(matches!(chunk, ChatChunk::Content(ref s) if s == "Hello!"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_parts_as_content_arrays() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"A cat.\"}}]}\n\n"),
+        );
+
+        let provider = GroqProvider::new(client.clone(), "test-api-key");
+        let messages =
+            [Message::user("What is in this picture?").part(Image::new(b"PNG", "image/png"))];
+        let options = ChatOptions::new("llama-3.2-11b-vision-preview").messages(&messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"],
+            serde_json::json!([
+                { "type": "text", "text": "What is in this picture?" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,UE5H" } },
+            ])
+        );
+        assert!(body["messages"][0].get("parts").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_http_error() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::UNAUTHORIZED).body("invalid api key"));
+
+        let provider = GroqProvider::new(client, "bad-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama-3.1-8b-instant").messages(messages);
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(result, Err(ChatError::RequestError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_chat_request_headers() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = GroqProvider::new(client.clone(), "my-secret-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama-3.1-8b-instant").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://api.groq.com/openai/v1/chat/completions"
+        );
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer my-secret-key"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_speed_metrics() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello!\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{}}],\"x_groq\":{\"id\":\"req_1\",\"usage\":{\"queue_time\":0.25,\"prompt_tokens\":12,\"prompt_time\":0.5,\"completion_tokens\":3,\"completion_time\":0.125,\"total_tokens\":15,\"total_time\":0.625}}}\n\n\
+             data: [DONE]\n\n",
+        ));
+
+        let provider = GroqProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama-3.1-8b-instant").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Hello!");
+
+        let usage = result.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(12));
+        assert_eq!(usage.output_tokens, Some(3));
+
+        let timing = usage.timing.unwrap();
+        assert_eq!(timing.queue_time, Some(Duration::from_millis(250)));
+        assert_eq!(timing.prompt_time, Some(Duration::from_millis(500)));
+        assert_eq!(timing.completion_time, Some(Duration::from_millis(125)));
+        assert_eq!(timing.total_time, Some(Duration::from_millis(625)));
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_reasoning() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"choices\":[{\"delta\":{\"reasoning\":\"Let me think...\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{\"content\":\"Hello!\"}}]}\n\n",
+        ));
+
+        let provider = GroqProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("qwen/qwen3-32b")
+            .messages(messages)
+            .thinking(Thinking::effort("default"));
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Hello!");
+        assert_eq!(result.thinking.as_deref(), Some("Let me think..."));
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["reasoning_effort"], "default");
+        assert_eq!(body["reasoning_format"], "parsed");
+    }
+
+    #[tokio::test]
+    async fn test_chat_reasoning_format_only_with_thinking() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(""))
+            .with_response(MockResponse::new(StatusCode::OK).body(""));
+
+        let provider = GroqProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("qwen/qwen3-32b").messages(messages);
+
+        provider
+            .chat(&options.clone().thinking(Thinking::Enabled))
+            .await
+            .unwrap();
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["reasoning_format"], "parsed");
+        assert!(body.get("reasoning_effort").is_none());
+
+        provider.chat(&options).await.unwrap();
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert!(body.get("reasoning_format").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_tool_calls() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"choices\":[{\"delta\":{\"content\":null,\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"{\\\"city\\\":\\\"Paris\\\"}\"}}]}}]}\n\n\
+             data: [DONE]\n\n",
+        ));
+
+        let provider = GroqProvider::new(client.clone(), "test-api-key");
+        let messages = &["What's the weather in Paris?".into()];
+        let tools = &[Tool::new(
+            "get_weather",
+            serde_json::json!({ "type": "object" }),
+        )];
+        let options = ChatOptions::new("llama-3.3-70b-versatile")
+            .messages(messages)
+            .tools(tools);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.tool_calls.len(), 1);
+        assert_eq!(result.tool_calls[0].name, "get_weather");
+        assert_eq!(result.tool_calls[0].arguments, "{\"city\":\"Paris\"}");
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::anyhow;
+use anyhttp::HttpClient;
+use anyml_core::{
+    models::{Model, ThinkingModes},
+    providers::list_models::{ListModelsError, ListModelsProvider},
+};
+use bytes::Bytes;
+use http::Request;
+use phf::phf_map;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+
+use crate::GroqProvider;
+
+type StaticThinkingModes = ThinkingModes<&'static [&'static str]>;
+
+static THINKING_MODELS: phf::Map<&'static str, StaticThinkingModes> = phf_map! {
+    "openai/gpt-oss-20b" => StaticThinkingModes { modes: &["low", "medium", "high"], budget: None },
+    "openai/gpt-oss-120b" => StaticThinkingModes { modes: &["low", "medium", "high"], budget: None },
+    "qwen/qwen3-32b" => StaticThinkingModes { modes: &["none", "default"], budget: None },
+};
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ListModelsProvider for GroqProvider<C> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(ListModelsError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/v1/models", self.url)))
+            .header(
+                "Authorization",
+                format!("Bearer {}", api_key.expose_secret()),
+            )
+            .body(Vec::new())
+            .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(ListModelsError::ResponseFetchFailed(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        let groq_response: GroqModelsResponse = serde_json::from_slice(&body)
+            .map_err(|e| ListModelsError::ParseError(anyhow::Error::new(e)))?;
+
+        let models = groq_response
+            .data
+            .into_iter()
+            .filter(|m| m.active)
+            .map(|m| {
+                let thinking = THINKING_MODELS
+                    .get(m.id.as_str())
+                    .map(|s| ThinkingModes {
+                        modes: s.modes.iter().map(|s| (*s).into()).collect(),
+                        budget: s.budget,
+                    });
+                Model {
+                    id: m.id,
+                    parameters: None,
+                    quantization: None,
+                    thinking,
+                    context_length: m.context_window,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Ok(models)
+    }
+}
+
+#[derive(Deserialize)]
+struct GroqModelsResponse {
+    data: Vec<GroqModel>,
+}
+
+#[derive(Deserialize)]
+struct GroqModel {
+    id: String,
+    #[serde(default = "default_active")]
+    active: bool,
+    context_window: Option<usize>,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_list_models_success() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(
+                r#"{"data":[{"id":"llama-3.1-8b-instant","active":true,"context_window":131072},{"id":"openai/gpt-oss-20b","active":true}]}"#,
+            ),
+        );
+
+        let provider = GroqProvider::new(client, "test-api-key");
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].id, "llama-3.1-8b-instant");
+        assert_eq!(models[0].context_length, Some(131072));
+        assert!(models[0].thinking.is_none());
+        assert_eq!(models[1].id, "openai/gpt-oss-20b");
+        assert_eq!(
+            models[1].thinking.as_ref().unwrap().modes,
+            vec!["low", "medium", "high"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_models_skips_inactive() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(
+                r#"{"data":[{"id":"llama-3.1-8b-instant","active":true},{"id":"old-model","active":false}]}"#,
+            ),
+        );
+
+        let provider = GroqProvider::new(client, "test-api-key");
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].id, "llama-3.1-8b-instant");
+    }
+
+    #[tokio::test]
+    async fn test_list_models_unauthorized() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::UNAUTHORIZED).body("invalid api key"));
+
+        let provider = GroqProvider::new(client, "bad-key");
+        let result = provider.list_models().await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list_models_request_headers() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"data":[]}"#));
+
+        let provider = GroqProvider::new(client.clone(), "my-secret-key");
+        provider.list_models().await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "https://api.groq.com/openai/v1/models");
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer my-secret-key"
+        );
+    }
+}
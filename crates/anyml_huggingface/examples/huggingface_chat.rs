@@ -0,0 +1,32 @@
+use std::env;
+
+use anyml_core::providers::chat::{ChatChunk, ChatOptions, ChatProvider};
+use anyml_huggingface::HuggingFaceProvider;
+
+const MODEL: &str = "meta-llama/Llama-3.1-8B-Instruct";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::from_filename(".env.test").ok();
+    let api_key = env::var("HF_TOKEN").expect("HF_TOKEN not set");
+
+    let provider = HuggingFaceProvider::new(reqwest::Client::new(), api_key);
+
+    let messages = &["Write me a short poem".into()];
+    let options = ChatOptions::new(MODEL).messages(messages);
+
+    let mut response = provider.chat(&options).await.map_err(anyhow::Error::new)?;
+
+    while let Some(chunk) = response.next().await {
+        match chunk? {
+            ChatChunk::Content(text) => print!("{text}"),
+            ChatChunk::Usage(usage) => {
+                println!("\n\nInput tokens: {:?}", usage.input_tokens);
+                println!("Output tokens: {:?}", usage.output_tokens);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
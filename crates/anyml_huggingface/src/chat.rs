@@ -0,0 +1,244 @@
+use anyhttp::HttpClient;
+use anyml_core::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse};
+use anyml_core::providers::openai_compat;
+use anyml_macros::json_string;
+use http::Request;
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::{Backend, HuggingFaceProvider, with_auth};
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ChatProvider for HuggingFaceProvider<C> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let options = options.or_default_model(self.default_model.as_deref());
+        options.with_timeouts(self.send_chat(&options)).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.default_model.as_deref()
+    }
+}
+
+impl<C: HttpClient> HuggingFaceProvider<C> {
+    /// An empty key is only rejected for the serverless API, as TGI servers may not need one.
+    fn check_api_key(&self, api_key: &SecretString, url: &str) -> Result<(), ChatError> {
+        if api_key.expose_secret().is_empty()
+            && self.backend == Backend::Serverless
+            && url == crate::DEFAULT_URL
+        {
+            return Err(ChatError::InvalidOptions {
+                field: "api_key",
+                reason: "it is empty".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn send_chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse<'_>, ChatError> {
+        let overrides = &options.overrides;
+        let url = overrides.url_or(&self.url);
+        let api_key = overrides
+            .api_key_or(&*self.api_key)
+            .await
+            .map_err(ChatError::RequestBuildFailed)?;
+        self.check_api_key(&api_key, url)?;
+        options.validate()?;
+        if options.prefill.is_some() {
+            return Err(ChatError::Unsupported { feature: "prefill" });
+        }
+        if options.thinking.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "thinking",
+            });
+        }
+        if options.response_format.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "response_format",
+            });
+        }
+        if options.logprobs {
+            return Err(ChatError::Unsupported {
+                feature: "logprobs",
+            });
+        }
+        if options.audio_output.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "audio_output",
+            });
+        }
+        if !options.server_tools.is_empty() {
+            return Err(ChatError::Unsupported {
+                feature: "server_tools",
+            });
+        }
+
+        let messages_json = openai_compat::messages_json(&options.messages, options.system, None)?;
+        let tools_json = openai_compat::tools_json(options.tools);
+
+        // TGI ignores the model, serving whichever it was started with.
+        let body: String = json_string! {
+            "model": options.model,
+            "messages": @raw messages_json,
+            "stream": options.stream,
+            if options.stream {
+                "stream_options": { "include_usage": true }
+            }
+            "max_tokens"?: options.max_tokens,
+            if let Some(tools) = &tools_json {
+                "tools": @raw tools,
+                "tool_choice"?: options.tool_choice.as_ref().map(openai_compat::tool_choice_json),
+                "parallel_tool_calls"?: options.parallel_tool_calls
+            }
+            @merge &options.extra
+        };
+
+        let request = self
+            .headers
+            .apply(Request::post(format!("{url}{}", self.chat_path)));
+        let request = with_auth(overrides.headers.apply(request), &api_key)
+            .body(body.into_bytes())
+            .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        openai_compat::send_chat(&self.client, request, options.include_raw).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::models::{Image, Message};
+    use anyml_core::providers::chat::{ChatStreamError, FinishReason};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_chat_success() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello!\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n\
+             data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2}}\n\n\
+             data: [DONE]\n\n",
+        ));
+
+        let provider = HuggingFaceProvider::new(client.clone(), "hf_token");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("meta-llama/Llama-3.1-8B-Instruct").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Hello!");
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(result.usage.unwrap().output_tokens, Some(2));
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://router.huggingface.co/v1/chat/completions"
+        );
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer hf_token"
+        );
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["stream_options"]["include_usage"], true);
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_parts_as_content_arrays() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"A cat.\"}}]}\n\n"),
+        );
+
+        let provider = HuggingFaceProvider::new(client.clone(), "hf_token");
+        let messages =
+            [Message::user("What is in this picture?").part(Image::new(b"PNG", "image/png"))];
+        let options = ChatOptions::new("Qwen/Qwen2.5-VL-7B-Instruct").messages(&messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"],
+            serde_json::json!([
+                { "type": "text", "text": "What is in this picture?" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,UE5H" } },
+            ])
+        );
+        assert!(body["messages"][0].get("parts").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_tgi_without_api_key() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = HuggingFaceProvider::tgi(client.clone(), "http://localhost:8080");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("tgi").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:8080/v1/chat/completions");
+        assert!(request.headers().get("Authorization").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_serverless_requires_api_key() {
+        let client = MockHttpClient::new();
+        let provider = HuggingFaceProvider::new(client.clone(), "");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("meta-llama/Llama-3.1-8B-Instruct").messages(messages);
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(
+            result,
+            Err(ChatError::InvalidOptions {
+                field: "api_key",
+                ..
+            })
+        ));
+        assert!(client.last_request().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_http_error() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::UNAUTHORIZED).body("invalid token"));
+
+        let provider = HuggingFaceProvider::new(client, "bad-token");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("meta-llama/Llama-3.1-8B-Instruct").messages(messages);
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(result, Err(ChatError::RequestError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_error() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"error\":\"Input validation error\",\"error_type\":\"validation\"}\n\n",
+        ));
+
+        let provider = HuggingFaceProvider::new(client, "hf_token");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("meta-llama/Llama-3.1-8B-Instruct").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+
+        assert!(matches!(
+            response.next().await,
+            Some(Err(ChatStreamError::ProviderError { kind, message }))
+                if kind == "validation" && message == "Input validation error"
+        ));
+    }
+}
@@ -0,0 +1,298 @@
+use anyhow::anyhow;
+use anyhttp::HttpClient;
+use anyml_core::decode::SseDecoder;
+use anyml_core::providers::{
+    chat::{ChatChunk, ChatError, ChatResponse, ChatStreamError, Usage},
+    completion::{CompletionOptions, CompletionProvider},
+    openai_compat,
+};
+use anyml_macros::json_string;
+use bytes::Bytes;
+use futures::StreamExt;
+use http::Request;
+use serde::Deserialize;
+
+use crate::{Backend, HuggingFaceProvider, with_auth};
+
+/// Continues prompts with TGI's native `/generate_stream` (or `/generate`) endpoint,
+/// or the serverless API's text generation task.
+///
+/// Prompts are always sent raw, as text generation doesn't apply the model's chat template.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> CompletionProvider for HuggingFaceProvider<C> {
+    async fn complete(&self, options: &CompletionOptions<'_>) -> Result<ChatResponse, ChatError> {
+        if options.suffix.is_some() {
+            return Err(ChatError::Unsupported { feature: "suffix" });
+        }
+        if options.system.is_some() {
+            return Err(ChatError::Unsupported { feature: "system" });
+        }
+
+        let url = match (self.backend, options.stream) {
+            (Backend::Tgi, true) => format!("{}/generate_stream", self.url),
+            (Backend::Tgi, false) => format!("{}/generate", self.url),
+            (Backend::Serverless, _) => {
+                format!("{}/hf-inference/models/{}", self.url, options.model)
+            }
+        };
+
+        let body: String = json_string! {
+            "inputs": options.prompt,
+            "parameters": {
+                "max_new_tokens"?: options.max_tokens,
+                "return_full_text": false,
+                "details": true
+            },
+            "stream": options.stream
+        };
+
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(ChatError::RequestBuildFailed)?;
+
+        let request = with_auth(self.headers.apply(Request::post(url)), &api_key)
+            .body(body.into_bytes())
+            .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ChatError::ResponseFetchFailed)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(ChatError::from_error_response(status, &err_body));
+        }
+
+        if !options.stream {
+            let body = response
+                .bytes()
+                .await
+                .map_err(ChatError::ResponseFetchFailed)?;
+            let chunks = parse_generated(&body).map_err(ChatError::RequestError)?;
+            return Ok(ChatResponse::new(futures::stream::iter(
+                chunks.into_iter().map(Ok),
+            )));
+        }
+
+        Ok(ChatResponse::new(
+            response
+                .bytes_stream()
+                .scan(SseDecoder::new(), |events, chunk| {
+                    let chunks = parse_sse_batch(chunk, events);
+                    futures::future::ready(Some(chunks))
+                })
+                .flat_map(futures::stream::iter),
+        ))
+    }
+}
+
+/// Parses every SSE event completed by the new chunk,
+/// keeping any trailing partial event in `events` for the next call.
+fn parse_sse_batch(
+    chunk: Result<Bytes, anyhow::Error>,
+    events: &mut SseDecoder,
+) -> Vec<Result<ChatChunk, ChatStreamError>> {
+    let chunk = match chunk {
+        Ok(chunk) => chunk,
+        Err(err) => return vec![Err(ChatStreamError::ParseError(err))],
+    };
+    events.push(&chunk);
+
+    let mut results = Vec::new();
+
+    while let Some(event) = events.next_event() {
+        let Some(event_body) = event.trim().strip_prefix("data:") else {
+            continue;
+        };
+
+        let event = match serde_json::from_str::<StreamEvent>(event_body.trim()) {
+            Ok(event) => event,
+            Err(err) => {
+                results.push(Err(ChatStreamError::ParseError(anyhow::Error::new(err))));
+                continue;
+            }
+        };
+
+        if let Some(message) = event.error {
+            results.push(Err(ChatStreamError::ProviderError {
+                kind: event.error_type.unwrap_or_default(),
+                message,
+            }));
+            continue;
+        }
+        if let Some(token) = event.token
+            && !token.special
+            && !token.text.is_empty()
+        {
+            results.push(Ok(ChatChunk::Content(token.text.into())));
+        }
+        if let Some(details) = event.details {
+            results.extend(details.into_chunks().into_iter().map(Ok));
+        }
+    }
+
+    results
+}
+
+/// Parses the single response of a request made without streaming, which the
+/// serverless API wraps in an array.
+fn parse_generated(body: &[u8]) -> Result<Vec<ChatChunk>, anyhow::Error> {
+    let generated = match serde_json::from_slice::<GeneratedResponse>(body)? {
+        GeneratedResponse::Single(generated) => generated,
+        GeneratedResponse::Batch(batch) => batch
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("the response has no generated text"))?,
+    };
+
+    let mut chunks = vec![ChatChunk::Content(generated.generated_text.into())];
+    if let Some(details) = generated.details {
+        chunks.extend(details.into_chunks());
+    }
+
+    Ok(chunks)
+}
+
+#[derive(Deserialize)]
+struct StreamEvent {
+    #[serde(default)]
+    token: Option<Token>,
+    #[serde(default)]
+    details: Option<Details>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    error_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Token {
+    text: String,
+    #[serde(default)]
+    special: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GeneratedResponse {
+    Single(Generated),
+    Batch(Vec<Generated>),
+}
+
+#[derive(Deserialize)]
+struct Generated {
+    generated_text: String,
+    #[serde(default)]
+    details: Option<Details>,
+}
+
+#[derive(Deserialize)]
+struct Details {
+    #[serde(default)]
+    finish_reason: Option<String>,
+    #[serde(default)]
+    generated_tokens: Option<usize>,
+    #[serde(default)]
+    input_length: Option<usize>,
+}
+
+impl Details {
+    fn into_chunks(self) -> Vec<ChatChunk> {
+        let mut chunks = Vec::new();
+        if let Some(reason) = self.finish_reason {
+            chunks.push(ChatChunk::Finish(openai_compat::finish_reason(reason)));
+        }
+        chunks.push(ChatChunk::Usage(Usage {
+            input_tokens: self.input_length,
+            output_tokens: self.generated_tokens,
+            cached_input_tokens: None,
+            timing: None,
+        }));
+        chunks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::providers::chat::FinishReason;
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_complete_tgi_stream() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data:{\"index\":1,\"token\":{\"id\":1,\"text\":\"Once\",\"logprob\":-0.1,\"special\":false},\"generated_text\":null,\"details\":null}\n\n\
+             data:{\"index\":2,\"token\":{\"id\":2,\"text\":\" upon\",\"logprob\":-0.2,\"special\":false},\"generated_text\":null,\"details\":null}\n\n\
+             data:{\"index\":3,\"token\":{\"id\":3,\"text\":\"</s>\",\"logprob\":0.0,\"special\":true},\"generated_text\":\"Once upon\",\"details\":{\"finish_reason\":\"eos_token\",\"generated_tokens\":3,\"input_length\":4,\"seed\":null}}\n\n",
+        ));
+
+        let provider = HuggingFaceProvider::tgi(client.clone(), "http://localhost:8080");
+        let options = CompletionOptions::new("tgi", "Tell me a story: ").max_tokens(16);
+
+        let mut response = provider.complete(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Once upon");
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+        let usage = result.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(4));
+        assert_eq!(usage.output_tokens, Some(3));
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:8080/generate_stream");
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["inputs"], "Tell me a story: ");
+        assert_eq!(body["parameters"]["max_new_tokens"], 16);
+    }
+
+    #[tokio::test]
+    async fn test_complete_serverless_without_streaming() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(r#"[{"generated_text":"Once upon a time"}]"#),
+        );
+
+        let provider = HuggingFaceProvider::new(client.clone(), "hf_token");
+        let options = CompletionOptions::new("gpt2", "Tell me a story: ").stream(false);
+
+        let mut response = provider.complete(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Once upon a time");
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://router.huggingface.co/hf-inference/models/gpt2"
+        );
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer hf_token"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_complete_rejects_suffix() {
+        let client = MockHttpClient::new();
+        let provider = HuggingFaceProvider::tgi(client.clone(), "http://localhost:8080");
+        let options = CompletionOptions::new("tgi", "def add(a, b):").suffix("\n");
+
+        let result = provider.complete(&options).await;
+
+        assert!(matches!(
+            result,
+            Err(ChatError::Unsupported { feature: "suffix" })
+        ));
+        assert!(client.last_request().is_none());
+    }
+}
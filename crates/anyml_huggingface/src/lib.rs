@@ -0,0 +1,112 @@
+use std::borrow::Cow;
+
+use anyhttp::HttpClient;
+use anyml_core::providers::{ApiKeyProvider, DefaultHeaders};
+use secrecy::{ExposeSecret, SecretString};
+
+mod chat;
+mod generate;
+mod list_models;
+
+const DEFAULT_URL: &str = "https://router.huggingface.co";
+const DEFAULT_CHAT_PATH: &str = "/v1/chat/completions";
+
+/// Where requests are sent, as the serverless API and TGI differ
+/// in how they list models and generate raw completions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Backend {
+    /// The serverless Inference API, routed to any of Hugging Face's inference providers.
+    Serverless,
+    /// A self-hosted Text Generation Inference server, serving a single model.
+    Tgi,
+}
+
+pub struct HuggingFaceProvider<C: HttpClient> {
+    client: C,
+    backend: Backend,
+    url: Cow<'static, str>,
+    chat_path: Cow<'static, str>,
+    default_model: Option<Cow<'static, str>>,
+    headers: DefaultHeaders,
+    api_key: Box<dyn ApiKeyProvider>,
+}
+
+impl<C: HttpClient> HuggingFaceProvider<C> {
+    /// Uses the serverless Inference API, authenticated with a Hugging Face access token.
+    pub fn new(client: C, api_key: impl Into<SecretString>) -> Self {
+        Self {
+            client,
+            backend: Backend::Serverless,
+            url: Cow::Borrowed(DEFAULT_URL),
+            chat_path: Cow::Borrowed(DEFAULT_CHAT_PATH),
+            default_model: None,
+            headers: DefaultHeaders::new(),
+            api_key: Box::new(api_key.into()),
+        }
+    }
+
+    /// Uses a Text Generation Inference server at `url`, e.g. `http://localhost:8080`.
+    ///
+    /// No API key is sent unless one is set with [`Self::api_key`], as for an Inference Endpoint.
+    pub fn tgi(client: C, url: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            client,
+            backend: Backend::Tgi,
+            url: url.into(),
+            chat_path: Cow::Borrowed(DEFAULT_CHAT_PATH),
+            default_model: None,
+            headers: DefaultHeaders::new(),
+            api_key: Box::new(SecretString::from("")),
+        }
+    }
+
+    pub fn url(mut self, url: impl Into<Cow<'static, str>>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Sets the path chat requests are sent to, after `url`. Defaults to `/v1/chat/completions`.
+    ///
+    /// It may end with a query string, for gateways which need one (e.g. `?api-version=`).
+    pub fn chat_path(mut self, path: impl Into<Cow<'static, str>>) -> Self {
+        self.chat_path = path.into();
+        self
+    }
+
+    /// Sets the model chats are sent to when their options leave it to the provider,
+    /// as with [`ChatOptions::default_model`](anyml_core::providers::chat::ChatOptions::default_model).
+    pub fn with_default_model(mut self, model: impl Into<Cow<'static, str>>) -> Self {
+        self.default_model = Some(model.into());
+        self
+    }
+
+    /// Adds a header sent with every request, e.g. for a proxy or gateway.
+    pub fn header(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<SecretString>) -> Self {
+        self.api_key = Box::new(api_key.into());
+        self
+    }
+
+    /// Fetches the API key before each request, for keys which rotate or expire.
+    pub fn api_key_provider(mut self, provider: impl ApiKeyProvider + 'static) -> Self {
+        self.api_key = Box::new(provider);
+        self
+    }
+}
+
+/// Adds the `Authorization` header to a request, unless the key is empty,
+/// as TGI servers usually don't authenticate requests.
+fn with_auth(request: http::request::Builder, api_key: &SecretString) -> http::request::Builder {
+    match api_key.expose_secret() {
+        "" => request,
+        api_key => request.header("Authorization", format!("Bearer {api_key}")),
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::anyhow;
+use anyhttp::HttpClient;
+use anyml_core::{
+    models::{Model, ModelCapabilities},
+    providers::list_models::{ListModelsError, ListModelsProvider},
+};
+use bytes::Bytes;
+use http::Request;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::{Backend, HuggingFaceProvider, with_auth};
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ListModelsProvider for HuggingFaceProvider<C> {
+    /// Lists the models served by the serverless API's inference providers,
+    /// or the single model deployed on a TGI server.
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        match self.backend {
+            Backend::Serverless => {
+                let response: ModelsResponse = self.fetch("/v1/models").await?;
+                Ok(response.data.into_iter().map(Into::into).collect())
+            }
+            Backend::Tgi => {
+                let info: TgiInfo = self.fetch("/info").await?;
+                Ok(vec![info.into()])
+            }
+        }
+    }
+}
+
+impl<C: HttpClient> HuggingFaceProvider<C> {
+    async fn fetch<T: DeserializeOwned>(&self, path: &str) -> Result<T, ListModelsError> {
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(ListModelsError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}{path}", self.url)));
+        let request = with_auth(request, &api_key)
+            .body(Vec::new())
+            .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(ListModelsError::ResponseFetchFailed(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        serde_json::from_slice(&body)
+            .map_err(|e| ListModelsError::ParseError(anyhow::Error::new(e)))
+    }
+}
+
+#[derive(Deserialize)]
+struct ModelsResponse {
+    data: Vec<ServerlessModel>,
+}
+
+#[derive(Deserialize)]
+struct ServerlessModel {
+    id: String,
+    #[serde(default)]
+    providers: Vec<InferenceProvider>,
+}
+
+/// One of the inference providers serving a model, which may differ in what they support.
+#[derive(Deserialize)]
+struct InferenceProvider {
+    #[serde(default)]
+    context_length: Option<usize>,
+    #[serde(default)]
+    supports_tools: Option<bool>,
+    #[serde(default)]
+    supports_structured_output: Option<bool>,
+}
+
+impl From<ServerlessModel> for Model {
+    /// Reports what the most capable of the model's providers supports,
+    /// as requests are routed to one which can serve them.
+    fn from(model: ServerlessModel) -> Self {
+        let providers = &model.providers;
+        Model {
+            context_length: providers.iter().filter_map(|p| p.context_length).max(),
+            capabilities: ModelCapabilities {
+                tool_use: providers.iter().any(|p| p.supports_tools == Some(true)),
+                json_mode: providers
+                    .iter()
+                    .any(|p| p.supports_structured_output == Some(true)),
+                ..Default::default()
+            },
+            id: model.id,
+            ..Default::default()
+        }
+    }
+}
+
+/// The model deployed on a TGI server, as reported by `/info`.
+#[derive(Deserialize)]
+struct TgiInfo {
+    model_id: String,
+    #[serde(default)]
+    max_total_tokens: Option<usize>,
+}
+
+impl From<TgiInfo> for Model {
+    fn from(info: TgiInfo) -> Self {
+        Model {
+            id: info.model_id,
+            context_length: info.max_total_tokens,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_list_models_serverless() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(
+                r#"{"object":"list","data":[{"id":"meta-llama/Llama-3.1-8B-Instruct","providers":[{"provider":"cerebras","status":"live","context_length":32768,"supports_tools":false},{"provider":"novita","status":"live","context_length":131072,"supports_tools":true,"supports_structured_output":false}]}]}"#,
+            ),
+        );
+
+        let provider = HuggingFaceProvider::new(client.clone(), "hf_token");
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].id, "meta-llama/Llama-3.1-8B-Instruct");
+        assert_eq!(models[0].context_length, Some(131072));
+        assert!(models[0].capabilities.tool_use);
+        assert!(!models[0].capabilities.json_mode);
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "https://router.huggingface.co/v1/models");
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer hf_token"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_models_tgi() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(
+                r#"{"model_id":"mistralai/Mistral-7B-Instruct-v0.3","model_sha":"e0bc86c","model_dtype":"torch.float16","max_input_tokens":4095,"max_total_tokens":4096,"version":"3.0.0"}"#,
+            ),
+        );
+
+        let provider = HuggingFaceProvider::tgi(client.clone(), "http://localhost:8080");
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].id, "mistralai/Mistral-7B-Instruct-v0.3");
+        assert_eq!(models[0].context_length, Some(4096));
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:8080/info");
+    }
+
+    #[tokio::test]
+    async fn test_list_models_unauthorized() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::UNAUTHORIZED).body("invalid token"));
+
+        let provider = HuggingFaceProvider::new(client, "bad-token");
+        let result = provider.list_models().await;
+
+        assert!(result.is_err());
+    }
+}
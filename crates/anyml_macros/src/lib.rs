@@ -1,26 +1,38 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::parse::{Parse, ParseStream};
-use syn::{
-    Expr, Ident, LitBool, LitFloat, LitInt, LitStr, Pat, Token, braced, token,
-};
+use syn::parse::{Parse, ParseStream, discouraged::Speculative};
+use syn::{Expr, Ident, Lit, LitStr, Pat, Token, braced, bracketed, token};
 
 /// A single field entry in the JSON object.
 enum Field {
     /// `"key": value`
     KeyValue(String, Value),
-    /// `if let Some(x) = expr { ...fields... }`
+    /// `"key"?: expr`, omitted entirely when `expr` is `None`
+    OptionalKeyValue(String, Expr),
+    /// `if let Some(x) = expr { ...fields... } else ...`
     IfLet {
         pat: Pat,
         expr: Expr,
         fields: Vec<Field>,
+        else_branch: Option<Else>,
     },
-    /// `if expr { ...fields... }`
+    /// `if expr { ...fields... } else ...`
     If {
         expr: Expr,
         fields: Vec<Field>,
+        else_branch: Option<Else>,
     },
+    /// `@merge expr`, writing every `(key, value)` pair of `expr` as a field
+    Merge(Expr),
+}
+
+/// The `else` branch of a conditional.
+enum Else {
+    /// `else if ...`, holding a [`Field::If`] or [`Field::IfLet`]
+    If(Box<Field>),
+    /// `else { ...fields... }`
+    Fields(Vec<Field>),
 }
 
 /// A JSON value.
@@ -29,16 +41,36 @@ enum Value {
     LitStr(String),
     /// A bool literal: `true` / `false`
     LitBool(bool),
-    /// An integer literal: `123`
+    /// The `null` literal
+    Null,
+    /// An integer literal: `123`, `-1`
     LitInt(String),
-    /// A float literal: `1.5`
+    /// A float literal: `1.5`, `-0.5`
     LitFloat(String),
     /// A variable/expression reference: `model`, `options.model`
     Variable(Expr),
     /// A nested object: `{ "key": value, ... }`
     Object(Vec<Field>),
+    /// An array: `[value, ...]`
+    Array(Vec<Element>),
     /// A raw (pre-serialized) value: `@raw expr`
     Raw(Expr),
+    /// An array built from an iterator of `JsonValue`s: `@iter expr`
+    Iter(Expr),
+    /// Any `serde::Serialize` value: `@serde expr`
+    Serde(Expr),
+}
+
+/// An element of an array.
+enum Element {
+    /// A single value
+    Value(Value),
+    /// `for pat in expr { value }`, writing `value` for every item of `expr`
+    For {
+        pat: Box<Pat>,
+        expr: Box<Expr>,
+        value: Value,
+    },
 }
 
 /// The top-level macro input: a list of fields inside `{ }`.
@@ -59,6 +91,8 @@ fn parse_fields(input: ParseStream) -> syn::Result<Vec<Field>> {
     while !input.is_empty() {
         if input.peek(Token![if]) {
             fields.push(parse_conditional(input)?);
+        } else if input.peek(Token![@]) {
+            fields.push(parse_merge(input)?);
         } else {
             fields.push(parse_key_value(input)?);
         }
@@ -72,45 +106,56 @@ fn parse_fields(input: ParseStream) -> syn::Result<Vec<Field>> {
 
 fn parse_key_value(input: ParseStream) -> syn::Result<Field> {
     let key: LitStr = input.parse()?;
+
+    if input.peek(Token![?]) {
+        input.parse::<Token![?]>()?;
+        input.parse::<Token![:]>()?;
+        let expr: Expr = input.parse()?;
+        return Ok(Field::OptionalKeyValue(key.value(), expr));
+    }
+
     input.parse::<Token![:]>()?;
     let value = parse_value(input)?;
     Ok(Field::KeyValue(key.value(), value))
 }
 
+fn parse_merge(input: ParseStream) -> syn::Result<Field> {
+    input.parse::<Token![@]>()?;
+    let ident: Ident = input.parse()?;
+    if ident != "merge" {
+        return Err(syn::Error::new(
+            ident.span(),
+            "expected `merge` after `@` in place of a field",
+        ));
+    }
+
+    Ok(Field::Merge(input.parse()?))
+}
+
 fn parse_value(input: ParseStream) -> syn::Result<Value> {
-    // @raw expr
+    // @raw expr / @iter expr / @serde expr
     if input.peek(Token![@]) {
         input.parse::<Token![@]>()?;
         let ident: Ident = input.parse()?;
-        if ident != "raw" {
-            return Err(syn::Error::new(ident.span(), "expected `raw` after `@`"));
-        }
         let expr: Expr = input.parse()?;
-        return Ok(Value::Raw(expr));
-    }
-
-    // String literal
-    if input.peek(LitStr) {
-        let lit: LitStr = input.parse()?;
-        return Ok(Value::LitStr(lit.value()));
-    }
-
-    // Bool literal
-    if input.peek(LitBool) {
-        let lit: LitBool = input.parse()?;
-        return Ok(Value::LitBool(lit.value()));
+        return match ident.to_string().as_str() {
+            "raw" => Ok(Value::Raw(expr)),
+            "iter" => Ok(Value::Iter(expr)),
+            "serde" => Ok(Value::Serde(expr)),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                "expected `raw`, `iter` or `serde` after `@`",
+            )),
+        };
     }
 
-    // Integer literal
-    if input.peek(LitInt) {
-        let lit: LitInt = input.parse()?;
-        return Ok(Value::LitInt(lit.to_string()));
-    }
-
-    // Float literal
-    if input.peek(LitFloat) {
-        let lit: LitFloat = input.parse()?;
-        return Ok(Value::LitFloat(lit.to_string()));
+    // Literals, unless they start a longer expression such as `-1 * x`
+    let fork = input.fork();
+    if let Some(value) = parse_literal(&fork)
+        && ends_value(&fork)
+    {
+        input.advance_to(&fork);
+        return Ok(value);
     }
 
     // Nested object
@@ -121,11 +166,77 @@ fn parse_value(input: ParseStream) -> syn::Result<Value> {
         return Ok(Value::Object(fields));
     }
 
+    // Array
+    if input.peek(token::Bracket) {
+        let content;
+        bracketed!(content in input);
+        let mut elements = Vec::new();
+        while !content.is_empty() {
+            elements.push(parse_element(&content)?);
+            if content.is_empty() {
+                break;
+            }
+            content.parse::<Token![,]>()?;
+        }
+        return Ok(Value::Array(elements));
+    }
+
     // Otherwise treat as a variable/expression
     let expr: Expr = input.parse()?;
     Ok(Value::Variable(expr))
 }
 
+fn parse_element(input: ParseStream) -> syn::Result<Element> {
+    if !input.peek(Token![for]) {
+        return Ok(Element::Value(parse_value(input)?));
+    }
+
+    // for Pat in Expr { value }
+    input.parse::<Token![for]>()?;
+    let pat = Pat::parse_multi_with_leading_vert(input)?;
+    input.parse::<Token![in]>()?;
+    let expr = Expr::parse_without_eager_brace(input)?;
+
+    let content;
+    braced!(content in input);
+    let value = parse_value(&content)?;
+    let _ = content.parse::<Token![,]>();
+    if !content.is_empty() {
+        return Err(content.error("expected a single value in the body of `for`"));
+    }
+
+    Ok(Element::For {
+        pat: Box::new(pat),
+        expr: Box::new(expr),
+        value,
+    })
+}
+
+/// Parses a string, bool, number or `null` literal, where numbers may be negative.
+fn parse_literal(input: ParseStream) -> Option<Value> {
+    let value = match input.parse::<Lit>() {
+        Ok(Lit::Str(lit)) => Value::LitStr(lit.value()),
+        Ok(Lit::Bool(lit)) => Value::LitBool(lit.value()),
+        Ok(Lit::Int(lit)) => Value::LitInt(lit.to_string()),
+        Ok(Lit::Float(lit)) => Value::LitFloat(lit.to_string()),
+        Ok(_) => return None,
+        Err(_) => match input.parse::<Ident>() {
+            Ok(ident) if ident == "null" => Value::Null,
+            _ => return None,
+        },
+    };
+    Some(value)
+}
+
+/// Whether the input is at the end of a value, before the next field or element.
+fn ends_value(input: ParseStream) -> bool {
+    input.is_empty()
+        || input.peek(Token![,])
+        || input.peek(LitStr)
+        || input.peek(Token![if])
+        || input.peek(Token![@])
+}
+
 fn parse_conditional(input: ParseStream) -> syn::Result<Field> {
     input.parse::<Token![if]>()?;
 
@@ -139,8 +250,14 @@ fn parse_conditional(input: ParseStream) -> syn::Result<Field> {
         let content;
         braced!(content in input);
         let fields = parse_fields(&content)?;
-
-        Ok(Field::IfLet { pat, expr, fields })
+        let else_branch = parse_else(input)?;
+
+        Ok(Field::IfLet {
+            pat,
+            expr,
+            fields,
+            else_branch,
+        })
     } else {
         // if Expr { fields }
         let expr = Expr::parse_without_eager_brace(input)?;
@@ -148,24 +265,54 @@ fn parse_conditional(input: ParseStream) -> syn::Result<Field> {
         let content;
         braced!(content in input);
         let fields = parse_fields(&content)?;
+        let else_branch = parse_else(input)?;
 
-        Ok(Field::If { expr, fields })
+        Ok(Field::If {
+            expr,
+            fields,
+            else_branch,
+        })
     }
 }
 
+fn parse_else(input: ParseStream) -> syn::Result<Option<Else>> {
+    if !input.peek(Token![else]) {
+        return Ok(None);
+    }
+    input.parse::<Token![else]>()?;
+
+    if input.peek(Token![if]) {
+        return Ok(Some(Else::If(Box::new(parse_conditional(input)?))));
+    }
+
+    let content;
+    braced!(content in input);
+    Ok(Some(Else::Fields(parse_fields(&content)?)))
+}
+
 /// Check if all fields (recursively) are fully static (no variables, no conditionals, no raw).
 fn is_all_static(fields: &[Field]) -> bool {
     fields.iter().all(|f| match f {
         Field::KeyValue(_, value) => is_value_static(value),
-        Field::IfLet { .. } | Field::If { .. } => false,
+        Field::OptionalKeyValue(..) | Field::IfLet { .. } | Field::If { .. } | Field::Merge(_) => {
+            false
+        }
     })
 }
 
 fn is_value_static(value: &Value) -> bool {
     match value {
-        Value::LitStr(_) | Value::LitBool(_) | Value::LitInt(_) | Value::LitFloat(_) => true,
+        Value::LitStr(_)
+        | Value::LitBool(_)
+        | Value::Null
+        | Value::LitInt(_)
+        | Value::LitFloat(_) => true,
         Value::Object(fields) => is_all_static(fields),
-        Value::Variable(_) | Value::Raw(_) => false,
+        Value::Array(elements) => elements.iter().all(|element| match element {
+            Element::Value(value) => is_value_static(value),
+            Element::For { .. } => false,
+        }),
+        Value::Variable(_) | Value::Raw(_) | Value::Iter(_) | Value::Serde(_) => false,
     }
 }
 
@@ -190,9 +337,22 @@ fn static_value_str(value: &Value) -> String {
     match value {
         Value::LitStr(s) => format!("\"{}\"", json_escape(s)),
         Value::LitBool(b) => b.to_string(),
+        Value::Null => "null".to_owned(),
         Value::LitInt(n) => n.clone(),
         Value::LitFloat(n) => n.clone(),
         Value::Object(fields) => static_object_str(fields),
+        Value::Array(elements) => {
+            let values = elements
+                .iter()
+                .map(|element| match element {
+                    Element::Value(value) => static_value_str(value),
+                    Element::For { .. } => {
+                        unreachable!("is_value_static should have returned false")
+                    }
+                })
+                .collect::<Vec<_>>();
+            format!("[{}]", values.join(","))
+        }
         _ => unreachable!("is_value_static should have returned false"),
     }
 }
@@ -215,20 +375,53 @@ fn json_escape(s: &str) -> String {
     out
 }
 
+/// Whether a comma is needed before the next field or array element.
+#[derive(Clone, Copy)]
+enum CommaState {
+    /// No field has been written yet.
+    First,
+    /// At least one field has been written.
+    NotFirst,
+    /// Only known at runtime, because the preceding fields were conditional
+    /// (or the preceding elements were written by a loop).
+    Unknown,
+}
+
+impl CommaState {
+    /// Generate the comma (if any) that precedes a field which is about to be written.
+    fn gen_comma(&mut self) -> TokenStream2 {
+        let comma = match self {
+            CommaState::First => quote! {},
+            CommaState::NotFirst => quote! { __json_buf.push(','); },
+            // Nothing but an opening bracket ends the buffer before the first field or element.
+            CommaState::Unknown => quote! {
+                if !__json_buf.ends_with(['{', '[']) {
+                    __json_buf.push(',');
+                }
+            },
+        };
+        *self = CommaState::NotFirst;
+        comma
+    }
+
+    /// The state after a conditional, which may or may not have written a field.
+    fn after_conditional(self) -> Self {
+        match self {
+            CommaState::NotFirst => CommaState::NotFirst,
+            CommaState::First | CommaState::Unknown => CommaState::Unknown,
+        }
+    }
+}
+
 /// Generate code that writes fields into a `String` variable named `__json_buf`.
-fn gen_dynamic_fields(fields: &[Field], is_first_field: &mut bool) -> TokenStream2 {
+fn gen_dynamic_fields(fields: &[Field], state: &mut CommaState) -> TokenStream2 {
     let mut stmts = Vec::new();
 
     for field in fields {
         match field {
             Field::KeyValue(key, value) => {
                 let escaped_key = json_escape(key);
-                let comma = if *is_first_field {
-                    *is_first_field = false;
-                    quote! {}
-                } else {
-                    quote! { __json_buf.push(','); }
-                };
+                let comma = state.gen_comma();
                 let write_value = gen_value_write(value);
                 stmts.push(quote! {
                     #comma
@@ -236,45 +429,39 @@ fn gen_dynamic_fields(fields: &[Field], is_first_field: &mut bool) -> TokenStrea
                     #write_value
                 });
             }
-            Field::IfLet { pat, expr, fields: inner } => {
-                // For conditionals, we must handle comma dynamically since we don't
-                // know at compile time whether preceding fields have been written.
-                let needs_comma = !*is_first_field;
-                *is_first_field = false;
-                let inner_code = gen_conditional_fields(inner);
-                if needs_comma {
-                    stmts.push(quote! {
-                        if let #pat = #expr {
-                            __json_buf.push(',');
-                            #inner_code
-                        }
-                    });
-                } else {
-                    stmts.push(quote! {
-                        if let #pat = #expr {
-                            #inner_code
-                        }
-                    });
-                }
+            Field::OptionalKeyValue(key, expr) => {
+                let escaped_key = json_escape(key);
+                let mut inner_state = *state;
+                let comma = inner_state.gen_comma();
+                *state = state.after_conditional();
+                stmts.push(quote! {
+                    if let ::core::option::Option::Some(__json_value) = &(#expr) {
+                        #comma
+                        __json_buf.push_str(concat!("\"", #escaped_key, "\":"));
+                        ::anyml_core::json::JsonValue::write_json(__json_value, &mut __json_buf);
+                    }
+                });
             }
-            Field::If { expr, fields: inner } => {
-                let needs_comma = !*is_first_field;
-                *is_first_field = false;
-                let inner_code = gen_conditional_fields(inner);
-                if needs_comma {
-                    stmts.push(quote! {
-                        if #expr {
-                            __json_buf.push(',');
-                            #inner_code
-                        }
-                    });
-                } else {
-                    stmts.push(quote! {
-                        if #expr {
-                            #inner_code
-                        }
-                    });
-                }
+            Field::IfLet { .. } | Field::If { .. } => {
+                let (code, state_after) = gen_conditional(field, *state);
+                *state = state_after;
+                stmts.push(code);
+            }
+            Field::Merge(expr) => {
+                // Any pair may be the first field written, so commas are decided at runtime.
+                let comma = CommaState::Unknown.gen_comma();
+                *state = state.after_conditional();
+                stmts.push(quote! {
+                    for (__json_key, __json_value) in ::core::iter::IntoIterator::into_iter(#expr) {
+                        #comma
+                        ::anyml_core::json::JsonValue::write_json(
+                            &::core::convert::AsRef::<str>::as_ref(&__json_key),
+                            &mut __json_buf,
+                        );
+                        __json_buf.push(':');
+                        ::anyml_core::json::JsonValue::write_json(&__json_value, &mut __json_buf);
+                    }
+                });
             }
         }
     }
@@ -282,11 +469,57 @@ fn gen_dynamic_fields(fields: &[Field], is_first_field: &mut bool) -> TokenStrea
     quote! { #(#stmts)* }
 }
 
-/// Generate writes for fields inside a conditional (always comma-separated from each other,
-/// but the first one doesn't need a leading comma since the caller handles it).
-fn gen_conditional_fields(fields: &[Field]) -> TokenStream2 {
-    let mut first = true;
-    gen_dynamic_fields(fields, &mut first)
+/// Generate an `if` / `if let` expression (including any `else` branches) whose
+/// branches write their fields, returning it along with the comma state afterwards.
+///
+/// For conditionals, we must handle commas dynamically since we don't
+/// know at compile time which branch (if any) will be taken.
+fn gen_conditional(field: &Field, state: CommaState) -> (TokenStream2, CommaState) {
+    let (head, fields, else_branch) = match field {
+        Field::IfLet {
+            pat,
+            expr,
+            fields,
+            else_branch,
+        } => (quote! { if let #pat = #expr }, fields, else_branch),
+        Field::If {
+            expr,
+            fields,
+            else_branch,
+        } => (quote! { if #expr }, fields, else_branch),
+        _ => unreachable!("only conditionals are passed to gen_conditional"),
+    };
+
+    let mut branch_state = state;
+    let inner_code = gen_dynamic_fields(fields, &mut branch_state);
+
+    let (else_code, else_state) = match else_branch {
+        None => (quote! {}, state.after_conditional()),
+        Some(Else::If(nested)) => {
+            let (nested_code, nested_state) = gen_conditional(nested, state);
+            (quote! { else #nested_code }, nested_state)
+        }
+        Some(Else::Fields(fields)) => {
+            let mut else_state = state;
+            let else_code = gen_dynamic_fields(fields, &mut else_state);
+            (quote! { else { #else_code } }, else_state)
+        }
+    };
+
+    // A field is only known to have been written if every branch writes one.
+    let state_after = match (branch_state, else_state) {
+        (CommaState::NotFirst, CommaState::NotFirst) => CommaState::NotFirst,
+        _ => state.after_conditional(),
+    };
+
+    (
+        quote! {
+            #head {
+                #inner_code
+            } #else_code
+        },
+        state_after,
+    )
 }
 
 /// Generate code that writes a value into `__json_buf`.
@@ -300,6 +533,9 @@ fn gen_value_write(value: &Value) -> TokenStream2 {
             let s = if *b { "true" } else { "false" };
             quote! { __json_buf.push_str(#s); }
         }
+        Value::Null => {
+            quote! { __json_buf.push_str("null"); }
+        }
         Value::LitInt(n) => {
             let s = n.as_str();
             quote! { __json_buf.push_str(#s); }
@@ -317,8 +553,7 @@ fn gen_value_write(value: &Value) -> TokenStream2 {
                 let s = static_object_str(fields);
                 quote! { __json_buf.push_str(#s); }
             } else {
-                let mut first = true;
-                let inner = gen_dynamic_fields(fields, &mut first);
+                let inner = gen_dynamic_fields(fields, &mut CommaState::First);
                 quote! {
                     __json_buf.push('{');
                     #inner
@@ -326,9 +561,67 @@ fn gen_value_write(value: &Value) -> TokenStream2 {
                 }
             }
         }
+        Value::Array(elements) => {
+            if is_value_static(value) {
+                let s = static_value_str(value);
+                quote! { __json_buf.push_str(#s); }
+            } else {
+                let mut state = CommaState::First;
+                let writes: Vec<_> = elements
+                    .iter()
+                    .map(|element| match element {
+                        Element::Value(value) => {
+                            let comma = state.gen_comma();
+                            let write_value = gen_value_write(value);
+                            quote! {
+                                #comma
+                                #write_value
+                            }
+                        }
+                        Element::For { pat, expr, value } => {
+                            // The loop may write nothing, so commas are decided at runtime.
+                            let comma = CommaState::Unknown.gen_comma();
+                            state = state.after_conditional();
+                            let write_value = gen_value_write(value);
+                            quote! {
+                                for #pat in #expr {
+                                    #comma
+                                    #write_value
+                                }
+                            }
+                        }
+                    })
+                    .collect();
+                quote! {
+                    __json_buf.push('[');
+                    #(#writes)*
+                    __json_buf.push(']');
+                }
+            }
+        }
         Value::Raw(expr) => {
             quote! { __json_buf.push_str(&(#expr)); }
         }
+        Value::Iter(expr) => {
+            quote! {
+                __json_buf.push('[');
+                for (__json_idx, __json_item) in ::core::iter::IntoIterator::into_iter(#expr).enumerate() {
+                    if __json_idx != 0 {
+                        __json_buf.push(',');
+                    }
+                    ::anyml_core::json::JsonValue::write_json(&__json_item, &mut __json_buf);
+                }
+                __json_buf.push(']');
+            }
+        }
+        Value::Serde(expr) => {
+            quote! {
+                ::anyml_core::json::JsonValue::write_json(
+                    &::anyml_core::json::Serde(&(#expr)),
+                    &mut __json_buf,
+                );
+            }
+        }
     }
 }
 
@@ -343,8 +636,7 @@ pub fn json_string(input: TokenStream) -> TokenStream {
     }
 
     // Dynamic — produce code that builds a String
-    let mut first = true;
-    let body = gen_dynamic_fields(&parsed.fields, &mut first);
+    let body = gen_dynamic_fields(&parsed.fields, &mut CommaState::First);
 
     let expanded = quote! {
         {
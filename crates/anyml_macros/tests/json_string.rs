@@ -0,0 +1,354 @@
+use anyml_macros::json_string;
+use serde_json::{Value, json};
+
+fn parse(json: &str) -> Value {
+    serde_json::from_str(json).unwrap_or_else(|err| panic!("invalid JSON {json:?}: {err}"))
+}
+
+#[test]
+fn static_object() {
+    let body: &str = json_string! {
+        "type": "enabled",
+        "nested": { "a": 1, "b": 1.5, "c": true }
+    };
+
+    assert_eq!(
+        parse(body),
+        json!({ "type": "enabled", "nested": { "a": 1, "b": 1.5, "c": true } })
+    );
+}
+
+#[test]
+fn variables_and_raw() {
+    let model = "gpt-4";
+    let max_tokens: usize = 16;
+    let messages = r#"[{"role":"user","content":"Hi \"there\""}]"#;
+
+    let body: String = json_string! {
+        "model": model,
+        "messages": @raw messages,
+        "max_tokens": max_tokens
+    };
+
+    assert_eq!(
+        parse(&body),
+        json!({
+            "model": "gpt-4",
+            "messages": [{ "role": "user", "content": "Hi \"there\"" }],
+            "max_tokens": 16
+        })
+    );
+}
+
+#[test]
+fn leading_conditional_not_taken() {
+    let effort: Option<&str> = None;
+
+    let body: String = json_string! {
+        if let Some(effort) = effort {
+            "effort": effort
+        }
+        "stream": true,
+        if false {
+            "unused": 1
+        }
+        "max_tokens": 10
+    };
+
+    assert_eq!(parse(&body), json!({ "stream": true, "max_tokens": 10 }));
+}
+
+#[test]
+fn option_writes_null() {
+    let max_tokens: Option<usize> = None;
+    let effort = Some("high");
+
+    let body: String = json_string! {
+        "max_tokens": max_tokens,
+        "effort": effort
+    };
+
+    assert_eq!(
+        parse(&body),
+        json!({ "max_tokens": null, "effort": "high" })
+    );
+}
+
+#[test]
+fn optional_key_is_omitted_for_none() {
+    let max_tokens: Option<usize> = None;
+    let effort = Some("high".to_owned());
+
+    let body: String = json_string! {
+        "max_tokens"?: max_tokens,
+        "model": "gpt-4",
+        "effort"?: effort,
+        "nested": { "temperature"?: None::<f64> }
+    };
+
+    assert_eq!(
+        parse(&body),
+        json!({ "model": "gpt-4", "effort": "high", "nested": {} })
+    );
+}
+
+#[test]
+fn static_array() {
+    let body: &str = json_string! {
+        "stop": ["a", "b"],
+        "empty": [],
+        "nested": [[1, 2], { "a": true }]
+    };
+
+    assert_eq!(
+        parse(body),
+        json!({ "stop": ["a", "b"], "empty": [], "nested": [[1, 2], { "a": true }] })
+    );
+}
+
+#[test]
+fn null_and_negative_literals() {
+    let body: &str = json_string! {
+        "seed": null,
+        "offset": -1,
+        "penalty": -0.5,
+        "list": [null, -2]
+    };
+
+    assert_eq!(
+        parse(body),
+        json!({ "seed": null, "offset": -1, "penalty": -0.5, "list": [null, -2] })
+    );
+}
+
+#[test]
+fn negative_expressions() {
+    let x: i64 = 3;
+
+    let body: String = json_string! {
+        "product": -2 * x,
+        "negated": -x
+    };
+
+    assert_eq!(parse(&body), json!({ "product": -6, "negated": -3 }));
+}
+
+#[test]
+fn dynamic_array() {
+    let stop = "\n\n";
+    let raw = r#"{"x":1}"#;
+
+    let body: String = json_string! {
+        "stop": ["a", stop, { "raw": @raw raw }]
+    };
+
+    assert_eq!(
+        parse(&body),
+        json!({ "stop": ["a", "\n\n", { "raw": { "x": 1 } }] })
+    );
+}
+
+#[test]
+fn iter_array() {
+    let names = vec!["a".to_owned(), "b".to_owned()];
+    let empty: [usize; 0] = [];
+
+    let body: String = json_string! {
+        "names": @iter &names,
+        "lengths": @iter names.iter().map(|name| name.len()),
+        "empty": @iter empty
+    };
+
+    assert_eq!(
+        parse(&body),
+        json!({ "names": ["a", "b"], "lengths": [1, 1], "empty": [] })
+    );
+}
+
+#[test]
+fn for_arrays() {
+    struct Turn {
+        role: &'static str,
+        content: String,
+    }
+
+    let turns = [
+        Turn {
+            role: "user",
+            content: "Hi".to_owned(),
+        },
+        Turn {
+            role: "assistant",
+            content: "Hello!".to_owned(),
+        },
+    ];
+    let none: [Turn; 0] = [];
+
+    let body: String = json_string! {
+        "messages": [for turn in &turns { { "role": turn.role, "content": turn.content } }],
+        "mixed": ["system", for turn in &none { turn.role }, for turn in &turns { turn.role }, "end"],
+        "pairs": [for (idx, turn) in turns.iter().enumerate() { [idx, turn.role] }],
+        "empty": [for turn in &none { turn.role }]
+    };
+
+    assert_eq!(
+        parse(&body),
+        json!({
+            "messages": [
+                { "role": "user", "content": "Hi" },
+                { "role": "assistant", "content": "Hello!" }
+            ],
+            "mixed": ["system", "user", "assistant", "end"],
+            "pairs": [[0, "user"], [1, "assistant"]],
+            "empty": []
+        })
+    );
+}
+
+fn thinking_body(effort: Option<&str>, budget: Option<usize>) -> String {
+    json_string! {
+        if let Some(effort) = effort {
+            "thinking": { "type": "adaptive", "effort": effort }
+        } else if let Some(budget) = budget {
+            "thinking": { "type": "enabled", "budget_tokens": budget }
+        } else if budget.is_none() && effort.is_none() {
+            "thinking": { "type": "disabled" }
+        }
+        "model": "claude"
+    }
+}
+
+#[test]
+fn else_if_branches() {
+    assert_eq!(
+        parse(&thinking_body(Some("high"), Some(10))),
+        json!({ "thinking": { "type": "adaptive", "effort": "high" }, "model": "claude" })
+    );
+    assert_eq!(
+        parse(&thinking_body(None, Some(10))),
+        json!({ "thinking": { "type": "enabled", "budget_tokens": 10 }, "model": "claude" })
+    );
+    assert_eq!(
+        parse(&thinking_body(None, None)),
+        json!({ "thinking": { "type": "disabled" }, "model": "claude" })
+    );
+}
+
+#[test]
+fn else_branch() {
+    for stream in [true, false] {
+        let body: String = json_string! {
+            if stream {
+                "stream": true,
+                "stream_options": { "include_usage": true }
+            } else {
+                "stream": false
+            }
+            "model": "gpt-4"
+        };
+
+        let expected = if stream {
+            json!({ "stream": true, "stream_options": { "include_usage": true }, "model": "gpt-4" })
+        } else {
+            json!({ "stream": false, "model": "gpt-4" })
+        };
+        assert_eq!(parse(&body), expected);
+    }
+}
+
+#[test]
+fn serde_values() {
+    #[derive(serde::Serialize)]
+    struct Tool<'a> {
+        name: &'a str,
+        parameters: Value,
+    }
+
+    let tool = Tool {
+        name: "get_weather",
+        parameters: json!({ "type": "object" }),
+    };
+    let tools = vec![tool];
+
+    let body: String = json_string! {
+        "tools": @serde tools,
+        "first": @serde &tools[0].name,
+        "nested": { "schema": @serde tools[0].parameters }
+    };
+
+    assert_eq!(
+        parse(&body),
+        json!({
+            "tools": [{ "name": "get_weather", "parameters": { "type": "object" } }],
+            "first": "get_weather",
+            "nested": { "schema": { "type": "object" } }
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "failed to serialize a value as JSON")]
+fn serde_panics_on_unrepresentable_values_in_debug() {
+    let scores = std::collections::BTreeMap::from([(vec![1, 2], 3)]);
+
+    let _: String = json_string! {
+        "scores": @serde scores
+    };
+}
+
+#[test]
+fn collections() {
+    use std::collections::{BTreeMap, HashMap};
+
+    let stop = vec!["\n", "END"];
+    let weights = [1.5, 2.5];
+    let empty: &[usize] = &[];
+    let headers = BTreeMap::from([("b", "2"), ("a \"quoted\"", "1")]);
+    let counts = HashMap::from([("tokens".to_owned(), vec![1_usize, 2])]);
+
+    let body: String = json_string! {
+        "stop": stop,
+        "weights": weights,
+        "empty": empty,
+        "headers": headers,
+        "counts": counts
+    };
+
+    assert_eq!(
+        parse(&body),
+        json!({
+            "stop": ["\n", "END"],
+            "weights": [1.5, 2.5],
+            "empty": [],
+            "headers": { "a \"quoted\"": "1", "b": "2" },
+            "counts": { "tokens": [1, 2] }
+        })
+    );
+}
+
+#[test]
+fn merged_fields() {
+    use std::collections::BTreeMap;
+
+    let extra = BTreeMap::from([("keep_alive", json!("5m")), ("top_k", json!(40))]);
+    let empty: BTreeMap<String, Value> = BTreeMap::new();
+
+    let body: String = json_string! {
+        "model": "llama3",
+        @merge &extra
+    };
+    assert_eq!(
+        parse(&body),
+        json!({ "model": "llama3", "keep_alive": "5m", "top_k": 40 })
+    );
+
+    let body: String = json_string! {
+        @merge &empty,
+        @merge &extra,
+        "stream": false
+    };
+    assert_eq!(
+        parse(&body),
+        json!({ "keep_alive": "5m", "top_k": 40, "stream": false })
+    );
+}
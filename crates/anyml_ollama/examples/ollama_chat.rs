@@ -62,6 +62,7 @@ async fn stream_response(mut response: ChatResponse<'_>) {
                 out.write_all(text.as_bytes()).await.unwrap();
                 out.flush().await.unwrap();
             }
+            _ => {}
         }
     }
 }
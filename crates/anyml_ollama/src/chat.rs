@@ -1,43 +1,119 @@
 use anyhow::anyhow;
 use anyhttp::HttpClient;
+use anyml_core::decode::ndjson_lines;
+use anyml_core::models::{ContentPart, Message, MessageRole};
 use anyml_core::providers::chat::{
-    ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError, Thinking,
+    ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError, Messages,
+    ResponseFormat, Thinking, ToolCallDelta, ToolChoice, Usage,
 };
+use anyml_core::providers::openai_compat;
 use anyml_macros::json_string;
 use bytes::Bytes;
 use futures::StreamExt;
 use http::Request;
 use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::OllamaProvider;
+use crate::{OllamaChatExt, OllamaProvider};
 
-#[async_trait::async_trait]
+/// Counts the tool calls received, to give them IDs, which Ollama doesn't.
+static TOOL_CALLS: AtomicU64 = AtomicU64::new(0);
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl<C: HttpClient> ChatProvider for OllamaProvider<C> {
     async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
-        let messages_json = options.messages.to_json();
+        let options = options.or_default_model(self.default_model.as_deref());
+        options.with_timeouts(self.send_chat(&options)).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.default_model.as_deref()
+    }
+}
 
-        let body: String = match &options.thinking {
+impl<C: HttpClient> OllamaProvider<C> {
+    async fn send_chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse<'_>, ChatError> {
+        // Without messages, Ollama loads the model into memory, or unloads it if
+        // `keep_alive` is 0.
+        options.validate_allowing_no_messages()?;
+        if options.logprobs {
+            return Err(ChatError::Unsupported {
+                feature: "logprobs",
+            });
+        }
+        if options.audio_output.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "audio_output",
+            });
+        }
+        if !options.server_tools.is_empty() {
+            return Err(ChatError::Unsupported {
+                feature: "server_tools",
+            });
+        }
+        // Ollama always leaves it to the model whether to call tools, and how many.
+        if !matches!(options.tool_choice, None | Some(ToolChoice::Auto)) {
+            return Err(ChatError::Unsupported {
+                feature: "tool_choice",
+            });
+        }
+        if options.parallel_tool_calls == Some(false) {
+            return Err(ChatError::Unsupported {
+                feature: "parallel_tool_calls",
+            });
+        }
+        // Ollama doesn't authenticate requests.
+        if options.overrides.api_key.is_some() {
+            return Err(ChatError::Unsupported { feature: "api_key" });
+        }
+
+        let messages_json = messages_json(&options.messages, options.system, options.prefill)?;
+        let tools_json = openai_compat::tools_json(options.tools);
+
+        let default_ext = OllamaChatExt::default();
+        let ext = options.extensions.get().unwrap_or(&default_ext);
+        ext.validate()?;
+
+        let mut model_options = self.options.clone();
+        model_options.overlay(&ext.options);
+        if let Some(max_tokens) = options.max_tokens {
+            model_options.num_predict.get_or_insert(max_tokens as i64);
+        }
+
+        let body: String = json_string! {
+            "model": options.model,
+            "messages": @raw messages_json,
+            "stream": options.stream,
+            if let Some(tools) = &tools_json {
+                "tools": @raw tools
+            }
             // GPT-OSS requires think to be a string level, not a boolean.
-            Some(Thinking::Effort(level)) => json_string! {
-                "model": options.model,
-                "messages": @raw messages_json,
-                "stream": options.stream,
+            if let Some(Thinking::Effort(level)) = &options.thinking {
                 "think": level
-            },
-            Some(_) => json_string! {
-                "model": options.model,
-                "messages": @raw messages_json,
-                "stream": options.stream,
+            } else if options.thinking.is_some() {
                 "think": true
-            },
-            None => json_string! {
-                "model": options.model,
-                "messages": @raw messages_json,
-                "stream": options.stream
-            },
+            }
+            if let Some(ResponseFormat::Json) = &options.response_format {
+                "format": "json"
+            } else if let Some(ResponseFormat::JsonSchema(schema)) = &options.response_format {
+                "format": @serde schema
+            }
+            "options": @serde model_options,
+            if let Some(keep_alive) = ext.keep_alive.or(self.keep_alive) {
+                "keep_alive": @serde keep_alive
+            }
+            @merge &options.extra
         };
 
-        let request = Request::post(format!("{}/api/chat", self.url))
+        let url = options.overrides.url_or(&self.url);
+        let request = self
+            .headers
+            .apply(Request::post(format!("{url}{}", self.chat_path)));
+        let request = options
+            .overrides
+            .headers
+            .apply(request)
             .body(body.into_bytes())
             .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
 
@@ -47,24 +123,36 @@ impl<C: HttpClient> ChatProvider for OllamaProvider<C> {
             .await
             .map_err(|this| ChatError::ResponseFetchFailed(this))?;
 
-        if !response.status().is_success() {
+        let status = response.status();
+        if !status.is_success() {
             let err_body = response
                 .bytes()
                 .await
                 .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
 
-            return Err(ChatError::RequestError(anyhow!(
-                String::from_utf8_lossy(&err_body).into_owned()
+            return Err(ChatError::from_error_response(status, &err_body));
+        }
+
+        let thinking_enabled = options.thinking.is_some();
+        let include_raw = options.include_raw;
+
+        if !options.stream {
+            let body = response
+                .bytes()
+                .await
+                .map_err(ChatError::ResponseFetchFailed)?;
+            let chunks = parse_reply(&body, thinking_enabled, include_raw)
+                .map_err(ChatError::RequestError)?;
+            return Ok(ChatResponse::new(futures::stream::iter(
+                chunks.into_iter().map(Ok),
             )));
         }
 
         let stream = response.bytes_stream();
-
-        let thinking_enabled = options.thinking.is_some();
         Ok(ChatResponse::new(
-            stream
-                .scan(false, move |in_thinking, chunk| {
-                    let chunks = parse_chunk(&chunk, in_thinking, thinking_enabled);
+            ndjson_lines(stream)
+                .scan(StreamState::default(), move |state, line| {
+                    let chunks = parse_line(&line, state, thinking_enabled, include_raw);
                     futures::future::ready(Some(chunks))
                 })
                 .flat_map(futures::stream::iter),
@@ -72,53 +160,232 @@ impl<C: HttpClient> ChatProvider for OllamaProvider<C> {
     }
 }
 
-fn parse_chunk(
-    chunk: &Result<bytes::Bytes, anyhow::Error>,
-    in_thinking: &mut bool,
+/// Serializes messages into Ollama's format, where tool call arguments are objects
+/// and tool results are identified by the name of the tool that produced them.
+fn messages_json(
+    messages: &Messages<'_>,
+    system: Option<&str>,
+    prefill: Option<&str>,
+) -> Result<String, ChatError> {
+    let Messages::Raw(messages) = messages else {
+        return Ok(messages.to_json_with(system, prefill));
+    };
+
+    let system =
+        system.map(|system| Ok(serde_json::json!({ "role": "system", "content": system })));
+    // Ollama continues a trailing assistant message rather than starting a new one.
+    let prefill =
+        prefill.map(|prefill| Ok(serde_json::json!({ "role": "assistant", "content": prefill })));
+    let messages = system
+        .into_iter()
+        .chain(
+            messages
+                .iter()
+                .map(|message| message_json(message, messages)),
+        )
+        .chain(prefill)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+    Ok(serde_json::Value::from(messages).to_string())
+}
+
+fn message_json(
+    message: &Message,
+    history: &[Message],
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut json = serde_json::json!({ "role": message.role, "content": message.content });
+
+    // Vision models take images as a separate array of base64 strings.
+    let images = message
+        .parts
+        .iter()
+        .filter_map(|part| match part {
+            ContentPart::Image(image) => Some(image.data.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !images.is_empty() {
+        json["images"] = images.into();
+    }
+
+    match message.role {
+        MessageRole::Tool => {
+            let tool_name = history
+                .iter()
+                .flat_map(|message| &message.tool_calls)
+                .find(|tool_call| Some(&tool_call.id) == message.tool_call_id.as_ref())
+                .map(|tool_call| tool_call.name.as_str());
+
+            if let Some(tool_name) = tool_name {
+                json["tool_name"] = tool_name.into();
+            }
+        }
+        MessageRole::Assistant if !message.tool_calls.is_empty() => {
+            let tool_calls = message
+                .tool_calls
+                .iter()
+                .map(|tool_call| {
+                    Ok(serde_json::json!({
+                        "function": {
+                            "name": tool_call.name,
+                            "arguments": tool_call.arguments_json()?,
+                        }
+                    }))
+                })
+                .collect::<Result<Vec<_>, serde_json::Error>>()?;
+
+            json["tool_calls"] = tool_calls.into();
+        }
+        _ => {}
+    }
+
+    Ok(json)
+}
+
+fn parse_line(
+    line: &Result<String, anyhow::Error>,
+    state: &mut StreamState,
     thinking_enabled: bool,
+    include_raw: bool,
 ) -> Vec<Result<ChatChunk, ChatStreamError>> {
-    let chunk = match chunk {
-        Ok(chunk) => chunk,
+    let line = match line {
+        Ok(line) => line,
         Err(err) => return vec![Err(ChatStreamError::ParseError(anyhow!("{err}")))],
     };
 
-    let response: OllamaChunkResponse = match serde_json::from_slice(chunk) {
+    let mut results = Vec::new();
+    if include_raw {
+        results.push(Ok(ChatChunk::Raw {
+            provider_event: line.clone(),
+        }));
+    }
+
+    let response: OllamaChunkResponse = match serde_json::from_str(line) {
         Ok(r) => r,
-        Err(e) => return vec![Err(ChatStreamError::ParseError(anyhow::Error::new(e)))],
+        Err(e) => {
+            results.push(Err(ChatStreamError::ParseError(anyhow::Error::new(e))));
+            return results;
+        }
     };
 
+    let usage = usage(&response);
+    results.extend(
+        message_chunks(response.message, state, thinking_enabled)
+            .into_iter()
+            .map(Ok),
+    );
+    results.extend(usage.map(|usage| Ok(ChatChunk::Usage(usage))));
+    results
+}
+
+/// Parses the single object Ollama replies with when not streaming.
+fn parse_reply(
+    body: &[u8],
+    thinking_enabled: bool,
+    include_raw: bool,
+) -> Result<Vec<ChatChunk>, anyhow::Error> {
+    let reply: OllamaChunkResponse = serde_json::from_slice(body)?;
+
+    let mut chunks = Vec::new();
+    if include_raw {
+        chunks.push(ChatChunk::Raw {
+            provider_event: String::from_utf8_lossy(body).into_owned(),
+        });
+    }
+    let usage = usage(&reply);
+    chunks.extend(message_chunks(
+        reply.message,
+        &mut StreamState::default(),
+        thinking_enabled,
+    ));
+    chunks.extend(usage.map(ChatChunk::Usage));
+
+    Ok(chunks)
+}
+
+/// The token counts sent with the last line of a stream, or with the whole reply.
+fn usage(response: &OllamaChunkResponse) -> Option<Usage> {
+    if response.prompt_eval_count.is_none() && response.eval_count.is_none() {
+        return None;
+    }
+
+    Some(Usage {
+        input_tokens: response.prompt_eval_count,
+        output_tokens: response.eval_count,
+        cached_input_tokens: None,
+        timing: None,
+    })
+}
+
+/// What is carried over between the lines of a stream.
+#[derive(Default)]
+struct StreamState {
+    /// Whether a `<think>` tag was opened and not yet closed.
+    in_thinking: bool,
+    /// How many tool calls were received, which index the next one.
+    tool_calls: usize,
+}
+
+fn message_chunks(
+    mut message: OllamaMessage,
+    state: &mut StreamState,
+    thinking_enabled: bool,
+) -> Vec<ChatChunk> {
+    let tool_calls = std::mem::take(&mut message.tool_calls);
+    let mut chunks = text_chunks(message, &mut state.in_thinking, thinking_enabled);
+
+    // Ollama sends each tool call whole, with its arguments as an object.
+    for tool_call in tool_calls {
+        let id = TOOL_CALLS.fetch_add(1, Ordering::Relaxed);
+        chunks.push(ChatChunk::ToolCallDelta(ToolCallDelta {
+            index: state.tool_calls,
+            id: Some(format!("call_{id}")),
+            name: Some(tool_call.function.name),
+            arguments: tool_call.function.arguments.to_string(),
+        }));
+        state.tool_calls += 1;
+    }
+    chunks
+}
+
+fn text_chunks(
+    message: OllamaMessage,
+    in_thinking: &mut bool,
+    thinking_enabled: bool,
+) -> Vec<ChatChunk> {
+    let mut chunks = Vec::new();
+
     // When thinking is not enabled, pass content through without parsing.
     if !thinking_enabled {
-        if !response.message.content.is_empty() {
-            return vec![Ok(ChatChunk::Content(response.message.content))];
+        if !message.content.is_empty() {
+            chunks.push(ChatChunk::Content(message.content.into()));
         }
-        return vec![];
+        return chunks;
     }
 
-    let mut results = Vec::new();
-
     // Prefer the structured `thinking` field (present when Ollama is called with "think": true).
-    if let Some(ref thinking) = response.message.thinking {
+    if let Some(ref thinking) = message.thinking {
         if !thinking.is_empty() {
-            results.push(Ok(ChatChunk::Thinking(thinking.clone())));
-            if !response.message.content.is_empty() {
-                results.push(Ok(ChatChunk::Content(response.message.content)));
+            chunks.push(ChatChunk::Thinking(thinking.as_str().into()));
+            if !message.content.is_empty() {
+                chunks.push(ChatChunk::Content(message.content.into()));
             }
-            return results;
+            return chunks;
         }
     }
 
     // Fallback: parse <think>...</think> tags from content.
-    let (content, thinking) = split_thinking(&response.message.content, in_thinking);
+    let (content, thinking) = split_thinking(&message.content, in_thinking);
     if let Some(thinking) = thinking {
         if !thinking.is_empty() {
-            results.push(Ok(ChatChunk::Thinking(thinking)));
+            chunks.push(ChatChunk::Thinking(thinking.into()));
         }
     }
     if !content.is_empty() {
-        results.push(Ok(ChatChunk::Content(content)));
+        chunks.push(ChatChunk::Content(content.into()));
     }
-    results
+    chunks
 }
 
 /// Separates `<think>...</think>` tagged content from regular content.
@@ -157,6 +424,11 @@ fn split_thinking(raw: &str, in_thinking: &mut bool) -> (String, Option<String>)
 #[derive(Deserialize)]
 struct OllamaChunkResponse {
     message: OllamaMessage,
+    /// Sent with the last line of a stream, or with the whole reply.
+    #[serde(default)]
+    prompt_eval_count: Option<usize>,
+    #[serde(default)]
+    eval_count: Option<usize>,
 }
 
 #[derive(Deserialize)]
@@ -165,12 +437,27 @@ struct OllamaMessage {
     content: String,
     #[serde(default)]
     thinking: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+#[derive(Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunction,
+}
+
+#[derive(Deserialize)]
+struct OllamaFunction {
+    name: String,
+    arguments: serde_json::Value,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{KeepAlive, OllamaOptions};
     use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::models::{Image, Tool, ToolCall};
     use anyml_core::providers::chat::Thinking;
     use http::StatusCode;
 
@@ -191,6 +478,128 @@ mod tests {
         assert!(matches!(chunk, ChatChunk::Content(ref s) if s == "Hello!"));
     }
 
+    #[tokio::test]
+    async fn test_chat_multiple_lines_per_chunk() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n\
+             {\"message\":{\"role\":\"assistant\",\"content\":\"lo!\"},\"done\":false}\n\
+             {\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}\n",
+        ));
+
+        let provider = OllamaProvider::new(client);
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama2").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Hello!");
+    }
+
+    #[tokio::test]
+    async fn test_chat_streamed_usage() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hello!\"},\"done\":false}\n\
+             {\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"prompt_eval_count\":12,\"eval_count\":4}\n",
+        ));
+
+        let provider = OllamaProvider::new(client);
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama2").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = response.next().await {
+            chunks.push(chunk.unwrap());
+        }
+
+        assert!(matches!(&chunks[..], [
+            ChatChunk::Content(content),
+            ChatChunk::Usage(usage),
+        ] if content == "Hello!"
+            && usage.input_tokens == Some(12)
+            && usage.output_tokens == Some(4)));
+    }
+
+    #[tokio::test]
+    async fn test_chat_without_streaming() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{
+                "model": "qwen3",
+                "message": {"role": "assistant", "content": "Hello!", "thinking": "A greeting."},
+                "done": true,
+                "done_reason": "stop",
+                "prompt_eval_count": 12,
+                "eval_count": 4
+            }"#,
+        ));
+
+        let provider = OllamaProvider::new(client.clone());
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("qwen3")
+            .messages(messages)
+            .thinking(Thinking::enabled())
+            .stream(false);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = response.next().await {
+            chunks.push(chunk.unwrap());
+        }
+
+        assert!(matches!(&chunks[..], [
+            ChatChunk::Thinking(thinking),
+            ChatChunk::Content(content),
+            ChatChunk::Usage(usage),
+        ] if thinking == "A greeting."
+            && content == "Hello!"
+            && usage.input_tokens == Some(12)
+            && usage.output_tokens == Some(4)));
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["stream"], false);
+    }
+
+    #[tokio::test]
+    async fn test_chat_default_model() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body(r#"{"message":{"role":"assistant","content":"Hello!"}}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone()).with_default_model("llama3.2");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::default_model().messages(messages);
+
+        assert_eq!(provider.default_model(), Some("llama3.2"));
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["model"], "llama3.2");
+    }
+
+    #[tokio::test]
+    async fn test_chat_include_raw() {
+        let line = r#"{"message":{"role":"assistant","content":"Hello!"},"done":false}"#;
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(format!("{line}\n")));
+
+        let provider = OllamaProvider::new(client);
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama2")
+            .messages(messages)
+            .include_raw(true);
+
+        let mut response = provider.chat(&options).await.unwrap();
+
+        let raw = response.next().await.unwrap().unwrap();
+        assert!(matches!(raw, ChatChunk::Raw { ref provider_event } if provider_event == line));
+        let chunk = response.next().await.unwrap().unwrap();
+        assert!(matches!(chunk, ChatChunk::Content(ref s) if s == "Hello!"));
+    }
+
     #[tokio::test]
     async fn test_chat_http_error() {
         let client = MockHttpClient::new().with_response(
@@ -203,7 +612,13 @@ mod tests {
 
         let result = provider.chat(&options).await;
 
-        assert!(matches!(result, Err(ChatError::RequestError(_))));
+        assert!(matches!(
+            result,
+            Err(ChatError::ProviderUnavailable {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                ..
+            })
+        ));
     }
 
     #[tokio::test]
@@ -338,4 +753,324 @@ mod tests {
 
         assert!(matches!(chunk, ChatChunk::Content(ref s) if s == "The answer."));
     }
+
+    #[tokio::test]
+    async fn test_chat_sends_tool_results() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body(r#"{"message":{"role":"assistant","content":"Sunny."}}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone());
+        let messages = &[
+            Message::user("What's the weather in Paris?"),
+            Message::assistant("").tool_calls(vec![ToolCall::new(
+                "call_1",
+                "get_weather",
+                r#"{"city":"Paris"}"#,
+            )]),
+            Message::tool("call_1", "sunny"),
+        ];
+        let options = ChatOptions::new("llama3.1")
+            .system("Use tools when needed.")
+            .messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        let messages = &body["messages"];
+
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[0]["content"], "Use tools when needed.");
+        assert_eq!(
+            messages[2]["tool_calls"][0]["function"]["arguments"]["city"],
+            "Paris"
+        );
+        assert_eq!(messages[3]["role"], "tool");
+        assert_eq!(messages[3]["tool_name"], "get_weather");
+        assert_eq!(messages[3]["content"], "sunny");
+    }
+
+    #[tokio::test]
+    async fn test_chat_tool_calls() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"\",\"tool_calls\":[{\"function\":{\"name\":\"get_weather\",\"arguments\":{\"city\":\"Paris\"}}}]},\"done\":false}\n\
+             {\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}\n",
+        ));
+
+        let provider = OllamaProvider::new(client.clone());
+        let messages = &["What's the weather in Paris?".into()];
+        let tools = [Tool::builder("get_weather")
+            .description("Gets the weather in a city.")
+            .build()];
+        let options = ChatOptions::new("llama3.1")
+            .messages(messages)
+            .tools(&tools);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.tool_calls.len(), 1);
+        assert_eq!(result.tool_calls[0].name, "get_weather");
+        assert_eq!(result.tool_calls[0].arguments, r#"{"city":"Paris"}"#);
+        assert!(result.tool_calls[0].id.starts_with("call_"));
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+    }
+
+    #[tokio::test]
+    async fn test_chat_tool_choice_unsupported() {
+        let provider = OllamaProvider::new(MockHttpClient::new());
+        let messages = &["Hi".into()];
+        let tools = [Tool::builder("get_weather").build()];
+        let options = ChatOptions::new("llama3.1")
+            .messages(messages)
+            .tools(&tools)
+            .tool_choice(ToolChoice::Required);
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(
+            result,
+            Err(ChatError::Unsupported {
+                feature: "tool_choice"
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_model_options() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body(r#"{"message":{"role":"assistant","content":"Hi"}}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone())
+            .options(
+                OllamaOptions::new()
+                    .num_ctx(8192)
+                    .temperature(0.5)
+                    .top_k(40)
+                    .stop(["</answer>"]),
+            )
+            .keep_alive(std::time::Duration::from_secs(300));
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama2")
+            .messages(messages)
+            .max_tokens(256);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+        assert_eq!(body["options"]["num_ctx"], 8192);
+        assert_eq!(body["options"]["num_predict"], 256);
+        assert_eq!(body["options"]["temperature"], 0.5);
+        assert_eq!(body["options"]["top_k"], 40);
+        assert_eq!(body["options"]["stop"][0], "</answer>");
+        assert!(body["options"].get("top_p").is_none());
+        assert_eq!(body["keep_alive"], 300);
+        assert!(body.get("think").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_without_messages_unloads_model() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{"model":"llama2","message":{"role":"assistant","content":""},"done_reason":"unload","done":true}"#,
+        ));
+
+        let provider = OllamaProvider::new(client.clone()).keep_alive(std::time::Duration::ZERO);
+        let options = ChatOptions::new("llama2");
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["messages"], serde_json::json!([]));
+        assert_eq!(body["keep_alive"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_chat_think_field() {
+        for (thinking, expected) in [
+            (Thinking::effort("high"), serde_json::json!("high")),
+            (Thinking::enabled(), serde_json::json!(true)),
+        ] {
+            let client = MockHttpClient::new().with_response(
+                MockResponse::new(StatusCode::OK)
+                    .body(r#"{"message":{"role":"assistant","content":"Hi"}}"#),
+            );
+
+            let provider = OllamaProvider::new(client.clone()).keep_alive(KeepAlive::Forever);
+            let messages = &["Hi".into()];
+            let options = ChatOptions::new("gpt-oss")
+                .messages(messages)
+                .thinking(thinking);
+
+            provider.chat(&options).await.unwrap();
+
+            let request = client.last_request().unwrap();
+            let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+            assert_eq!(body["think"], expected);
+            assert_eq!(body["keep_alive"], -1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_extra_fields() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body(r#"{"message":{"role":"assistant","content":"Hi"}}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone());
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama3.2")
+            .messages(messages)
+            .extra("keep_alive", "10m");
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["keep_alive"], "10m");
+    }
+
+    #[tokio::test]
+    async fn test_chat_images() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body(r#"{"message":{"role":"assistant","content":"A cat."}}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone());
+        let messages = [
+            Message::user("What is in this picture?").part(Image::new(b"PNG", "image/png")),
+            Message::assistant("A cat."),
+        ];
+        let options = ChatOptions::new("llava").messages(&messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["content"], "What is in this picture?");
+        assert_eq!(messages[0]["images"], serde_json::json!(["UE5H"]));
+        assert!(messages[1].get("images").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_extension_overrides_provider() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body(r#"{"message":{"role":"assistant","content":"Hi"}}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone())
+            .options(OllamaOptions::new().num_ctx(8192).temperature(0.2))
+            .keep_alive(KeepAlive::Forever);
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama3.2").messages(messages).extension(
+            OllamaChatExt::new()
+                .options(OllamaOptions::new().temperature(0.5))
+                .keep_alive(KeepAlive::Unload),
+        );
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["options"]["num_ctx"], 8192);
+        assert_eq!(body["options"]["temperature"], 0.5);
+        assert_eq!(body["keep_alive"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_chat_extension_invalid() {
+        let provider = OllamaProvider::new(MockHttpClient::new());
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama3.2")
+            .messages(messages)
+            .extension(OllamaChatExt::new().options(OllamaOptions::new().top_p(1.5)));
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(
+            result,
+            Err(ChatError::InvalidOptions { field: "top_p", .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_json_format() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body(r#"{"message":{"role":"assistant","content":"{}"}}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone());
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("llama3.2")
+            .messages(messages)
+            .response_format(ResponseFormat::json());
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["format"], "json");
+    }
+
+    #[tokio::test]
+    async fn test_chat_json_schema_round_trip() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Country {
+            name: String,
+            capital: String,
+            languages: Vec<String>,
+        }
+
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "capital": { "type": "string" },
+                "languages": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["name", "capital", "languages"]
+        });
+
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{"message":{"role":"assistant","content":"{\"name\":\"Canada\",\"capital\":\"Ottawa\",\"languages\":[\"English\",\"French\"]}"}}"#,
+        ));
+
+        let provider = OllamaProvider::new(client.clone());
+        let messages = &["Tell me about Canada.".into()];
+        let options = ChatOptions::new("llama3.2")
+            .messages(messages)
+            .response_format(ResponseFormat::json_schema(schema.clone()));
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let aggregated = response.aggregate().await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["format"], schema);
+
+        let country: Country = serde_json::from_str(&aggregated.content).unwrap();
+        assert_eq!(
+            country,
+            Country {
+                name: "Canada".into(),
+                capital: "Ottawa".into(),
+                languages: vec!["English".into(), "French".into()],
+            }
+        );
+    }
 }
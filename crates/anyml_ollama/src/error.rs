@@ -0,0 +1,80 @@
+use std::io;
+
+use thiserror::Error;
+
+/// Errors returned by the Ollama-specific APIs on [`crate::OllamaProvider`].
+#[derive(Debug, Error)]
+pub enum OllamaError {
+    #[error("Failed to build the request: {0}.")]
+    RequestBuildFailed(#[source] anyhow::Error),
+
+    #[error("Failed to retrieve the response: {0}.")]
+    ResponseFetchFailed(#[source] anyhow::Error),
+
+    /// The connection was refused, usually because the Ollama daemon isn't running.
+    #[error("Ollama is not running at {url}.")]
+    NotRunning {
+        url: String,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    #[error("The request failed: {0}.")]
+    RequestError(#[source] anyhow::Error),
+
+    #[error("Failed to parse response: {0}.")]
+    ParseError(#[source] anyhow::Error),
+}
+
+impl OllamaError {
+    /// Classifies an error from sending a request to `url`,
+    /// returning [`OllamaError::NotRunning`] if the connection was refused.
+    pub(crate) fn fetch_failed(url: &str, err: anyhow::Error) -> Self {
+        let refused = err.chain().any(|source| {
+            source
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == io::ErrorKind::ConnectionRefused)
+        });
+
+        if refused {
+            Self::NotRunning {
+                url: url.to_owned(),
+                source: err,
+            }
+        } else {
+            Self::ResponseFetchFailed(err)
+        }
+    }
+
+    pub fn is_not_running(&self) -> bool {
+        matches!(self, Self::NotRunning { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_failed_connection_refused() {
+        let err = anyhow::Error::new(io::Error::from(io::ErrorKind::ConnectionRefused))
+            .context("error sending request");
+
+        let err = OllamaError::fetch_failed("http://localhost:11434", err);
+
+        assert!(err.is_not_running());
+        assert_eq!(
+            err.to_string(),
+            "Ollama is not running at http://localhost:11434."
+        );
+    }
+
+    #[test]
+    fn test_fetch_failed_other_error() {
+        let err = anyhow::Error::new(io::Error::from(io::ErrorKind::TimedOut));
+
+        let err = OllamaError::fetch_failed("http://localhost:11434", err);
+
+        assert!(matches!(err, OllamaError::ResponseFetchFailed(_)));
+    }
+}
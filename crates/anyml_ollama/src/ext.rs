@@ -0,0 +1,58 @@
+use anyml_core::providers::chat::ChatError;
+use serde::Serialize;
+
+use crate::{KeepAlive, OllamaOptions};
+
+/// Ollama-specific chat options, attached with
+/// [`ChatOptions::extension`](anyml_core::providers::chat::ChatOptions::extension).
+///
+/// These take precedence over the options set on the [`OllamaProvider`](crate::OllamaProvider),
+/// field by field for the model options.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OllamaChatExt {
+    pub options: OllamaOptions,
+    pub keep_alive: Option<KeepAlive>,
+}
+
+impl OllamaChatExt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn options(mut self, options: OllamaOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn keep_alive(mut self, keep_alive: impl Into<KeepAlive>) -> Self {
+        self.keep_alive = Some(keep_alive.into());
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ChatError> {
+        if let Some(temperature) = self.options.temperature
+            && temperature < 0.0
+        {
+            return Err(ChatError::InvalidOptions {
+                field: "temperature",
+                reason: format!("{temperature} is negative"),
+            });
+        }
+        if let Some(top_p) = self.options.top_p
+            && !(0.0..=1.0).contains(&top_p)
+        {
+            return Err(ChatError::InvalidOptions {
+                field: "top_p",
+                reason: format!("{top_p} is outside of 0.0..=1.0"),
+            });
+        }
+        if self.options.num_ctx == Some(0) {
+            return Err(ChatError::InvalidOptions {
+                field: "num_ctx",
+                reason: "it must be at least 1".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+}
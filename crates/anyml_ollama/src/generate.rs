@@ -0,0 +1,229 @@
+use anyhow::anyhow;
+use anyhttp::HttpClient;
+use anyml_core::decode::ndjson_lines;
+use anyml_core::providers::{
+    chat::{ChatChunk, ChatError, ChatResponse, ChatStreamError, Usage},
+    completion::{CompletionOptions, CompletionProvider},
+};
+use anyml_macros::json_string;
+use bytes::Bytes;
+use futures::StreamExt;
+use http::Request;
+use serde::Deserialize;
+
+use crate::OllamaProvider;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> CompletionProvider for OllamaProvider<C> {
+    async fn complete(&self, options: &CompletionOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let mut model_options = self.options.clone();
+        if let Some(max_tokens) = options.max_tokens {
+            model_options.num_predict.get_or_insert(max_tokens as i64);
+        }
+
+        let body: String = json_string! {
+            "model": options.model,
+            "prompt": options.prompt,
+            "suffix"?: options.suffix,
+            "system"?: options.system,
+            "raw": options.raw,
+            "stream": options.stream,
+            "options": @serde model_options,
+            if let Some(keep_alive) = &self.keep_alive {
+                "keep_alive": @serde keep_alive
+            }
+        };
+
+        let request = self
+            .headers
+            .apply(Request::post(format!("{}/api/generate", self.url)))
+            .body(body.into_bytes())
+            .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ChatError::ResponseFetchFailed)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(ChatError::from_error_response(status, &err_body));
+        }
+
+        Ok(ChatResponse::new(
+            ndjson_lines(response.bytes_stream())
+                .map(|line| parse_line(line.map_err(ChatStreamError::ParseError)?))
+                .flat_map(|chunks| {
+                    futures::stream::iter(match chunks {
+                        Ok(chunks) => chunks.into_iter().map(Ok).collect(),
+                        Err(err) => vec![Err(err)],
+                    })
+                }),
+        ))
+    }
+}
+
+fn parse_line(line: String) -> Result<Vec<ChatChunk>, ChatStreamError> {
+    let response: OllamaGenerateResponse = serde_json::from_str(&line)
+        .map_err(|this| ChatStreamError::ParseError(anyhow::Error::new(this)))?;
+
+    if let Some(error) = response.error {
+        return Err(ChatStreamError::ParseError(anyhow!(error)));
+    }
+
+    let mut chunks = Vec::new();
+    if let Some(thinking) = response.thinking.filter(|thinking| !thinking.is_empty()) {
+        chunks.push(ChatChunk::Thinking(thinking.into()));
+    }
+    if !response.response.is_empty() {
+        chunks.push(ChatChunk::Content(response.response.into()));
+    }
+    if response.done {
+        chunks.push(ChatChunk::Usage(Usage {
+            input_tokens: response.prompt_eval_count,
+            output_tokens: response.eval_count,
+            cached_input_tokens: None,
+            timing: None,
+        }));
+    }
+
+    Ok(chunks)
+}
+
+#[derive(Deserialize)]
+struct OllamaGenerateResponse {
+    #[serde(default)]
+    response: String,
+    #[serde(default)]
+    thinking: Option<String>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    prompt_eval_count: Option<usize>,
+    #[serde(default)]
+    eval_count: Option<usize>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_complete_streams_response() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "{\"response\":\"fn main\",\"done\":false}\n\
+             {\"response\":\"() {}\",\"done\":false}\n\
+             {\"response\":\"\",\"done\":true,\"prompt_eval_count\":12,\"eval_count\":4}\n",
+        ));
+
+        let provider = OllamaProvider::new(client);
+        let options = CompletionOptions::new("codellama:code", "// entry point\n");
+
+        let aggregated = provider
+            .complete(&options)
+            .await
+            .unwrap()
+            .aggregate()
+            .await
+            .unwrap();
+
+        assert_eq!(aggregated.content, "fn main() {}");
+        let usage = aggregated.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(12));
+        assert_eq!(usage.output_tokens, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_complete_raw_request_body() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(r#"{"response":"x + y","done":true}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone());
+        let options = CompletionOptions::new(
+            "codellama:code",
+            "<PRE> fn add(x: i32, y: i32) -> i32 { <SUF> } <MID>",
+        )
+        .raw(true)
+        .stream(false)
+        .max_tokens(64);
+
+        let aggregated = provider
+            .complete(&options)
+            .await
+            .unwrap()
+            .aggregate()
+            .await
+            .unwrap();
+        assert_eq!(aggregated.content, "x + y");
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:11434/api/generate");
+
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["model"], "codellama:code");
+        assert_eq!(body["raw"], true);
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["options"]["num_predict"], 64);
+        assert!(body.get("suffix").is_none());
+        assert!(body.get("system").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_complete_with_suffix() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(r#"{"response":"","done":true}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone());
+        let options = CompletionOptions::new("qwen2.5-coder", "def add(a, b):\n    ")
+            .suffix("\n\nprint(add(1, 2))");
+
+        provider.complete(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["prompt"], "def add(a, b):\n    ");
+        assert_eq!(body["suffix"], "\n\nprint(add(1, 2))");
+        assert_eq!(body["raw"], false);
+    }
+
+    #[tokio::test]
+    async fn test_complete_stream_error() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body("{\"error\":\"model crashed\"}\n"),
+        );
+
+        let provider = OllamaProvider::new(client);
+        let options = CompletionOptions::new("llama3.2", "Hello");
+
+        let result = provider.complete(&options).await.unwrap().aggregate().await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_complete_http_error() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::NOT_FOUND).body(r#"{"error":"model not found"}"#),
+        );
+
+        let provider = OllamaProvider::new(client);
+        let options = CompletionOptions::new("missing", "Hello");
+
+        let result = provider.complete(&options).await;
+
+        assert!(matches!(result, Err(ChatError::RequestError(_))));
+    }
+}
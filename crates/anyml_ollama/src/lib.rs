@@ -1,15 +1,37 @@
 use std::borrow::Cow;
 
 use anyhttp::HttpClient;
+use anyml_core::providers::DefaultHeaders;
 
 mod chat;
+mod error;
+mod ext;
+mod generate;
 mod list_models;
+mod manage;
+mod options;
+mod running_models;
+mod version;
+
+pub use error::OllamaError;
+pub use ext::OllamaChatExt;
+pub use manage::{CreateModel, Progress, ProgressResponse};
+pub use options::{KeepAlive, OllamaOptions};
+pub use running_models::RunningModel;
 
 const DEFAULT_URL: &str = "http://localhost:11434";
+const DEFAULT_CHAT_PATH: &str = "/api/chat";
+const DEFAULT_PROBE_CONCURRENCY: usize = 8;
 
 pub struct OllamaProvider<C: HttpClient> {
     client: C,
     url: Cow<'static, str>,
+    chat_path: Cow<'static, str>,
+    default_model: Option<Cow<'static, str>>,
+    headers: DefaultHeaders,
+    options: OllamaOptions,
+    keep_alive: Option<KeepAlive>,
+    probe_concurrency: usize,
 }
 
 impl<C: HttpClient> OllamaProvider<C> {
@@ -17,6 +39,12 @@ impl<C: HttpClient> OllamaProvider<C> {
         Self {
             client,
             url: Cow::Borrowed(DEFAULT_URL),
+            chat_path: Cow::Borrowed(DEFAULT_CHAT_PATH),
+            default_model: None,
+            headers: DefaultHeaders::new(),
+            options: OllamaOptions::default(),
+            keep_alive: None,
+            probe_concurrency: DEFAULT_PROBE_CONCURRENCY,
         }
     }
 
@@ -24,4 +52,55 @@ impl<C: HttpClient> OllamaProvider<C> {
         self.url = url.into();
         self
     }
+
+    /// Sets the path chat requests are sent to, after `url`. Defaults to `/api/chat`.
+    ///
+    /// It may end with a query string, for gateways which need one (e.g. `?api-version=`).
+    pub fn chat_path(mut self, path: impl Into<Cow<'static, str>>) -> Self {
+        self.chat_path = path.into();
+        self
+    }
+
+    /// Sets the model chats are sent to when their options leave it to the provider,
+    /// as with [`ChatOptions::default_model`](anyml_core::providers::chat::ChatOptions::default_model).
+    pub fn with_default_model(mut self, model: impl Into<Cow<'static, str>>) -> Self {
+        self.default_model = Some(model.into());
+        self
+    }
+
+    /// Adds a header sent with every request, e.g. for a proxy or gateway.
+    pub fn header(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets the model parameters sent with every chat request.
+    pub fn options(mut self, options: OllamaOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets how long models stay loaded after each request.
+    pub fn keep_alive(mut self, keep_alive: impl Into<KeepAlive>) -> Self {
+        self.keep_alive = Some(keep_alive.into());
+        self
+    }
+
+    /// Sets how many `/api/show` requests `list_models` sends at once
+    /// to probe model capabilities. Defaults to 8.
+    ///
+    /// A limit of `0` skips probing, leaving `Model::thinking` unset.
+    pub fn probe_concurrency(mut self, limit: usize) -> Self {
+        self.probe_concurrency = limit;
+        self
+    }
+
+    /// Skips probing model capabilities in `list_models`.
+    pub fn skip_capability_probing(self) -> Self {
+        self.probe_concurrency(0)
+    }
 }
@@ -1,19 +1,27 @@
 use anyhow::anyhow;
 use anyhttp::HttpClient;
 use anyml_core::{
-    models::{Model, ModelParams, ModelQuant, ThinkingModes},
+    models::{Model, ModelCapabilities, ModelParams, ModelQuant, ThinkingModes},
     providers::list_models::{ListModelsError, ListModelsProvider},
 };
+use anyml_macros::json_string;
 use bytes::Bytes;
-use http::Request;
+use futures::StreamExt;
+use http::{Request, StatusCode};
 use serde::Deserialize;
 
 use crate::OllamaProvider;
 
-#[async_trait::async_trait]
+/// The levels of `think` taken by models which think at an effort level.
+const EFFORT_LEVELS: [&str; 3] = ["low", "medium", "high"];
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl<C: HttpClient> ListModelsProvider for OllamaProvider<C> {
     async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
-        let request = Request::get(format!("{}/api/tags", self.url))
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/api/tags", self.url)))
             .body(Vec::new())
             .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
 
@@ -42,38 +50,110 @@ impl<C: HttpClient> ListModelsProvider for OllamaProvider<C> {
         let ollama_response: OllamaTagsResponse = serde_json::from_slice(&body)
             .map_err(|e| ListModelsError::ParseError(anyhow::Error::new(e)))?;
 
-        let mut models = Vec::with_capacity(ollama_response.models.len());
-        for m in ollama_response.models {
-            let (parameters, quantization) = m
-                .details
-                .map(|d| {
-                    let params = d.parameter_size.map(|p| ModelParams::new(&p));
-                    let quant = d.quantization_level.map(|q| ModelQuant::new(&q));
-                    (params, quant)
-                })
-                .unwrap_or((None, None));
-
-            let thinking = self.fetch_thinking_modes(&m.name).await;
-
-            models.push(Model {
-                id: m.name,
-                parameters,
-                quantization,
-                thinking,
-            });
+        let mut models: Vec<Model> = ollama_response
+            .models
+            .into_iter()
+            .map(|m| {
+                let (parameters, quantization) = m
+                    .details
+                    .map(|d| {
+                        let params = d.parameter_size.map(|p| ModelParams::new(&p));
+                        let quant = d.quantization_level.map(|q| ModelQuant::new(&q));
+                        (params, quant)
+                    })
+                    .unwrap_or((None, None));
+
+                Model {
+                    id: m.name,
+                    parameters,
+                    quantization,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        if self.probe_concurrency > 0 {
+            let ids: Vec<String> = models.iter().map(|model| model.id.clone()).collect();
+            let probes = futures::stream::iter(ids.into_iter().enumerate())
+                .map(|(idx, id)| async move { (idx, self.fetch_show(&id).await) })
+                .buffer_unordered(self.probe_concurrency)
+                .collect::<Vec<_>>()
+                .await;
+
+            for (idx, show) in probes {
+                let Some(show) = show else {
+                    continue;
+                };
+                models[idx].thinking = show.thinking_modes();
+                models[idx].capabilities = show.capabilities();
+            }
         }
 
         Ok(models)
     }
+
+    /// Looks the model up with `/api/show`, which also reports its
+    /// capabilities and context length.
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        let body: String = json_string! { "model": id };
+        let request = self
+            .headers
+            .apply(Request::post(format!("{}/api/show", self.url)))
+            .body(body.into_bytes())
+            .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ListModelsError::NotFound { id: id.to_owned() });
+        }
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(ListModelsError::ResponseFetchFailed(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        let show: OllamaShowResponse = serde_json::from_slice(&body)
+            .map_err(|e| ListModelsError::ParseError(anyhow::Error::new(e)))?;
+
+        let details = show.details.as_ref();
+        Ok(Model {
+            id: id.to_owned(),
+            parameters: details
+                .and_then(|d| d.parameter_size.as_deref())
+                .map(ModelParams::new),
+            quantization: details
+                .and_then(|d| d.quantization_level.as_deref())
+                .map(ModelQuant::new),
+            thinking: show.thinking_modes(),
+            context_length: show.context_length(),
+            capabilities: show.capabilities(),
+            ..Default::default()
+        })
+    }
 }
 
 impl<C: HttpClient> OllamaProvider<C> {
-    /// Calls `/api/show` for a model and returns `ThinkingModes` if the model
-    /// has the `"thinking"` capability. Returns `None` on any error or if
-    /// the capability is absent.
-    async fn fetch_thinking_modes(&self, model: &str) -> Option<ThinkingModes> {
+    /// Calls `/api/show` for a model, returning `None` on any error.
+    async fn fetch_show(&self, model: &str) -> Option<OllamaShowResponse> {
         let body = format!(r#"{{"model":"{}"}}"#, model);
-        let request = Request::post(format!("{}/api/show", self.url))
+        let request = self
+            .headers
+            .apply(Request::post(format!("{}/api/show", self.url)))
             .body(body.into_bytes())
             .ok()?;
 
@@ -84,16 +164,7 @@ impl<C: HttpClient> OllamaProvider<C> {
         }
 
         let bytes = response.bytes().await.ok()?;
-        let show: OllamaShowResponse = serde_json::from_slice(&bytes).ok()?;
-
-        if show.capabilities.contains(&"thinking".to_string()) {
-            Some(ThinkingModes {
-                modes: vec!["enabled".into()],
-                budget: None,
-            })
-        } else {
-            None
-        }
+        serde_json::from_slice(&bytes).ok()
     }
 }
 
@@ -110,6 +181,7 @@ struct OllamaModel {
 
 #[derive(Deserialize)]
 struct OllamaModelDetails {
+    family: Option<String>,
     parameter_size: Option<String>,
     quantization_level: Option<String>,
 }
@@ -118,12 +190,66 @@ struct OllamaModelDetails {
 struct OllamaShowResponse {
     #[serde(default)]
     capabilities: Vec<String>,
+    details: Option<OllamaModelDetails>,
+    /// Architecture-specific metadata, keyed like `"llama.context_length"`.
+    #[serde(default)]
+    model_info: serde_json::Map<String, serde_json::Value>,
+}
+
+impl OllamaShowResponse {
+    fn capabilities(&self) -> ModelCapabilities {
+        let has = |capability: &str| self.capabilities.iter().any(|this| this == capability);
+        ModelCapabilities {
+            vision: has("vision"),
+            tool_use: has("tools"),
+            // Any model that can complete can be constrained with `format`.
+            json_mode: has("completion"),
+            thinking: has("thinking"),
+            embeddings: has("embedding"),
+        }
+    }
+
+    /// The ways the model can think, if it can. Models which think at an effort
+    /// level (GPT-OSS) take `"low"`, `"medium"` or `"high"`, and the others can
+    /// only be told to think.
+    fn thinking_modes(&self) -> Option<ThinkingModes> {
+        if !self.capabilities().thinking {
+            return None;
+        }
+
+        let modes: &[&str] = if self.architecture() == Some("gptoss") {
+            &EFFORT_LEVELS
+        } else {
+            &["enabled"]
+        };
+        Some(ThinkingModes {
+            modes: modes.iter().map(|&mode| mode.to_owned()).collect(),
+            budget: None,
+        })
+    }
+
+    /// The model's architecture, e.g. `"llama"` or `"gptoss"`.
+    fn architecture(&self) -> Option<&str> {
+        self.model_info
+            .get("general.architecture")
+            .and_then(|architecture| architecture.as_str())
+            .or_else(|| self.details.as_ref()?.family.as_deref())
+    }
+
+    fn context_length(&self) -> Option<usize> {
+        self.model_info
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+            .map(|length| length as usize)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::providers::chat::Thinking;
     use http::StatusCode;
 
     #[tokio::test]
@@ -161,7 +287,9 @@ mod tests {
                     .body(r#"{"capabilities":["completion","thinking"]}"#),
             )
             // /api/show for llama2 — no thinking
-            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"capabilities":["completion"]}"#));
+            .with_response(
+                MockResponse::new(StatusCode::OK).body(r#"{"capabilities":["completion"]}"#),
+            );
 
         let provider = OllamaProvider::new(client);
         let models = provider.list_models().await.unwrap();
@@ -172,6 +300,105 @@ mod tests {
         assert!(models[1].thinking.is_none());
     }
 
+    #[tokio::test]
+    async fn test_list_models_with_effort_levels() {
+        let client = MockHttpClient::new()
+            .with_response(
+                MockResponse::new(StatusCode::OK)
+                    .body(r#"{"models":[{"name":"gpt-oss:20b"},{"name":"qwen3"}]}"#),
+            )
+            .with_response(MockResponse::new(StatusCode::OK).body(
+                r#"{"capabilities":["completion","thinking"],"model_info":{"general.architecture":"gptoss"}}"#,
+            ))
+            .with_response(MockResponse::new(StatusCode::OK).body(
+                r#"{"capabilities":["completion","thinking"],"details":{"family":"qwen3"}}"#,
+            ));
+
+        let provider = OllamaProvider::new(client).probe_concurrency(1);
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(
+            models[0].thinking.as_ref().unwrap().modes,
+            ["low", "medium", "high"]
+        );
+        assert!(models[0].supports_thinking(&Thinking::effort("high")));
+        assert_eq!(models[1].thinking.as_ref().unwrap().modes, ["enabled"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_models_capabilities() {
+        let client = MockHttpClient::new()
+            .with_response(
+                MockResponse::new(StatusCode::OK)
+                    .body(r#"{"models":[{"name":"llava"},{"name":"nomic-embed-text"}]}"#),
+            )
+            .with_response(
+                MockResponse::new(StatusCode::OK)
+                    .body(r#"{"capabilities":["completion","vision","tools"]}"#),
+            )
+            .with_response(
+                MockResponse::new(StatusCode::OK).body(r#"{"capabilities":["embedding"]}"#),
+            );
+
+        let provider = OllamaProvider::new(client).probe_concurrency(1);
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(
+            models[0].capabilities,
+            ModelCapabilities {
+                vision: true,
+                tool_use: true,
+                json_mode: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            models[1].capabilities,
+            ModelCapabilities {
+                embeddings: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_model() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{
+                "details":{"parameter_size":"8.0B","quantization_level":"Q4_K_M"},
+                "model_info":{"general.architecture":"llama","llama.context_length":131072},
+                "capabilities":["completion","tools"]
+            }"#,
+        ));
+
+        let provider = OllamaProvider::new(client.clone());
+        let model = provider.get_model("llama3.1:8b").await.unwrap();
+
+        assert_eq!(model.id, "llama3.1:8b");
+        assert!(model.parameters.is_some());
+        assert!(model.quantization.is_some());
+        assert_eq!(model.context_length, Some(131072));
+        assert!(model.capabilities.tool_use);
+        assert!(model.thinking.is_none());
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:11434/api/show");
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["model"], "llama3.1:8b");
+    }
+
+    #[tokio::test]
+    async fn test_get_model_not_found() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::NOT_FOUND).body(r#"{"error":"model 'nope' not found"}"#),
+        );
+
+        let provider = OllamaProvider::new(client);
+        let result = provider.get_model("nope").await;
+
+        assert!(matches!(result, Err(ListModelsError::NotFound { id }) if id == "nope"));
+    }
+
     #[tokio::test]
     async fn test_list_models_empty() {
         let client = MockHttpClient::new()
@@ -223,8 +450,7 @@ mod tests {
         // If /api/show fails, thinking should be None (not an error)
         let client = MockHttpClient::new()
             .with_response(
-                MockResponse::new(StatusCode::OK)
-                    .body(r#"{"models":[{"name":"llama2"}]}"#),
+                MockResponse::new(StatusCode::OK).body(r#"{"models":[{"name":"llama2"}]}"#),
             )
             // /api/show returns error
             .with_response(MockResponse::new(StatusCode::INTERNAL_SERVER_ERROR).body("error"));
@@ -235,4 +461,45 @@ mod tests {
         assert_eq!(models.len(), 1);
         assert!(models[0].thinking.is_none());
     }
+
+    #[tokio::test]
+    async fn test_list_models_skip_capability_probing() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(r#"{"models":[{"name":"deepseek-r1:7b"}]}"#),
+        );
+
+        let provider = OllamaProvider::new(client.clone()).skip_capability_probing();
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 1);
+        assert!(models[0].thinking.is_none());
+        // Only the /api/tags request was sent.
+        assert_eq!(
+            client.last_request().unwrap().uri(),
+            "http://localhost:11434/api/tags"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_models_probe_concurrency_preserves_order() {
+        let client = MockHttpClient::new()
+            .with_response(
+                MockResponse::new(StatusCode::OK)
+                    .body(r#"{"models":[{"name":"a"},{"name":"b"},{"name":"c"}]}"#),
+            )
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"capabilities":[]}"#))
+            .with_response(
+                MockResponse::new(StatusCode::OK).body(r#"{"capabilities":["thinking"]}"#),
+            )
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"capabilities":[]}"#));
+
+        let provider = OllamaProvider::new(client).probe_concurrency(2);
+        let models = provider.list_models().await.unwrap();
+
+        let ids: Vec<_> = models.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert!(models[0].thinking.is_none());
+        assert!(models[1].thinking.is_some());
+        assert!(models[2].thinking.is_none());
+    }
 }
@@ -0,0 +1,374 @@
+use std::{
+    ops::{Deref, DerefMut},
+    pin::Pin,
+};
+
+use anyhow::anyhow;
+use anyhttp::{HttpClient, Response};
+use anyml_core::{
+    decode::ndjson_lines,
+    maybe_send::{BoxStream, MaybeSend},
+};
+use anyml_macros::json_string;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use http::Request;
+use serde::{Deserialize, Serialize};
+
+use crate::{OllamaError, OllamaOptions, OllamaProvider};
+
+impl<C: HttpClient> OllamaProvider<C> {
+    /// Downloads a model from the Ollama library, streaming its progress.
+    pub async fn pull_model(&self, model: &str) -> Result<ProgressResponse, OllamaError> {
+        let body: String = json_string! {
+            "model": model,
+            "stream": true
+        };
+
+        let response = self
+            .send(Request::post(format!("{}/api/pull", self.url)), body)
+            .await?;
+        Ok(ProgressResponse::from_response(response))
+    }
+
+    /// Creates a model from an existing one, streaming its progress.
+    pub async fn create_model(&self, model: &CreateModel) -> Result<ProgressResponse, OllamaError> {
+        let body = serde_json::to_string(model)
+            .map_err(|this| OllamaError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        let response = self
+            .send(Request::post(format!("{}/api/create", self.url)), body)
+            .await?;
+        Ok(ProgressResponse::from_response(response))
+    }
+
+    pub async fn delete_model(&self, model: &str) -> Result<(), OllamaError> {
+        let body: String = json_string! { "model": model };
+
+        self.send(Request::delete(format!("{}/api/delete", self.url)), body)
+            .await?;
+        Ok(())
+    }
+
+    /// Copies `source` to a new model named `destination`.
+    pub async fn copy_model(&self, source: &str, destination: &str) -> Result<(), OllamaError> {
+        let body: String = json_string! {
+            "source": source,
+            "destination": destination
+        };
+
+        self.send(Request::post(format!("{}/api/copy", self.url)), body)
+            .await?;
+        Ok(())
+    }
+
+    /// Sends a request and returns the response if its status is successful.
+    pub(crate) async fn send(
+        &self,
+        request: http::request::Builder,
+        body: String,
+    ) -> Result<Response, OllamaError> {
+        let request = self
+            .headers
+            .apply(request)
+            .body(body.into_bytes())
+            .map_err(|this| OllamaError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(|err| OllamaError::fetch_failed(&self.url, err))?;
+
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(OllamaError::RequestError(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        Ok(response)
+    }
+}
+
+/// Options for [`OllamaProvider::create_model`].
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CreateModel {
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<OllamaOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantize: Option<String>,
+    stream: bool,
+}
+
+impl CreateModel {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            stream: true,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the existing model to create the new model from.
+    pub fn from(mut self, from: impl Into<String>) -> Self {
+        self.from = Some(from.into());
+        self
+    }
+
+    pub fn system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    pub fn template(mut self, template: impl Into<String>) -> Self {
+        self.template = Some(template.into());
+        self
+    }
+
+    /// Sets the default model parameters of the new model.
+    pub fn parameters(mut self, parameters: OllamaOptions) -> Self {
+        self.parameters = Some(parameters);
+        self
+    }
+
+    /// Quantizes a non-quantized model (e.g. `"q4_K_M"`).
+    pub fn quantize(mut self, quantize: impl Into<String>) -> Self {
+        self.quantize = Some(quantize.into());
+        self
+    }
+}
+
+/// A status update from a long-running model operation.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub status: String,
+    #[serde(default)]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+}
+
+impl Progress {
+    /// How much of the current layer has been downloaded, from `0.0` to `100.0`.
+    pub fn percent(&self) -> Option<f64> {
+        match (self.completed, self.total) {
+            (Some(completed), Some(total)) if total > 0 => {
+                Some(completed as f64 / total as f64 * 100.0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this is the final update of a successful operation.
+    pub fn is_success(&self) -> bool {
+        self.status == "success"
+    }
+}
+
+/// A stream of [`Progress`] updates.
+pub struct ProgressResponse(BoxStream<'static, Result<Progress, OllamaError>>);
+
+impl ProgressResponse {
+    pub fn new(
+        stream: impl Stream<Item = Result<Progress, OllamaError>> + MaybeSend + 'static,
+    ) -> Self {
+        Self(Box::pin(stream))
+    }
+
+    fn from_response(response: Response) -> Self {
+        Self::new(ndjson_lines(response.bytes_stream()).map(|line| {
+            line.map_err(OllamaError::ResponseFetchFailed)
+                .and_then(parse_progress_line)
+        }))
+    }
+
+    pub async fn next(&mut self) -> Option<Result<Progress, OllamaError>> {
+        self.0.next().await
+    }
+
+    /// Waits for the operation to finish, ignoring intermediate updates.
+    pub async fn finish(&mut self) -> Result<(), OllamaError> {
+        while let Some(progress) = self.next().await {
+            if progress?.is_success() {
+                return Ok(());
+            }
+        }
+
+        Err(OllamaError::RequestError(anyhow!(
+            "The stream ended before the operation succeeded."
+        )))
+    }
+}
+
+impl Stream for ProgressResponse {
+    type Item = Result<Progress, OllamaError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
+
+impl Deref for ProgressResponse {
+    type Target = BoxStream<'static, Result<Progress, OllamaError>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ProgressResponse {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+fn parse_progress_line(line: String) -> Result<Progress, OllamaError> {
+    match serde_json::from_str::<ProgressLine>(&line) {
+        Ok(ProgressLine::Error { error }) => Err(OllamaError::RequestError(anyhow!(error))),
+        Ok(ProgressLine::Progress(progress)) => Ok(progress),
+        Err(err) => Err(OllamaError::ParseError(anyhow::Error::new(err))),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProgressLine {
+    Error { error: String },
+    Progress(Progress),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_pull_model_progress() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "{\"status\":\"pulling manifest\"}\n\
+             {\"status\":\"pulling abc\",\"digest\":\"sha256:abc\",\"total\":200,\"completed\":50}\n\
+             {\"status\":\"success\"}\n",
+        ));
+
+        let provider = OllamaProvider::new(client.clone());
+        let mut response = provider.pull_model("llama3.2").await.unwrap();
+
+        let first = response.next().await.unwrap().unwrap();
+        assert_eq!(first.status, "pulling manifest");
+        assert_eq!(first.percent(), None);
+
+        let second = response.next().await.unwrap().unwrap();
+        assert_eq!(second.digest.as_deref(), Some("sha256:abc"));
+        assert_eq!(second.percent(), Some(25.0));
+
+        assert!(response.next().await.unwrap().unwrap().is_success());
+        assert!(response.next().await.is_none());
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:11434/api/pull");
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["model"], "llama3.2");
+    }
+
+    #[tokio::test]
+    async fn test_pull_model_stream_error() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("{\"status\":\"pulling manifest\"}\n{\"error\":\"file does not exist\"}\n"),
+        );
+
+        let provider = OllamaProvider::new(client);
+        let result = provider.pull_model("missing").await.unwrap().finish().await;
+
+        assert!(matches!(result, Err(OllamaError::RequestError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_create_model() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body("{\"status\":\"success\"}\n"));
+
+        let provider = OllamaProvider::new(client.clone());
+        let model = CreateModel::new("mario")
+            .from("llama3.2")
+            .system("You are Mario.")
+            .parameters(OllamaOptions::new().temperature(0.5));
+
+        provider
+            .create_model(&model)
+            .await
+            .unwrap()
+            .finish()
+            .await
+            .unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:11434/api/create");
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["model"], "mario");
+        assert_eq!(body["from"], "llama3.2");
+        assert_eq!(body["system"], "You are Mario.");
+        assert_eq!(body["parameters"]["temperature"], 0.5);
+        assert_eq!(body["stream"], true);
+        assert!(body.get("template").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_model() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK));
+
+        let provider = OllamaProvider::new(client.clone());
+        provider.delete_model("llama3.2").await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.method(), http::Method::DELETE);
+        assert_eq!(request.uri(), "http://localhost:11434/api/delete");
+    }
+
+    #[tokio::test]
+    async fn test_delete_model_not_found() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::NOT_FOUND).body("{\"error\":\"model not found\"}"),
+        );
+
+        let provider = OllamaProvider::new(client);
+        let result = provider.delete_model("missing").await;
+
+        assert!(matches!(result, Err(OllamaError::RequestError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_copy_model() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK));
+
+        let provider = OllamaProvider::new(client.clone());
+        provider
+            .copy_model("llama3.2", "llama3.2-backup")
+            .await
+            .unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:11434/api/copy");
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["source"], "llama3.2");
+        assert_eq!(body["destination"], "llama3.2-backup");
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+
+/// Model parameters sent as the `options` object of an Ollama request.
+///
+/// `num_predict` defaults to the request's `max_tokens`, if any, when unset.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+}
+
+impl OllamaOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the size of the context window, in tokens.
+    pub fn num_ctx(mut self, num_ctx: u32) -> Self {
+        self.num_ctx = Some(num_ctx);
+        self
+    }
+
+    /// Sets the maximum number of tokens to generate. `-1` generates until the model stops.
+    pub fn num_predict(mut self, num_predict: i64) -> Self {
+        self.num_predict = Some(num_predict);
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn top_k(mut self, top_k: u32) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    pub fn repeat_penalty(mut self, repeat_penalty: f32) -> Self {
+        self.repeat_penalty = Some(repeat_penalty);
+        self
+    }
+
+    /// Sets the sequences which stop generation when produced.
+    pub fn stop<I, S>(mut self, stop: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stop = stop.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Replaces each parameter which is set in `other`.
+    pub(crate) fn overlay(&mut self, other: &OllamaOptions) {
+        self.num_ctx = other.num_ctx.or(self.num_ctx);
+        self.num_predict = other.num_predict.or(self.num_predict);
+        self.temperature = other.temperature.or(self.temperature);
+        self.top_k = other.top_k.or(self.top_k);
+        self.top_p = other.top_p.or(self.top_p);
+        self.repeat_penalty = other.repeat_penalty.or(self.repeat_penalty);
+        if !other.stop.is_empty() {
+            self.stop = other.stop.clone();
+        }
+    }
+}
+
+/// How long Ollama keeps a model loaded in memory after a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAlive {
+    /// Keep the model loaded for this long.
+    Duration(Duration),
+    /// Keep the model loaded until the daemon exits.
+    Forever,
+    /// Unload the model as soon as the request finishes.
+    Unload,
+}
+
+impl From<Duration> for KeepAlive {
+    fn from(duration: Duration) -> Self {
+        Self::Duration(duration)
+    }
+}
+
+impl Serialize for KeepAlive {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Ollama reads bare numbers as seconds.
+        match self {
+            Self::Duration(duration) => serializer.serialize_u64(duration.as_secs()),
+            Self::Forever => serializer.serialize_i64(-1),
+            Self::Unload => serializer.serialize_i64(0),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use anyhttp::HttpClient;
+use http::Request;
+use serde::Deserialize;
+
+use crate::{OllamaError, OllamaProvider};
+
+impl<C: HttpClient> OllamaProvider<C> {
+    /// Lists the models currently loaded into memory.
+    pub async fn running_models(&self) -> Result<Vec<RunningModel>, OllamaError> {
+        let response = self
+            .send(Request::get(format!("{}/api/ps", self.url)), String::new())
+            .await?;
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(OllamaError::ResponseFetchFailed)?;
+
+        let ps: OllamaPsResponse = serde_json::from_slice(&body)
+            .map_err(|e| OllamaError::ParseError(anyhow::Error::new(e)))?;
+
+        Ok(ps.models)
+    }
+}
+
+/// A model loaded into memory by the Ollama daemon.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct RunningModel {
+    pub name: String,
+    pub model: String,
+    /// The total memory used by the model, in bytes.
+    #[serde(default)]
+    pub size: u64,
+    /// The portion of `size` loaded into VRAM, in bytes.
+    #[serde(default)]
+    pub size_vram: u64,
+    #[serde(default)]
+    pub digest: String,
+    /// When the model will be unloaded, as an RFC 3339 timestamp.
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    #[serde(default)]
+    pub context_length: Option<u64>,
+}
+
+impl RunningModel {
+    /// Whether the model is fully loaded into VRAM.
+    pub fn is_fully_on_gpu(&self) -> bool {
+        self.size > 0 && self.size_vram >= self.size
+    }
+}
+
+#[derive(Deserialize)]
+struct OllamaPsResponse {
+    #[serde(default)]
+    models: Vec<RunningModel>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_running_models() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{"models":[{"name":"llama3.2:latest","model":"llama3.2:latest","size":5137025024,"digest":"a80c4f17","details":{"family":"llama"},"expires_at":"2024-06-04T14:38:31.83753-07:00","size_vram":5137025024,"context_length":4096}]}"#,
+        ));
+
+        let provider = OllamaProvider::new(client.clone());
+        let models = provider.running_models().await.unwrap();
+
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].name, "llama3.2:latest");
+        assert_eq!(models[0].size_vram, 5137025024);
+        assert_eq!(
+            models[0].expires_at.as_deref(),
+            Some("2024-06-04T14:38:31.83753-07:00")
+        );
+        assert_eq!(models[0].context_length, Some(4096));
+        assert!(models[0].is_fully_on_gpu());
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:11434/api/ps");
+    }
+
+    #[tokio::test]
+    async fn test_running_models_empty() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"models":[]}"#));
+
+        let provider = OllamaProvider::new(client);
+        let models = provider.running_models().await.unwrap();
+
+        assert!(models.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_running_models_http_error() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::INTERNAL_SERVER_ERROR).body("server error"),
+        );
+
+        let provider = OllamaProvider::new(client);
+        let result = provider.running_models().await;
+
+        assert!(matches!(result, Err(OllamaError::RequestError(_))));
+    }
+}
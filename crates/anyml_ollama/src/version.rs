@@ -0,0 +1,120 @@
+use anyhttp::HttpClient;
+use anyml_core::providers::{HealthCheckProvider, HealthStatus};
+use http::Request;
+use serde::Deserialize;
+
+use crate::{OllamaError, OllamaProvider};
+
+impl<C: HttpClient> OllamaProvider<C> {
+    /// Returns the version of the Ollama daemon (e.g. `"0.5.1"`).
+    pub async fn version(&self) -> Result<String, OllamaError> {
+        let response = self
+            .send(
+                Request::get(format!("{}/api/version", self.url)),
+                String::new(),
+            )
+            .await?;
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(OllamaError::ResponseFetchFailed)?;
+
+        let version: OllamaVersionResponse = serde_json::from_slice(&body)
+            .map_err(|e| OllamaError::ParseError(anyhow::Error::new(e)))?;
+
+        Ok(version.version)
+    }
+
+    /// Checks that the Ollama daemon is reachable.
+    ///
+    /// Returns [`OllamaError::NotRunning`] if nothing is listening at the
+    /// provider's URL, so apps can prompt the user to start Ollama.
+    pub async fn health(&self) -> Result<(), OllamaError> {
+        self.version().await.map(|_| ())
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> HealthCheckProvider for OllamaProvider<C> {
+    /// Asks the daemon for its version, which needs no model loaded.
+    async fn health(&self) -> HealthStatus {
+        match self.version().await {
+            Ok(_) => HealthStatus::Healthy,
+            Err(err @ (OllamaError::NotRunning { .. } | OllamaError::ResponseFetchFailed(_))) => {
+                HealthStatus::Unreachable {
+                    reason: err.to_string(),
+                }
+            }
+            Err(err) => HealthStatus::Unhealthy {
+                reason: err.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OllamaVersionResponse {
+    version: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_version() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"version":"0.5.1"}"#));
+
+        let provider = OllamaProvider::new(client.clone());
+        let version = provider.version().await.unwrap();
+
+        assert_eq!(version, "0.5.1");
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:11434/api/version");
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"version":"0.5.1"}"#));
+
+        let provider = OllamaProvider::new(client);
+
+        assert!(provider.health().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_health_unexpected_response() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body("not valid json"));
+
+        let provider = OllamaProvider::new(client);
+        let result = provider.health().await;
+
+        assert!(matches!(result, Err(OllamaError::ParseError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_health_check() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"version":"0.5.1"}"#))
+            .with_response(MockResponse::new(StatusCode::OK).body("not valid json"));
+
+        let provider = OllamaProvider::new(client);
+
+        assert_eq!(
+            HealthCheckProvider::health(&provider).await,
+            HealthStatus::Healthy
+        );
+        assert!(matches!(
+            HealthCheckProvider::health(&provider).await,
+            HealthStatus::Unhealthy { .. }
+        ));
+    }
+}
@@ -0,0 +1,631 @@
+use std::{
+    ops::{Deref, DerefMut},
+    pin::Pin,
+};
+
+use anyhow::anyhow;
+use anyhttp::{HttpClient, Response};
+use anyml_core::{
+    decode::ndjson_lines,
+    maybe_send::{BoxStream, MaybeSend},
+    multipart::Multipart,
+    providers::chat::{
+        AggregatedChat, ChatChunk, ChatError, ChatOptions, FinishReason, ToolCallDelta, Usage,
+    },
+};
+use anyml_macros::json_string;
+use bytes::Bytes;
+use futures::{Stream, StreamExt, stream};
+use http::Request;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::{BatchError, OpenAiProvider, chat::request_body};
+
+const CHAT_COMPLETIONS: &str = "/v1/chat/completions";
+
+impl<C: HttpClient> OpenAiProvider<C> {
+    /// Uploads `requests` as the JSONL input file of a batch.
+    pub async fn upload_batch_file(
+        &self,
+        requests: &[BatchRequest],
+    ) -> Result<UploadedFile, BatchError> {
+        let mut jsonl = Vec::new();
+        for request in requests {
+            serde_json::to_writer(&mut jsonl, request)
+                .map_err(|this| BatchError::RequestBuildFailed(anyhow::Error::new(this)))?;
+            jsonl.push(b'\n');
+        }
+
+        let (content_type, body) = Multipart::new()
+            .text("purpose", "batch")
+            .file("file", "batch.jsonl", "application/jsonl", jsonl)
+            .finish();
+
+        let request =
+            Request::post(format!("{}/v1/files", self.url)).header("Content-Type", content_type);
+        let response = self.send(request, body).await?;
+        parse_json(response).await
+    }
+
+    /// Uploads `requests` and submits them as a batch, processed within 24 hours.
+    pub async fn create_batch(&self, requests: &[BatchRequest]) -> Result<Batch, BatchError> {
+        let file = self.upload_batch_file(requests).await?;
+        self.create_batch_from_file(&file.id).await
+    }
+
+    /// Submits a batch from an input file uploaded with [`Self::upload_batch_file`].
+    pub async fn create_batch_from_file(&self, input_file_id: &str) -> Result<Batch, BatchError> {
+        let body: String = json_string! {
+            "input_file_id": input_file_id,
+            "endpoint": CHAT_COMPLETIONS,
+            "completion_window": "24h"
+        };
+
+        let request = Request::post(format!("{}/v1/batches", self.url))
+            .header("Content-Type", "application/json");
+        let response = self.send(request, body.into_bytes()).await?;
+        parse_json(response).await
+    }
+
+    pub async fn get_batch(&self, batch_id: &str) -> Result<Batch, BatchError> {
+        let request = Request::get(format!("{}/v1/batches/{batch_id}", self.url));
+        let response = self.send(request, Vec::new()).await?;
+        parse_json(response).await
+    }
+
+    /// Streams the results of a finished batch, one per request, including the
+    /// requests which failed. Results are not guaranteed to be in the same order as the requests.
+    pub async fn batch_results(&self, batch: &Batch) -> Result<BatchResults, BatchError> {
+        let mut files = Vec::new();
+        for file_id in [&batch.output_file_id, &batch.error_file_id]
+            .into_iter()
+            .flatten()
+        {
+            let request = Request::get(format!("{}/v1/files/{file_id}/content", self.url));
+            files.push(self.send(request, Vec::new()).await?.bytes_stream());
+        }
+
+        Ok(BatchResults::new(
+            stream::iter(files).flat_map(ndjson_lines).map(|line| {
+                let line: WireResult =
+                    serde_json::from_str(&line.map_err(BatchError::ResponseFetchFailed)?)
+                        .map_err(|this| BatchError::ParseError(anyhow::Error::new(this)))?;
+                line.try_into()
+            }),
+        ))
+    }
+
+    async fn send(
+        &self,
+        request: http::request::Builder,
+        body: Vec<u8>,
+    ) -> Result<Response, BatchError> {
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(BatchError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(request)
+            .header(
+                "Authorization",
+                format!("Bearer {}", api_key.expose_secret()),
+            )
+            .body(body)
+            .map_err(|this| BatchError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(BatchError::ResponseFetchFailed)?;
+
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(BatchError::RequestError(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        Ok(response)
+    }
+}
+
+async fn parse_json<T: for<'de> Deserialize<'de>>(response: Response) -> Result<T, BatchError> {
+    let body = response
+        .bytes()
+        .await
+        .map_err(BatchError::ResponseFetchFailed)?;
+
+    serde_json::from_slice(&body).map_err(|this| BatchError::ParseError(anyhow::Error::new(this)))
+}
+
+/// A single chat request within a batch, as a line of the batch's input file.
+#[derive(Serialize, Debug, Clone)]
+pub struct BatchRequest {
+    /// Identifies the request's result, which may arrive in any order.
+    pub custom_id: String,
+    method: &'static str,
+    url: &'static str,
+    body: Box<RawValue>,
+}
+
+impl BatchRequest {
+    /// Builds a request from the same options used for chat requests.
+    /// Streaming is always disabled, as batches can't stream.
+    pub fn new(custom_id: impl Into<String>, options: &ChatOptions<'_>) -> Result<Self, ChatError> {
+        let body = request_body(&options.clone().stream(false))?;
+        let body = RawValue::from_string(body)
+            .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        Ok(Self {
+            custom_id: custom_id.into(),
+            method: "POST",
+            url: CHAT_COMPLETIONS,
+            body,
+        })
+    }
+}
+
+/// A file uploaded to the Files API.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct UploadedFile {
+    pub id: String,
+    pub bytes: u64,
+    pub filename: String,
+    pub purpose: String,
+    /// When the file was uploaded, as a Unix timestamp in seconds.
+    pub created_at: u64,
+}
+
+/// The status of a batch.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub id: String,
+    pub status: BatchStatus,
+    pub input_file_id: String,
+    /// The file holding the results of the successful requests, once the batch has finished.
+    #[serde(default)]
+    pub output_file_id: Option<String>,
+    /// The file holding the results of the failed requests, if any failed.
+    #[serde(default)]
+    pub error_file_id: Option<String>,
+    #[serde(default)]
+    pub request_counts: RequestCounts,
+    /// When the batch was created, as a Unix timestamp in seconds.
+    pub created_at: u64,
+    /// When the batch expires if it hasn't finished, as a Unix timestamp in seconds.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl Batch {
+    /// Whether processing has stopped, so no more results will be produced.
+    pub fn is_ended(&self) -> bool {
+        matches!(
+            self.status,
+            BatchStatus::Completed
+                | BatchStatus::Failed
+                | BatchStatus::Expired
+                | BatchStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Validating,
+    /// The input file failed validation, so no request was processed.
+    Failed,
+    InProgress,
+    Finalizing,
+    Completed,
+    Expired,
+    Cancelling,
+    Cancelled,
+}
+
+/// How many of a batch's requests have been processed.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct RequestCounts {
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+/// The result of a single request within a batch.
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    pub custom_id: String,
+    pub result: BatchOutcome,
+}
+
+#[derive(Debug, Clone)]
+pub enum BatchOutcome {
+    Succeeded(Box<AggregatedChat>),
+    Errored { kind: String, message: String },
+}
+
+/// A stream of [`BatchResult`]s.
+pub struct BatchResults(BoxStream<'static, Result<BatchResult, BatchError>>);
+
+impl BatchResults {
+    pub fn new(
+        stream: impl Stream<Item = Result<BatchResult, BatchError>> + MaybeSend + 'static,
+    ) -> Self {
+        Self(Box::pin(stream))
+    }
+
+    pub async fn next(&mut self) -> Option<Result<BatchResult, BatchError>> {
+        self.0.next().await
+    }
+
+    /// Collects all remaining results.
+    /// If any error occurs then it will be returned instead.
+    pub async fn collect_all(&mut self) -> Result<Vec<BatchResult>, BatchError> {
+        let mut results = Vec::new();
+
+        while let Some(result) = self.next().await {
+            results.push(result?);
+        }
+
+        Ok(results)
+    }
+}
+
+impl Stream for BatchResults {
+    type Item = Result<BatchResult, BatchError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
+
+impl Deref for BatchResults {
+    type Target = BoxStream<'static, Result<BatchResult, BatchError>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for BatchResults {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[derive(Deserialize)]
+struct WireResult {
+    custom_id: String,
+    #[serde(default)]
+    response: Option<WireResponse>,
+    /// Set when the request couldn't be sent at all.
+    #[serde(default)]
+    error: Option<WireError>,
+}
+
+#[derive(Deserialize)]
+struct WireResponse {
+    status_code: u16,
+    body: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct WireError {
+    #[serde(default, alias = "type")]
+    code: Option<String>,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct WireErrorEnvelope {
+    error: WireError,
+}
+
+#[derive(Deserialize)]
+struct WireCompletion {
+    choices: Vec<WireChoice>,
+    #[serde(default)]
+    usage: Option<WireUsage>,
+}
+
+#[derive(Deserialize)]
+struct WireChoice {
+    message: WireMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WireMessage {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<WireToolCall>,
+}
+
+#[derive(Deserialize)]
+struct WireToolCall {
+    id: String,
+    function: WireFunction,
+}
+
+#[derive(Deserialize)]
+struct WireFunction {
+    name: String,
+    arguments: String,
+}
+
+#[derive(Deserialize)]
+struct WireUsage {
+    #[serde(default)]
+    prompt_tokens: Option<usize>,
+    #[serde(default)]
+    completion_tokens: Option<usize>,
+}
+
+impl TryFrom<WireResult> for BatchResult {
+    type Error = BatchError;
+
+    fn try_from(result: WireResult) -> Result<Self, BatchError> {
+        let outcome = match (result.response, result.error) {
+            (_, Some(error)) => errored(error),
+            (Some(response), None) if !(200..300).contains(&response.status_code) => {
+                match serde_json::from_value::<WireErrorEnvelope>(response.body) {
+                    Ok(envelope) => errored(envelope.error),
+                    Err(_) => BatchOutcome::Errored {
+                        kind: response.status_code.to_string(),
+                        message: String::new(),
+                    },
+                }
+            }
+            (Some(response), None) => {
+                let completion: WireCompletion = serde_json::from_value(response.body)
+                    .map_err(|this| BatchError::ParseError(anyhow::Error::new(this)))?;
+                BatchOutcome::Succeeded(Box::new(completion.into()))
+            }
+            (None, None) => {
+                return Err(BatchError::ParseError(anyhow!(
+                    "The result of \"{}\" has neither a response nor an error.",
+                    result.custom_id
+                )));
+            }
+        };
+
+        Ok(Self {
+            custom_id: result.custom_id,
+            result: outcome,
+        })
+    }
+}
+
+fn errored(error: WireError) -> BatchOutcome {
+    BatchOutcome::Errored {
+        kind: error.code.unwrap_or_default(),
+        message: error.message,
+    }
+}
+
+impl From<WireCompletion> for AggregatedChat {
+    fn from(completion: WireCompletion) -> Self {
+        let mut chat = AggregatedChat::default();
+
+        if let Some(choice) = completion.choices.into_iter().next() {
+            if let Some(reasoning) = choice.message.reasoning_content {
+                chat.push(&ChatChunk::Thinking(reasoning.into()));
+            }
+            if let Some(content) = choice.message.content {
+                chat.push(&ChatChunk::Content(content.into()));
+            }
+            for (index, tool_call) in choice.message.tool_calls.into_iter().enumerate() {
+                chat.push(&ChatChunk::ToolCallDelta(ToolCallDelta {
+                    index,
+                    id: Some(tool_call.id),
+                    name: Some(tool_call.function.name),
+                    arguments: tool_call.function.arguments,
+                }));
+            }
+            if let Some(reason) = choice.finish_reason {
+                chat.push(&ChatChunk::Finish(finish_reason(reason)));
+            }
+        }
+        if let Some(usage) = completion.usage {
+            chat.push(&ChatChunk::Usage(Usage {
+                input_tokens: usage.prompt_tokens,
+                output_tokens: usage.completion_tokens,
+                cached_input_tokens: None,
+                timing: None,
+            }));
+        }
+
+        chat
+    }
+}
+
+fn finish_reason(reason: String) -> FinishReason {
+    match reason.as_str() {
+        "stop" => FinishReason::Stop,
+        "length" => FinishReason::Length,
+        "tool_calls" | "function_call" => FinishReason::ToolCalls,
+        "content_filter" => FinishReason::ContentFilter,
+        _ => FinishReason::Other(reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::models::Message;
+    use http::StatusCode;
+
+    const BATCH: &str = r#"{
+        "id": "batch_abc123",
+        "object": "batch",
+        "endpoint": "/v1/chat/completions",
+        "errors": null,
+        "input_file_id": "file-abc123",
+        "completion_window": "24h",
+        "status": "validating",
+        "output_file_id": null,
+        "error_file_id": null,
+        "created_at": 1711471533,
+        "expires_at": 1711557933,
+        "request_counts": {"total": 0, "completed": 0, "failed": 0}
+    }"#;
+
+    #[tokio::test]
+    async fn test_upload_batch_file() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{"id":"file-abc123","object":"file","bytes":120,"created_at":1711471533,"filename":"batch.jsonl","purpose":"batch"}"#,
+        ));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = [Message::user("Hi")];
+        let requests = [BatchRequest::new(
+            "first",
+            &ChatOptions::new("gpt-4o-mini").messages(&messages),
+        )
+        .unwrap()];
+
+        let file = provider.upload_batch_file(&requests).await.unwrap();
+        assert_eq!(file.id, "file-abc123");
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "https://api.openai.com/v1/files");
+        let upload = String::from_utf8_lossy(request.body());
+        assert!(upload.contains("name=\"purpose\"\r\n\r\nbatch\r\n"));
+        let line = upload
+            .lines()
+            .find(|line| line.starts_with("{\"custom_id\""))
+            .unwrap();
+        let line: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(line["custom_id"], "first");
+        assert_eq!(line["method"], "POST");
+        assert_eq!(line["url"], "/v1/chat/completions");
+        assert_eq!(line["body"]["model"], "gpt-4o-mini");
+        assert_eq!(line["body"]["stream"], false);
+    }
+
+    #[tokio::test]
+    async fn test_create_batch() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(
+                r#"{"id":"file-abc123","object":"file","bytes":120,"created_at":1711471533,"filename":"batch.jsonl","purpose":"batch"}"#,
+            ))
+            .with_response(MockResponse::new(StatusCode::OK).body(BATCH));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = [Message::user("Hi")];
+        let requests = [BatchRequest::new(
+            "first",
+            &ChatOptions::new("gpt-4o-mini").messages(&messages),
+        )
+        .unwrap()];
+
+        let batch = provider.create_batch(&requests).await.unwrap();
+
+        assert_eq!(batch.id, "batch_abc123");
+        assert_eq!(batch.status, BatchStatus::Validating);
+        assert!(!batch.is_ended());
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "https://api.openai.com/v1/batches");
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["input_file_id"], "file-abc123");
+        assert_eq!(body["endpoint"], "/v1/chat/completions");
+        assert_eq!(body["completion_window"], "24h");
+    }
+
+    #[tokio::test]
+    async fn test_batch_results() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(
+                "{\"id\":\"batch_req_1\",\"custom_id\":\"first\",\"response\":{\"status_code\":200,\"request_id\":\"req_1\",\"body\":{\"choices\":[{\"index\":0,\"message\":{\"role\":\"assistant\",\"content\":null,\"tool_calls\":[{\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"{\\\"city\\\":\\\"Paris\\\"}\"}}]},\"finish_reason\":\"tool_calls\"}],\"usage\":{\"prompt_tokens\":10,\"completion_tokens\":5}}},\"error\":null}\n\
+                 {\"id\":\"batch_req_2\",\"custom_id\":\"second\",\"response\":{\"status_code\":200,\"request_id\":\"req_2\",\"body\":{\"choices\":[{\"index\":0,\"message\":{\"role\":\"assistant\",\"content\":\"Hello!\"},\"finish_reason\":\"stop\"}]}},\"error\":null}\n",
+            ))
+            .with_response(MockResponse::new(StatusCode::OK).body(
+                "{\"id\":\"batch_req_3\",\"custom_id\":\"third\",\"response\":{\"status_code\":400,\"request_id\":\"req_3\",\"body\":{\"error\":{\"message\":\"Bad request\",\"type\":\"invalid_request_error\"}}},\"error\":null}\n",
+            ));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let batch: Batch = serde_json::from_str(
+            &BATCH
+                .replace("\"validating\"", "\"completed\"")
+                .replace(
+                    "\"output_file_id\": null",
+                    "\"output_file_id\": \"file-out\"",
+                )
+                .replace("\"error_file_id\": null", "\"error_file_id\": \"file-err\""),
+        )
+        .unwrap();
+        assert!(batch.is_ended());
+
+        let results = provider
+            .batch_results(&batch)
+            .await
+            .unwrap()
+            .collect_all()
+            .await
+            .unwrap();
+
+        // The failed requests are read last, from the error file.
+        assert_eq!(
+            client.last_request().unwrap().uri(),
+            "https://api.openai.com/v1/files/file-err/content"
+        );
+        assert_eq!(results.len(), 3);
+
+        let BatchOutcome::Succeeded(chat) = &results[0].result else {
+            panic!("expected the first request to succeed");
+        };
+        assert_eq!(chat.finish_reason, Some(FinishReason::ToolCalls));
+        assert_eq!(chat.usage.as_ref().unwrap().output_tokens, Some(5));
+        assert_eq!(chat.tool_calls[0].name, "get_weather");
+        assert_eq!(
+            chat.tool_calls[0].arguments_json().unwrap(),
+            serde_json::json!({ "city": "Paris" })
+        );
+
+        let BatchOutcome::Succeeded(chat) = &results[1].result else {
+            panic!("expected the second request to succeed");
+        };
+        assert_eq!(chat.content, "Hello!");
+        assert_eq!(chat.finish_reason, Some(FinishReason::Stop));
+
+        assert_eq!(results[2].custom_id, "third");
+        assert!(matches!(
+            &results[2].result,
+            BatchOutcome::Errored { kind, message }
+                if kind == "invalid_request_error" && message == "Bad request"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_batch_http_error() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::NOT_FOUND).body("not found"));
+
+        let provider = OpenAiProvider::new(client, "test-api-key");
+        let result = provider.get_batch("missing").await;
+
+        assert!(matches!(result, Err(BatchError::RequestError(_))));
+    }
+}
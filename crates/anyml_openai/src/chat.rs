@@ -1,141 +1,163 @@
-use anyhow::anyhow;
 use anyhttp::HttpClient;
 use anyml_core::providers::chat::{
-    ChatChunk, ChatError, ChatOptions, ChatProvider, ChatResponse, ChatStreamError, Thinking,
+    ChatError, ChatOptions, ChatProvider, ChatResponse, ServerTool, Thinking,
 };
+use anyml_core::providers::openai_compat;
 use anyml_macros::json_string;
-use bytes::Bytes;
-use futures::StreamExt;
 use http::Request;
-use secrecy::ExposeSecret;
-use serde::Deserialize;
-use smallvec::SmallVec;
+use secrecy::{ExposeSecret, SecretString};
 
-use crate::OpenAiProvider;
+use crate::{OpenAiChatExt, OpenAiProvider, list_models::THINKING_MODELS};
 
-#[async_trait::async_trait]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl<C: HttpClient> ChatProvider for OpenAiProvider<C> {
     async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
-        let messages_json = options.messages.to_json();
-
-        let body: String = match &options.thinking {
-            Some(Thinking::Effort(effort)) => json_string! {
-                "model": options.model,
-                "messages": @raw messages_json,
-                "stream": options.stream,
-                "max_completion_tokens": options.max_tokens,
-                "reasoning_effort": effort
-            },
-            Some(_) => json_string! {
-                "model": options.model,
-                "messages": @raw messages_json,
-                "stream": options.stream,
-                "max_completion_tokens": options.max_tokens,
-                "reasoning_effort": "medium"
-            },
-            None => json_string! {
-                "model": options.model,
-                "messages": @raw messages_json,
-                "stream": options.stream,
-                "max_tokens": options.max_tokens
-            },
-        };
-
-        let request = Request::post(format!("{}/v1/chat/completions", self.url))
+        let options = options.or_default_model(self.default_model.as_deref());
+        options.with_timeouts(self.send_chat(&options)).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.default_model.as_deref()
+    }
+}
+
+impl<C: HttpClient> OpenAiProvider<C> {
+    /// An empty key is only rejected for the hosted API, as other servers may not need one.
+    fn check_api_key(&self, api_key: &SecretString, url: &str) -> Result<(), ChatError> {
+        if api_key.expose_secret().is_empty() && url == crate::DEFAULT_URL {
+            return Err(ChatError::InvalidOptions {
+                field: "api_key",
+                reason: "it is empty".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn send_chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse<'_>, ChatError> {
+        let overrides = &options.overrides;
+        let url = overrides.url_or(&self.url);
+        let api_key = overrides
+            .api_key_or(&*self.api_key)
+            .await
+            .map_err(ChatError::RequestBuildFailed)?;
+        self.check_api_key(&api_key, url)?;
+        let body = request_body(options)?;
+
+        let request = self
+            .headers
+            .apply(Request::post(format!("{url}{}", self.chat_path)));
+        let request = overrides
+            .headers
+            .apply(request)
             .header(
                 "Authorization",
-                format!("Bearer {}", self.api_key.expose_secret()),
+                format!("Bearer {}", api_key.expose_secret()),
             )
             .body(body.into_bytes())
             .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
 
-        let response = self
-            .client
-            .execute(request)
-            .await
-            .map_err(|this| ChatError::ResponseFetchFailed(this))?;
-
-        if !response.status().is_success() {
-            let err_body = response
-                .bytes()
-                .await
-                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
-
-            return Err(ChatError::RequestError(anyhow!(
-                String::from_utf8_lossy(&err_body).into_owned()
-            )));
-        }
-
-        let stream = response.bytes_stream();
-
-        Ok(ChatResponse::new(
-            stream
-                .map(parse_sse_chunk)
-                .flat_map(futures::stream::iter),
-        ))
+        openai_compat::send_chat(&self.client, request, options.include_raw).await
     }
 }
 
-fn parse_sse_chunk(
-    chunk: Result<bytes::Bytes, anyhow::Error>,
-) -> Vec<Result<ChatChunk, ChatStreamError>> {
-    let chunk = match chunk {
-        Ok(chunk) => chunk,
-        Err(err) => return vec![Err(ChatStreamError::ParseError(err))],
-    };
-    let chunk = String::from_utf8_lossy(&chunk);
-
-    let mut results = Vec::new();
-
-    for event in chunk.split("\n\n") {
-        if let Some(event_body) = event.strip_prefix("data:") {
-            let parsed_event = match serde_json::from_str::<OpenAiChunkResponse>(event_body) {
-                Ok(parsed_event) => parsed_event,
-                Err(err) => {
-                    results.push(Err(ChatStreamError::ParseError(anyhow::Error::new(err))));
-                    continue;
-                }
-            };
-
-            if let Some(choice) = parsed_event.choices.first() {
-                if let Some(ref reasoning) = choice.delta.reasoning_content {
-                    if !reasoning.is_empty() {
-                        results.push(Ok(ChatChunk::Thinking(reasoning.clone())));
-                    }
-                }
-                if !choice.delta.content.is_empty() {
-                    results.push(Ok(ChatChunk::Content(choice.delta.content.clone())));
-                }
-            }
-        }
+/// Builds the body of a `/v1/chat/completions` request.
+pub(crate) fn request_body(options: &ChatOptions<'_>) -> Result<String, ChatError> {
+    options.validate()?;
+    if options.prefill.is_some() {
+        return Err(ChatError::Unsupported { feature: "prefill" });
+    }
+    // Unknown models are passed through and left to the API to reject.
+    if let Some(thinking) = &options.thinking
+        && let Some(modes) = THINKING_MODELS.get(options.model)
+        && !modes.supports(thinking)
+    {
+        return Err(ChatError::InvalidOptions {
+            field: "thinking",
+            reason: format!("{} doesn't support {thinking:?}", options.model),
+        });
     }
 
-    results
-}
-
-#[derive(Deserialize)]
-struct OpenAiChunkResponse {
-    choices: SmallVec<[OpenAiChunkResponseChoice; 1]>,
-}
-
-#[derive(Deserialize)]
-struct OpenAiChunkResponseChoice {
-    delta: OpenAiChunkResponseDelta,
-}
+    let default_ext = OpenAiChatExt::default();
+    let ext = options.extensions.get().unwrap_or(&default_ext);
+    ext.validate()?;
+
+    let messages_json = openai_compat::messages_json(&options.messages, options.system, None)?;
+    let tools_json = openai_compat::tools_json(options.tools);
+    let response_format = options
+        .response_format
+        .as_ref()
+        .map(openai_compat::response_format_json);
+
+    // Reasoning is opt-in: without `thinking`, no reasoning keys are sent, as some
+    // OpenAI-compatible backends reject them.
+    let body: String = json_string! {
+        "model": options.model,
+        "messages": @raw messages_json,
+        "stream": options.stream,
+        if options.stream {
+            "stream_options": { "include_usage": true }
+        }
+        if options.thinking.is_some() {
+            "max_completion_tokens"?: options.max_tokens
+        } else {
+            "max_tokens"?: options.max_tokens
+        }
+        if let Some(Thinking::Effort(effort)) = &options.thinking {
+            "reasoning_effort": effort
+        } else if options.thinking.is_some() {
+            "reasoning_effort": "medium"
+        }
+        if options.logprobs {
+            "logprobs": true
+        }
+        "top_logprobs"?: options.top_logprobs,
+        if let Some(audio) = &options.audio_output {
+            "modalities": ["text", "audio"],
+            "audio": {
+                "voice": audio.voice,
+                "format": audio.format
+            }
+        }
+        if let Some(tools) = &tools_json {
+            "tools": @raw tools,
+            "tool_choice"?: options.tool_choice.as_ref().map(openai_compat::tool_choice_json),
+            "parallel_tool_calls"?: options.parallel_tool_calls
+        }
+        "response_format"?: response_format,
+        // Search is built into the search models, and cited through annotations.
+        if options
+            .server_tools
+            .iter()
+            .any(|tool| matches!(tool, ServerTool::WebSearch { .. }))
+        {
+            "web_search_options": {}
+        }
+        "temperature"?: ext.temperature,
+        "top_p"?: ext.top_p,
+        "frequency_penalty"?: ext.frequency_penalty,
+        "presence_penalty"?: ext.presence_penalty,
+        "seed"?: ext.seed,
+        "user"?: ext.user,
+        @merge &options.extra
+    };
 
-#[derive(Deserialize)]
-struct OpenAiChunkResponseDelta {
-    #[serde(default)]
-    content: String,
-    #[serde(default)]
-    reasoning_content: Option<String>,
+    Ok(body)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use anyhow::anyhow;
     use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::models::{Audio, AudioOutput, Image, Message, Tool, ToolCall};
+    use anyml_core::providers::chat::{
+        ChatChunk, FinishReason, ResponseFormat, ServerToolResult, ToolChoice,
+    };
+    use anyml_core::providers::{ApiKeyProvider, RequestOverrides};
     use http::StatusCode;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[tokio::test]
     async fn test_chat_success() {
@@ -154,6 +176,98 @@ mod tests {
         assert!(matches!(chunk, ChatChunk::Content(ref s) if s == "Hello!"));
     }
 
+    #[tokio::test]
+    async fn test_chat_include_raw() {
+        let event = "data:{\"choices\":[{\"delta\":{\"content\":\"Hello!\"},\"index\":0}]}";
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(format!("{event}\n\n")));
+
+        let provider = OpenAiProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4")
+            .messages(messages)
+            .include_raw(true);
+
+        let mut response = provider.chat(&options).await.unwrap();
+
+        let raw = response.next().await.unwrap().unwrap();
+        assert!(matches!(raw, ChatChunk::Raw { ref provider_event } if provider_event == event));
+        let chunk = response.next().await.unwrap().unwrap();
+        assert!(matches!(chunk, ChatChunk::Content(ref s) if s == "Hello!"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_ends_at_done() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"},\"index\":0}]}\n\n\
+             data: {\"choices\":[{\"delta\":{\"content\":\"!\"},\"index\":0}]}\n\n\
+             data: [DONE]\n\n",
+        ));
+
+        let provider = OpenAiProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Hello!");
+        assert!(response.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_finish_reason_and_usage() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hello!\"},\"index\":0}]}\n\n\
+             data: {\"choices\":[{\"delta\":{},\"index\":0,\"finish_reason\":\"length\"}]}\n\n\
+             data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2,\"prompt_tokens_details\":{\"cached_tokens\":8}}}\n\n\
+             data: [DONE]\n\n",
+        ));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.finish_reason, Some(FinishReason::Length));
+        let usage = result.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(9));
+        assert_eq!(usage.output_tokens, Some(2));
+        assert_eq!(usage.cached_input_tokens, Some(8));
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["stream_options"]["include_usage"], true);
+    }
+
+    #[tokio::test]
+    async fn test_chat_response_format() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body("data: [DONE]\n\n"));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4o")
+            .messages(messages)
+            .response_format(ResponseFormat::json_schema(
+                serde_json::json!({ "type": "object" }),
+            ));
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["response_format"],
+            serde_json::json!({
+                "type": "json_schema",
+                "json_schema": { "name": "response", "schema": { "type": "object" } },
+            })
+        );
+    }
+
     #[tokio::test]
     async fn test_chat_http_error() {
         let client = MockHttpClient::new()
@@ -168,6 +282,45 @@ mod tests {
         assert!(matches!(result, Err(ChatError::RequestError(_))));
     }
 
+    #[tokio::test]
+    async fn test_chat_server_error() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::SERVICE_UNAVAILABLE).body(
+                r#"{"error":{"message":"The server is overloaded.","type":"server_error"}}"#,
+            ));
+
+        let provider = OpenAiProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(
+            result,
+            Err(ChatError::ProviderUnavailable {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_context_length_exceeded() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::BAD_REQUEST).body(
+                r#"{"error":{"message":"This model's maximum context length is 8192 tokens. However, your messages resulted in 9000 tokens.","type":"invalid_request_error","param":"messages","code":"context_length_exceeded"}}"#,
+            ),
+        );
+
+        let provider = OpenAiProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(result, Err(ChatError::ContextLengthExceeded(_))));
+    }
+
     #[tokio::test]
     async fn test_chat_request_headers() {
         let client = MockHttpClient::new().with_response(
@@ -189,7 +342,142 @@ mod tests {
         );
     }
 
+    struct RotatingKeys(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl ApiKeyProvider for RotatingKeys {
+        async fn current_key(&self) -> anyhow::Result<SecretString> {
+            let n = self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("key-{n}").into())
+        }
+    }
+
+    struct NoKey;
+
+    #[async_trait::async_trait]
+    impl ApiKeyProvider for NoKey {
+        async fn current_key(&self) -> anyhow::Result<SecretString> {
+            Err(anyhow!("token expired"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_api_key_provider() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "unused")
+            .api_key_provider(RotatingKeys(AtomicUsize::new(0)));
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer key-0"
+        );
+    }
+
     #[tokio::test]
+    async fn test_chat_api_key_provider_error() {
+        let client = MockHttpClient::new();
+        let provider = OpenAiProvider::new(client.clone(), "unused").api_key_provider(NoKey);
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(result, Err(ChatError::RequestBuildFailed(_))));
+        assert!(client.last_request().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_request_overrides() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider =
+            OpenAiProvider::new(client.clone(), "my-secret-key").header("x-team", "research");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages).overrides(
+            RequestOverrides::new()
+                .api_key("tenant-key")
+                .url("https://tenant.example.com")
+                .header("x-tenant", "acme"),
+        );
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://tenant.example.com/v1/chat/completions"
+        );
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer tenant-key"
+        );
+        assert_eq!(request.headers().get("x-tenant").unwrap(), "acme");
+        assert_eq!(request.headers().get("x-team").unwrap(), "research");
+    }
+
+    #[tokio::test]
+    async fn test_chat_path() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "my-secret-key")
+            .url("https://gateway.example.com")
+            .chat_path("/openai/deployments/gpt-4/chat/completions?api-version=2024-10-21");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://gateway.example.com/openai/deployments/gpt-4/chat/completions?api-version=2024-10-21"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_default_headers() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "my-secret-key")
+            .header("Helicone-Auth", "Bearer helicone-key")
+            .header("x-team", "research");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.headers().get("Helicone-Auth").unwrap(),
+            "Bearer helicone-key"
+        );
+        assert_eq!(request.headers().get("x-team").unwrap(), "research");
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer my-secret-key"
+        );
+    }
+
+    #[tokio::test]
+    #[allow(deprecated)]
     async fn test_chat_open_router() {
         let client = MockHttpClient::new().with_response(
             MockResponse::new(StatusCode::OK)
@@ -228,4 +516,479 @@ mod tests {
         assert_eq!(result.content, "Hello!");
         assert_eq!(result.thinking.as_deref(), Some("Let me think..."));
     }
+
+    #[tokio::test]
+    async fn test_chat_with_tool_calls() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data:{\"choices\":[{\"delta\":{\"content\":null,\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"\"}}]}}]}\n\n\
+             data:{\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"city\\\":\"}}]}}]}\n\n\
+             data:{\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"Paris\\\"}\"}}]}}]}\n\n",
+        ));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["What's the weather in Paris?".into()];
+        let tools = &[Tool::new(
+            "get_weather",
+            serde_json::json!({ "type": "object", "properties": { "city": { "type": "string" } } }),
+        )];
+        let options = ChatOptions::new("gpt-4").messages(messages).tools(tools);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert!(result.content.is_empty());
+        assert_eq!(result.tool_calls.len(), 1);
+        assert_eq!(result.tool_calls[0].id, "call_1");
+        assert_eq!(result.tool_calls[0].name, "get_weather");
+        assert_eq!(
+            result.tool_calls[0].arguments_json().unwrap(),
+            serde_json::json!({ "city": "Paris" })
+        );
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(body["tools"][0]["function"]["parameters"]["type"], "object");
+        assert!(body.get("tool_choice").is_none());
+        assert!(body.get("parallel_tool_calls").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_tool_choice() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body("data: [DONE]\n\n"));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["What's the weather in Paris?".into()];
+        let tools = &[Tool::new(
+            "get_weather",
+            serde_json::json!({ "type": "object" }),
+        )];
+        let options = ChatOptions::new("gpt-4")
+            .messages(messages)
+            .tools(tools)
+            .tool_choice(ToolChoice::tool("get_weather"))
+            .parallel_tool_calls(false);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["tool_choice"],
+            serde_json::json!({ "type": "function", "function": { "name": "get_weather" } })
+        );
+        assert_eq!(body["parallel_tool_calls"], false);
+    }
+
+    #[tokio::test]
+    async fn test_chat_tool_choice_unknown_tool() {
+        let client = MockHttpClient::new();
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let tools = &[Tool::new(
+            "get_weather",
+            serde_json::json!({ "type": "object" }),
+        )];
+        let options = ChatOptions::new("gpt-4")
+            .messages(messages)
+            .tools(tools)
+            .tool_choice(ToolChoice::tool("get_time"));
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(
+            result,
+            Err(ChatError::InvalidOptions {
+                field: "tool_choice",
+                ..
+            })
+        ));
+        assert!(client.last_request().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_tool_results() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Sunny.\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &[
+            Message::user("What's the weather in Paris?"),
+            Message::assistant("").tool_calls(vec![ToolCall::new(
+                "call_1",
+                "get_weather",
+                "{\"city\":\"Paris\"}",
+            )]),
+            Message::tool("call_1", "sunny"),
+        ];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        let messages = &body["messages"];
+
+        assert_eq!(messages[1]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(messages[1]["tool_calls"][0]["type"], "function");
+        assert_eq!(
+            messages[1]["tool_calls"][0]["function"]["arguments"],
+            "{\"city\":\"Paris\"}"
+        );
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["tool_call_id"], "call_1");
+        assert_eq!(messages[2]["content"], "sunny");
+    }
+
+    #[tokio::test]
+    async fn test_chat_system_prompt() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4")
+            .system("Be concise.")
+            .messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][0]["content"], "Be concise.");
+        assert_eq!(body["messages"][1]["role"], "user");
+    }
+
+    #[tokio::test]
+    async fn test_chat_extra_fields() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4")
+            .messages(messages)
+            .extra("user", "user-1234")
+            .extra("seed", 7);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+        assert_eq!(body["user"], "user-1234");
+        assert_eq!(body["seed"], 7);
+        assert_eq!(body["model"], "gpt-4");
+    }
+
+    #[tokio::test]
+    async fn test_chat_extension() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4")
+            .messages(messages)
+            .extension(OpenAiChatExt::new().temperature(0.5).seed(7));
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+        assert_eq!(body["temperature"], 0.5);
+        assert_eq!(body["seed"], 7);
+        assert!(body.get("top_p").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_extension_invalid() {
+        let provider = OpenAiProvider::new(MockHttpClient::new(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4")
+            .messages(messages)
+            .extension(OpenAiChatExt::new().temperature(3.0));
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(
+            result,
+            Err(ChatError::InvalidOptions {
+                field: "temperature",
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_web_search() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data:{\"choices\":[{\"delta\":{\"content\":\"Rust is a language.\"}}]}\n\n\
+             data:{\"choices\":[{\"delta\":{\"annotations\":[{\"type\":\"url_citation\",\"url_citation\":{\"url\":\"https://www.rust-lang.org\"}}]}}]}\n\n",
+        ));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["What is Rust?".into()];
+        let options = ChatOptions::new("gpt-4o-search-preview")
+            .messages(messages)
+            .server_tool(ServerTool::web_search());
+
+        let chat = provider
+            .chat(&options)
+            .await
+            .unwrap()
+            .aggregate()
+            .await
+            .unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["web_search_options"], serde_json::json!({}));
+
+        assert_eq!(chat.content, "Rust is a language.");
+        assert_eq!(
+            chat.server_tool_results,
+            [ServerToolResult {
+                tool_use_id: None,
+                content: serde_json::json!({
+                    "type": "url_citation",
+                    "url_citation": { "url": "https://www.rust-lang.org" },
+                }),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_prefill_unsupported() {
+        let client = MockHttpClient::new();
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages).prefill("{");
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(
+            result,
+            Err(ChatError::Unsupported { feature: "prefill" })
+        ));
+        assert!(client.last_request().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_without_thinking_sends_no_reasoning() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages).max_tokens(100);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+        assert_eq!(body["max_tokens"], 100);
+        assert!(body.get("max_completion_tokens").is_none());
+        assert!(body.get("reasoning").is_none());
+        assert!(body.get("reasoning_effort").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_omits_unset_max_tokens() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("gpt-4").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+        assert!(body.get("max_tokens").is_none());
+        assert!(body.get("max_completion_tokens").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_thinking_sends_reasoning_effort() {
+        for (thinking, expected) in [
+            (Thinking::effort("low"), "low"),
+            (Thinking::enabled(), "medium"),
+        ] {
+            let client = MockHttpClient::new().with_response(
+                MockResponse::new(StatusCode::OK)
+                    .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+            );
+
+            let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+            let messages = &["Hi".into()];
+            let options = ChatOptions::new("o3-mini")
+                .messages(messages)
+                .max_tokens(100)
+                .thinking(thinking);
+
+            provider.chat(&options).await.unwrap();
+
+            let request = client.last_request().unwrap();
+            let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+            assert_eq!(body["reasoning_effort"], expected);
+            assert_eq!(body["max_completion_tokens"], 100);
+            assert!(body.get("max_tokens").is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_thinking_unsupported() {
+        for thinking in [Thinking::budget_tokens(2048), Thinking::effort("xhigh")] {
+            let client = MockHttpClient::new();
+            let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+            let messages = &["Hi".into()];
+            let options = ChatOptions::new("o3").messages(messages).thinking(thinking);
+
+            let result = provider.chat(&options).await;
+
+            assert!(matches!(
+                result,
+                Err(ChatError::InvalidOptions { field: "thinking", reason }) if reason.starts_with("o3 ")
+            ));
+            assert!(client.last_request().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_logprobs() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data:{\"choices\":[{\"delta\":{\"content\":\"Yes\"},\"logprobs\":{\"content\":[{\"token\":\"Yes\",\"logprob\":-0.01,\"bytes\":[89,101,115],\"top_logprobs\":[{\"token\":\"Yes\",\"logprob\":-0.01,\"bytes\":[89,101,115]},{\"token\":\"No\",\"logprob\":-4.6,\"bytes\":[78,111]}]}]}}]}\n\n\
+             data:{\"choices\":[{\"delta\":{},\"logprobs\":null}]}\n\n",
+        ));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Is the sky blue? Answer Yes or No.".into()];
+        let options = ChatOptions::new("gpt-4o")
+            .messages(messages)
+            .top_logprobs(2);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let aggregated = response.aggregate().await.unwrap();
+
+        assert_eq!(aggregated.content, "Yes");
+        assert_eq!(aggregated.logprobs.len(), 1);
+        assert_eq!(aggregated.logprobs[0].token, "Yes");
+        assert_eq!(aggregated.logprobs[0].bytes.as_deref(), Some(&b"Yes"[..]));
+        assert_eq!(aggregated.logprobs[0].top_logprobs[1].token, "No");
+        assert_eq!(aggregated.logprobs[0].top_logprobs[1].logprob, -4.6);
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["logprobs"], true);
+        assert_eq!(body["top_logprobs"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_chat_audio_input_parts() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = [
+            Message::user("What is in this recording?").part(Audio::new(b"RIFF", "wav")),
+            Message::assistant("A greeting."),
+        ];
+        let options = ChatOptions::new("gpt-4o-audio-preview")
+            .system("Be brief.")
+            .messages(&messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        let messages = body["messages"].as_array().unwrap();
+
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(
+            messages[1]["content"],
+            serde_json::json!([
+                { "type": "text", "text": "What is in this recording?" },
+                { "type": "input_audio", "input_audio": { "data": "UklGRg==", "format": "wav" } },
+            ])
+        );
+        assert!(messages[1].get("parts").is_none());
+        assert_eq!(messages[2]["content"], "A greeting.");
+    }
+
+    #[tokio::test]
+    async fn test_chat_image_parts() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data:{\"choices\":[{\"delta\":{\"content\":\"A cat.\"}}]}\n\n"),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages =
+            [Message::user("What is in this picture?").part(Image::new(b"PNG", "image/png"))];
+        let options = ChatOptions::new("gpt-4o").messages(&messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"][1],
+            serde_json::json!({
+                "type": "image_url",
+                "image_url": { "url": "data:image/png;base64,UE5H" },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_audio_output() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data:{\"choices\":[{\"delta\":{\"audio\":{\"id\":\"audio_1\",\"transcript\":\"Hel\"}}}]}\n\n\
+             data:{\"choices\":[{\"delta\":{\"audio\":{\"data\":\"AAEC\"}}}]}\n\n\
+             data:{\"choices\":[{\"delta\":{\"audio\":{\"transcript\":\"lo\",\"data\":\"AwQ=\"}}}]}\n\n",
+        ));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let messages = &["Say hello".into()];
+        let options = ChatOptions::new("gpt-4o-audio-preview")
+            .messages(messages)
+            .audio_output(AudioOutput::new("alloy", "pcm16"));
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let aggregated = response.aggregate().await.unwrap();
+
+        assert_eq!(aggregated.content, "Hello");
+        assert_eq!(aggregated.audio, [0, 1, 2, 3, 4]);
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["modalities"], serde_json::json!(["text", "audio"]));
+        assert_eq!(body["audio"]["voice"], "alloy");
+        assert_eq!(body["audio"]["format"], "pcm16");
+    }
 }
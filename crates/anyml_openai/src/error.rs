@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+/// Errors returned by the Files and Batch APIs on [`crate::OpenAiProvider`].
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("Failed to build the request: {0}.")]
+    RequestBuildFailed(#[source] anyhow::Error),
+
+    #[error("Failed to retrieve the response: {0}.")]
+    ResponseFetchFailed(#[source] anyhow::Error),
+
+    #[error("The request failed: {0}.")]
+    RequestError(#[source] anyhow::Error),
+
+    #[error("Failed to parse response: {0}.")]
+    ParseError(#[source] anyhow::Error),
+}
@@ -0,0 +1,79 @@
+use std::ops::RangeInclusive;
+
+use anyml_core::providers::chat::ChatError;
+use serde::Serialize;
+
+/// OpenAI-specific chat options, attached with
+/// [`ChatOptions::extension`](anyml_core::providers::chat::ChatOptions::extension).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OpenAiChatExt {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    pub seed: Option<u64>,
+    pub user: Option<String>,
+}
+
+impl OpenAiChatExt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the sampling temperature, between `0.0` and `2.0`.
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Sets the nucleus sampling probability mass, between `0.0` and `1.0`.
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Penalizes tokens by how often they already appear, between `-2.0` and `2.0`.
+    pub fn frequency_penalty(mut self, frequency_penalty: f32) -> Self {
+        self.frequency_penalty = Some(frequency_penalty);
+        self
+    }
+
+    /// Penalizes tokens which already appear, between `-2.0` and `2.0`.
+    pub fn presence_penalty(mut self, presence_penalty: f32) -> Self {
+        self.presence_penalty = Some(presence_penalty);
+        self
+    }
+
+    /// Samples deterministically, on a best effort basis.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Identifies the end user, for OpenAI's abuse monitoring.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ChatError> {
+        check_range("temperature", self.temperature, 0.0..=2.0)?;
+        check_range("top_p", self.top_p, 0.0..=1.0)?;
+        check_range("frequency_penalty", self.frequency_penalty, -2.0..=2.0)?;
+        check_range("presence_penalty", self.presence_penalty, -2.0..=2.0)
+    }
+}
+
+fn check_range(
+    field: &'static str,
+    value: Option<f32>,
+    range: RangeInclusive<f32>,
+) -> Result<(), ChatError> {
+    match value {
+        Some(value) if !range.contains(&value) => Err(ChatError::InvalidOptions {
+            field,
+            reason: format!("{value} is outside of {range:?}"),
+        }),
+        _ => Ok(()),
+    }
+}
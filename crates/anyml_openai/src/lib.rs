@@ -1,18 +1,35 @@
 use std::borrow::Cow;
 
 use anyhttp::HttpClient;
+use anyml_core::providers::{ApiKeyProvider, DefaultHeaders};
 use secrecy::SecretString;
 
+mod batches;
 mod chat;
+mod error;
+mod ext;
 mod list_models;
+mod transcription;
+
+pub use batches::{
+    Batch, BatchOutcome, BatchRequest, BatchResult, BatchResults, BatchStatus, RequestCounts,
+    UploadedFile,
+};
+pub use error::BatchError;
+pub use ext::OpenAiChatExt;
 
 const DEFAULT_URL: &str = "https://api.openai.com";
+const DEFAULT_CHAT_PATH: &str = "/v1/chat/completions";
 const OPEN_ROUTER_URL: &str = "https://openrouter.ai/api";
 
 pub struct OpenAiProvider<C: HttpClient> {
     client: C,
     url: Cow<'static, str>,
-    api_key: SecretString,
+    chat_path: Cow<'static, str>,
+    default_model: Option<Cow<'static, str>>,
+    headers: DefaultHeaders,
+    api_key: Box<dyn ApiKeyProvider>,
+    chat_models_only: bool,
 }
 
 impl<C: HttpClient> OpenAiProvider<C> {
@@ -20,15 +37,26 @@ impl<C: HttpClient> OpenAiProvider<C> {
         Self {
             client,
             url: Cow::Borrowed(DEFAULT_URL),
-            api_key: api_key.into(),
+            chat_path: Cow::Borrowed(DEFAULT_CHAT_PATH),
+            default_model: None,
+            headers: DefaultHeaders::new(),
+            api_key: Box::new(api_key.into()),
+            chat_models_only: false,
         }
     }
 
+    #[deprecated(
+        note = "use `anyml_openrouter::OpenRouterProvider`, which supports OpenRouter's headers, routing and reasoning output"
+    )]
     pub fn open_router(client: C, api_key: impl Into<SecretString>) -> Self {
         Self {
             client,
             url: Cow::Borrowed(OPEN_ROUTER_URL),
-            api_key: api_key.into(),
+            chat_path: Cow::Borrowed(DEFAULT_CHAT_PATH),
+            default_model: None,
+            headers: DefaultHeaders::new(),
+            api_key: Box::new(api_key.into()),
+            chat_models_only: false,
         }
     }
 
@@ -37,8 +65,46 @@ impl<C: HttpClient> OpenAiProvider<C> {
         self
     }
 
+    /// Sets the path chat requests are sent to, after `url`. Defaults to `/v1/chat/completions`.
+    ///
+    /// It may end with a query string, for gateways which need one (e.g. `?api-version=`).
+    pub fn chat_path(mut self, path: impl Into<Cow<'static, str>>) -> Self {
+        self.chat_path = path.into();
+        self
+    }
+
+    /// Sets the model chats are sent to when their options leave it to the provider,
+    /// as with [`ChatOptions::default_model`](anyml_core::providers::chat::ChatOptions::default_model).
+    pub fn with_default_model(mut self, model: impl Into<Cow<'static, str>>) -> Self {
+        self.default_model = Some(model.into());
+        self
+    }
+
+    /// Adds a header sent with every request, e.g. for a proxy or gateway.
+    pub fn header(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
     pub fn api_key(mut self, api_key: impl Into<SecretString>) -> Self {
-        self.api_key = api_key.into();
+        self.api_key = Box::new(api_key.into());
+        self
+    }
+
+    /// Fetches the API key before each request, for keys which rotate or expire.
+    pub fn api_key_provider(mut self, provider: impl ApiKeyProvider + 'static) -> Self {
+        self.api_key = Box::new(provider);
+        self
+    }
+
+    /// Leaves models which can't chat, such as Whisper, TTS and embedding models,
+    /// out of the listed models.
+    pub fn chat_models_only(mut self, chat_models_only: bool) -> Self {
+        self.chat_models_only = chat_models_only;
         self
     }
 }
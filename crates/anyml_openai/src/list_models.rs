@@ -1,11 +1,14 @@
 use anyhow::anyhow;
 use anyhttp::HttpClient;
 use anyml_core::{
-    models::{Model, ThinkingModes},
-    providers::list_models::{ListModelsError, ListModelsProvider},
+    models::{Model, ModelCapabilities, ThinkingModes},
+    providers::{
+        HealthCheckProvider, HealthStatus,
+        list_models::{ListModelsError, ListModelsProvider},
+    },
 };
 use bytes::Bytes;
-use http::Request;
+use http::{Request, StatusCode};
 use phf::phf_map;
 use secrecy::ExposeSecret;
 use serde::Deserialize;
@@ -14,7 +17,7 @@ use crate::OpenAiProvider;
 
 type StaticThinkingModes = ThinkingModes<&'static [&'static str]>;
 
-static THINKING_MODELS: phf::Map<&'static str, StaticThinkingModes> = phf_map! {
+pub(crate) static THINKING_MODELS: phf::Map<&'static str, StaticThinkingModes> = phf_map! {
     "o1" => StaticThinkingModes { modes: &["low", "medium", "high"], budget: None },
     "o1-mini" => StaticThinkingModes { modes: &["low", "medium", "high"], budget: None },
     "o1-preview" => StaticThinkingModes { modes: &["low", "medium", "high"], budget: None },
@@ -28,13 +31,111 @@ static THINKING_MODELS: phf::Map<&'static str, StaticThinkingModes> = phf_map! {
     "gpt-5.2" => StaticThinkingModes { modes: &["none", "low", "medium", "high", "xhigh"], budget: None },
 };
 
-#[async_trait::async_trait]
+const CHAT: ModelCapabilities = ModelCapabilities {
+    vision: false,
+    tool_use: true,
+    json_mode: true,
+    thinking: false,
+    embeddings: false,
+};
+const VISION: ModelCapabilities = ModelCapabilities {
+    vision: true,
+    ..CHAT
+};
+const REASONING: ModelCapabilities = ModelCapabilities {
+    thinking: true,
+    ..VISION
+};
+const EMBEDDINGS: ModelCapabilities = ModelCapabilities {
+    vision: false,
+    tool_use: false,
+    json_mode: false,
+    thinking: false,
+    embeddings: true,
+};
+
+/// The capabilities of OpenAI's models, by ID without a snapshot date.
+static CAPABILITIES: phf::Map<&'static str, ModelCapabilities> = phf_map! {
+    "gpt-3.5-turbo" => CHAT,
+    "gpt-4" => CHAT,
+    "gpt-4-turbo" => VISION,
+    "gpt-4o" => VISION,
+    "gpt-4o-mini" => VISION,
+    "gpt-4.1" => VISION,
+    "gpt-4.1-mini" => VISION,
+    "gpt-4.1-nano" => VISION,
+    "o1" => REASONING,
+    "o1-mini" => ModelCapabilities { vision: false, tool_use: false, json_mode: false, ..REASONING },
+    "o1-preview" => ModelCapabilities { vision: false, tool_use: false, json_mode: false, ..REASONING },
+    "o3" => REASONING,
+    "o3-mini" => ModelCapabilities { vision: false, ..REASONING },
+    "o3-pro" => REASONING,
+    "o4-mini" => REASONING,
+    "gpt-5" => REASONING,
+    "gpt-5-mini" => REASONING,
+    "gpt-5-nano" => REASONING,
+    "gpt-5.1" => REASONING,
+    "gpt-5.2" => REASONING,
+    "text-embedding-3-small" => EMBEDDINGS,
+    "text-embedding-3-large" => EMBEDDINGS,
+    "text-embedding-ada-002" => EMBEDDINGS,
+};
+
+/// Prefixes and infixes of the IDs of models which can't chat.
+const NON_CHAT_PREFIXES: &[&str] = &[
+    "whisper",
+    "tts",
+    "dall-e",
+    "gpt-image",
+    "sora",
+    "text-embedding",
+    "omni-moderation",
+    "text-moderation",
+    "davinci",
+    "babbage",
+];
+const NON_CHAT_INFIXES: &[&str] = &["-transcribe", "-tts", "-realtime"];
+
+/// Whether the model can be sent chats, as far as its ID tells.
+fn is_chat_model(id: &str) -> bool {
+    !NON_CHAT_PREFIXES
+        .iter()
+        .any(|prefix| id.starts_with(prefix))
+        && !NON_CHAT_INFIXES.iter().any(|infix| id.contains(infix))
+}
+
+/// Strips the snapshot date from a model's ID, e.g. `"o3-2025-04-16"` to `"o3"`.
+fn base_id(id: &str) -> &str {
+    let bytes = id.as_bytes();
+    let is_date = bytes.len() > 11 && {
+        let date = &bytes[bytes.len() - 11..];
+        date[0] == b'-'
+            && date[5] == b'-'
+            && date[8] == b'-'
+            && date
+                .iter()
+                .enumerate()
+                .all(|(i, byte)| matches!(i, 0 | 5 | 8) || byte.is_ascii_digit())
+    };
+    if is_date { &id[..id.len() - 11] } else { id }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl<C: HttpClient> ListModelsProvider for OpenAiProvider<C> {
     async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
-        let request = Request::get(format!("{}/v1/models", self.url))
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(ListModelsError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/v1/models", self.url)))
             .header(
                 "Authorization",
-                format!("Bearer {}", self.api_key.expose_secret()),
+                format!("Bearer {}", api_key.expose_secret()),
             )
             .body(Vec::new())
             .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
@@ -67,24 +168,92 @@ impl<C: HttpClient> ListModelsProvider for OpenAiProvider<C> {
         let models = openai_response
             .data
             .into_iter()
-            .map(|m| {
-                let thinking = THINKING_MODELS
-                    .get(m.id.as_str())
-                    .map(|s| ThinkingModes {
-                        modes: s.modes.iter().map(|s| (*s).into()).collect(),
-                        budget: s.budget,
-                    });
-                Model {
-                    id: m.id,
-                    parameters: None,
-                    quantization: None,
-                    thinking,
-                }
-            })
+            .filter(|model| !self.chat_models_only || is_chat_model(&model.id))
+            .map(OpenAiModel::into_model)
             .collect();
 
         Ok(models)
     }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(ListModelsError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/v1/models/{id}", self.url)))
+            .header(
+                "Authorization",
+                format!("Bearer {}", api_key.expose_secret()),
+            )
+            .body(Vec::new())
+            .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ListModelsError::NotFound { id: id.to_owned() });
+        }
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(ListModelsError::ResponseFetchFailed(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        let model: OpenAiModel = serde_json::from_slice(&body)
+            .map_err(|e| ListModelsError::ParseError(anyhow::Error::new(e)))?;
+
+        Ok(model.into_model())
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> HealthCheckProvider for OpenAiProvider<C> {
+    /// Lists the models, which is free and checks the API key.
+    async fn health(&self) -> HealthStatus {
+        let api_key = match self.api_key.current_key().await {
+            Ok(api_key) => api_key,
+            Err(err) => {
+                return HealthStatus::Unhealthy {
+                    reason: format!("{err:#}"),
+                };
+            }
+        };
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/v1/models", self.url)))
+            .header(
+                "Authorization",
+                format!("Bearer {}", api_key.expose_secret()),
+            )
+            .body(Vec::new());
+
+        match request {
+            Ok(request) => HealthStatus::probe(&self.client, request).await,
+            Err(err) => HealthStatus::Unhealthy {
+                reason: err.to_string(),
+            },
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -97,6 +266,27 @@ struct OpenAiModel {
     id: String,
 }
 
+impl OpenAiModel {
+    /// Fills in the thinking modes and capabilities of known models, including
+    /// their dated snapshots.
+    fn into_model(self) -> Model {
+        let base_id = base_id(&self.id);
+        let thinking = THINKING_MODELS.get(base_id).map(|s| ThinkingModes {
+            modes: s.modes.iter().map(|s| (*s).into()).collect(),
+            budget: s.budget,
+        });
+        let capabilities = CAPABILITIES.get(base_id).copied().unwrap_or_default();
+        Model {
+            id: self.id,
+            parameters: None,
+            quantization: None,
+            thinking,
+            capabilities,
+            ..Default::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,6 +308,48 @@ mod tests {
         assert_eq!(models[1].id, "gpt-3.5-turbo");
     }
 
+    #[tokio::test]
+    async fn test_list_models_capabilities() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{"data":[{"id":"o3-2025-04-16"},{"id":"gpt-4.1"},{"id":"whisper-1"},{"id":"text-embedding-3-small"}]}"#,
+        ));
+
+        let provider = OpenAiProvider::new(client, "test-api-key");
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 4);
+        assert_eq!(
+            models[0].thinking.as_ref().unwrap().modes,
+            ["low", "medium", "high"]
+        );
+        assert!(models[0].capabilities.thinking);
+        assert!(models[1].capabilities.vision && models[1].capabilities.tool_use);
+        assert!(models[1].thinking.is_none());
+        assert_eq!(models[2].capabilities, ModelCapabilities::default());
+        assert!(models[3].capabilities.embeddings);
+    }
+
+    #[tokio::test]
+    async fn test_list_models_chat_models_only() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{"data":[{"id":"gpt-4o"},{"id":"whisper-1"},{"id":"tts-1-hd"},{"id":"gpt-4o-mini-transcribe"},{"id":"text-embedding-3-small"},{"id":"o4-mini"}]}"#,
+        ));
+
+        let provider = OpenAiProvider::new(client, "test-api-key").chat_models_only(true);
+        let models = provider.list_models().await.unwrap();
+
+        let ids: Vec<_> = models.iter().map(|model| model.id.as_str()).collect();
+        assert_eq!(ids, ["gpt-4o", "o4-mini"]);
+    }
+
+    #[test]
+    fn test_base_id() {
+        assert_eq!(base_id("o3-2025-04-16"), "o3");
+        assert_eq!(base_id("gpt-4o-mini-2024-07-18"), "gpt-4o-mini");
+        assert_eq!(base_id("gpt-4.1"), "gpt-4.1");
+        assert_eq!(base_id("gpt-3.5-turbo-0125"), "gpt-3.5-turbo-0125");
+    }
+
     #[tokio::test]
     async fn test_list_models_empty() {
         let client = MockHttpClient::new()
@@ -151,6 +383,33 @@ mod tests {
         assert!(matches!(result, Err(ListModelsError::ParseError(_))));
     }
 
+    #[tokio::test]
+    async fn test_get_model() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body(r#"{"id":"o3","object":"model","owned_by":"openai"}"#),
+        );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let model = provider.get_model("o3").await.unwrap();
+
+        assert_eq!(model.id, "o3");
+        assert!(model.thinking.is_some());
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "https://api.openai.com/v1/models/o3");
+    }
+
+    #[tokio::test]
+    async fn test_get_model_not_found() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::NOT_FOUND).body("model not found"));
+
+        let provider = OpenAiProvider::new(client, "test-api-key");
+        let result = provider.get_model("gpt-unknown").await;
+
+        assert!(matches!(result, Err(ListModelsError::NotFound { id }) if id == "gpt-unknown"));
+    }
+
     #[tokio::test]
     async fn test_list_models_request_headers() {
         let client = MockHttpClient::new()
@@ -166,4 +425,29 @@ mod tests {
             "Bearer my-secret-key"
         );
     }
+
+    #[tokio::test]
+    async fn test_health() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"data":[]}"#))
+            .with_response(
+                MockResponse::new(StatusCode::UNAUTHORIZED)
+                    .body(r#"{"error":{"message":"Incorrect API key provided"}}"#),
+            );
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+
+        assert_eq!(provider.health().await, HealthStatus::Healthy);
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "https://api.openai.com/v1/models");
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer test-api-key"
+        );
+
+        assert!(matches!(
+            provider.health().await,
+            HealthStatus::Unhealthy { reason } if reason.contains("Incorrect API key")
+        ));
+    }
 }
@@ -0,0 +1,118 @@
+use anyhttp::HttpClient;
+use anyml_core::providers::openai_compat;
+use anyml_core::providers::transcription::{
+    Transcription, TranscriptionError, TranscriptionOptions, TranscriptionProvider,
+};
+use http::Request;
+use secrecy::ExposeSecret;
+
+use crate::OpenAiProvider;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> TranscriptionProvider for OpenAiProvider<C> {
+    async fn transcribe(
+        &self,
+        options: &TranscriptionOptions<'_>,
+    ) -> Result<Transcription, TranscriptionError> {
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(TranscriptionError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(Request::post(format!(
+                "{}/v1/audio/transcriptions",
+                self.url
+            )))
+            .header(
+                "Authorization",
+                format!("Bearer {}", api_key.expose_secret()),
+            );
+
+        openai_compat::transcribe(&self.client, request, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::providers::transcription::TranscriptionSegment;
+    use http::StatusCode;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_transcribe() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"text":"Hello there."}"#));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let options = TranscriptionOptions::new("whisper-1", b"RIFF", "wav").language("en");
+
+        let transcription = provider.transcribe(&options).await.unwrap();
+
+        assert_eq!(transcription.text, "Hello there.");
+        assert!(transcription.segments.is_empty());
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://api.openai.com/v1/audio/transcriptions"
+        );
+        let content_type = request.headers()["Content-Type"].to_str().unwrap();
+        assert!(content_type.starts_with("multipart/form-data; boundary="));
+
+        let body = String::from_utf8_lossy(request.body());
+        assert!(body.contains(
+            "name=\"file\"; filename=\"audio.wav\"\r\nContent-Type: audio/wav\r\n\r\nRIFF\r\n"
+        ));
+        assert!(body.contains("name=\"model\"\r\n\r\nwhisper-1\r\n"));
+        assert!(body.contains("name=\"language\"\r\n\r\nen\r\n"));
+        assert!(body.contains("name=\"response_format\"\r\n\r\njson\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_timestamps() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            r#"{"text":"Hello there.","language":"english","duration":1.5,"segments":[{"id":0,"start":0.0,"end":1.5,"text":"Hello there."}]}"#,
+        ));
+
+        let provider = OpenAiProvider::new(client.clone(), "test-api-key");
+        let options = TranscriptionOptions::new("whisper-1", b"RIFF", "wav").timestamps(true);
+
+        let transcription = provider.transcribe(&options).await.unwrap();
+
+        assert_eq!(transcription.language.as_deref(), Some("english"));
+        assert_eq!(transcription.duration, Some(Duration::from_millis(1500)));
+        assert_eq!(
+            transcription.segments,
+            [TranscriptionSegment {
+                start: Duration::ZERO,
+                end: Duration::from_millis(1500),
+                text: "Hello there.".to_owned(),
+            }]
+        );
+
+        let request = client.last_request().unwrap();
+        let body = String::from_utf8_lossy(request.body());
+        assert!(body.contains("name=\"response_format\"\r\n\r\nverbose_json\r\n"));
+        assert!(body.contains("name=\"timestamp_granularities[]\"\r\n\r\nsegment\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_http_error() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::BAD_REQUEST).body(r#"{"error":"Invalid file format."}"#),
+        );
+
+        let provider = OpenAiProvider::new(client, "test-api-key");
+        let options = TranscriptionOptions::new("whisper-1", b"????", "xyz");
+
+        let result = provider.transcribe(&options).await;
+
+        assert!(matches!(result, Err(TranscriptionError::RequestError(_))));
+    }
+}
@@ -0,0 +1,309 @@
+use anyhttp::HttpClient;
+use anyml_core::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse, Thinking};
+use anyml_core::providers::openai_compat;
+use anyml_macros::json_string;
+use http::Request;
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::OpenRouterProvider;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ChatProvider for OpenRouterProvider<C> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let options = options.or_default_model(self.default_model.as_deref());
+        options.with_timeouts(self.send_chat(&options)).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.default_model.as_deref()
+    }
+}
+
+impl<C: HttpClient> OpenRouterProvider<C> {
+    /// An empty key is only rejected for the hosted API, as other servers may not need one.
+    fn check_api_key(&self, api_key: &SecretString, url: &str) -> Result<(), ChatError> {
+        if api_key.expose_secret().is_empty() && url == crate::DEFAULT_URL {
+            return Err(ChatError::InvalidOptions {
+                field: "api_key",
+                reason: "it is empty".to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn send_chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse<'_>, ChatError> {
+        let overrides = &options.overrides;
+        let url = overrides.url_or(&self.url);
+        let api_key = overrides
+            .api_key_or(&*self.api_key)
+            .await
+            .map_err(ChatError::RequestBuildFailed)?;
+        self.check_api_key(&api_key, url)?;
+        options.validate()?;
+        if options.response_format.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "response_format",
+            });
+        }
+        if options.logprobs {
+            return Err(ChatError::Unsupported {
+                feature: "logprobs",
+            });
+        }
+        if options.audio_output.is_some() {
+            return Err(ChatError::Unsupported {
+                feature: "audio_output",
+            });
+        }
+        if !options.server_tools.is_empty() {
+            return Err(ChatError::Unsupported {
+                feature: "server_tools",
+            });
+        }
+
+        let messages_json =
+            openai_compat::messages_json(&options.messages, options.system, options.prefill)?;
+        let tools_json = openai_compat::tools_json(options.tools);
+
+        let reasoning_json: Option<String> = options.thinking.as_ref().map(|thinking| match thinking {
+            Thinking::Effort(effort) => json_string! { "effort": effort },
+            Thinking::BudgetTokens(budget) => json_string! { "max_tokens": budget },
+            Thinking::Enabled => json_string! { "enabled": true }.to_owned(),
+        });
+
+        let body: String = json_string! {
+            "model": options.model,
+            "messages": @raw messages_json,
+            "stream": options.stream,
+            // Usage is only reported when asked for.
+            "usage": { "include": true },
+            "max_tokens"?: options.max_tokens,
+            if let Some(reasoning) = &reasoning_json {
+                "reasoning": @raw reasoning
+            }
+            if let Some(provider) = &self.provider_preferences {
+                "provider": @serde provider
+            }
+            if !self.transforms.is_empty() {
+                "transforms": @iter &self.transforms
+            }
+            if let Some(tools) = &tools_json {
+                "tools": @raw tools,
+                "tool_choice"?: options.tool_choice.as_ref().map(openai_compat::tool_choice_json),
+                "parallel_tool_calls"?: options.parallel_tool_calls
+            }
+            @merge &options.extra
+        };
+
+        let request = overrides
+            .headers
+            .apply(self.with_attribution(Request::post(format!("{url}{}", self.chat_path))))
+            .header(
+                "Authorization",
+                format!("Bearer {}", api_key.expose_secret()),
+            )
+            .body(body.into_bytes())
+            .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        openai_compat::send_chat(&self.client, request, options.include_raw).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataCollection, ProviderPreferences, ProviderSort};
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::models::{Image, Message, Tool};
+    use anyml_core::providers::chat::ChatChunk;
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_chat_success() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"Hello!\"}}]}\n\n"),
+        );
+
+        let provider = OpenRouterProvider::new(client, "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("deepseek/deepseek-chat-v3-0324").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let chunk = response.next().await.unwrap().unwrap();
+
+        assert!(matches!(chunk, ChatChunk::Content(ref s) if s == "Hello!"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_parts_as_content_arrays() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"A cat.\"}}]}\n\n"),
+        );
+
+        let provider = OpenRouterProvider::new(client.clone(), "test-api-key");
+        let messages =
+            [Message::user("What is in this picture?").part(Image::new(b"PNG", "image/png"))];
+        let options = ChatOptions::new("openai/gpt-4o").messages(&messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"],
+            serde_json::json!([
+                { "type": "text", "text": "What is in this picture?" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,UE5H" } },
+            ])
+        );
+        assert!(body["messages"][0].get("parts").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_http_error() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::UNAUTHORIZED).body("invalid api key"));
+
+        let provider = OpenRouterProvider::new(client, "bad-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("openai/gpt-4o").messages(messages);
+
+        let result = provider.chat(&options).await;
+
+        assert!(matches!(result, Err(ChatError::RequestError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_chat_request_headers() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenRouterProvider::new(client.clone(), "router-key")
+            .referer("https://astrum.chat")
+            .title("Astrum");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("openai/gpt-4o").messages(messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.uri(),
+            "https://openrouter.ai/api/v1/chat/completions"
+        );
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer router-key"
+        );
+        assert_eq!(
+            request.headers().get("HTTP-Referer").unwrap(),
+            "https://astrum.chat"
+        );
+        assert_eq!(request.headers().get("X-Title").unwrap(), "Astrum");
+    }
+
+    #[tokio::test]
+    async fn test_chat_routing_options_in_body() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n"),
+        );
+
+        let provider = OpenRouterProvider::new(client.clone(), "router-key")
+            .provider_preferences(
+                ProviderPreferences::new()
+                    .order(["anthropic", "openai"])
+                    .allow_fallbacks(false)
+                    .data_collection(DataCollection::Deny)
+                    .sort(ProviderSort::Throughput),
+            )
+            .transforms(["middle-out"]);
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("openai/gpt-4o")
+            .messages(messages)
+            .thinking(Thinking::effort("high"));
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+
+        assert_eq!(body["reasoning"]["effort"], "high");
+        assert_eq!(body["provider"]["order"][1], "openai");
+        assert_eq!(body["provider"]["allow_fallbacks"], false);
+        assert_eq!(body["provider"]["data_collection"], "deny");
+        assert_eq!(body["provider"]["sort"], "throughput");
+        assert!(body["provider"].get("only").is_none());
+        assert_eq!(body["transforms"][0], "middle-out");
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_reasoning_and_usage() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            ": OPENROUTER PROCESSING\n\n\
+             data: {\"choices\":[{\"delta\":{\"content\":\"\",\"reasoning\":\"Let me think...\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{\"content\":\"Hello!\",\"reasoning\":null}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{}}],\"usage\":{\"prompt_tokens\":10,\"completion_tokens\":5,\"total_tokens\":15}}\n\n\
+             data: [DONE]\n\n",
+        ));
+
+        let provider = OpenRouterProvider::new(client.clone(), "test-api-key");
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("deepseek/deepseek-r1")
+            .messages(messages)
+            .thinking(Thinking::enabled());
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "Hello!");
+        assert_eq!(result.thinking.as_deref(), Some("Let me think..."));
+
+        let usage = result.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(10));
+        assert_eq!(usage.output_tokens, Some(5));
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["usage"]["include"], true);
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_tool_calls() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"choices\":[{\"delta\":{\"content\":null,\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"{\\\"city\\\":\"}}]}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"Paris\\\"}\"}}]}}]}\n\n\
+             data: [DONE]\n\n",
+        ));
+
+        let provider = OpenRouterProvider::new(client.clone(), "test-api-key");
+        let messages = &["What's the weather in Paris?".into()];
+        let tools = &[Tool::new(
+            "get_weather",
+            serde_json::json!({ "type": "object" }),
+        )];
+        let options = ChatOptions::new("openai/gpt-4o")
+            .messages(messages)
+            .tools(tools);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.tool_calls.len(), 1);
+        assert_eq!(result.tool_calls[0].id, "call_1");
+        assert_eq!(
+            result.tool_calls[0].arguments_json().unwrap(),
+            serde_json::json!({ "city": "Paris" })
+        );
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["tools"][0]["type"], "function");
+    }
+}
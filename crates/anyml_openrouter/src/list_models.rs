@@ -0,0 +1,257 @@
+use anyhow::anyhow;
+use anyhttp::HttpClient;
+use anyml_core::{
+    models::{Model, ModelCapabilities, ModelPricing, ThinkingModes},
+    providers::list_models::{ListModelsError, ListModelsProvider},
+};
+use bytes::Bytes;
+use http::Request;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+
+use crate::OpenRouterProvider;
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ListModelsProvider for OpenRouterProvider<C> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(ListModelsError::RequestBuildFailed)?;
+
+        let request = self
+            .with_attribution(Request::get(format!("{}/v1/models", self.url)))
+            .header(
+                "Authorization",
+                format!("Bearer {}", api_key.expose_secret()),
+            )
+            .body(Vec::new())
+            .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(ListModelsError::ResponseFetchFailed(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        let openrouter_response: OpenRouterModelsResponse = serde_json::from_slice(&body)
+            .map_err(|e| ListModelsError::ParseError(anyhow::Error::new(e)))?;
+
+        let models = openrouter_response
+            .data
+            .into_iter()
+            .map(|m| {
+                let supports =
+                    |param: &str| m.supported_parameters.iter().any(|this| this == param);
+                let capabilities = ModelCapabilities {
+                    vision: m
+                        .architecture
+                        .as_ref()
+                        .is_some_and(|this| this.has_input("image")),
+                    tool_use: supports("tools"),
+                    json_mode: supports("response_format") || supports("structured_outputs"),
+                    thinking: supports("reasoning"),
+                    embeddings: m
+                        .architecture
+                        .as_ref()
+                        .is_some_and(|this| this.has_output("embeddings")),
+                };
+                let thinking = capabilities.thinking.then(|| ThinkingModes {
+                    modes: vec!["low".into(), "medium".into(), "high".into()],
+                    budget: None,
+                });
+                let pricing = m.pricing.and_then(|pricing| {
+                    Some(ModelPricing {
+                        input: pricing.prompt.parse().ok()?,
+                        output: pricing.completion.parse().ok()?,
+                    })
+                });
+                Model {
+                    id: m.id,
+                    thinking,
+                    context_length: m.context_length,
+                    pricing,
+                    capabilities,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Ok(models)
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenRouterModelsResponse {
+    data: Vec<OpenRouterModel>,
+}
+
+#[derive(Deserialize)]
+struct OpenRouterModel {
+    id: String,
+    context_length: Option<usize>,
+    pricing: Option<OpenRouterPricing>,
+    #[serde(default)]
+    supported_parameters: Vec<String>,
+    architecture: Option<OpenRouterArchitecture>,
+}
+
+#[derive(Deserialize)]
+struct OpenRouterArchitecture {
+    /// E.g. `"text+image->text"`, which older models only report this way.
+    modality: Option<String>,
+    #[serde(default)]
+    input_modalities: Vec<String>,
+    #[serde(default)]
+    output_modalities: Vec<String>,
+}
+
+impl OpenRouterArchitecture {
+    fn has_input(&self, modality: &str) -> bool {
+        self.input_modalities.iter().any(|this| this == modality)
+            || self
+                .modalities()
+                .is_some_and(|(input, _)| input.split('+').any(|this| this == modality))
+    }
+
+    fn has_output(&self, modality: &str) -> bool {
+        self.output_modalities.iter().any(|this| this == modality)
+            || self
+                .modalities()
+                .is_some_and(|(_, output)| output.split('+').any(|this| this == modality))
+    }
+
+    fn modalities(&self) -> Option<(&str, &str)> {
+        self.modality.as_deref()?.split_once("->")
+    }
+}
+
+/// Prices in USD per token, encoded as decimal strings.
+#[derive(Deserialize)]
+struct OpenRouterPricing {
+    prompt: String,
+    completion: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_list_models_success() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(
+                r#"{"data":[
+                    {"id":"anthropic/claude-sonnet-4","context_length":200000,"pricing":{"prompt":"0.000003","completion":"0.000015"},"supported_parameters":["max_tokens","reasoning","tools"]},
+                    {"id":"meta-llama/llama-3.1-8b-instruct","context_length":131072,"pricing":{"prompt":"0.00000002","completion":"0.00000003"},"supported_parameters":["max_tokens"]}
+                ]}"#,
+            ),
+        );
+
+        let provider = OpenRouterProvider::new(client, "test-api-key");
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].id, "anthropic/claude-sonnet-4");
+        assert_eq!(models[0].context_length, Some(200000));
+        assert_eq!(
+            models[0].pricing,
+            Some(ModelPricing {
+                input: 0.000003,
+                output: 0.000015
+            })
+        );
+        assert!(models[0].thinking.is_some());
+        assert_eq!(models[1].context_length, Some(131072));
+        assert!(models[1].thinking.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_models_capabilities() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(
+                r#"{"data":[
+                    {"id":"openai/gpt-4o","architecture":{"input_modalities":["text","image"],"output_modalities":["text"]},"supported_parameters":["tools","response_format"]},
+                    {"id":"openai/text-embedding-3-small","architecture":{"modality":"text->embeddings"}}
+                ]}"#,
+            ),
+        );
+
+        let provider = OpenRouterProvider::new(client, "test-api-key");
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(
+            models[0].capabilities,
+            ModelCapabilities {
+                vision: true,
+                tool_use: true,
+                json_mode: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            models[1].capabilities,
+            ModelCapabilities {
+                embeddings: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_models_missing_metadata() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(r#"{"data":[{"id":"openrouter/auto"}]}"#),
+        );
+
+        let provider = OpenRouterProvider::new(client, "test-api-key");
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 1);
+        assert!(models[0].context_length.is_none());
+        assert!(models[0].pricing.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_models_invalid_json() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body("not valid json"));
+
+        let provider = OpenRouterProvider::new(client, "test-api-key");
+        let result = provider.list_models().await;
+
+        assert!(matches!(result, Err(ListModelsError::ParseError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_list_models_request_url() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"data":[]}"#));
+
+        let provider = OpenRouterProvider::new(client.clone(), "router-key");
+        provider.list_models().await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "https://openrouter.ai/api/v1/models");
+    }
+}
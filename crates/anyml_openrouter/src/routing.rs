@@ -0,0 +1,97 @@
+use serde::Serialize;
+
+/// Preferences controlling which upstream providers OpenRouter routes a request to.
+///
+/// Serialized as the `provider` object of a chat request.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ProviderPreferences {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_fallbacks: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_parameters: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_collection: Option<DataCollection>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ProviderSort>,
+}
+
+impl ProviderPreferences {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the providers to try, in order.
+    pub fn order<I, S>(mut self, order: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.order = order.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether to fall back to other providers when the preferred ones are unavailable.
+    pub fn allow_fallbacks(mut self, allow_fallbacks: bool) -> Self {
+        self.allow_fallbacks = Some(allow_fallbacks);
+        self
+    }
+
+    /// Only route to providers which support every parameter in the request.
+    pub fn require_parameters(mut self, require_parameters: bool) -> Self {
+        self.require_parameters = Some(require_parameters);
+        self
+    }
+
+    pub fn data_collection(mut self, data_collection: DataCollection) -> Self {
+        self.data_collection = Some(data_collection);
+        self
+    }
+
+    /// Restricts routing to these providers.
+    pub fn only<I, S>(mut self, only: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.only = only.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Never routes to these providers.
+    pub fn ignore<I, S>(mut self, ignore: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.ignore = ignore.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn sort(mut self, sort: ProviderSort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+}
+
+/// Whether upstream providers may store or train on request data.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DataCollection {
+    Allow,
+    Deny,
+}
+
+/// The attribute OpenRouter sorts candidate providers by.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderSort {
+    Price,
+    Throughput,
+    Latency,
+}
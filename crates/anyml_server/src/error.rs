@@ -0,0 +1,59 @@
+use anyml_core::providers::chat::{ChatError, ChatStreamError};
+use http::StatusCode;
+use serde_json::json;
+use thiserror::Error;
+
+/// Errors answering a request, to be sent back with [`ServerError::status`]
+/// and [`ServerError::to_json`].
+#[derive(Debug, Error)]
+pub enum ServerError {
+    #[error("The request is invalid: {0}.")]
+    InvalidRequest(String),
+
+    #[error(transparent)]
+    Chat(#[from] ChatError),
+
+    /// The reply failed while being collected for a non-streamed response.
+    #[error(transparent)]
+    Stream(#[from] ChatStreamError),
+}
+
+impl ServerError {
+    /// The HTTP status to respond with, as OpenAI would.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Chat(error) => match error {
+                ChatError::InvalidOptions { .. }
+                | ChatError::Unsupported { .. }
+                | ChatError::ContextLengthExceeded(_) => StatusCode::BAD_REQUEST,
+                ChatError::UnknownProvider { .. } => StatusCode::NOT_FOUND,
+                ChatError::RateLimited { .. }
+                | ChatError::TooManyInFlight { .. }
+                | ChatError::BudgetExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+                ChatError::CircuitOpen { .. } => StatusCode::SERVICE_UNAVAILABLE,
+                ChatError::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::BAD_GATEWAY,
+            },
+            Self::Stream(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    /// The error as an OpenAI error body, `{"error": {"message", "type", ...}}`.
+    pub fn to_json(&self) -> String {
+        let kind = match self.status() {
+            StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND => "invalid_request_error",
+            StatusCode::TOO_MANY_REQUESTS => "rate_limit_error",
+            _ => "server_error",
+        };
+        json!({
+            "error": {
+                "message": self.to_string(),
+                "type": kind,
+                "param": null,
+                "code": null,
+            }
+        })
+        .to_string()
+    }
+}
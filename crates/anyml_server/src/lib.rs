@@ -0,0 +1,142 @@
+//! Serves any [`ChatProvider`] behind OpenAI's `/v1/chat/completions` API, so
+//! tools which only speak OpenAI can chat with Anthropic, Ollama, or any other
+//! provider through a local gateway.
+//!
+//! The crate isn't tied to an HTTP framework: [`chat_completions`] takes a
+//! request's body and gives back the response's, to be sent with whichever
+//! server the gateway is built on. Errors carry the status and body to respond
+//! with.
+//!
+//! ```ignore
+//! match anyml_server::chat_completions(&provider, &body).await {
+//!     Ok(CompletionBody::Json(json)) => respond(200, "application/json", json),
+//!     Ok(CompletionBody::Events(events)) => respond_streaming(200, "text/event-stream", events),
+//!     Err(err) => respond(err.status(), "application/json", err.to_json()),
+//! }
+//! ```
+
+use anyml_core::{BoxStream, providers::chat::ChatProvider};
+
+mod error;
+mod request;
+mod response;
+
+pub use error::ServerError;
+pub use request::ChatCompletionRequest;
+pub use response::{ChunkEncoder, Completion};
+
+/// The body of a response to `/v1/chat/completions`.
+pub enum CompletionBody<'a> {
+    /// A `chat.completion` object, for requests which didn't ask to stream.
+    Json(String),
+    /// Server-sent `chat.completion.chunk` events, ending with `data: [DONE]`.
+    Events(BoxStream<'a, String>),
+}
+
+impl CompletionBody<'_> {
+    /// The `Content-Type` to respond with.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json(_) => "application/json",
+            Self::Events(_) => "text/event-stream",
+        }
+    }
+}
+
+/// Answers a request to `/v1/chat/completions` with `provider`.
+///
+/// Requests without a model are sent to the provider's default one. Streamed
+/// requests are answered as soon as the provider starts replying, so errors in
+/// the middle of the stream are sent as events; other requests wait for the
+/// whole reply.
+pub async fn chat_completions<'a, P: ChatProvider + ?Sized>(
+    provider: &'a P,
+    body: &[u8],
+) -> Result<CompletionBody<'a>, ServerError> {
+    let request = ChatCompletionRequest::parse(body)?;
+
+    let model = match request.model.as_str() {
+        "" => provider.default_model().unwrap_or_default(),
+        model => model,
+    };
+    let completion = Completion::new(model);
+
+    let mut response = provider.chat(&request.options()).await?;
+
+    if request.stream {
+        let events = completion.events(request.include_usage, response);
+        Ok(CompletionBody::Events(Box::pin(events)))
+    } else {
+        let chat = response.aggregate().await?;
+        Ok(CompletionBody::Json(completion.to_json(&chat)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyml_core::providers::chat::{ChatError, ChatStreamError};
+    use anyml_core::providers::mock::{MockChatProvider, MockReply};
+    use futures::{StreamExt, executor::block_on};
+    use http::StatusCode;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    const REQUEST: &str =
+        r#"{"model": "llama3.2", "messages": [{"role": "user", "content": "Hi"}]}"#;
+
+    #[test]
+    fn answers_with_a_completion() {
+        let provider = MockChatProvider::new().reply(MockReply::text("Hello!"));
+
+        let body = block_on(chat_completions(&provider, REQUEST.as_bytes())).unwrap();
+
+        assert_eq!(body.content_type(), "application/json");
+        let CompletionBody::Json(json) = body else {
+            panic!("expected a JSON body");
+        };
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["model"], "llama3.2");
+        assert_eq!(json["choices"][0]["message"]["content"], "Hello!");
+        assert_eq!(json["choices"][0]["finish_reason"], "stop");
+    }
+
+    #[test]
+    fn streams_events() {
+        let provider = MockChatProvider::new()
+            .reply(MockReply::text("Hello!").error(ChatStreamError::IncompleteChunk));
+        let request = REQUEST.replace("\"messages\"", "\"stream\": true, \"messages\"");
+
+        let body = block_on(chat_completions(&provider, request.as_bytes())).unwrap();
+
+        assert_eq!(body.content_type(), "text/event-stream");
+        let CompletionBody::Events(events) = body else {
+            panic!("expected events");
+        };
+        let events: Vec<String> = block_on(events.collect());
+        assert_eq!(events.len(), 4);
+        assert!(events[0].contains(r#""content":"Hello!""#));
+        assert!(events[2].starts_with(r#"data: {"error":"#));
+        assert_eq!(events[3], "data: [DONE]\n\n");
+    }
+
+    #[test]
+    fn responds_to_errors_like_openai() {
+        let provider = MockChatProvider::new().reply(MockReply::fail(ChatError::RateLimited {
+            retry_after: Duration::from_secs(1),
+        }));
+
+        let Err(err) = block_on(chat_completions(&provider, REQUEST.as_bytes())) else {
+            panic!("expected an error");
+        };
+
+        assert_eq!(err.status(), StatusCode::TOO_MANY_REQUESTS);
+        let json: Value = serde_json::from_str(&err.to_json()).unwrap();
+        assert_eq!(json["error"]["type"], "rate_limit_error");
+
+        let Err(err) = block_on(chat_completions(&provider, b"{}")) else {
+            panic!("expected an error");
+        };
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+    }
+}
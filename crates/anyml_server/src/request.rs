@@ -0,0 +1,364 @@
+use anyml_core::{
+    models::{Audio, ContentPart, Image, Message, MessageRole, Tool, ToolCall},
+    providers::chat::{ChatOptions, ResponseFormat, Thinking, ToolChoice},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::ServerError;
+
+/// Sampling options passed on to the provider as extra fields, as they're named
+/// the same by most APIs.
+const PASSED_THROUGH: &[&str] = &["temperature", "top_p"];
+
+/// A request to OpenAI's `/v1/chat/completions`, parsed into anyml's types.
+///
+/// Options without an equivalent in [`ChatOptions`] (such as `n` or `stop`) are
+/// ignored, apart from `temperature` and `top_p`, which are passed on as-is.
+#[derive(Debug, Clone)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<Message>,
+    pub tools: Vec<Tool>,
+    pub tool_choice: Option<ToolChoice>,
+    pub parallel_tool_calls: Option<bool>,
+    pub response_format: Option<ResponseFormat>,
+    pub max_tokens: Option<usize>,
+    /// From `reasoning_effort`.
+    pub thinking: Option<Thinking>,
+    pub logprobs: bool,
+    pub top_logprobs: Option<usize>,
+    pub stream: bool,
+    /// From `stream_options.include_usage`, which asks for a final chunk with the usage.
+    pub include_usage: bool,
+    pub extra: Map<String, Value>,
+}
+
+impl ChatCompletionRequest {
+    /// Parses the JSON body of a request.
+    pub fn parse(body: &[u8]) -> Result<Self, ServerError> {
+        let request: RequestBody = serde_json::from_slice(body)
+            .map_err(|err| ServerError::InvalidRequest(err.to_string()))?;
+
+        let messages = request
+            .messages
+            .into_iter()
+            .map(RequestMessage::into_message)
+            .collect::<Result<_, _>>()?;
+
+        let tool_choice = match request.tool_choice {
+            None => None,
+            Some(RequestToolChoice::Mode(mode)) => Some(match mode.as_str() {
+                "auto" => ToolChoice::Auto,
+                "none" => ToolChoice::None,
+                "required" => ToolChoice::Required,
+                other => {
+                    return Err(ServerError::InvalidRequest(format!(
+                        "unknown tool_choice \"{other}\""
+                    )));
+                }
+            }),
+            Some(RequestToolChoice::Function { function }) => Some(ToolChoice::Tool(function.name)),
+        };
+
+        let response_format = match request.response_format {
+            None | Some(RequestResponseFormat::Text) => None,
+            Some(RequestResponseFormat::JsonObject) => Some(ResponseFormat::Json),
+            Some(RequestResponseFormat::JsonSchema { json_schema }) => {
+                Some(ResponseFormat::JsonSchema(json_schema.schema))
+            }
+        };
+
+        let extra = request
+            .rest
+            .into_iter()
+            .filter(|(key, _)| PASSED_THROUGH.contains(&key.as_str()))
+            .collect();
+
+        Ok(Self {
+            model: request.model,
+            messages,
+            tools: request
+                .tools
+                .into_iter()
+                .map(|tool| Tool {
+                    name: tool.function.name,
+                    description: tool.function.description,
+                    parameters: tool.function.parameters,
+                })
+                .collect(),
+            tool_choice,
+            parallel_tool_calls: request.parallel_tool_calls,
+            response_format,
+            max_tokens: request.max_completion_tokens.or(request.max_tokens),
+            thinking: request.reasoning_effort.map(Thinking::effort),
+            logprobs: request.logprobs,
+            top_logprobs: request.top_logprobs,
+            stream: request.stream,
+            include_usage: request
+                .stream_options
+                .is_some_and(|options| options.include_usage),
+            extra,
+        })
+    }
+
+    /// The options to send the chat to a provider with.
+    pub fn options(&self) -> ChatOptions<'_> {
+        let mut options = ChatOptions::new(&self.model)
+            .messages(&self.messages)
+            .tools(&self.tools)
+            .stream(self.stream)
+            .logprobs(self.logprobs);
+        options.tool_choice = self.tool_choice.clone();
+        options.parallel_tool_calls = self.parallel_tool_calls;
+        options.response_format = self.response_format.clone();
+        options.max_tokens = self.max_tokens;
+        options.thinking = self.thinking.clone();
+        options.top_logprobs = self.top_logprobs;
+        options.extra = self.extra.clone();
+        options
+    }
+}
+
+#[derive(Deserialize)]
+struct RequestBody {
+    #[serde(default)]
+    model: String,
+    messages: Vec<RequestMessage>,
+    #[serde(default)]
+    tools: Vec<RequestTool>,
+    tool_choice: Option<RequestToolChoice>,
+    parallel_tool_calls: Option<bool>,
+    response_format: Option<RequestResponseFormat>,
+    max_tokens: Option<usize>,
+    max_completion_tokens: Option<usize>,
+    reasoning_effort: Option<String>,
+    #[serde(default)]
+    logprobs: bool,
+    top_logprobs: Option<usize>,
+    #[serde(default)]
+    stream: bool,
+    stream_options: Option<StreamOptions>,
+    #[serde(flatten)]
+    rest: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct RequestMessage {
+    role: String,
+    #[serde(default)]
+    content: Option<RequestContent>,
+    #[serde(default)]
+    tool_calls: Vec<RequestToolCall>,
+    tool_call_id: Option<String>,
+}
+
+impl RequestMessage {
+    fn into_message(self) -> Result<Message, ServerError> {
+        let role = match self.role.as_str() {
+            "developer" => MessageRole::System,
+            role => MessageRole::from_str(role),
+        };
+
+        let mut content = String::new();
+        let mut parts = Vec::new();
+        match self.content {
+            None => {}
+            Some(RequestContent::Text(text)) => content = text,
+            Some(RequestContent::Parts(request_parts)) => {
+                for part in request_parts {
+                    match part {
+                        RequestPart::Text { text } => content.push_str(&text),
+                        RequestPart::ImageUrl { image_url } => {
+                            parts.push(ContentPart::Image(image_from_url(&image_url.url)?))
+                        }
+                        RequestPart::InputAudio { input_audio } => {
+                            parts.push(ContentPart::Audio(Audio {
+                                data: input_audio.data,
+                                format: input_audio.format,
+                            }))
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut message = Message::new(content, role);
+        message.parts = parts;
+        message.tool_call_id = self.tool_call_id;
+        message.tool_calls = self
+            .tool_calls
+            .into_iter()
+            .map(|call| ToolCall::new(call.id, call.function.name, call.function.arguments))
+            .collect();
+        Ok(message)
+    }
+}
+
+/// Reads an image from a `data:` URL, as images can't be fetched from elsewhere.
+fn image_from_url(url: &str) -> Result<Image, ServerError> {
+    url.strip_prefix("data:")
+        .and_then(|url| url.split_once(";base64,"))
+        .map(|(media_type, data)| Image {
+            data: data.to_owned(),
+            media_type: media_type.to_owned(),
+        })
+        .ok_or_else(|| {
+            ServerError::InvalidRequest("images must be sent as base64 data URLs".to_owned())
+        })
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RequestContent {
+    Text(String),
+    Parts(Vec<RequestPart>),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RequestPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+    InputAudio { input_audio: InputAudio },
+}
+
+#[derive(Deserialize)]
+struct ImageUrl {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct InputAudio {
+    data: String,
+    format: String,
+}
+
+#[derive(Deserialize)]
+struct RequestToolCall {
+    id: String,
+    function: RequestFunctionCall,
+}
+
+#[derive(Deserialize)]
+struct RequestFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: String,
+}
+
+#[derive(Deserialize)]
+struct RequestTool {
+    function: RequestFunction,
+}
+
+#[derive(Deserialize)]
+struct RequestFunction {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    parameters: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RequestToolChoice {
+    Mode(String),
+    Function { function: RequestFunctionName },
+}
+
+#[derive(Deserialize)]
+struct RequestFunctionName {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RequestResponseFormat {
+    Text,
+    JsonObject,
+    JsonSchema { json_schema: JsonSchema },
+}
+
+#[derive(Deserialize)]
+struct JsonSchema {
+    #[serde(default)]
+    schema: Value,
+}
+
+#[derive(Deserialize)]
+struct StreamOptions {
+    #[serde(default)]
+    include_usage: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_request() {
+        let request = ChatCompletionRequest::parse(
+            br#"{
+                "model": "claude-sonnet-4-5",
+                "messages": [
+                    {"role": "developer", "content": "Be brief."},
+                    {"role": "user", "content": [
+                        {"type": "text", "text": "What's this?"},
+                        {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw=="}}
+                    ]},
+                    {"role": "assistant", "content": null, "tool_calls": [
+                        {"id": "call_1", "type": "function", "function": {"name": "look", "arguments": "{}"}}
+                    ]},
+                    {"role": "tool", "tool_call_id": "call_1", "content": "A cat."}
+                ],
+                "tools": [{"type": "function", "function": {"name": "look", "parameters": {"type": "object"}}}],
+                "tool_choice": {"type": "function", "function": {"name": "look"}},
+                "response_format": {"type": "json_object"},
+                "max_completion_tokens": 100,
+                "temperature": 0.2,
+                "n": 1,
+                "stream": true,
+                "stream_options": {"include_usage": true}
+            }"#,
+        )
+        .unwrap();
+
+        let roles: Vec<_> = request
+            .messages
+            .iter()
+            .map(|message| message.role.as_str())
+            .collect();
+        assert_eq!(roles, ["system", "user", "assistant", "tool"]);
+        assert_eq!(request.messages[1].content, "What's this?");
+        assert_eq!(
+            request.messages[1].parts,
+            [ContentPart::Image(Image {
+                data: "iVBORw==".to_owned(),
+                media_type: "image/png".to_owned(),
+            })]
+        );
+        assert_eq!(request.messages[2].tool_calls[0].name, "look");
+        assert_eq!(request.messages[3].tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(request.tools[0].name, "look");
+        assert_eq!(request.tool_choice, Some(ToolChoice::tool("look")));
+        assert!(request.stream && request.include_usage);
+
+        let options = request.options();
+        assert_eq!(options.model, "claude-sonnet-4-5");
+        assert_eq!(options.max_tokens, Some(100));
+        assert_eq!(options.response_format, Some(ResponseFormat::Json));
+        assert_eq!(options.extra.keys().collect::<Vec<_>>(), ["temperature"]);
+    }
+
+    #[test]
+    fn rejects_remote_images() {
+        let result = ChatCompletionRequest::parse(
+            br#"{"model": "gpt-4o", "messages": [{"role": "user", "content": [
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+            ]}]}"#,
+        );
+
+        assert!(matches!(result, Err(ServerError::InvalidRequest(_))));
+    }
+}
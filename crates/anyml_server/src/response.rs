@@ -0,0 +1,355 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyml_core::providers::chat::{
+    AggregatedChat, ChatChunk, ChatStreamError, FinishReason, TokenLogProb, Usage,
+};
+use futures::{Stream, StreamExt, future::ready, stream};
+use serde_json::{Value, json};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// Counts the completions made, to tell apart those created in the same second.
+static COMPLETIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Identifies a completion in the responses sent for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub id: String,
+    pub model: String,
+    /// When the completion was created, in seconds since the Unix epoch.
+    pub created: u64,
+}
+
+impl Completion {
+    /// A completion created now, with a new ID.
+    pub fn new(model: impl Into<String>) -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let count = COMPLETIONS.fetch_add(1, Ordering::Relaxed);
+        Self {
+            id: format!("chatcmpl-{created:x}{count:06x}"),
+            model: model.into(),
+            created,
+        }
+    }
+
+    /// Encodes a whole reply as a `chat.completion` body.
+    pub fn to_json(&self, chat: &AggregatedChat) -> String {
+        let mut message = json!({
+            "role": "assistant",
+            "content": (!chat.content.is_empty() || chat.tool_calls.is_empty())
+                .then_some(&chat.content),
+        });
+        if let Some(thinking) = &chat.thinking {
+            message["reasoning_content"] = json!(thinking);
+        }
+        if !chat.tool_calls.is_empty() {
+            message["tool_calls"] = chat
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": { "name": call.name, "arguments": call.arguments },
+                    })
+                })
+                .collect();
+        }
+
+        let mut body = json!({
+            "id": self.id,
+            "object": "chat.completion",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "message": message,
+                "logprobs": logprobs_json(&chat.logprobs),
+                "finish_reason": chat.finish_reason.as_ref().map(FinishReason::as_str),
+            }],
+        });
+        if let Some(usage) = &chat.usage {
+            body["usage"] = usage_json(usage);
+        }
+        body.to_string()
+    }
+
+    /// Encodes a chat's stream as server-sent `chat.completion.chunk` events with
+    /// a [`ChunkEncoder`], ending with `[DONE]`.
+    pub fn events<S>(self, include_usage: bool, chunks: S) -> impl Stream<Item = String>
+    where
+        S: Stream<Item = Result<ChatChunk, ChatStreamError>>,
+    {
+        chunks
+            .map(Some)
+            .chain(stream::once(ready(None)))
+            .scan(
+                ChunkEncoder::new(self).include_usage(include_usage),
+                |encoder, item| {
+                    let event = match item {
+                        Some(item) => encoder.encode(&item),
+                        None => Some(encoder.finish()),
+                    };
+                    ready(Some(event))
+                },
+            )
+            .filter_map(ready)
+    }
+}
+
+/// Encodes the items of a chat's stream as OpenAI's server-sent
+/// `chat.completion.chunk` events.
+///
+/// Thinking is sent as `reasoning_content`, as by DeepSeek and vLLM, and log
+/// probabilities as the choice's `logprobs`. Chunks OpenAI has no field for, such
+/// as server tool results, are left out, and errors are sent as an
+/// `{"error": {"message"}}` event.
+#[derive(Debug, Clone)]
+pub struct ChunkEncoder {
+    completion: Completion,
+    include_usage: bool,
+    usage: Option<Usage>,
+    /// Whether the assistant's role was sent, which it is with the first delta.
+    started: bool,
+}
+
+impl ChunkEncoder {
+    pub fn new(completion: Completion) -> Self {
+        Self {
+            completion,
+            include_usage: false,
+            usage: None,
+            started: false,
+        }
+    }
+
+    /// Sends the usage in a last chunk without choices, as asked for with
+    /// `stream_options.include_usage`.
+    pub fn include_usage(mut self, include_usage: bool) -> Self {
+        self.include_usage = include_usage;
+        self
+    }
+
+    /// Encodes an item of the stream, returning `None` for chunks without an event.
+    pub fn encode(&mut self, item: &Result<ChatChunk, ChatStreamError>) -> Option<String> {
+        let chunk = match item {
+            Ok(chunk) => chunk,
+            Err(err) => return Some(event(&json!({ "error": { "message": err.to_string() } }))),
+        };
+
+        let (delta, finish_reason) = match chunk {
+            ChatChunk::Content(text) => (json!({ "content": &**text }), None),
+            ChatChunk::Thinking(text) => (json!({ "reasoning_content": &**text }), None),
+            ChatChunk::ToolCallDelta(delta) => {
+                let mut function = json!({ "arguments": delta.arguments });
+                if let Some(name) = &delta.name {
+                    function["name"] = json!(name);
+                }
+                let mut tool_call = json!({ "index": delta.index, "function": function });
+                if let Some(id) = &delta.id {
+                    tool_call["id"] = json!(id);
+                    tool_call["type"] = json!("function");
+                }
+                (json!({ "tool_calls": [tool_call] }), None)
+            }
+            ChatChunk::Finish(reason) => (json!({}), Some(reason.as_str())),
+            ChatChunk::LogProbs(logprobs) => {
+                return Some(self.chunk_with_logprobs(json!({}), None, logprobs));
+            }
+            ChatChunk::Usage(usage) => {
+                // Usage is reported as running totals, some fields at a time.
+                self.usage.get_or_insert_with(Usage::default).merge(usage);
+                return None;
+            }
+            _ => return None,
+        };
+
+        Some(self.chunk(delta, finish_reason))
+    }
+
+    /// Encodes the usage, if asked for, and the `[DONE]` event once the stream has ended.
+    pub fn finish(&mut self) -> String {
+        let mut events = String::new();
+        if self.include_usage {
+            let mut chunk = self.chunk_json(json!([]));
+            chunk["usage"] = self.usage.as_ref().map_or(Value::Null, usage_json);
+            events.push_str(&event(&chunk));
+        }
+        events.push_str("data: [DONE]\n\n");
+        events
+    }
+
+    fn chunk(&mut self, delta: Value, finish_reason: Option<&str>) -> String {
+        self.chunk_with_logprobs(delta, finish_reason, &[])
+    }
+
+    fn chunk_with_logprobs(
+        &mut self,
+        mut delta: Value,
+        finish_reason: Option<&str>,
+        logprobs: &[TokenLogProb],
+    ) -> String {
+        if !self.started {
+            self.started = true;
+            delta["role"] = json!("assistant");
+        }
+        let chunk = self.chunk_json(json!([{
+            "index": 0,
+            "delta": delta,
+            "logprobs": logprobs_json(logprobs),
+            "finish_reason": finish_reason,
+        }]));
+        event(&chunk)
+    }
+
+    fn chunk_json(&self, choices: Value) -> Value {
+        json!({
+            "id": self.completion.id,
+            "object": "chat.completion.chunk",
+            "created": self.completion.created,
+            "model": self.completion.model,
+            "choices": choices,
+        })
+    }
+}
+
+fn event(data: &Value) -> String {
+    format!("data: {data}\n\n")
+}
+
+/// A choice's `logprobs`, which is null when none were reported.
+fn logprobs_json(logprobs: &[TokenLogProb]) -> Value {
+    if logprobs.is_empty() {
+        return Value::Null;
+    }
+    json!({ "content": logprobs })
+}
+
+fn usage_json(usage: &Usage) -> Value {
+    let prompt_tokens = usage.input_tokens.unwrap_or(0);
+    let completion_tokens = usage.output_tokens.unwrap_or(0);
+    let mut usage_json = json!({
+        "prompt_tokens": prompt_tokens,
+        "completion_tokens": completion_tokens,
+        "total_tokens": prompt_tokens + completion_tokens,
+    });
+    if let Some(cached_tokens) = usage.cached_input_tokens {
+        usage_json["prompt_tokens_details"] = json!({ "cached_tokens": cached_tokens });
+    }
+    usage_json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyml_core::providers::chat::ToolCallDelta;
+    use futures::executor::block_on;
+
+    fn completion() -> Completion {
+        Completion {
+            id: "chatcmpl-1".to_owned(),
+            model: "claude-sonnet-4-5".to_owned(),
+            created: 1760000000,
+        }
+    }
+
+    fn data(event: &str) -> Value {
+        let data = event
+            .strip_prefix("data: ")
+            .and_then(|event| event.strip_suffix("\n\n"))
+            .unwrap();
+        serde_json::from_str(data).unwrap()
+    }
+
+    #[test]
+    fn streams_chunks() {
+        let chunks = [
+            Ok(ChatChunk::Usage(Usage {
+                input_tokens: Some(10),
+                ..Default::default()
+            })),
+            Ok(ChatChunk::Thinking("Hmm".into())),
+            Ok(ChatChunk::Content("Hi".into())),
+            Ok(ChatChunk::LogProbs(vec![TokenLogProb {
+                token: "Hi".to_owned(),
+                logprob: -0.5,
+                bytes: None,
+                top_logprobs: Vec::new(),
+            }])),
+            Ok(ChatChunk::ToolCallDelta(ToolCallDelta {
+                index: 0,
+                id: Some("call_1".to_owned()),
+                name: Some("get_time".to_owned()),
+                arguments: String::new(),
+            })),
+            Ok(ChatChunk::Finish(FinishReason::ToolCalls)),
+            Ok(ChatChunk::Usage(Usage {
+                output_tokens: Some(5),
+                ..Default::default()
+            })),
+        ];
+
+        let events: Vec<String> =
+            block_on(completion().events(true, stream::iter(chunks)).collect());
+
+        assert_eq!(events.len(), 6);
+        let first = data(&events[0]);
+        assert_eq!(first["object"], "chat.completion.chunk");
+        assert_eq!(first["id"], "chatcmpl-1");
+        assert_eq!(
+            first["choices"][0]["delta"],
+            json!({ "role": "assistant", "reasoning_content": "Hmm" })
+        );
+        assert_eq!(
+            data(&events[1])["choices"][0]["delta"],
+            json!({ "content": "Hi" })
+        );
+        assert_eq!(
+            data(&events[2])["choices"][0]["logprobs"]["content"][0]["logprob"],
+            -0.5
+        );
+        assert_eq!(
+            data(&events[3])["choices"][0]["delta"]["tool_calls"][0],
+            json!({
+                "index": 0,
+                "id": "call_1",
+                "type": "function",
+                "function": { "name": "get_time", "arguments": "" },
+            })
+        );
+        assert_eq!(
+            data(&events[4])["choices"][0]["finish_reason"],
+            "tool_calls"
+        );
+        assert!(events[5].ends_with("data: [DONE]\n\n"));
+        let usage = data(events[5].strip_suffix("data: [DONE]\n\n").unwrap());
+        assert_eq!(usage["choices"], json!([]));
+        assert_eq!(usage["usage"]["prompt_tokens"], 10);
+        assert_eq!(usage["usage"]["total_tokens"], 15);
+    }
+
+    #[test]
+    fn encodes_a_whole_reply() {
+        let mut chat = AggregatedChat::default();
+        chat.push(&ChatChunk::ToolCallDelta(ToolCallDelta {
+            index: 0,
+            id: Some("call_1".to_owned()),
+            name: Some("get_time".to_owned()),
+            arguments: "{}".to_owned(),
+        }));
+        chat.push(&ChatChunk::Finish(FinishReason::ToolCalls));
+
+        let body: Value = serde_json::from_str(&completion().to_json(&chat)).unwrap();
+
+        assert_eq!(body["object"], "chat.completion");
+        let choice = &body["choices"][0];
+        assert_eq!(choice["message"]["content"], Value::Null);
+        assert_eq!(choice["finish_reason"], "tool_calls");
+        assert_eq!(
+            choice["message"]["tool_calls"][0]["function"],
+            json!({ "name": "get_time", "arguments": "{}" })
+        );
+        assert!(body.get("usage").is_none());
+    }
+}
@@ -0,0 +1,38 @@
+use std::env;
+
+use anyml_core::providers::chat::{ChatChunk, ChatOptions, ChatProvider};
+use anyml_vllm::{VllmChatExt, VllmProvider};
+
+const MODEL: &str = "Qwen/Qwen3-8B";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::from_filename(".env.test").ok();
+    let url = env::var("VLLM_URL").unwrap_or_else(|_| "http://localhost:8000".to_owned());
+
+    let mut provider = VllmProvider::new(reqwest::Client::new()).url(url);
+    if let Ok(api_key) = env::var("VLLM_API_KEY") {
+        provider = provider.api_key(api_key);
+    }
+
+    let messages = &["Write me a short poem".into()];
+    let options = ChatOptions::new(MODEL)
+        .messages(messages)
+        .extension(VllmChatExt::new().min_tokens(16));
+
+    let mut response = provider.chat(&options).await.map_err(anyhow::Error::new)?;
+
+    while let Some(chunk) = response.next().await {
+        match chunk? {
+            ChatChunk::Content(text) => print!("{text}"),
+            ChatChunk::Usage(usage) => {
+                println!("\n\nInput tokens: {:?}", usage.input_tokens);
+                println!("Cached input tokens: {:?}", usage.cached_input_tokens);
+                println!("Output tokens: {:?}", usage.output_tokens);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
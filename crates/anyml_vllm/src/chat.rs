@@ -0,0 +1,292 @@
+use anyhttp::HttpClient;
+use anyml_core::providers::chat::{ChatError, ChatOptions, ChatProvider, ChatResponse};
+use anyml_core::providers::openai_compat;
+use anyml_macros::json_string;
+use http::Request;
+
+use crate::{GuidedDecoding, VllmChatExt, VllmProvider, with_auth};
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ChatProvider for VllmProvider<C> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        let options = options.or_default_model(self.default_model.as_deref());
+        options.with_timeouts(self.send_chat(&options)).await
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        self.default_model.as_deref()
+    }
+}
+
+impl<C: HttpClient> VllmProvider<C> {
+    async fn send_chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse<'_>, ChatError> {
+        let overrides = &options.overrides;
+        let url = overrides.url_or(&self.url);
+        let api_key = overrides
+            .api_key_or(&*self.api_key)
+            .await
+            .map_err(ChatError::RequestBuildFailed)?;
+
+        let body = chat_body(options)?;
+
+        let request = self
+            .headers
+            .apply(Request::post(format!("{url}{}", self.chat_path)));
+        let request = with_auth(overrides.headers.apply(request), &api_key)
+            .body(body.into_bytes())
+            .map_err(|this| ChatError::RequestBuildFailed(anyhow::Error::new(this)))?;
+
+        openai_compat::send_chat(&self.client, request, options.include_raw).await
+    }
+}
+
+/// Builds the request body, rejecting options vLLM can't serve.
+fn chat_body(options: &ChatOptions<'_>) -> Result<String, ChatError> {
+    options.validate()?;
+    if options.prefill.is_some() {
+        return Err(ChatError::Unsupported { feature: "prefill" });
+    }
+    // Reasoning is set up when the server is started, with `--reasoning-parser`.
+    if options.thinking.is_some() {
+        return Err(ChatError::Unsupported {
+            feature: "thinking",
+        });
+    }
+    if options.audio_output.is_some() {
+        return Err(ChatError::Unsupported {
+            feature: "audio_output",
+        });
+    }
+    if !options.server_tools.is_empty() {
+        return Err(ChatError::Unsupported {
+            feature: "server_tools",
+        });
+    }
+
+    let default_ext = VllmChatExt::default();
+    let ext = options.extensions.get().unwrap_or(&default_ext);
+    ext.validate(options)?;
+
+    let messages_json = openai_compat::messages_json(&options.messages, options.system, None)?;
+    let tools_json = openai_compat::tools_json(options.tools);
+    let response_format = options
+        .response_format
+        .as_ref()
+        .map(openai_compat::response_format_json);
+
+    let body: String = json_string! {
+        "model": options.model,
+        "messages": @raw messages_json,
+        "stream": options.stream,
+        if options.stream {
+            "stream_options": { "include_usage": true }
+        }
+        "max_tokens"?: options.max_tokens,
+        if let Some(tools) = &tools_json {
+            "tools": @raw tools,
+            "tool_choice"?: options.tool_choice.as_ref().map(openai_compat::tool_choice_json),
+            "parallel_tool_calls"?: options.parallel_tool_calls
+        }
+        "response_format"?: response_format,
+        if options.logprobs {
+            "logprobs": true
+        }
+        "top_logprobs"?: options.top_logprobs,
+        "best_of"?: ext.best_of,
+        "use_beam_search"?: ext.use_beam_search,
+        "min_tokens"?: ext.min_tokens,
+        if let Some(GuidedDecoding::Json(schema)) = &ext.guided {
+            "guided_json": schema
+        } else if let Some(GuidedDecoding::Regex(regex)) = &ext.guided {
+            "guided_regex": regex
+        }
+        @merge &options.extra
+    };
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use anyml_core::models::{Image, Message};
+    use anyml_core::providers::chat::{ChatStreamError, FinishReason, ResponseFormat};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_chat_success() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"choices\":[{\"delta\":{\"reasoning_content\":\"Greeting.\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{\"content\":\"Hello!\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n\
+             data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2,\"total_tokens\":11,\"prompt_tokens_details\":{\"cached_tokens\":8}}}\n\n\
+             data: [DONE]\n\n",
+        ));
+
+        let provider = VllmProvider::new(client.clone());
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("Qwen/Qwen3-8B").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.thinking.as_deref(), Some("Greeting."));
+        assert_eq!(result.content, "Hello!");
+        assert_eq!(result.finish_reason, Some(FinishReason::Stop));
+        let usage = result.usage.unwrap();
+        assert_eq!(usage.input_tokens, Some(9));
+        assert_eq!(usage.output_tokens, Some(2));
+        assert_eq!(usage.cached_input_tokens, Some(8));
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:8000/v1/chat/completions");
+        assert!(request.headers().get("Authorization").is_none());
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["stream_options"]["include_usage"], true);
+        assert!(body.get("best_of").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_sends_parts_as_content_arrays() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK)
+                .body("data: {\"choices\":[{\"delta\":{\"content\":\"A cat.\"}}]}\n\n"),
+        );
+
+        let provider = VllmProvider::new(client.clone());
+        let messages =
+            [Message::user("What is in this picture?").part(Image::new(b"PNG", "image/png"))];
+        let options = ChatOptions::new("Qwen/Qwen2.5-VL-7B-Instruct").messages(&messages);
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"],
+            serde_json::json!([
+                { "type": "text", "text": "What is in this picture?" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,UE5H" } },
+            ])
+        );
+        assert!(body["messages"][0].get("parts").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_extension() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body("data: [DONE]\n\n"));
+
+        let provider = VllmProvider::new(client.clone()).api_key("vllm-key");
+        let messages = &["Pick a color".into()];
+        let options = ChatOptions::new("Qwen/Qwen3-8B")
+            .messages(messages)
+            .max_tokens(32)
+            .extension(
+                VllmChatExt::new()
+                    .best_of(4)
+                    .use_beam_search(true)
+                    .min_tokens(2)
+                    .guided_regex("(red|green|blue)"),
+            );
+
+        provider.chat(&options).await.unwrap();
+
+        let request = client.last_request().unwrap();
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer vllm-key"
+        );
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(body["best_of"], 4);
+        assert_eq!(body["use_beam_search"], true);
+        assert_eq!(body["min_tokens"], 2);
+        assert_eq!(body["guided_regex"], "(red|green|blue)");
+        assert!(body.get("guided_json").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_response_format_and_logprobs() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"{}\"},\"logprobs\":{\"content\":[{\"token\":\"{}\",\"logprob\":-0.01,\"top_logprobs\":[]}]}}]}\n\n",
+        ));
+
+        let provider = VllmProvider::new(client.clone());
+        let messages = &["Describe a cat".into()];
+        let options = ChatOptions::new("Qwen/Qwen3-8B")
+            .messages(messages)
+            .response_format(ResponseFormat::Json)
+            .logprobs(true)
+            .top_logprobs(2);
+
+        let mut response = provider.chat(&options).await.unwrap();
+        let result = response.aggregate().await.unwrap();
+
+        assert_eq!(result.content, "{}");
+        assert_eq!(result.logprobs.len(), 1);
+        assert_eq!(result.logprobs[0].token, "{}");
+
+        let request = client.last_request().unwrap();
+        let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+        assert_eq!(
+            body["response_format"],
+            serde_json::json!({ "type": "json_object" })
+        );
+        assert_eq!(body["logprobs"], true);
+        assert_eq!(body["top_logprobs"], 2);
+    }
+
+    #[test]
+    fn test_guided_json() {
+        let messages = &["Describe a cat".into()];
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } }
+        });
+        let options = ChatOptions::new("Qwen/Qwen3-8B")
+            .messages(messages)
+            .extension(VllmChatExt::new().guided_json(schema.clone()));
+
+        let body: serde_json::Value = serde_json::from_str(&chat_body(&options).unwrap()).unwrap();
+
+        assert_eq!(body["guided_json"], schema);
+    }
+
+    #[test]
+    fn test_min_tokens_above_max_tokens() {
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("Qwen/Qwen3-8B")
+            .messages(messages)
+            .max_tokens(8)
+            .extension(VllmChatExt::new().min_tokens(16));
+
+        assert!(matches!(
+            chat_body(&options),
+            Err(ChatError::InvalidOptions {
+                field: "min_tokens",
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_error() {
+        let client = MockHttpClient::new().with_response(MockResponse::new(StatusCode::OK).body(
+            "data: {\"error\":{\"object\":\"error\",\"message\":\"The model does not exist.\",\"type\":\"NotFoundError\",\"code\":404}}\n\n",
+        ));
+
+        let provider = VllmProvider::new(client);
+        let messages = &["Hi".into()];
+        let options = ChatOptions::new("Qwen/Qwen3-8B").messages(messages);
+
+        let mut response = provider.chat(&options).await.unwrap();
+
+        assert!(matches!(
+            response.next().await,
+            Some(Err(ChatStreamError::ProviderError { kind, message }))
+                if kind == "NotFoundError" && message == "The model does not exist."
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+use anyml_core::providers::chat::{ChatError, ChatOptions};
+use serde::Serialize;
+
+/// vLLM-specific chat options, attached with
+/// [`ChatOptions::extension`](anyml_core::providers::chat::ChatOptions::extension).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VllmChatExt {
+    pub best_of: Option<usize>,
+    pub use_beam_search: Option<bool>,
+    pub min_tokens: Option<usize>,
+    pub guided: Option<GuidedDecoding>,
+}
+
+/// Constrains the output to a structure, enforced by the server while decoding.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum GuidedDecoding {
+    /// JSON matching the given JSON schema.
+    Json(serde_json::Value),
+    /// Text matching the given regular expression.
+    Regex(String),
+}
+
+impl VllmChatExt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates `best_of` sequences and returns the one with the highest log probability.
+    pub fn best_of(mut self, best_of: usize) -> Self {
+        self.best_of = Some(best_of);
+        self
+    }
+
+    /// Uses beam search instead of sampling, with `best_of` as the beam width.
+    pub fn use_beam_search(mut self, use_beam_search: bool) -> Self {
+        self.use_beam_search = Some(use_beam_search);
+        self
+    }
+
+    /// Sets the number of tokens to generate before the end of sequence token is allowed.
+    pub fn min_tokens(mut self, min_tokens: usize) -> Self {
+        self.min_tokens = Some(min_tokens);
+        self
+    }
+
+    pub fn guided_json(mut self, schema: serde_json::Value) -> Self {
+        self.guided = Some(GuidedDecoding::Json(schema));
+        self
+    }
+
+    pub fn guided_regex(mut self, regex: impl Into<String>) -> Self {
+        self.guided = Some(GuidedDecoding::Regex(regex.into()));
+        self
+    }
+
+    pub(crate) fn validate(&self, options: &ChatOptions<'_>) -> Result<(), ChatError> {
+        if self.best_of == Some(0) {
+            return Err(ChatError::InvalidOptions {
+                field: "best_of",
+                reason: "it must be at least 1".to_owned(),
+            });
+        }
+        if let (Some(min_tokens), Some(max_tokens)) = (self.min_tokens, options.max_tokens)
+            && min_tokens > max_tokens
+        {
+            return Err(ChatError::InvalidOptions {
+                field: "min_tokens",
+                reason: format!("{min_tokens} is greater than max_tokens ({max_tokens})"),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::anyhow;
+use anyhttp::HttpClient;
+use anyml_core::{
+    models::Model,
+    providers::{
+        HealthCheckProvider, HealthStatus,
+        list_models::{ListModelsError, ListModelsProvider},
+    },
+};
+use bytes::Bytes;
+use http::Request;
+use serde::Deserialize;
+
+use crate::{VllmProvider, with_auth};
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ListModelsProvider for VllmProvider<C> {
+    /// Lists the model the server was started with, and any LoRA adapters loaded onto it.
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        let api_key = self
+            .api_key
+            .current_key()
+            .await
+            .map_err(ListModelsError::RequestBuildFailed)?;
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/v1/models", self.url)));
+        let request = with_auth(request, &api_key)
+            .body(Vec::new())
+            .map_err(|e| ListModelsError::RequestBuildFailed(anyhow::Error::new(e)))?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        if !response.status().is_success() {
+            let err_body = response
+                .bytes()
+                .await
+                .unwrap_or_else(|_| Bytes::from_static(b"<failed to read>"));
+
+            return Err(ListModelsError::ResponseFetchFailed(anyhow!(
+                String::from_utf8_lossy(&err_body).into_owned()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(ListModelsError::ResponseFetchFailed)?;
+
+        let vllm_response: VllmModelsResponse = serde_json::from_slice(&body)
+            .map_err(|e| ListModelsError::ParseError(anyhow::Error::new(e)))?;
+
+        Ok(vllm_response
+            .data
+            .into_iter()
+            .map(|m| Model {
+                id: m.id,
+                context_length: m.max_model_len,
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> HealthCheckProvider for VllmProvider<C> {
+    /// Lists the models rather than calling `/health`, which doesn't check the
+    /// API key.
+    async fn health(&self) -> HealthStatus {
+        let api_key = match self.api_key.current_key().await {
+            Ok(api_key) => api_key,
+            Err(err) => {
+                return HealthStatus::Unhealthy {
+                    reason: format!("{err:#}"),
+                };
+            }
+        };
+
+        let request = self
+            .headers
+            .apply(Request::get(format!("{}/v1/models", self.url)));
+        match with_auth(request, &api_key).body(Vec::new()) {
+            Ok(request) => HealthStatus::probe(&self.client, request).await,
+            Err(err) => HealthStatus::Unhealthy {
+                reason: err.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct VllmModelsResponse {
+    data: Vec<VllmModel>,
+}
+
+#[derive(Deserialize)]
+struct VllmModel {
+    id: String,
+    #[serde(default)]
+    max_model_len: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhttp::mock::{MockHttpClient, MockResponse};
+    use http::StatusCode;
+
+    #[tokio::test]
+    async fn test_list_models_success() {
+        let client = MockHttpClient::new().with_response(
+            MockResponse::new(StatusCode::OK).body(
+                r#"{"object":"list","data":[{"id":"Qwen/Qwen3-8B","object":"model","created":1760000000,"owned_by":"vllm","root":"Qwen/Qwen3-8B","parent":null,"max_model_len":40960}]}"#,
+            ),
+        );
+
+        let provider = VllmProvider::new(client.clone()).url("http://gpu-box:8000");
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].id, "Qwen/Qwen3-8B");
+        assert_eq!(models[0].context_length, Some(40960));
+
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://gpu-box:8000/v1/models");
+    }
+
+    #[tokio::test]
+    async fn test_list_models_unauthorized() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::UNAUTHORIZED).body("Unauthorized"));
+
+        let provider = VllmProvider::new(client).api_key("bad-key");
+        let result = provider.list_models().await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let client = MockHttpClient::new()
+            .with_response(MockResponse::new(StatusCode::OK).body(r#"{"object":"list","data":[]}"#))
+            .with_response(
+                MockResponse::new(StatusCode::UNAUTHORIZED).body(r#"{"error":"Unauthorized"}"#),
+            );
+
+        let provider = VllmProvider::new(client.clone()).api_key("secret");
+
+        assert_eq!(provider.health().await, HealthStatus::Healthy);
+        let request = client.last_request().unwrap();
+        assert_eq!(request.uri(), "http://localhost:8000/v1/models");
+        assert_eq!(
+            request.headers().get("Authorization").unwrap(),
+            "Bearer secret"
+        );
+
+        assert!(matches!(
+            provider.health().await,
+            HealthStatus::Unhealthy { .. }
+        ));
+    }
+}
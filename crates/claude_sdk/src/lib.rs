@@ -13,7 +13,7 @@ pub use install::install_cli;
 pub use session::{create_session, normalize_session_id};
 pub use transport::AgentHandle;
 pub use types::{
-    AgentMessage, ContentBlock, Message, QueryOptions, Role, StreamDelta, StreamEvent,
+    AgentMessage, ContentBlock, Image, Message, QueryOptions, Role, StreamDelta, StreamEvent,
     ThinkingConfig,
 };
 
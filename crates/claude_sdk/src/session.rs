@@ -67,7 +67,7 @@ pub fn create_session(
             Role::Assistant => "assistant",
         };
 
-        let content = json!([{"type": "text", "text": msg.content}]);
+        let content = msg.content_blocks();
         let line = json!({
             "type": msg_type,
             "message": {
@@ -130,8 +130,8 @@ mod tests {
     #[test]
     fn test_create_session_returns_jsonl_path() {
         let messages = vec![
-            Message { role: Role::User, content: "hello".into() },
-            Message { role: Role::Assistant, content: "hi there".into() },
+            Message { role: Role::User, content: "hello".into(), images: Vec::new() },
+            Message { role: Role::Assistant, content: "hi there".into(), images: Vec::new() },
         ];
 
         let path = create_session(&messages, None, Path::new("/tmp/claude")).unwrap();
@@ -161,7 +161,7 @@ mod tests {
 
     #[test]
     fn test_create_session_deterministic_path_with_id() {
-        let messages = vec![Message { role: Role::User, content: "test".into() }];
+        let messages = vec![Message { role: Role::User, content: "test".into(), images: Vec::new() }];
 
         let path1 = create_session(&messages, Some("my-session"), Path::new("/tmp/claude")).unwrap();
         let path2 = create_session(&messages, Some("my-session"), Path::new("/tmp/claude")).unwrap();
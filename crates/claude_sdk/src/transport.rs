@@ -164,7 +164,11 @@ pub fn spawn_agent(
             "session_id": session_id,
             "message": {
                 "role": "user",
-                "content": last_user_msg.content,
+                "content": if last_user_msg.images.is_empty() {
+                    serde_json::json!(last_user_msg.content)
+                } else {
+                    last_user_msg.content_blocks()
+                },
             },
             "parent_tool_use_id": null,
         });
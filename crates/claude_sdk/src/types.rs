@@ -73,6 +73,36 @@ pub enum StreamDelta {
 pub struct Message {
     pub role: Role,
     pub content: String,
+    /// Images sent after the text.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<Image>,
+}
+
+impl Message {
+    /// The message's content blocks: its text, followed by its images.
+    pub fn content_blocks(&self) -> serde_json::Value {
+        let text = (!self.content.is_empty() || self.images.is_empty())
+            .then(|| serde_json::json!({ "type": "text", "text": self.content }));
+        let images = self.images.iter().map(|image| {
+            serde_json::json!({
+                "type": "image",
+                "source": {
+                    "type": "base64",
+                    "media_type": image.media_type,
+                    "data": image.data,
+                },
+            })
+        });
+        text.into_iter().chain(images).collect()
+    }
+}
+
+/// A base64-encoded image in a message.
+#[derive(Debug, Clone, Serialize)]
+pub struct Image {
+    /// The MIME type of the image (e.g. `"image/png"`).
+    pub media_type: String,
+    pub data: String,
 }
 
 /// The role of a message in the conversation.
@@ -219,5 +249,24 @@ mod tests {
         let result = serde_json::from_str::<AgentMessage>(json);
         assert!(result.is_err());
     }
-}
 
+    #[test]
+    fn message_content_blocks_with_image() {
+        let message = Message {
+            role: Role::User,
+            content: "What's this?".into(),
+            images: vec![Image { media_type: "image/png".into(), data: "cG5n".into() }],
+        };
+
+        assert_eq!(
+            message.content_blocks(),
+            serde_json::json!([
+                { "type": "text", "text": "What's this?" },
+                {
+                    "type": "image",
+                    "source": { "type": "base64", "media_type": "image/png", "data": "cG5n" },
+                },
+            ])
+        );
+    }
+}
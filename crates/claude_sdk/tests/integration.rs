@@ -14,11 +14,11 @@ fn fake_cli() -> PathBuf {
 }
 
 fn user_msg(content: &str) -> Message {
-    Message { role: Role::User, content: content.into() }
+    Message { role: Role::User, content: content.into(), images: Vec::new() }
 }
 
 fn assistant_msg(content: &str) -> Message {
-    Message { role: Role::Assistant, content: content.into() }
+    Message { role: Role::Assistant, content: content.into(), images: Vec::new() }
 }
 
 // ── Streaming ────────────────────────────────────────────────────────
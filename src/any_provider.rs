@@ -0,0 +1,172 @@
+use anyhttp::HttpClient;
+use anyml_core::{
+    ChatError, ChatOptions, ChatProvider, ChatResponse, ListModelsError, ListModelsProvider, Model,
+};
+
+#[cfg(feature = "anthropic")]
+use anyml_anthropic::AnthropicProvider;
+#[cfg(feature = "claude_sdk")]
+use anyml_claude_sdk::ClaudeSdkProvider;
+#[cfg(feature = "groq")]
+use anyml_groq::GroqProvider;
+#[cfg(feature = "huggingface")]
+use anyml_huggingface::HuggingFaceProvider;
+#[cfg(feature = "ollama")]
+use anyml_ollama::OllamaProvider;
+#[cfg(feature = "openai")]
+use anyml_openai::OpenAiProvider;
+#[cfg(feature = "openrouter")]
+use anyml_openrouter::OpenRouterProvider;
+#[cfg(feature = "vllm")]
+use anyml_vllm::VllmProvider;
+
+/// Any of the enabled providers, chosen at runtime.
+///
+/// Unlike `Box<dyn ChatProvider>` this keeps the concrete provider type,
+/// so it can be matched on and implements every shared provider trait.
+pub enum AnyProvider<C: HttpClient> {
+    #[cfg(feature = "anthropic")]
+    Anthropic(AnthropicProvider<C>),
+    #[cfg(feature = "openai")]
+    OpenAi(OpenAiProvider<C>),
+    #[cfg(feature = "ollama")]
+    Ollama(OllamaProvider<C>),
+    #[cfg(feature = "groq")]
+    Groq(GroqProvider<C>),
+    #[cfg(feature = "openrouter")]
+    OpenRouter(OpenRouterProvider<C>),
+    #[cfg(feature = "huggingface")]
+    HuggingFace(HuggingFaceProvider<C>),
+    #[cfg(feature = "vllm")]
+    Vllm(VllmProvider<C>),
+    #[cfg(feature = "claude_sdk")]
+    ClaudeSdk(ClaudeSdkProvider),
+}
+
+/// Evaluates `$expr` with `$provider` bound to whichever provider `$this` holds.
+macro_rules! delegate {
+    ($this:expr, $provider:ident => $expr:expr) => {
+        match $this {
+            #[cfg(feature = "anthropic")]
+            Self::Anthropic($provider) => $expr,
+            #[cfg(feature = "openai")]
+            Self::OpenAi($provider) => $expr,
+            #[cfg(feature = "ollama")]
+            Self::Ollama($provider) => $expr,
+            #[cfg(feature = "groq")]
+            Self::Groq($provider) => $expr,
+            #[cfg(feature = "openrouter")]
+            Self::OpenRouter($provider) => $expr,
+            #[cfg(feature = "huggingface")]
+            Self::HuggingFace($provider) => $expr,
+            #[cfg(feature = "vllm")]
+            Self::Vllm($provider) => $expr,
+            #[cfg(feature = "claude_sdk")]
+            Self::ClaudeSdk($provider) => $expr,
+        }
+    };
+}
+
+impl<C: HttpClient> AnyProvider<C> {
+    /// The name of the provider, matching its crate feature (e.g. `"openai"`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "anthropic")]
+            Self::Anthropic(_) => "anthropic",
+            #[cfg(feature = "openai")]
+            Self::OpenAi(_) => "openai",
+            #[cfg(feature = "ollama")]
+            Self::Ollama(_) => "ollama",
+            #[cfg(feature = "groq")]
+            Self::Groq(_) => "groq",
+            #[cfg(feature = "openrouter")]
+            Self::OpenRouter(_) => "openrouter",
+            #[cfg(feature = "huggingface")]
+            Self::HuggingFace(_) => "huggingface",
+            #[cfg(feature = "vllm")]
+            Self::Vllm(_) => "vllm",
+            #[cfg(feature = "claude_sdk")]
+            Self::ClaudeSdk(_) => "claude_sdk",
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ChatProvider for AnyProvider<C> {
+    async fn chat(&self, options: &ChatOptions<'_>) -> Result<ChatResponse, ChatError> {
+        delegate!(self, provider => provider.chat(options).await)
+    }
+
+    fn default_model(&self) -> Option<&str> {
+        delegate!(self, provider => provider.default_model())
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<C: HttpClient> ListModelsProvider for AnyProvider<C> {
+    async fn list_models(&self) -> Result<Vec<Model>, ListModelsError> {
+        delegate!(self, provider => provider.list_models().await)
+    }
+
+    async fn get_model(&self, id: &str) -> Result<Model, ListModelsError> {
+        delegate!(self, provider => provider.get_model(id).await)
+    }
+}
+
+#[cfg(feature = "anthropic")]
+impl<C: HttpClient> From<AnthropicProvider<C>> for AnyProvider<C> {
+    fn from(provider: AnthropicProvider<C>) -> Self {
+        Self::Anthropic(provider)
+    }
+}
+
+#[cfg(feature = "openai")]
+impl<C: HttpClient> From<OpenAiProvider<C>> for AnyProvider<C> {
+    fn from(provider: OpenAiProvider<C>) -> Self {
+        Self::OpenAi(provider)
+    }
+}
+
+#[cfg(feature = "ollama")]
+impl<C: HttpClient> From<OllamaProvider<C>> for AnyProvider<C> {
+    fn from(provider: OllamaProvider<C>) -> Self {
+        Self::Ollama(provider)
+    }
+}
+
+#[cfg(feature = "groq")]
+impl<C: HttpClient> From<GroqProvider<C>> for AnyProvider<C> {
+    fn from(provider: GroqProvider<C>) -> Self {
+        Self::Groq(provider)
+    }
+}
+
+#[cfg(feature = "openrouter")]
+impl<C: HttpClient> From<OpenRouterProvider<C>> for AnyProvider<C> {
+    fn from(provider: OpenRouterProvider<C>) -> Self {
+        Self::OpenRouter(provider)
+    }
+}
+
+#[cfg(feature = "huggingface")]
+impl<C: HttpClient> From<HuggingFaceProvider<C>> for AnyProvider<C> {
+    fn from(provider: HuggingFaceProvider<C>) -> Self {
+        Self::HuggingFace(provider)
+    }
+}
+
+#[cfg(feature = "vllm")]
+impl<C: HttpClient> From<VllmProvider<C>> for AnyProvider<C> {
+    fn from(provider: VllmProvider<C>) -> Self {
+        Self::Vllm(provider)
+    }
+}
+
+#[cfg(feature = "claude_sdk")]
+impl<C: HttpClient> From<ClaudeSdkProvider> for AnyProvider<C> {
+    fn from(provider: ClaudeSdkProvider) -> Self {
+        Self::ClaudeSdk(provider)
+    }
+}
@@ -0,0 +1,383 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use anyhttp::HttpClient;
+use anyml_core::{ChatProvider, DefaultModelProvider, MessageRules};
+use http::{HeaderName, HeaderValue};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// The HTTP-based providers which can be built from a [`ProviderConfig`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    Anthropic,
+    OpenAi,
+    Ollama,
+    Groq,
+    OpenRouter,
+    /// The serverless Hugging Face Inference API.
+    HuggingFace,
+    /// A self-hosted vLLM server, which only needs an API key if started with `--api-key`.
+    Vllm,
+}
+
+impl ProviderKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Anthropic => "anthropic",
+            Self::OpenAi => "openai",
+            Self::Ollama => "ollama",
+            Self::Groq => "groq",
+            Self::OpenRouter => "openrouter",
+            Self::HuggingFace => "huggingface",
+            Self::Vllm => "vllm",
+        }
+    }
+
+    /// The environment variable the API key is read from by default,
+    /// or `None` if the provider doesn't take one.
+    pub fn default_api_key_env(&self) -> Option<&'static str> {
+        match self {
+            Self::Anthropic => Some("ANTHROPIC_API_KEY"),
+            Self::OpenAi => Some("OPENAI_API_KEY"),
+            Self::Ollama => None,
+            Self::Groq => Some("GROQ_API_KEY"),
+            Self::OpenRouter => Some("OPENROUTER_API_KEY"),
+            Self::HuggingFace => Some("HF_TOKEN"),
+            Self::Vllm => None,
+        }
+    }
+
+    /// The rules the provider places on the order of messages.
+    pub fn message_rules(&self) -> MessageRules {
+        match self {
+            Self::Anthropic | Self::HuggingFace | Self::Vllm => MessageRules::Alternating,
+            Self::OpenAi | Self::Ollama | Self::Groq | Self::OpenRouter => MessageRules::Any,
+        }
+    }
+}
+
+impl From<ProviderKind> for MessageRules {
+    fn from(kind: ProviderKind) -> Self {
+        kind.message_rules()
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ProviderKind {
+    type Err = ConfigError;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind.to_ascii_lowercase().as_str() {
+            "anthropic" => Ok(Self::Anthropic),
+            "openai" => Ok(Self::OpenAi),
+            "ollama" => Ok(Self::Ollama),
+            "groq" => Ok(Self::Groq),
+            "openrouter" => Ok(Self::OpenRouter),
+            "huggingface" => Ok(Self::HuggingFace),
+            "vllm" => Ok(Self::Vllm),
+            _ => Err(ConfigError::UnknownKind {
+                kind: kind.to_owned(),
+            }),
+        }
+    }
+}
+
+/// A declarative description of a provider, e.g. loaded from a config file.
+///
+/// API keys are never part of the config, only the name of the
+/// environment variable they are read from.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Defaults to [`ProviderKind::default_api_key_env`].
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    /// Headers added to every request.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The model chats are sent to when their options don't name one
+    /// (see [`ChatOptions::default_model`](anyml_core::ChatOptions::default_model)).
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl ProviderConfig {
+    pub fn new(kind: ProviderKind) -> Self {
+        Self {
+            kind,
+            base_url: None,
+            api_key_env: None,
+            headers: BTreeMap::new(),
+            model: None,
+        }
+    }
+
+    /// Reads the config from the `ANYML_PROVIDER`, `ANYML_BASE_URL`,
+    /// `ANYML_API_KEY_ENV` and `ANYML_MODEL` environment variables.
+    ///
+    /// Only `ANYML_PROVIDER` is required.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let kind = env_var("ANYML_PROVIDER")?.parse()?;
+
+        Ok(Self {
+            base_url: env_var("ANYML_BASE_URL").ok(),
+            api_key_env: env_var("ANYML_API_KEY_ENV").ok(),
+            model: env_var("ANYML_MODEL").ok(),
+            ..Self::new(kind)
+        })
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn api_key_env(mut self, name: impl Into<String>) -> Self {
+        self.api_key_env = Some(name.into());
+        self
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    #[cfg_attr(
+        not(any(
+            feature = "anthropic",
+            feature = "openai",
+            feature = "groq",
+            feature = "openrouter",
+            feature = "huggingface",
+            feature = "vllm"
+        )),
+        allow(dead_code)
+    )]
+    fn api_key(&self) -> Result<String, ConfigError> {
+        match self
+            .api_key_env
+            .as_deref()
+            .or(self.kind.default_api_key_env())
+        {
+            Some(name) => env_var(name),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Checks the headers up front, as providers only report invalid headers once
+    /// a request is sent.
+    fn validate_headers(&self) -> Result<(), ConfigError> {
+        for (name, value) in &self.headers {
+            let invalid = |source: anyhow::Error| ConfigError::InvalidHeader {
+                name: name.clone(),
+                source,
+            };
+            HeaderName::from_str(name).map_err(|this| invalid(this.into()))?;
+            HeaderValue::from_str(value).map_err(|this| invalid(this.into()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the provider described by `config`, sending requests through `client`.
+pub fn from_config<C: HttpClient + 'static>(
+    client: C,
+    config: &ProviderConfig,
+) -> Result<Box<dyn ChatProvider>, ConfigError> {
+    let provider = build_provider(client, config)?;
+    Ok(match &config.model {
+        Some(model) => Box::new(DefaultModelProvider::new(provider, model.clone())),
+        None => provider,
+    })
+}
+
+#[cfg_attr(
+    not(any(
+        feature = "anthropic",
+        feature = "openai",
+        feature = "ollama",
+        feature = "groq",
+        feature = "openrouter",
+        feature = "huggingface",
+        feature = "vllm"
+    )),
+    allow(unused_variables)
+)]
+fn build_provider<C: HttpClient + 'static>(
+    client: C,
+    config: &ProviderConfig,
+) -> Result<Box<dyn ChatProvider>, ConfigError> {
+    config.validate_headers()?;
+
+    // Every provider has the same builder methods for headers and the base URL.
+    #[allow(unused_macros)]
+    macro_rules! configured {
+        ($provider:expr) => {{
+            let mut provider = $provider;
+            for (name, value) in &config.headers {
+                provider = provider.header(name.clone(), value.clone());
+            }
+            if let Some(url) = &config.base_url {
+                provider = provider.url(url.clone());
+            }
+            Ok(Box::new(provider))
+        }};
+    }
+
+    match config.kind {
+        #[cfg(feature = "anthropic")]
+        ProviderKind::Anthropic => {
+            configured!(crate::AnthropicProvider::new(client, config.api_key()?))
+        }
+        #[cfg(feature = "openai")]
+        ProviderKind::OpenAi => configured!(crate::OpenAiProvider::new(client, config.api_key()?)),
+        #[cfg(feature = "ollama")]
+        ProviderKind::Ollama => configured!(crate::OllamaProvider::new(client)),
+        #[cfg(feature = "groq")]
+        ProviderKind::Groq => configured!(crate::GroqProvider::new(client, config.api_key()?)),
+        #[cfg(feature = "openrouter")]
+        ProviderKind::OpenRouter => {
+            configured!(crate::OpenRouterProvider::new(client, config.api_key()?))
+        }
+        #[cfg(feature = "huggingface")]
+        ProviderKind::HuggingFace => {
+            configured!(crate::HuggingFaceProvider::new(client, config.api_key()?))
+        }
+        #[cfg(feature = "vllm")]
+        ProviderKind::Vllm => {
+            configured!(crate::VllmProvider::new(client).api_key(config.api_key()?))
+        }
+        #[allow(unreachable_patterns)]
+        kind => Err(ConfigError::Disabled { kind }),
+    }
+}
+
+/// Builds the provider described by [`ProviderConfig::from_env`].
+pub fn from_env<C: HttpClient + 'static>(client: C) -> Result<Box<dyn ChatProvider>, ConfigError> {
+    from_config(client, &ProviderConfig::from_env()?)
+}
+
+fn env_var(name: &str) -> Result<String, ConfigError> {
+    std::env::var(name).map_err(|_| ConfigError::MissingEnv {
+        name: name.to_owned(),
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("The environment variable \"{name}\" is not set.")]
+    MissingEnv { name: String },
+
+    #[error("\"{kind}\" is not a known provider.")]
+    UnknownKind { kind: String },
+
+    #[error("The \"{kind}\" provider is not enabled, enable the feature of the same name.")]
+    Disabled { kind: ProviderKind },
+
+    #[error("The header \"{name}\" is invalid: {source}.")]
+    InvalidHeader {
+        name: String,
+        #[source]
+        source: anyhow::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyml_core::{Message, providers::chat::Messages};
+
+    #[test]
+    fn kind_from_str() {
+        assert_eq!(
+            "OpenAI".parse::<ProviderKind>().unwrap(),
+            ProviderKind::OpenAi
+        );
+        assert_eq!(
+            "openrouter".parse::<ProviderKind>().unwrap(),
+            ProviderKind::OpenRouter
+        );
+        assert!(matches!(
+            "bard".parse::<ProviderKind>(),
+            Err(ConfigError::UnknownKind { kind }) if kind == "bard"
+        ));
+    }
+
+    #[test]
+    fn kind_validates_messages() {
+        let messages = [Message::user("Hi"), Message::user("Are you there?")];
+        let messages = Messages::Raw(&messages);
+
+        assert!(messages.validate_for(ProviderKind::OpenAi).is_ok());
+        assert!(messages.validate_for(ProviderKind::Anthropic).is_err());
+    }
+
+    #[test]
+    fn config_deserializes() {
+        let config: ProviderConfig = serde_json::from_str(
+            r#"{
+                "kind": "openai",
+                "base_url": "http://localhost:8080",
+                "headers": { "x-team": "research" }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            ProviderConfig::new(ProviderKind::OpenAi)
+                .base_url("http://localhost:8080")
+                .header("x-team", "research")
+        );
+    }
+
+    #[test]
+    fn invalid_header() {
+        let config = ProviderConfig::new(ProviderKind::Ollama).header("bad header", "value");
+
+        let result = from_config(reqwest::Client::new(), &config);
+
+        assert!(matches!(
+            result,
+            Err(ConfigError::InvalidHeader { name, .. }) if name == "bad header"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "groq")]
+    fn missing_api_key() {
+        let config =
+            ProviderConfig::new(ProviderKind::Groq).api_key_env("ANYML_TEST_UNSET_API_KEY");
+
+        let result = from_config(reqwest::Client::new(), &config);
+
+        assert!(matches!(
+            result,
+            Err(ConfigError::MissingEnv { name }) if name == "ANYML_TEST_UNSET_API_KEY"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "ollama")]
+    fn model_sets_the_default() {
+        let config = ProviderConfig::new(ProviderKind::Ollama).model("llama3.2");
+
+        let provider = from_config(reqwest::Client::new(), &config).unwrap();
+
+        assert_eq!(provider.default_model(), Some("llama3.2"));
+    }
+}
@@ -1,4 +1,27 @@
+#[cfg(any(
+    feature = "anthropic",
+    feature = "openai",
+    feature = "ollama",
+    feature = "groq",
+    feature = "openrouter",
+    feature = "huggingface",
+    feature = "vllm"
+))]
+mod any_provider;
+mod config;
+
+#[cfg(any(
+    feature = "anthropic",
+    feature = "openai",
+    feature = "ollama",
+    feature = "groq",
+    feature = "openrouter",
+    feature = "huggingface",
+    feature = "vllm"
+))]
+pub use any_provider::AnyProvider;
 pub use anyml_core::*;
+pub use config::{ConfigError, ProviderConfig, ProviderKind, from_config, from_env};
 
 #[cfg(feature = "anthropic")]
 pub use anyml_anthropic::*;
@@ -11,3 +34,18 @@ pub use anyml_openai::*;
 
 #[cfg(feature = "claude_sdk")]
 pub use anyml_claude_sdk::*;
+
+#[cfg(feature = "groq")]
+pub use anyml_groq::*;
+
+#[cfg(feature = "openrouter")]
+pub use anyml_openrouter::*;
+
+#[cfg(feature = "huggingface")]
+pub use anyml_huggingface::*;
+
+#[cfg(feature = "vllm")]
+pub use anyml_vllm::*;
+
+#[cfg(feature = "server")]
+pub use anyml_server::*;